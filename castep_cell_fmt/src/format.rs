@@ -19,6 +19,10 @@ fn fmt_cell_value(v: &CellValue<'_>, buf: &mut String) {
         CellValue::UInt(u) => buf.push_str(&format!("{u:4}")),
         CellValue::Int(i) => buf.push_str(&format!("{i:4}")),
         CellValue::Float(f) => buf.push_str(&format!("{f:20.16}")),
+        CellValue::Comment(c) => {
+            buf.push_str("! ");
+            buf.push_str(c);
+        }
         CellValue::Array(items) => {
             for (idx, item) in items.iter().enumerate() {
                 if idx > 0 {
@@ -125,6 +129,13 @@ mod format_test {
         assert!(output.ends_with('\n'));
     }
 
+    #[test]
+    fn comment_format() {
+        let row = CellValue::Array(vec![CellValue::UInt(1), CellValue::Comment("X")]);
+        let block = Cell::Block("BS_KPOINT_PATH", vec![row]);
+        assert!(to_string(&block).contains("   1 ! X\n"));
+    }
+
     #[test]
     fn keyvalue_format() {
         let kv = Cell::KeyValue("FIX_COM", CellValue::Bool(true));
@@ -92,6 +92,7 @@ pub trait ToCellFile {
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[non_exhaustive]
 pub enum CellValue<'a> {
     Null,
     Bool(bool),
@@ -101,6 +102,11 @@ pub enum CellValue<'a> {
     Int(i32),
     Float(f64),
     Array(Vec<CellValue<'a>>),
+    /// Trailing `!`/`#` comment of a block row, without the marker.
+    ///
+    /// Removed before [`FromBlock::from_block_rows`] unless the block sets
+    /// [`FromBlock::KEEP_COMMENTS`], e.g. to read labels written as comments.
+    Comment(&'a str),
 }
 
 impl<'a> CellValue<'a> {
@@ -3,7 +3,7 @@ use crate::{
     error::{CResult, Error},
     parser::parse_cell_file,
     query::{find_block, find_keyvalue, value_as_bool, value_as_f64, value_as_i32,
-            value_as_string, value_as_u32},
+            value_as_string, value_as_u32, without_comments},
 };
 
 // ── Core traits ──────────────────────────────────────────────────────────────
@@ -28,16 +28,30 @@ pub trait FromBlock: Sized {
     /// Defaults to `&[]` (no aliases) for backward compatibility.
     const BLOCK_ALIASES: &'static [&'static str] = &[];
 
+    /// Whether `from_block_rows` receives the trailing comments of the rows as
+    /// [`CellValue::Comment`]. Defaults to `false`: `from_file_rows` removes them first.
+    const KEEP_COMMENTS: bool = false;
+
     fn from_block_rows(rows: &[CellValue<'_>]) -> CResult<Self>;
 
+    /// Provided: parse the rows of a block as read from a file, removing their trailing
+    /// comments unless `KEEP_COMMENTS` is set.
+    fn from_file_rows(rows: &[CellValue<'_>]) -> CResult<Self> {
+        if Self::KEEP_COMMENTS {
+            Self::from_block_rows(rows)
+        } else {
+            Self::from_block_rows(&without_comments(rows))
+        }
+    }
+
     /// Provided: find the block in the token slice and parse it.
     fn from_cells(tokens: &[Cell<'_>]) -> CResult<Self> {
         match find_block(tokens, Self::BLOCK_NAME) {
-            Ok(rows) => Self::from_block_rows(rows),
+            Ok(rows) => Self::from_file_rows(rows),
             Err(Error::KeyNotFound(_)) => {
                 for alias in Self::BLOCK_ALIASES {
                     if let Ok(rows) = find_block(tokens, alias) {
-                        return Self::from_block_rows(rows);
+                        return Self::from_file_rows(rows);
                     }
                 }
                 Err(Error::KeyNotFound(Self::BLOCK_NAME.to_string()))
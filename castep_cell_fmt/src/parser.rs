@@ -109,18 +109,25 @@ fn cell_primitives<'src>()
 /// and each line contains a variety of `Cell` type.
 fn block_lines<'src>()
 -> impl Parser<'src, &'src str, Vec<CellValue<'src>>, extra::Err<Rich<'src, char>>> {
-    // Recognize the basic types
-    cell_primitives()
-        // Ignore trailing comments (single '#' or '!' without content is also allowed)
-        // Since a valid block line should not be interrupted by comments, we just handle the trailing
-        // comments
-        .then_ignore(one_of("#!").repeated().or(comment()).or_not())
+    // Recognize the basic types, keeping a trailing comment as a value so that the
+    // reader of the block can decide whether it is worth keeping
+    let trailing_comment = one_of("#!")
+        .ignore_then(any().and_is(newline().not()).repeated().to_slice())
+        .map(|s: &str| CellValue::Comment(s.trim()));
+    choice((
+        trailing_comment.map(|comment| vec![comment]),
+        // A comment may follow a value without a space, e.g. `0.5 0.0 0.5!X`
+        cell_primitives()
+            .then(trailing_comment.or_not())
+            .map(|(value, comment)| std::iter::once(value).chain(comment).collect()),
+    ))
         // .. then separated by at least one whitespace
         .separated_by(just(' ').then(whitespace()).to_slice())
         // Since `CASTEP` and `Materials Studio` prefers formatting the data in right-align and with fixed-width
         // style, leading whitespaces are of high likelihood.
         .allow_leading()
-        .collect::<Vec<CellValue>>()
+        .collect::<Vec<Vec<CellValue>>>()
+        .map(|items| items.into_iter().flatten().collect::<Vec<CellValue>>())
         // Turn `Input` to `Parser` for convenience
         .boxed()
         // The final line before "%ENDBLOCK" goes with a trailing newline,
@@ -168,12 +175,9 @@ fn block<'src>() -> impl Parser<'src, &'src str, Cell<'src>, extra::Err<Rich<'sr
             lines
                 .into_iter()
                 .filter(|line| {
-                    if let CellValue::Array(l) = line {
-                        // Skips empty vec because of presence of comments, actually
-                        !l.is_empty()
-                    } else {
-                        false
-                    }
+                    // Skips lines holding nothing but comments
+                    matches!(line, CellValue::Array(l)
+                        if l.iter().any(|item| !matches!(item, CellValue::Comment(_))))
                 })
                 .collect::<Vec<CellValue>>()
         }))
@@ -344,6 +348,57 @@ INV_LENGTH_UNIT: 1/bohr
             .unwrap();
         dbg!(parsed);
     }
+    #[test]
+    fn trailing_comments_are_kept() {
+        use crate::{Cell, CellValue};
+
+        let input = "%BLOCK BS_KPOINT_PATH
+0.0 0.0 0.0 ! G
+0.5 0.0 0.5 # X
+! a whole-line comment
+BREAK
+0.5 0.5 0.5
+0.5 0.5 0.0!M
+%ENDBLOCK BS_KPOINT_PATH
+%BLOCK CELL_CONSTRAINTS
+1 2 3 ! lengths
+4 5 6
+%ENDBLOCK CELL_CONSTRAINTS
+";
+        let parsed = parse_cell_file(input).unwrap();
+        let Cell::Block(_, path) = &parsed[0] else {
+            panic!("expected a block")
+        };
+        assert_eq!(path.len(), 5);
+        assert_eq!(
+            path[0],
+            CellValue::Array(vec![
+                CellValue::Float(0.0),
+                CellValue::Float(0.0),
+                CellValue::Float(0.0),
+                CellValue::Comment("G"),
+            ])
+        );
+        assert_eq!(path[1].as_array().unwrap()[3], CellValue::Comment("X"));
+        assert_eq!(path[2], CellValue::Array(vec![CellValue::Str("BREAK")]));
+        assert_eq!(
+            path[4],
+            CellValue::Array(vec![
+                CellValue::Float(0.5),
+                CellValue::Float(0.5),
+                CellValue::Float(0.0),
+                CellValue::Comment("M"),
+            ])
+        );
+        let Cell::Block(_, constraints) = &parsed[1] else {
+            panic!("expected a block")
+        };
+        assert_eq!(
+            constraints[0].as_array().unwrap()[3],
+            CellValue::Comment("lengths")
+        );
+    }
+
     #[test]
     fn parser_test_part() {
         let parsed = parse_cell_file(POSITIONS)
@@ -25,6 +25,22 @@ pub fn find_block_any<'a>(tokens: &'a [Cell<'a>], names: &[&str]) -> CResult<&'a
         .ok_or_else(|| Error::KeyNotFound(first_name.to_string()))
 }
 
+/// Returns block rows with their trailing [`CellValue::Comment`]s removed.
+pub fn without_comments<'a>(rows: &[CellValue<'a>]) -> Vec<CellValue<'a>> {
+    rows.iter()
+        .map(|row| match row {
+            CellValue::Array(values) => CellValue::Array(
+                values
+                    .iter()
+                    .filter(|value| !matches!(value, CellValue::Comment(_)))
+                    .cloned()
+                    .collect(),
+            ),
+            other => other.clone(),
+        })
+        .collect()
+}
+
 /// Find a key-value entry by key (case-insensitive). Returns reference to its CellValue.
 pub fn find_keyvalue<'a>(tokens: &'a [Cell<'a>], key: &str) -> CResult<&'a CellValue<'a>> {
     for token in tokens {
@@ -1,52 +1,46 @@
-use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, query::value_as_f64};
+use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult};
+
+use super::{path_from_rows, path_point_from_row, path_point_to_row, path_segments, path_to_rows};
 
 /// Represents a single point entry within the BS_KPOINT_PATH block.
 ///
 /// Each entry contains three fractional k-point coordinates relative to the reciprocal space lattice vectors.
-/// Format: <x> <y> <z>
-#[derive(Debug, Clone, Copy, PartialEq, bon::Builder)]
+/// Format: <x> <y> <z> [! label]
+#[derive(Debug, Clone, PartialEq, bon::Builder)]
 pub struct BsKpointPathEntry {
     /// Fractional k-point coordinates [x, y, z].
     pub coord: [f64; 3],
+    /// Label written as a trailing comment, e.g. `X` in `0.5 0.0 0.5 ! X`.
+    #[builder(into)]
+    pub label: Option<String>,
 }
 
 impl FromCellValue for BsKpointPathEntry {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
-        match value {
-            CellValue::Array(arr) if arr.len() == 3 => {
-                let coord = [
-                    value_as_f64(&arr[0])?,
-                    value_as_f64(&arr[1])?,
-                    value_as_f64(&arr[2])?,
-                ];
-                Ok(BsKpointPathEntry { coord })
-            }
-            _ => Err(castep_cell_fmt::Error::Message(
-                "BsKpointPathEntry must be an array of 3 floats".into(),
-            )),
-        }
+        let (coord, label) = path_point_from_row(value).ok_or_else(|| {
+            castep_cell_fmt::Error::Message("BsKpointPathEntry must be an array of 3 floats".into())
+        })?;
+        Ok(BsKpointPathEntry { coord, label })
     }
 }
 
 impl ToCellValue for BsKpointPathEntry {
     /// Converts the entry into a `CellValue::Array` representing one line of the block.
     fn to_cell_value(&self) -> CellValue<'_> {
-        CellValue::Array(
-            self.coord
-                .into_iter()
-                .map(CellValue::Float)
-                .collect(),
-        )
+        path_point_to_row(self.coord, self.label.as_deref())
     }
 }
 
 /// Represents the BS_KPOINT_PATH block.
 ///
 /// Contains a list of k-points that define a path through reciprocal space for band structure calculations.
+/// A `BREAK` line ends one segment of the path and starts the next.
 /// Format:
 /// %BLOCK BS_KPOINT_PATH
-/// R1i R1j R1k
+/// R1i R1j R1k ! label
 /// R2i R2j R2k
+/// BREAK
+/// R3i R3j R3k
 /// ...
 /// %ENDBLOCK BS_KPOINT_PATH
 #[derive(Debug, Clone, PartialEq, bon::Builder)]
@@ -54,31 +48,33 @@ pub struct BsKpointPath {
     /// The list of k-point entries.
     #[builder(default)]
     pub points: Vec<BsKpointPathEntry>,
+    /// Indices of the points that start a new segment, written as `BREAK` lines.
+    #[builder(default)]
+    pub breaks: Vec<usize>,
 }
 
 impl FromBlock for BsKpointPath {
     const BLOCK_NAME: &'static str = "BS_KPOINT_PATH";
     const BLOCK_ALIASES: &'static [&'static str] = &["BS_KPOINTS_PATH"];
+    const KEEP_COMMENTS: bool = true;
 
     fn from_block_rows(rows: &[CellValue<'_>]) -> CResult<Self> {
-        let points = rows
-            .iter()
-            .map(BsKpointPathEntry::from_cell_value)
-            .collect::<CResult<Vec<_>>>()?;
-        Ok(BsKpointPath { points })
+        let (points, breaks) = path_from_rows(rows, BsKpointPathEntry::from_cell_value)?;
+        Ok(BsKpointPath { points, breaks })
     }
 }
 
 impl ToCell for BsKpointPath {
     /// Converts the block into the intermediate `Cell` representation for serialization.
     fn to_cell(&self) -> Cell<'_> {
-        Cell::Block(
-            "BS_KPOINT_PATH",
-            self.points
-                .iter()
-                .map(|entry| entry.to_cell_value())
-                .collect::<Vec<CellValue>>(),
-        )
+        Cell::Block("BS_KPOINT_PATH", path_to_rows(&self.points, &self.breaks))
+    }
+}
+
+impl BsKpointPath {
+    /// Returns the continuous runs of points between `BREAK` lines.
+    pub fn segments(&self) -> impl Iterator<Item = &[BsKpointPathEntry]> {
+        path_segments(&self.points, &self.breaks)
     }
 }
 
@@ -123,6 +119,7 @@ mod tests {
     fn test_bs_kpoint_path_entry_to_cell_value() {
         let entry = BsKpointPathEntry {
             coord: [0.3333333333, 0.3750000000, 0.3333333333],
+            label: None,
         };
         let val = entry.to_cell_value();
         match val {
@@ -158,6 +155,31 @@ mod tests {
         assert_eq!(result.points.len(), 0);
     }
 
+    #[test]
+    fn test_bs_kpoint_path_labels_and_breaks_round_trip() {
+        let input = "%BLOCK BS_KPOINT_PATH
+0.0 0.0 0.0 ! G
+0.5 0.0 0.5 ! X
+BREAK
+0.5 0.5 0.5!L
+0.0 0.0 0.0
+%ENDBLOCK BS_KPOINT_PATH
+";
+        let tokens = castep_cell_fmt::parse_cell_file(input).unwrap();
+        let path = BsKpointPath::from_cells(&tokens).unwrap();
+        assert_eq!(path.points[1].label.as_deref(), Some("X"));
+        assert_eq!(path.points[2].label.as_deref(), Some("L"));
+        assert_eq!(path.points[3].label, None);
+        assert_eq!(path.breaks, vec![2]);
+        assert_eq!(path.segments().map(<[_]>::len).collect::<Vec<_>>(), [2, 2]);
+
+        let output = castep_cell_fmt::to_string(&path.to_cell());
+        assert!(output.contains("! L\n"));
+        assert!(output.contains("BREAK\n"));
+        let tokens = castep_cell_fmt::parse_cell_file(&output).unwrap();
+        assert_eq!(BsKpointPath::from_cells(&tokens).unwrap(), path);
+    }
+
     #[test]
     fn test_bs_kpoint_path_block_name() {
         assert_eq!(BsKpointPath::BLOCK_NAME, "BS_KPOINT_PATH");
@@ -168,7 +190,9 @@ mod tests {
         let path = BsKpointPath {
             points: vec![BsKpointPathEntry {
                 coord: [0.3333333333, 0.3750000000, 0.3333333333],
+                label: None,
             }],
+            breaks: vec![],
         };
         let cell = path.to_cell();
         match cell {
@@ -0,0 +1,195 @@
+//! Symmetry reduction of explicit k-point sets.
+
+use std::collections::HashMap;
+
+use castep_cell_fmt::CResult;
+
+use crate::CellDocument;
+use crate::math::{self, IMat3};
+
+use super::{Kpoint, KpointsList, KpointsMpGrid, KpointsMpSpacing};
+
+/// Resolution used to decide whether two fractional k-points coincide.
+const KPOINT_RESOLUTION: f64 = 1e6;
+
+impl CellDocument {
+    /// Returns the Monkhorst-Pack set CASTEP would sample, reduced by symmetry.
+    ///
+    /// The grid is `KPOINT_MP_GRID`, or the one derived from `KPOINT_MP_SPACING`
+    /// (default 0.1 Å⁻¹), shifted by `KPOINT_MP_OFFSET`. See
+    /// [`reduce_kpoints`](Self::reduce_kpoints) for the reduction.
+    pub fn mp_kpoints_list(&self) -> CResult<KpointsList> {
+        self.reduce_kpoints(&self.mp_grid()?.kpoints(self.kpoints_mp_offset))
+    }
+
+    /// Returns `KPOINT_MP_GRID`, or the grid CASTEP derives from `KPOINT_MP_SPACING`.
+    pub(crate) fn mp_grid(&self) -> CResult<KpointsMpGrid> {
+        match (self.kpoints_mp_grid, self.kpoints_mp_spacing) {
+            (Some(grid), _) => Ok(grid),
+            (None, spacing) => spacing
+                .unwrap_or(KpointsMpSpacing {
+                    value: 0.1,
+                    unit: None,
+                })
+                .to_grid(&self.lattice),
+        }
+    }
+
+    /// Reduces `kpoints` to one representative per star under the point group of the
+    /// crystal and time reversal, `k → -k`.
+    ///
+    /// The rotations come from `SYMMETRY_OPS` when present and from
+    /// [`find_symmetry`](Self::find_symmetry) otherwise. Each representative is the
+    /// first member of its star in input order and carries the summed weight of the
+    /// star, normalised so the weights add up to 1. Images of a point that are not in
+    /// the input set are ignored, so a set that breaks the symmetry is still weighted
+    /// correctly.
+    pub fn reduce_kpoints(&self, kpoints: &KpointsList) -> CResult<KpointsList> {
+        let rotations = self.point_group_rotations()?;
+        let mut members: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        for (i, k) in kpoints.kpts.iter().enumerate() {
+            members.entry(key(k.coord)).or_default().push(i);
+        }
+
+        let mut assigned = vec![false; kpoints.kpts.len()];
+        let mut reduced: Vec<Kpoint> = Vec::new();
+        for (i, k) in kpoints.kpts.iter().enumerate() {
+            if assigned[i] {
+                continue;
+            }
+            let mut weight = 0.0;
+            for w in &rotations {
+                let image = math::vec_mat(k.coord, math::to_f64(*w));
+                for image in [image, math::scale(image, -1.0)] {
+                    for &j in members.get(&key(image)).into_iter().flatten() {
+                        if !assigned[j] {
+                            assigned[j] = true;
+                            weight += kpoints.kpts[j].weight;
+                        }
+                    }
+                }
+            }
+            reduced.push(Kpoint {
+                coord: k.coord,
+                weight,
+            });
+        }
+
+        let total: f64 = reduced.iter().map(|k| k.weight).sum();
+        if total > 0.0 {
+            reduced.iter_mut().for_each(|k| k.weight /= total);
+        }
+        Ok(KpointsList { kpts: reduced })
+    }
+
+    /// Returns the distinct fractional rotations of the crystal's point group.
+    fn point_group_rotations(&self) -> CResult<Vec<IMat3>> {
+        let all: Vec<IMat3> = match &self.symmetry_ops {
+            Some(ops) => ops
+                .ops
+                .iter()
+                .map(|op| op.to_frac(&self.lattice).map(|f| f.rotation))
+                .collect::<CResult<_>>()?,
+            None => self
+                .find_symmetry()?
+                .operations
+                .iter()
+                .map(|op| op.rotation)
+                .collect(),
+        };
+        let mut rotations = vec![[[1, 0, 0], [0, 1, 0], [0, 0, 1]]];
+        for w in all {
+            if !rotations.contains(&w) {
+                rotations.push(w);
+            }
+        }
+        Ok(rotations)
+    }
+}
+
+impl KpointsList {
+    /// Re-expresses the points in the reciprocal basis of the supercell whose vectors
+    /// are the columns of `matrix`, in fractional coordinates of the current lattice, as
+    /// for [`CellDocument::transform`].
+    ///
+    /// Points that become equivalent in the smaller Brillouin zone are merged and their
+    /// weights summed; coordinates are wrapped into `[0, 1)`.
+    pub(crate) fn fold(&self, matrix: IMat3) -> KpointsList {
+        let matrix = math::to_f64(matrix);
+        let mut index: HashMap<[i64; 3], usize> = HashMap::new();
+        let mut kpts: Vec<Kpoint> = Vec::new();
+        for k in &self.kpts {
+            let coord = math::wrap(math::vec_mat(k.coord, matrix));
+            match index.get(&key(coord)) {
+                Some(&i) => kpts[i].weight += k.weight,
+                None => {
+                    index.insert(key(coord), kpts.len());
+                    kpts.push(Kpoint {
+                        coord,
+                        weight: k.weight,
+                    });
+                }
+            }
+        }
+        KpointsList { kpts }
+    }
+}
+
+/// Identifies a fractional k-point modulo reciprocal lattice vectors.
+fn key(k: [f64; 3]) -> [i64; 3] {
+    k.map(|x| ((x * KPOINT_RESOLUTION).round() as i64).rem_euclid(KPOINT_RESOLUTION as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::bz_sampling_kpoints::KpointsMpOffset;
+    use crate::cell::symmetry::{SymmetryOp, SymmetryOps};
+    use crate::test_fixtures::cubic_cell;
+
+    fn cubic() -> CellDocument {
+        CellDocument {
+            kpoints_mp_grid: Some(KpointsMpGrid([4, 4, 4])),
+            ..cubic_cell(3.0, &[("Po", [0.0; 3])])
+        }
+    }
+
+    fn weights(list: &KpointsList) -> Vec<f64> {
+        let mut w: Vec<f64> = list.kpts.iter().map(|k| k.weight * 64.0).collect();
+        w.sort_by(f64::total_cmp);
+        w
+    }
+
+    #[test]
+    fn cubic_grid_reduces_to_four_stars() {
+        let reduced = cubic().mp_kpoints_list().unwrap();
+        assert_eq!(reduced.kpts.len(), 4);
+        let w = weights(&reduced);
+        for (got, want) in w.iter().zip([8.0, 8.0, 24.0, 24.0]) {
+            assert!((got - want).abs() < 1e-9, "{w:?}");
+        }
+    }
+
+    #[test]
+    fn symmetry_ops_override_detection() {
+        let mut doc = cubic();
+        doc.symmetry_ops = Some(SymmetryOps {
+            ops: vec![SymmetryOp::identity()],
+        });
+        // Only time reversal is left to pair k with -k.
+        let reduced = doc.mp_kpoints_list().unwrap();
+        assert_eq!(reduced.kpts.len(), 32);
+        assert!(weights(&reduced).iter().all(|w| (w - 2.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn offset_grid_keeps_total_weight() {
+        let mut doc = cubic();
+        doc.kpoints_mp_grid = Some(KpointsMpGrid([3, 3, 3]));
+        doc.kpoints_mp_offset = Some(KpointsMpOffset([0.1, 0.0, 0.0]));
+        let reduced = doc.mp_kpoints_list().unwrap();
+        let total: f64 = reduced.kpts.iter().map(|k| k.weight).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert!(reduced.kpts.len() < 27);
+    }
+}
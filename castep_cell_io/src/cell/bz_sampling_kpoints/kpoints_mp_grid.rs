@@ -3,6 +3,8 @@ use castep_cell_fmt::parse::{FromCellValue, FromKeyValue};
 use castep_cell_fmt::{CResult, Error};
 use castep_cell_fmt::query::value_as_u32;
 
+use super::{Kpoint, KpointsList, KpointsMpOffset};
+
 /// Specifies the Monkhorst-Pack grid parameters for generating SCF k-points.
 ///
 /// Keyword type: Block (but actually a key-value with 3 integers)
@@ -14,6 +16,36 @@ use castep_cell_fmt::query::value_as_u32;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct KpointsMpGrid(pub [u32; 3]);
 
+impl KpointsMpGrid {
+    /// Returns every point of the Monkhorst-Pack grid with equal weights, shifted by
+    /// `offset`.
+    ///
+    /// Along an axis with `n` points the fractional coordinates are
+    /// `(2r - n - 1) / 2n` for `r = 1..=n`, so odd grids contain Γ and even grids do not.
+    pub fn kpoints(&self, offset: Option<KpointsMpOffset>) -> KpointsList {
+        let [n1, n2, n3] = self.0.map(|n| n.max(1));
+        let shift = offset.map_or([0.0; 3], |o| o.0);
+        let coord = |r: u32, n: u32| f64::from(2 * r + 1) / f64::from(2 * n) - 0.5;
+        let weight = 1.0 / f64::from(n1 * n2 * n3);
+        let mut kpts = Vec::with_capacity((n1 * n2 * n3) as usize);
+        for i in 0..n1 {
+            for j in 0..n2 {
+                for k in 0..n3 {
+                    kpts.push(Kpoint {
+                        coord: [
+                            coord(i, n1) + shift[0],
+                            coord(j, n2) + shift[1],
+                            coord(k, n3) + shift[2],
+                        ],
+                        weight,
+                    });
+                }
+            }
+        }
+        KpointsList { kpts }
+    }
+}
+
 impl FromCellValue for KpointsMpGrid {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value {
@@ -160,4 +192,19 @@ mod tests {
         let grid = KpointsMpGrid::from_cell_value_kv(&val).unwrap();
         assert_eq!(grid.0, [5, 5, 5]);
     }
+
+    #[test]
+    fn test_kpoints_mp_grid_kpoints() {
+        let odd = KpointsMpGrid([3, 1, 1]).kpoints(None);
+        let coords: Vec<f64> = odd.kpts.iter().map(|k| k.coord[0]).collect();
+        assert_eq!(coords.len(), 3);
+        assert!((coords[0] + 1.0 / 3.0).abs() < 1e-12);
+        assert!(coords[1].abs() < 1e-12);
+        assert!((coords[2] - 1.0 / 3.0).abs() < 1e-12);
+
+        let even = KpointsMpGrid([2, 2, 2]).kpoints(Some(KpointsMpOffset([0.25, 0.0, 0.0])));
+        assert_eq!(even.kpts.len(), 8);
+        assert_eq!(even.kpts[0].coord, [0.0, -0.25, -0.25]);
+        assert!((even.kpts.iter().map(|k| k.weight).sum::<f64>() - 1.0).abs() < 1e-12);
+    }
 }
@@ -3,6 +3,8 @@ use castep_cell_fmt::parse::{FromCellValue, FromKeyValue};
 use castep_cell_fmt::{CResult, Error};
 use castep_cell_fmt::query::value_as_f64;
 use crate::units::InvLengthUnit;
+use super::{KpointsMpGrid, grid_from_spacing};
+use crate::Lattice;
 
 /// Specifies the spacing of k-points in the Monkhorst-Pack grid.
 ///
@@ -20,6 +22,16 @@ pub struct KpointsMpSpacing {
     pub unit: Option<InvLengthUnit>,
 }
 
+impl KpointsMpSpacing {
+    /// Returns the `KPOINT_MP_GRID` CASTEP derives from this spacing in `lattice`.
+    ///
+    /// Fails unless the spacing is positive and the lattice vectors are linearly
+    /// independent.
+    pub fn to_grid(&self, lattice: &Lattice) -> CResult<KpointsMpGrid> {
+        grid_from_spacing(lattice, self.value, self.unit).map(KpointsMpGrid)
+    }
+}
+
 impl FromCellValue for KpointsMpSpacing {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value {
@@ -84,6 +96,7 @@ impl ToCellValue for KpointsMpSpacing {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_fixtures::abc_lattice;
 
     #[test]
     fn test_kpoints_mp_spacing_scalar() {
@@ -169,4 +182,41 @@ mod tests {
         assert_eq!(parsed.value, original.value);
         assert_eq!(parsed.unit, original.unit);
     }
+
+    #[test]
+    fn test_kpoints_mp_spacing_to_grid() {
+        let spacing = KpointsMpSpacing {
+            value: 0.05,
+            unit: None,
+        };
+        // Exact multiples stay put, everything else rounds up.
+        let cubic = abc_lattice([5.0, 5.0, 5.0], [90.0, 90.0, 90.0]);
+        assert_eq!(spacing.to_grid(&cubic).unwrap(), KpointsMpGrid([4, 4, 4]));
+        let hexagonal = abc_lattice([3.0, 3.0, 5.0], [90.0, 90.0, 120.0]);
+        assert_eq!(spacing.to_grid(&hexagonal).unwrap(), KpointsMpGrid([8, 8, 4]));
+        let long = abc_lattice([5.0, 5.0, 100.0], [90.0, 90.0, 90.0]);
+        assert_eq!(spacing.to_grid(&long).unwrap(), KpointsMpGrid([4, 4, 1]));
+    }
+
+    #[test]
+    fn test_kpoints_mp_spacing_to_grid_units() {
+        let cubic = abc_lattice([5.0, 5.0, 5.0], [90.0, 90.0, 90.0]);
+        let bohr = KpointsMpSpacing {
+            value: 0.05,
+            unit: Some(InvLengthUnit::Bohr),
+        };
+        assert_eq!(bohr.to_grid(&cubic).unwrap(), KpointsMpGrid([3, 3, 3]));
+        let nm = KpointsMpSpacing {
+            value: 0.5,
+            unit: Some(InvLengthUnit::NanoMeter),
+        };
+        assert_eq!(nm.to_grid(&cubic).unwrap(), KpointsMpGrid([4, 4, 4]));
+        let zero = KpointsMpSpacing {
+            value: 0.0,
+            unit: None,
+        };
+        assert!(zero.to_grid(&cubic).is_err());
+        let flat = abc_lattice([5.0, 5.0, 5.0], [90.0, 90.0, 180.0]);
+        assert!(nm.to_grid(&flat).is_err());
+    }
 }
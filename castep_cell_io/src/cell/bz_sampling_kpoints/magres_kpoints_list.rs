@@ -1,5 +1,7 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromBlock, FromCellValue, CResult, query::value_as_f64};
 
+use super::KpointsList;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 /// A line of block `MagresKpointsList`
 /// The first three entries on a line are the fractional positions of the
@@ -58,6 +60,23 @@ pub struct MagresKpointsList {
     pub kpoints: Vec<MagresKpointsListEntry>,
 }
 
+impl From<KpointsList> for MagresKpointsList {
+    /// Reuses the points and weights of a `KPOINTS_LIST`, e.g. one from
+    /// [`CellDocument::mp_kpoints_list`](crate::CellDocument::mp_kpoints_list).
+    fn from(list: KpointsList) -> Self {
+        MagresKpointsList {
+            kpoints: list
+                .kpts
+                .into_iter()
+                .map(|k| MagresKpointsListEntry {
+                    coord: k.coord,
+                    weight: k.weight,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl FromBlock for MagresKpointsList {
     const BLOCK_NAME: &'static str = "MAGRES_KPOINT_LIST";
     const BLOCK_ALIASES: &'static [&'static str] = &["MAGRES_KPOINTS_LIST"];
@@ -14,6 +14,8 @@ mod spectral_kpoints_mp_spacing;
 mod spectral_kpoints_mp_offset;
 mod magres_kpoints_list;
 mod optics_kpoints_list;
+mod kpoint_reduction;
+mod path_sampling;
 pub use bs_kpoints_list::BSKpointList;
 pub use kpoint::Kpoint;
 pub use kpoints_list::KpointsList;
@@ -32,3 +34,127 @@ pub use spectral_kpoints_mp_spacing::SpectralKpointsMpSpacing;
 pub use spectral_kpoints_mp_offset::SpectralKpointsMpOffset;
 pub use magres_kpoints_list::MagresKpointsList;
 pub use optics_kpoints_list::OpticsKpointsList;
+pub use path_sampling::{SampledKpoint, SampledPath};
+
+use castep_cell_fmt::query::value_as_f64;
+use castep_cell_fmt::{CResult, CellValue, Error, ToCellValue};
+
+use crate::Lattice;
+use crate::math;
+use crate::units::InvLengthUnit;
+
+/// Returns the Monkhorst-Pack grid CASTEP builds from a k-point spacing.
+///
+/// CASTEP measures the spacing without the factor 2π and rounds the number of points
+/// along each reciprocal vector up, so the actual spacing never exceeds the requested
+/// one; a cubic cell of side `a` gets `⌈1 / (a · spacing)⌉` points per axis.
+pub(crate) fn grid_from_spacing(
+    lattice: &Lattice,
+    value: f64,
+    unit: Option<InvLengthUnit>,
+) -> CResult<[u32; 3]> {
+    let spacing = value * unit.unwrap_or_default().in_inv_angstrom();
+    if spacing <= 0.0 || !spacing.is_finite() {
+        return Err(Error::Message(format!(
+            "k-point spacing must be positive, got {value}"
+        )));
+    }
+    Ok(lattice.reciprocal_vectors()?.map(|b| {
+        // Absorb rounding noise so an exact multiple is not bumped up a point.
+        let points = (math::norm(b) / spacing - 1e-8).ceil();
+        points.max(1.0) as u32
+    }))
+}
+
+/// Keyword separating two disconnected segments of a k-point path block.
+const PATH_BREAK: &str = "BREAK";
+
+/// Reads the coordinates and optional trailing-comment label of a path block row.
+pub(crate) fn path_point_from_row(value: &CellValue<'_>) -> Option<([f64; 3], Option<String>)> {
+    let CellValue::Array(arr) = value else {
+        return None;
+    };
+    let (label, coord) = match arr.as_slice() {
+        [coord @ .., CellValue::Comment(label)] => {
+            (Some(label.to_string()).filter(|l| !l.is_empty()), coord)
+        }
+        coord => (None, coord),
+    };
+    match coord {
+        [x, y, z] => Some((
+            [value_as_f64(x).ok()?, value_as_f64(y).ok()?, value_as_f64(z).ok()?],
+            label,
+        )),
+        _ => None,
+    }
+}
+
+/// Writes a path block row, with the label as a trailing comment.
+pub(crate) fn path_point_to_row<'a>(coord: [f64; 3], label: Option<&'a str>) -> CellValue<'a> {
+    CellValue::Array(
+        coord
+            .into_iter()
+            .map(CellValue::Float)
+            .chain(label.map(CellValue::Comment))
+            .collect(),
+    )
+}
+
+/// Splits the rows of a path block into its points and the indices of the points that
+/// start a new segment after a `BREAK`.
+///
+/// Breaks before the first or after the last point, and repeated breaks, are dropped.
+pub(crate) fn path_from_rows<T>(
+    rows: &[CellValue<'_>],
+    entry: impl Fn(&CellValue<'_>) -> CResult<T>,
+) -> CResult<(Vec<T>, Vec<usize>)> {
+    let mut points = Vec::with_capacity(rows.len());
+    let mut breaks: Vec<usize> = Vec::new();
+    for row in rows {
+        let is_break = matches!(
+            row.as_array().map(Vec::as_slice),
+            Some([CellValue::Str(word)]) if word.eq_ignore_ascii_case(PATH_BREAK)
+        );
+        if is_break {
+            if !points.is_empty() && breaks.last() != Some(&points.len()) {
+                breaks.push(points.len());
+            }
+        } else {
+            points.push(entry(row)?);
+        }
+    }
+    breaks.retain(|&i| i < points.len());
+    Ok((points, breaks))
+}
+
+/// Writes the rows of a path block, inserting `BREAK` before each segment start.
+pub(crate) fn path_to_rows<'a, T: ToCellValue>(points: &'a [T], breaks: &[usize]) -> Vec<CellValue<'a>> {
+    let mut rows = Vec::with_capacity(points.len() + breaks.len());
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 && breaks.contains(&i) {
+            rows.push(CellValue::Array(vec![CellValue::Str(PATH_BREAK)]));
+        }
+        rows.push(point.to_cell_value());
+    }
+    rows
+}
+
+/// Returns the continuous segments of a path with the given segment starts.
+pub(crate) fn path_segments<'a, T>(
+    points: &'a [T],
+    breaks: &'a [usize],
+) -> impl Iterator<Item = &'a [T]> {
+    let mut bounds: Vec<usize> = breaks
+        .iter()
+        .copied()
+        .filter(|&i| 0 < i && i < points.len())
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+    let starts = std::iter::once(0).chain(bounds.clone());
+    let ends = bounds.into_iter().chain(std::iter::once(points.len()));
+    starts
+        .zip(ends)
+        .filter(|(start, end)| start < end)
+        .map(move |(start, end)| &points[start..end])
+}
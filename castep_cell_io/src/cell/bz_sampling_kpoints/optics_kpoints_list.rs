@@ -1,5 +1,7 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromBlock, FromCellValue, CResult, query::value_as_f64};
 
+use super::KpointsList;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 /// A line of block `OpticsKpointsList`
 /// The first three entries on a line are the fractional positions of the
@@ -58,6 +60,23 @@ pub struct OpticsKpointsList {
     pub kpoints: Vec<OpticsKpointsListEntry>,
 }
 
+impl From<KpointsList> for OpticsKpointsList {
+    /// Reuses the points and weights of a `KPOINTS_LIST`, e.g. one from
+    /// [`CellDocument::mp_kpoints_list`](crate::CellDocument::mp_kpoints_list).
+    fn from(list: KpointsList) -> Self {
+        OpticsKpointsList {
+            kpoints: list
+                .kpts
+                .into_iter()
+                .map(|k| OpticsKpointsListEntry {
+                    coord: k.coord,
+                    weight: k.weight,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl FromBlock for OpticsKpointsList {
     const BLOCK_NAME: &'static str = "OPTICS_KPOINT_LIST";
     const BLOCK_ALIASES: &'static [&'static str] = &["OPTICS_KPOINTS_LIST"];
@@ -0,0 +1,282 @@
+//! Explicit k-point lists along k-point paths.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::Lattice;
+use crate::cell::phonon::{
+    PhononFineKpointPath, PhononFineKpointPathSpacing, PhononKpointPath, PhononKpointPathEntry,
+    PhononKpointPathSpacing,
+};
+use crate::math;
+use crate::units::InvLengthUnit;
+
+use super::{
+    BsKpointPath, BsKpointPathSpacing, Kpoint, KpointsList, SpectralKpointPath,
+    SpectralKpointPathSpacing, path_segments,
+};
+
+/// CASTEP default for all `*_KPOINT_PATH_SPACING` keywords, in Å⁻¹.
+const DEFAULT_PATH_SPACING: f64 = 0.1;
+
+/// One point of a sampled path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledKpoint {
+    /// Fractional coordinates in the reciprocal basis.
+    pub coord: [f64; 3],
+    /// Distance along the path from its first point, in [`SampledPath::unit`].
+    ///
+    /// `BREAK` jumps add no distance, so a band plot shows the segments side by side.
+    pub distance: f64,
+    /// Label of the path point this sample sits on, if it has one.
+    pub label: Option<String>,
+}
+
+/// The k-points CASTEP samples along a path at a given spacing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SampledPath {
+    /// Samples in path order; path points are included, joins between legs only once.
+    pub kpoints: Vec<SampledKpoint>,
+    /// Unit of the distances, taken from the spacing keyword.
+    pub unit: InvLengthUnit,
+}
+
+impl SampledPath {
+    /// Returns the distance and label of every labelled sample, for the tick marks of
+    /// a band plot.
+    pub fn label_positions(&self) -> impl Iterator<Item = (f64, &str)> {
+        self.kpoints
+            .iter()
+            .filter_map(|k| k.label.as_deref().map(|label| (k.distance, label)))
+    }
+
+    /// Returns the samples as an explicit list with equal weights.
+    pub fn to_kpoints_list(&self) -> KpointsList {
+        let weight = 1.0 / self.kpoints.len().max(1) as f64;
+        KpointsList {
+            kpts: self
+                .kpoints
+                .iter()
+                .map(|k| Kpoint {
+                    coord: k.coord,
+                    weight,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Samples every leg of a path with the fewest equal divisions that keep the spacing
+/// at or below `value`.
+///
+/// The spacing is measured without the factor 2π, as for Monkhorst-Pack spacings.
+fn sample_path(
+    lattice: &Lattice,
+    points: &[([f64; 3], Option<&str>)],
+    breaks: &[usize],
+    value: f64,
+    unit: Option<InvLengthUnit>,
+) -> CResult<SampledPath> {
+    let unit = unit.unwrap_or_default();
+    let spacing = value * unit.in_inv_angstrom();
+    if spacing <= 0.0 || !spacing.is_finite() {
+        return Err(Error::Message(format!(
+            "k-point path spacing must be positive, got {value}"
+        )));
+    }
+    let reciprocal = lattice.reciprocal_vectors()?;
+    let sample = |coord, distance: f64, label: Option<&str>| SampledKpoint {
+        coord,
+        distance: distance / unit.in_inv_angstrom(),
+        label: label.map(str::to_string),
+    };
+
+    let mut kpoints = Vec::new();
+    let mut distance = 0.0;
+    for segment in path_segments(points, breaks) {
+        let (first, label) = segment[0];
+        kpoints.push(sample(first, distance, label));
+        for leg in segment.windows(2) {
+            let ((from, _), (to, label)) = (leg[0], leg[1]);
+            let step = math::sub(to, from);
+            let length = math::norm(math::vec_mat(step, reciprocal));
+            // Absorb rounding noise so an exact multiple is not bumped up a point.
+            let divisions = ((length / spacing - 1e-8).ceil() as usize).max(1);
+            for i in 1..divisions {
+                let t = i as f64 / divisions as f64;
+                let coord = math::add(from, math::scale(step, t));
+                kpoints.push(sample(coord, distance + t * length, None));
+            }
+            distance += length;
+            kpoints.push(sample(to, distance, label));
+        }
+    }
+    Ok(SampledPath { kpoints, unit })
+}
+
+fn phonon_points(points: &[PhononKpointPathEntry]) -> Vec<([f64; 3], Option<&str>)> {
+    points
+        .iter()
+        .map(|p| (p.coord, p.label.as_deref()))
+        .collect()
+}
+
+impl BsKpointPath {
+    /// Returns the k-points CASTEP samples along the path in `lattice` at `spacing`,
+    /// which defaults to 0.1 Å⁻¹ like `BS_KPOINT_PATH_SPACING`.
+    ///
+    /// Fails unless the spacing is positive and the lattice vectors are linearly
+    /// independent.
+    pub fn sample(
+        &self,
+        lattice: &Lattice,
+        spacing: Option<BsKpointPathSpacing>,
+    ) -> CResult<SampledPath> {
+        let (value, unit) = spacing.map_or((DEFAULT_PATH_SPACING, None), |s| (s.value, s.unit));
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .map(|p| (p.coord, p.label.as_deref()))
+            .collect();
+        sample_path(lattice, &points, &self.breaks, value, unit)
+    }
+}
+
+impl SpectralKpointPath {
+    /// Returns the k-points CASTEP samples along the path at
+    /// `SPECTRAL_KPOINT_PATH_SPACING`, see [`BsKpointPath::sample`].
+    pub fn sample(
+        &self,
+        lattice: &Lattice,
+        spacing: Option<SpectralKpointPathSpacing>,
+    ) -> CResult<SampledPath> {
+        let (value, unit) = spacing.map_or((DEFAULT_PATH_SPACING, None), |s| (s.value, s.unit));
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .map(|p| (p.coord, p.label.as_deref()))
+            .collect();
+        sample_path(lattice, &points, &self.breaks, value, unit)
+    }
+}
+
+impl PhononKpointPath {
+    /// Returns the q-points CASTEP samples along the path at
+    /// `PHONON_KPOINT_PATH_SPACING`, see [`BsKpointPath::sample`].
+    pub fn sample(
+        &self,
+        lattice: &Lattice,
+        spacing: Option<PhononKpointPathSpacing>,
+    ) -> CResult<SampledPath> {
+        let (value, unit) = spacing.map_or((DEFAULT_PATH_SPACING, None), |s| (s.value, s.unit));
+        let points = phonon_points(&self.points);
+        sample_path(lattice, &points, &self.breaks, value, unit)
+    }
+}
+
+impl PhononFineKpointPath {
+    /// Returns the q-points CASTEP samples along the path at
+    /// `PHONON_FINE_KPOINT_PATH_SPACING`, see [`BsKpointPath::sample`].
+    pub fn sample(
+        &self,
+        lattice: &Lattice,
+        spacing: Option<PhononFineKpointPathSpacing>,
+    ) -> CResult<SampledPath> {
+        let (value, unit) = spacing.map_or((DEFAULT_PATH_SPACING, None), |s| (s.value, s.unit));
+        let points = phonon_points(&self.points);
+        sample_path(lattice, &points, &self.breaks, value, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::bz_sampling_kpoints::BsKpointPathEntry;
+    use crate::test_fixtures::{cubic_lattice, orthorhombic_lattice};
+
+    fn entry(coord: [f64; 3], label: &str) -> BsKpointPathEntry {
+        BsKpointPathEntry::builder()
+            .coord(coord)
+            .label(label)
+            .build()
+    }
+
+    #[test]
+    fn legs_are_divided_to_the_spacing() {
+        // |Γ-X| = 0.5 / 5 Å = 0.1 Å⁻¹, |X-M| the same.
+        let path = BsKpointPath::builder()
+            .points(vec![
+                entry([0.0; 3], "G"),
+                entry([0.5, 0.0, 0.0], "X"),
+                entry([0.5, 0.5, 0.0], "M"),
+            ])
+            .build();
+        let spacing = BsKpointPathSpacing {
+            value: 0.025,
+            unit: None,
+        };
+        let sampled = path.sample(&cubic_lattice(5.0), Some(spacing)).unwrap();
+        assert_eq!(sampled.kpoints.len(), 9);
+        assert_eq!(sampled.kpoints[1].coord, [0.125, 0.0, 0.0]);
+        let ticks: Vec<_> = sampled.label_positions().collect();
+        assert_eq!(ticks.len(), 3);
+        assert_eq!(ticks[1].1, "X");
+        assert!((ticks[1].0 - 0.1).abs() < 1e-12);
+        assert!((ticks[2].0 - 0.2).abs() < 1e-12);
+        let total: f64 = sampled
+            .to_kpoints_list()
+            .kpts
+            .iter()
+            .map(|k| k.weight)
+            .sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn breaks_add_no_distance_and_units_follow_the_spacing() {
+        let path = BsKpointPath::builder()
+            .points(vec![
+                entry([0.0; 3], "G"),
+                entry([0.5, 0.0, 0.0], "X"),
+                entry([0.5, 0.5, 0.5], "R"),
+                entry([0.5, 0.5, 0.0], "M"),
+            ])
+            .breaks(vec![2])
+            .build();
+        let spacing = BsKpointPathSpacing {
+            value: 1.0,
+            unit: Some(InvLengthUnit::NanoMeter),
+        };
+        let sampled = path.sample(&cubic_lattice(5.0), Some(spacing)).unwrap();
+        assert_eq!(sampled.unit, InvLengthUnit::NanoMeter);
+        // Each leg is 0.1 Å⁻¹ = 1 nm⁻¹ long, so one step at this spacing.
+        assert_eq!(sampled.kpoints.len(), 4);
+        let ticks: Vec<_> = sampled.label_positions().map(|(d, _)| d).collect();
+        for (got, want) in ticks.iter().zip([0.0, 1.0, 1.0, 2.0]) {
+            assert!((got - want).abs() < 1e-9, "{ticks:?}");
+        }
+    }
+
+    #[test]
+    fn spacing_must_be_positive() {
+        let path = PhononFineKpointPath::builder()
+            .points(vec![PhononKpointPathEntry {
+                coord: [0.0; 3],
+                label: None,
+            }])
+            .build();
+        let spacing = PhononFineKpointPathSpacing {
+            value: 0.0,
+            unit: None,
+        };
+        assert!(path.sample(&cubic_lattice(5.0), Some(spacing)).is_err());
+    }
+
+    #[test]
+    fn degenerate_lattice_fails() {
+        let path = BsKpointPath::builder()
+            .points(vec![entry([0.0; 3], "G"), entry([0.5, 0.0, 0.0], "X")])
+            .build();
+        let flat = orthorhombic_lattice(5.0, 5.0, 0.0);
+        assert!(path.sample(&flat, None).is_err());
+    }
+}
@@ -1,36 +1,27 @@
-use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, query::value_as_f64};
+use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult};
 
-#[derive(Debug, Clone, Copy, PartialEq, bon::Builder)]
+use super::{path_from_rows, path_point_from_row, path_point_to_row, path_segments, path_to_rows};
+
+#[derive(Debug, Clone, PartialEq, bon::Builder)]
 pub struct SpectralKpointPathEntry {
     pub coord: [f64; 3],
+    /// Label written as a trailing comment, e.g. `X` in `0.5 0.0 0.5 ! X`.
+    #[builder(into)]
+    pub label: Option<String>,
 }
 
 impl FromCellValue for SpectralKpointPathEntry {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
-        match value {
-            CellValue::Array(arr) if arr.len() == 3 => {
-                let coord = [
-                    value_as_f64(&arr[0])?,
-                    value_as_f64(&arr[1])?,
-                    value_as_f64(&arr[2])?,
-                ];
-                Ok(SpectralKpointPathEntry { coord })
-            }
-            _ => Err(castep_cell_fmt::Error::Message(
-                "SpectralKpointPathEntry must be an array of 3 floats".into(),
-            )),
-        }
+        let (coord, label) = path_point_from_row(value).ok_or_else(|| {
+            castep_cell_fmt::Error::Message("SpectralKpointPathEntry must be an array of 3 floats".into())
+        })?;
+        Ok(SpectralKpointPathEntry { coord, label })
     }
 }
 
 impl ToCellValue for SpectralKpointPathEntry {
     fn to_cell_value(&self) -> CellValue<'_> {
-        CellValue::Array(
-            self.coord
-                .into_iter()
-                .map(CellValue::Float)
-                .collect(),
-        )
+        path_point_to_row(self.coord, self.label.as_deref())
     }
 }
 
@@ -38,30 +29,32 @@ impl ToCellValue for SpectralKpointPathEntry {
 pub struct SpectralKpointPath {
     #[builder(default)]
     pub points: Vec<SpectralKpointPathEntry>,
+    /// Indices of the points that start a new segment, written as `BREAK` lines.
+    #[builder(default)]
+    pub breaks: Vec<usize>,
 }
 
 impl FromBlock for SpectralKpointPath {
     const BLOCK_NAME: &'static str = "SPECTRAL_KPOINT_PATH";
     const BLOCK_ALIASES: &'static [&'static str] = &["SPECTRAL_KPOINTS_PATH", "BS_KPOINT_PATH", "BS_KPOINTS_PATH"];
+    const KEEP_COMMENTS: bool = true;
 
     fn from_block_rows(rows: &[CellValue<'_>]) -> CResult<Self> {
-        let points = rows
-            .iter()
-            .map(SpectralKpointPathEntry::from_cell_value)
-            .collect::<CResult<Vec<_>>>()?;
-        Ok(SpectralKpointPath { points })
+        let (points, breaks) = path_from_rows(rows, SpectralKpointPathEntry::from_cell_value)?;
+        Ok(SpectralKpointPath { points, breaks })
     }
 }
 
 impl ToCell for SpectralKpointPath {
     fn to_cell(&self) -> Cell<'_> {
-        Cell::Block(
-            "SPECTRAL_KPOINT_PATH",
-            self.points
-                .iter()
-                .map(|entry| entry.to_cell_value())
-                .collect::<Vec<CellValue>>(),
-        )
+        Cell::Block("SPECTRAL_KPOINT_PATH", path_to_rows(&self.points, &self.breaks))
+    }
+}
+
+impl SpectralKpointPath {
+    /// Returns the continuous runs of points between `BREAK` lines.
+    pub fn segments(&self) -> impl Iterator<Item = &[SpectralKpointPathEntry]> {
+        path_segments(&self.points, &self.breaks)
     }
 }
 
@@ -106,6 +99,7 @@ mod tests {
     fn test_spectral_kpoint_path_entry_to_cell_value() {
         let entry = SpectralKpointPathEntry {
             coord: [0.3333333333, 0.3750000000, 0.3333333333],
+            label: None,
         };
         let val = entry.to_cell_value();
         match val {
@@ -151,7 +145,9 @@ mod tests {
         let path = SpectralKpointPath {
             points: vec![SpectralKpointPathEntry {
                 coord: [0.3333333333, 0.3750000000, 0.3333333333],
+                label: None,
             }],
+            breaks: vec![],
         };
         let cell = path.to_cell();
         match cell {
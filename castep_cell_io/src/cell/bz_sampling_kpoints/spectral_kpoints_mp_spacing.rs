@@ -3,6 +3,8 @@ use castep_cell_fmt::parse::{FromCellValue, FromKeyValue};
 use castep_cell_fmt::{CResult, Error};
 use castep_cell_fmt::query::value_as_f64;
 use crate::units::InvLengthUnit;
+use super::{SpectralKpointsMpGrid, grid_from_spacing};
+use crate::Lattice;
 
 /// Specifies the spacing of k-points in the Monkhorst-Pack grid for spectral sampling.
 ///
@@ -20,6 +22,16 @@ pub struct SpectralKpointsMpSpacing {
     pub unit: Option<InvLengthUnit>,
 }
 
+impl SpectralKpointsMpSpacing {
+    /// Returns the `SPECTRAL_KPOINT_MP_GRID` CASTEP derives from this spacing in `lattice`.
+    ///
+    /// Fails unless the spacing is positive and the lattice vectors are linearly
+    /// independent.
+    pub fn to_grid(&self, lattice: &Lattice) -> CResult<SpectralKpointsMpGrid> {
+        grid_from_spacing(lattice, self.value, self.unit).map(SpectralKpointsMpGrid)
+    }
+}
+
 impl FromCellValue for SpectralKpointsMpSpacing {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value {
@@ -1,6 +1,10 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, Error, query::value_as_f64};
 
+use std::collections::HashMap;
+
+use crate::CellDocument;
 use crate::cell::species::Species;
+use crate::math::{self, Vec3};
 
 /// Represents a single constraint entry within the IONIC_CONSTRAINTS block.
 ///
@@ -80,6 +84,207 @@ pub struct IonicConstraints {
     pub constraints: Vec<IonicConstraintEntry>,
 }
 
+/// Chooses the atoms an [`IonicConstraints`] selection applies to.
+///
+/// Coordinates are tested as written in the positions block, without wrapping into
+/// the cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AtomSelector {
+    /// Atoms by 0-based index into the positions block.
+    Indices(Vec<usize>),
+    /// Every atom of the listed species.
+    Species(Vec<Species>),
+    /// Atoms whose fractional coordinates lie within `min..=max` on every axis.
+    FracRegion { min: [f64; 3], max: [f64; 3] },
+    /// Atoms whose Cartesian coordinates in Å lie within `min..=max` on every axis.
+    CartRegion { min: [f64; 3], max: [f64; 3] },
+    /// Atoms whose height above the plane spanned by `a` and `b` is at most this many Å,
+    /// e.g. the bottom layers of a slab.
+    BelowHeight(f64),
+}
+
+impl AtomSelector {
+    /// Returns the indices of the selected atoms in block order.
+    ///
+    /// Fails if an index is out of range or a species does not occur in the cell.
+    pub fn select(&self, doc: &CellDocument) -> CResult<Vec<usize>> {
+        let n = doc.positions.len();
+        let within = |x: [f64; 3], min: &[f64; 3], max: &[f64; 3]| {
+            (0..3).all(|k| x[k] >= min[k] && x[k] <= max[k])
+        };
+        let selected = match self {
+            AtomSelector::Indices(indices) => {
+                if let Some(i) = indices.iter().find(|&&i| i >= n) {
+                    return Err(Error::Message(format!(
+                        "atom index {i} is out of range for a cell with {n} atoms"
+                    )));
+                }
+                let mut indices = indices.clone();
+                indices.sort_unstable();
+                indices.dedup();
+                indices
+            }
+            AtomSelector::Species(list) => {
+                let index = doc.ion_index();
+                if let Some(missing) = list.iter().find(|s| index.count(s) == 0) {
+                    return Err(Error::Message(format!(
+                        "species {missing} does not occur in the cell"
+                    )));
+                }
+                (0..n)
+                    .filter(|&i| list.iter().any(|s| s.same_species(index.species(i))))
+                    .collect()
+            }
+            AtomSelector::FracRegion { min, max } => {
+                let coords = doc.positions.frac_coords(&doc.lattice)?;
+                (0..n).filter(|&i| within(coords[i], min, max)).collect()
+            }
+            AtomSelector::CartRegion { min, max } => {
+                let coords = doc.positions.cart_coords(&doc.lattice);
+                (0..n).filter(|&i| within(coords[i], min, max)).collect()
+            }
+            AtomSelector::BelowHeight(height) => {
+                let [a, b, _] = doc.lattice.vectors();
+                let normal = math::cross(a, b);
+                let normal = math::scale(normal, 1.0 / math::norm(normal));
+                let coords = doc.positions.cart_coords(&doc.lattice);
+                (0..n)
+                    .filter(|&i| math::dot(coords[i], normal) <= *height)
+                    .collect()
+            }
+        };
+        Ok(selected)
+    }
+}
+
+/// How far a selected atom may still move once constrained.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IonMotion {
+    /// Fixed in all three Cartesian directions.
+    Fixed,
+    /// Free to move only within the plane with this Cartesian normal.
+    Plane([f64; 3]),
+    /// Free to move only along this Cartesian direction.
+    Line([f64; 3]),
+}
+
+impl IonMotion {
+    /// Returns the coefficient rows that remove the forbidden directions.
+    fn coefficients(&self) -> CResult<Vec<Vec3>> {
+        let unit = |v: Vec3| {
+            let length = math::norm(v);
+            if length < 1e-12 {
+                Err(Error::Message(
+                    "constraint direction must be a non-zero vector".into(),
+                ))
+            } else {
+                Ok(clean(math::scale(v, 1.0 / length)))
+            }
+        };
+        Ok(match self {
+            IonMotion::Fixed => vec![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            IonMotion::Plane(normal) => vec![unit(*normal)?],
+            IonMotion::Line(direction) => {
+                let d = unit(*direction)?;
+                // Cross with the Cartesian axis least aligned with the line.
+                let k = (0..3)
+                    .min_by(|&i, &j| d[i].abs().total_cmp(&d[j].abs()))
+                    .unwrap_or(0);
+                let mut axis = [0.0; 3];
+                axis[k] = 1.0;
+                let u = unit(math::cross(d, axis))?;
+                let v = unit(math::cross(d, u))?;
+                vec![u, v]
+            }
+        })
+    }
+}
+
+fn clean(v: Vec3) -> Vec3 {
+    v.map(|x| if x.abs() < 1e-12 { 0.0 } else { x })
+}
+
+impl IonicConstraints {
+    /// Builds constraints restricting every atom picked by `selector` to `motion`,
+    /// with one constraint per atom and removed direction.
+    pub fn from_selection(
+        doc: &CellDocument,
+        selector: &AtomSelector,
+        motion: IonMotion,
+    ) -> CResult<Self> {
+        let mut constraints = IonicConstraints {
+            constraints: Vec::new(),
+        };
+        constraints.add_selection(doc, selector, motion)?;
+        Ok(constraints)
+    }
+
+    /// Appends constraints restricting every atom picked by `selector` to `motion`.
+    ///
+    /// New constraints are numbered after the highest constraint number already in the
+    /// block, and ions are numbered within their species in positions order. Rows that
+    /// an ion's existing single-ion constraints already imply are skipped, so overlapping
+    /// selections never produce linearly dependent constraints.
+    pub fn add_selection(
+        &mut self,
+        doc: &CellDocument,
+        selector: &AtomSelector,
+        motion: IonMotion,
+    ) -> CResult<()> {
+        let rows = motion.coefficients()?;
+        let atoms = selector.select(doc)?;
+        let index = doc.ion_index();
+        let mut entries: HashMap<u32, usize> = HashMap::new();
+        for c in &self.constraints {
+            *entries.entry(c.constraint_number).or_insert(0) += 1;
+        }
+        // Orthonormal basis of the directions each ion is already constrained in.
+        let mut spans: Vec<Vec<Vec3>> = vec![Vec::new(); index.len()];
+        for c in &self.constraints {
+            if entries[&c.constraint_number] == 1
+                && let Some(i) = index.index(&c.species, c.ion_number)
+            {
+                extend_span(&mut spans[i], c.coefficients);
+            }
+        }
+        let mut number = self
+            .constraints
+            .iter()
+            .map(|c| c.constraint_number)
+            .max()
+            .unwrap_or(0);
+        for i in atoms {
+            for coefficients in &rows {
+                if !extend_span(&mut spans[i], *coefficients) {
+                    continue;
+                }
+                number += 1;
+                self.constraints.push(IonicConstraintEntry {
+                    constraint_number: number,
+                    species: index.species(i).clone(),
+                    ion_number: index.ion_number(i),
+                    coefficients: *coefficients,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds `row` to the orthonormal basis `span` unless it already lies in it; returns
+/// whether it was added.
+pub(crate) fn extend_span(span: &mut Vec<Vec3>, row: Vec3) -> bool {
+    let residual = span.iter().fold(row, |r, u| {
+        math::sub(r, math::scale(*u, math::dot(r, *u)))
+    });
+    let length = math::norm(residual);
+    if length <= 1e-6 * math::norm(row).max(1e-12) {
+        return false;
+    }
+    span.push(math::scale(residual, 1.0 / length));
+    true
+}
+
 impl FromBlock for IonicConstraints {
     const BLOCK_NAME: &'static str = "IONIC_CONSTRAINTS";
 
@@ -332,5 +537,131 @@ mod tests {
         assert_eq!(constraints.constraints[2].constraint_number, 3);
         assert_eq!(constraints.constraints[2].species, Species::Symbol("C".to_string()));
     }
-}
 
+    fn slab() -> CellDocument {
+        use crate::test_fixtures::{cell, frac_atoms, orthorhombic_lattice};
+        cell(
+            orthorhombic_lattice(5.0, 5.0, 20.0),
+            frac_atoms(&[
+                ("Si", [0.0, 0.5, 0.05]),
+                ("O", [0.0, 0.5, 0.1]),
+                ("Si", [0.0, 0.5, 0.15]),
+                ("Si", [0.0, 0.5, 0.3]),
+            ]),
+        )
+    }
+
+    #[test]
+    fn test_fix_bottom_layers() {
+        let doc = slab();
+        let selector = AtomSelector::BelowHeight(3.5);
+        let constraints =
+            IonicConstraints::from_selection(&doc, &selector, IonMotion::Fixed).unwrap();
+        assert_eq!(constraints.constraints.len(), 9);
+        let numbers = constraints
+            .constraints
+            .iter()
+            .map(|c| c.constraint_number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, (1..=9).collect::<Vec<_>>());
+        let ions = constraints
+            .constraints
+            .iter()
+            .step_by(3)
+            .map(|c| (c.species.to_string(), c.ion_number))
+            .collect::<Vec<_>>();
+        assert_eq!(ions, vec![("Si".into(), 1), ("O".into(), 1), ("Si".into(), 2)]);
+        assert_eq!(constraints.constraints[2].coefficients, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_plane_and_line_selections() {
+        let doc = slab();
+        let mut constraints = IonicConstraints::from_selection(
+            &doc,
+            &AtomSelector::Species(vec![Species::Symbol("O".into())]),
+            IonMotion::Plane([0.0, 0.0, 2.0]),
+        )
+        .unwrap();
+        assert_eq!(constraints.constraints.len(), 1);
+        assert_eq!(constraints.constraints[0].coefficients, [0.0, 0.0, 1.0]);
+
+        constraints
+            .add_selection(
+                &doc,
+                &AtomSelector::FracRegion {
+                    min: [0.0, 0.0, 0.2],
+                    max: [1.0, 1.0, 1.0],
+                },
+                IonMotion::Line([0.0, 0.0, 1.0]),
+            )
+            .unwrap();
+        assert_eq!(constraints.constraints.len(), 3);
+        for entry in &constraints.constraints[1..] {
+            assert_eq!(entry.species, Species::Symbol("Si".into()));
+            assert_eq!(entry.ion_number, 3);
+            assert_eq!(entry.coefficients[2], 0.0);
+        }
+        assert_eq!(constraints.constraints[2].constraint_number, 3);
+
+        let cart = AtomSelector::CartRegion {
+            min: [-1.0, 0.0, 0.0],
+            max: [1.0, 5.0, 2.5],
+        };
+        assert_eq!(cart.select(&doc).unwrap(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_overlapping_selections() {
+        let doc = slab();
+        let bottom = AtomSelector::BelowHeight(2.5);
+        let mut constraints =
+            IonicConstraints::from_selection(&doc, &bottom, IonMotion::Plane([0.0, 0.0, 1.0]))
+                .unwrap();
+        assert_eq!(constraints.constraints.len(), 2);
+
+        // Fixing the same atoms only adds the in-plane rows.
+        constraints
+            .add_selection(&doc, &bottom, IonMotion::Fixed)
+            .unwrap();
+        assert_eq!(constraints.constraints.len(), 6);
+        assert!(
+            constraints.constraints[2..]
+                .iter()
+                .all(|c| c.coefficients[2] == 0.0)
+        );
+        // Everything is already fixed, and repeating a selection adds nothing.
+        constraints
+            .add_selection(&doc, &bottom, IonMotion::Line([1.0, 1.0, 0.0]))
+            .unwrap();
+        constraints
+            .add_selection(&doc, &bottom, IonMotion::Fixed)
+            .unwrap();
+        assert_eq!(constraints.constraints.len(), 6);
+        let numbers = constraints
+            .constraints
+            .iter()
+            .map(|c| c.constraint_number)
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, (1..=6).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_selection_errors() {
+        let doc = slab();
+        assert!(AtomSelector::Indices(vec![4]).select(&doc).is_err());
+        assert!(
+            AtomSelector::Species(vec![Species::Symbol("Fe".into())])
+                .select(&doc)
+                .is_err()
+        );
+        assert!(
+            IonicConstraints::from_selection(
+                &doc,
+                &AtomSelector::Indices(vec![0]),
+                IonMotion::Line([0.0; 3])
+            )
+            .is_err()
+        );
+    }
+}
@@ -11,7 +11,8 @@ pub use fix_all_ions::FixAllIons;
 pub use fix_com::FixCOM;
 pub use cell_constraints::CellConstraints;
 pub use fix_vol::FixVOL;
-pub use ionic_constraints::{IonicConstraintEntry, IonicConstraints};
+pub use ionic_constraints::{AtomSelector, IonMotion, IonicConstraintEntry, IonicConstraints};
+pub(crate) use ionic_constraints::extend_span;
 pub use nonlinear_constraints::{
     AtomSite, ConstraintType, NonlinearConstraint, NonlinearConstraints,
 };
@@ -1,4 +1,6 @@
+use crate::{CellDocument, IonIndex};
 use crate::cell::species::Species;
+use crate::geometry::check_indices;
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, Error, query::value_as_str, query::value_as_i32};
 
 /// Represents a specific atom site, including its species, index within that species,
@@ -85,6 +87,124 @@ pub struct NonlinearConstraint {
     pub atom_sites: Vec<AtomSite>,
 }
 
+impl NonlinearConstraint {
+    /// Constrains the distance between atoms `a` and `b`, given as indices into the
+    /// positions block of `doc`, using the image of `b` nearest to `a`.
+    ///
+    /// Returns the constraint and the current distance in Å.
+    pub fn distance(doc: &CellDocument, [a, b]: [usize; 2]) -> CResult<(Self, f64)> {
+        check_indices(doc.positions.len(), &[a, b])?;
+        let image_b = doc.nearest_image(a, b)?;
+        from_sites(doc, ConstraintType::Distance, &[(a, [0; 3]), (b, image_b)])
+    }
+
+    /// Constrains the angle `a`–`b`–`c` at atom `b`, using the images of `a` and `c`
+    /// nearest to `b`.
+    ///
+    /// Returns the constraint and the current angle in degrees.
+    pub fn bend(doc: &CellDocument, [a, b, c]: [usize; 3]) -> CResult<(Self, f64)> {
+        check_indices(doc.positions.len(), &[a, b, c])?;
+        let sites = [
+            (a, doc.nearest_image(b, a)?),
+            (b, [0; 3]),
+            (c, doc.nearest_image(b, c)?),
+        ];
+        from_sites(doc, ConstraintType::Bend, &sites)
+    }
+
+    /// Constrains the torsion angle of the chain `a`–`b`–`c`–`d`, taking each atom at
+    /// the image nearest to its neighbour in the chain.
+    ///
+    /// Returns the constraint and the current dihedral angle in degrees.
+    pub fn torsion(doc: &CellDocument, [a, b, c, d]: [usize; 4]) -> CResult<(Self, f64)> {
+        check_indices(doc.positions.len(), &[a, b, c, d])?;
+        let image_c = doc.nearest_image(b, c)?;
+        let step = doc.nearest_image(c, d)?;
+        let image_d = [0, 1, 2].map(|k| image_c[k] + step[k]);
+        let sites = [
+            (a, doc.nearest_image(b, a)?),
+            (b, [0; 3]),
+            (c, image_c),
+            (d, image_d),
+        ];
+        from_sites(doc, ConstraintType::Torsion, &sites)
+    }
+
+    /// Returns the current value of the constrained coordinate in `doc`: a distance in
+    /// Å, or an angle in degrees.
+    ///
+    /// Fails if a site refers to an ion that does not exist, if the number of sites
+    /// does not match the constraint type, or if two sites coincide.
+    pub fn value(&self, doc: &CellDocument) -> CResult<f64> {
+        self.value_in(doc, &doc.ion_index())
+    }
+
+    /// [`value`](Self::value) with the ion index of `doc` already built.
+    fn value_in(&self, doc: &CellDocument, index: &IonIndex) -> CResult<f64> {
+        let expected = match self.constraint_type {
+            ConstraintType::Distance => 2,
+            ConstraintType::Bend => 3,
+            ConstraintType::Torsion => 4,
+        };
+        if self.atom_sites.len() != expected {
+            return Err(Error::Message(format!(
+                "{:?} constraint needs {expected} atoms, found {}",
+                self.constraint_type,
+                self.atom_sites.len()
+            )));
+        }
+        let sites = self
+            .atom_sites
+            .iter()
+            .map(|site| {
+                index
+                    .resolve(&site.species, site.ion_number)
+                    .map(|i| (i, site.image_indices))
+            })
+            .collect::<CResult<Vec<_>>>()?;
+        for (k, &(i, image)) in sites.iter().enumerate() {
+            for &(j, other) in &sites[k + 1..] {
+                let shift = [0, 1, 2].map(|n| other[n] - image[n]);
+                if doc.distance(i, j, shift)? < 1e-8 {
+                    return Err(Error::Message("nonlinear constraint sites coincide".into()));
+                }
+            }
+        }
+        let relative = |(j, image): (usize, [i32; 3]), (i, origin): (usize, [i32; 3])| {
+            (j, [0, 1, 2].map(|n| image[n] - origin[n]), i)
+        };
+        Ok(match self.constraint_type {
+            ConstraintType::Distance => {
+                let (j, image, i) = relative(sites[1], sites[0]);
+                doc.distance(i, j, image)?
+            }
+            ConstraintType::Bend => {
+                let (a, image_a, b) = relative(sites[0], sites[1]);
+                let (c, image_c, _) = relative(sites[2], sites[1]);
+                doc.angle(b, (a, image_a), (c, image_c))?
+            }
+            ConstraintType::Torsion => doc.torsion(sites[0], sites[1], sites[2], sites[3])?,
+        })
+    }
+}
+
+fn from_sites(
+    doc: &CellDocument,
+    constraint_type: ConstraintType,
+    sites: &[(usize, [i32; 3])],
+) -> CResult<(NonlinearConstraint, f64)> {
+    let index = doc.ion_index();
+    let constraint = NonlinearConstraint {
+        constraint_type,
+        atom_sites: sites
+            .iter()
+            .map(|&(i, image)| AtomSite::new(index.species(i).clone(), index.ion_number(i), image))
+            .collect(),
+    };
+    let value = constraint.value_in(doc, &index)?;
+    Ok((constraint, value))
+}
+
 impl FromCellValue for NonlinearConstraint {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value {
@@ -161,6 +281,16 @@ pub struct NonlinearConstraints {
     pub constraints: Vec<NonlinearConstraint>,
 }
 
+impl NonlinearConstraints {
+    /// Checks every constraint against `doc`, see [`NonlinearConstraint::value`].
+    pub fn validate(&self, doc: &CellDocument) -> CResult<()> {
+        let index = doc.ion_index();
+        self.constraints
+            .iter()
+            .try_for_each(|c| c.value_in(doc, &index).map(|_| ()))
+    }
+}
+
 impl FromBlock for NonlinearConstraints {
     const BLOCK_NAME: &'static str = "NONLINEAR_CONSTRAINTS";
 
@@ -561,5 +691,65 @@ mod tests {
         assert_eq!(constraints.constraints[1].constraint_type, ConstraintType::Torsion);
         assert_eq!(constraints.constraints[1].atom_sites.len(), 4);
     }
+
+    fn water_doc() -> crate::CellDocument {
+        crate::test_fixtures::cubic_cell(
+            10.0,
+            &[
+                ("O", [0.02, 0.5, 0.5]),
+                ("H", [0.92, 0.5, 0.5]),
+                ("H", [0.02, 0.6, 0.5]),
+                ("H", [0.92, 0.5, 0.6]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_builders_from_geometry() {
+        let doc = water_doc();
+        let (distance, value) = NonlinearConstraint::distance(&doc, [0, 1]).unwrap();
+        assert!((value - 1.0).abs() < 1e-10);
+        assert_eq!(distance.atom_sites[1].species, Species::Symbol("H".into()));
+        assert_eq!(distance.atom_sites[1].ion_number, 1);
+        assert_eq!(distance.atom_sites[1].image_indices, [-1, 0, 0]);
+
+        let (bend, angle) = NonlinearConstraint::bend(&doc, [1, 0, 2]).unwrap();
+        assert!((angle - 90.0).abs() < 1e-10);
+        assert_eq!(bend.atom_sites[0].image_indices, [-1, 0, 0]);
+        assert_eq!(bend.atom_sites[2].ion_number, 2);
+
+        let (torsion, dihedral) = NonlinearConstraint::torsion(&doc, [2, 0, 1, 3]).unwrap();
+        assert!((dihedral.abs() - 90.0).abs() < 1e-10);
+        assert_eq!(torsion.atom_sites[3].image_indices, [-1, 0, 0]);
+        assert!((torsion.value(&doc).unwrap() - dihedral).abs() < 1e-12);
+
+        let block = NonlinearConstraints {
+            constraints: vec![distance, bend, torsion],
+        };
+        assert!(block.validate(&doc).is_ok());
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let doc = water_doc();
+        assert!(NonlinearConstraint::distance(&doc, [0, 4]).is_err());
+        assert!(NonlinearConstraint::distance(&doc, [1, 1]).is_err());
+
+        let missing = NonlinearConstraint {
+            constraint_type: ConstraintType::Distance,
+            atom_sites: vec![
+                AtomSite::new(Species::Symbol("O".into()), 1, [0, 0, 0]),
+                AtomSite::new(Species::Symbol("O".into()), 2, [0, 0, 0]),
+            ],
+        };
+        let err = missing.value(&doc).unwrap_err().to_string();
+        assert!(err.contains("O 2"), "{err}");
+
+        let short = NonlinearConstraint {
+            constraint_type: ConstraintType::Bend,
+            atom_sites: missing.atom_sites[..1].to_vec(),
+        };
+        assert!(short.value(&doc).is_err());
+    }
 }
 
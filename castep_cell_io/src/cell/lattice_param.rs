@@ -20,6 +20,14 @@ pub struct LatticeCart {
     pub c: [f64; 3],
 }
 
+impl LatticeCart {
+    /// Returns the lattice vectors `[a, b, c]` as rows, converted to Å.
+    pub fn vectors(&self) -> [[f64; 3]; 3] {
+        let f = self.unit.unwrap_or_default().in_angstrom();
+        [self.a, self.b, self.c].map(|v| v.map(|x| x * f))
+    }
+}
+
 impl FromBlock for LatticeCart {
     const BLOCK_NAME: &'static str = "LATTICE_CART";
 
@@ -76,6 +84,25 @@ pub struct LatticeABC {
     pub angles: [f64; 3],
 }
 
+impl LatticeABC {
+    /// Returns the Cartesian lattice vectors `[a, b, c]` as rows, converted to Å.
+    ///
+    /// Follows the CASTEP orientation: `a` along x and `b` in the xy-plane.
+    pub fn vectors(&self) -> [[f64; 3]; 3] {
+        let f = self.unit.unwrap_or_default().in_angstrom();
+        let [a, b, c] = self.abc.map(|x| x * f);
+        let [alpha, beta, gamma] = self.angles.map(f64::to_radians);
+        let (cos_a, cos_b, cos_g, sin_g) = (alpha.cos(), beta.cos(), gamma.cos(), gamma.sin());
+        let cy = (cos_a - cos_b * cos_g) / sin_g;
+        let cz = (1.0 - cos_b * cos_b - cy * cy).max(0.0).sqrt();
+        [
+            [a, 0.0, 0.0],
+            [b * cos_g, b * sin_g, 0.0],
+            [c * cos_b, c * cy, c * cz],
+        ]
+    }
+}
+
 impl FromBlock for LatticeABC {
     const BLOCK_NAME: &'static str = "LATTICE_ABC";
 
@@ -337,5 +364,29 @@ mod tests {
         assert_eq!(lattice.abc, [10.0, 10.0, 10.0]);
         assert_eq!(lattice.angles, [120.0, 90.0, 90.0]);
     }
-}
 
+    #[test]
+    fn test_lattice_cart_vectors_in_angstrom() {
+        let lattice = LatticeCart::builder()
+            .unit(LengthUnit::Nanometer)
+            .a([0.5, 0.0, 0.0])
+            .b([0.0, 0.5, 0.0])
+            .c([0.0, 0.0, 0.5])
+            .build();
+        assert_eq!(lattice.vectors()[0], [5.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_lattice_abc_vectors_hexagonal() {
+        let lattice = LatticeABC::builder()
+            .abc([3.0, 3.0, 5.0])
+            .angles([90.0, 90.0, 120.0])
+            .build();
+        let [a, b, c] = lattice.vectors();
+        assert_eq!(a, [3.0, 0.0, 0.0]);
+        assert!((b[0] + 1.5).abs() < 1e-12);
+        assert!((b[1] - 1.5 * 3f64.sqrt()).abs() < 1e-12);
+        assert!(c[0].abs() < 1e-12 && c[1].abs() < 1e-12);
+        assert!((c[2] - 5.0).abs() < 1e-12);
+    }
+}
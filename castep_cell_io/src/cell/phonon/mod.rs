@@ -13,6 +13,7 @@ mod phonon_fine_kpoints_mp_grid;
 mod phonon_fine_kpoints_mp_offset;
 mod phonon_fine_kpoint_path_spacing;
 mod phonon_supercell_matrix;
+mod supercell_search;
 pub use phonon_kpoints_mp_grid::PhononKpointsMpGrid;
 pub use phonon_kpoints_mp_offset::PhononKpointsMpOffset;
 pub use phonon_kpoints_mp_spacing::PhononKpointsMpSpacing;
@@ -30,3 +31,4 @@ pub use phonon_fine_kpoints_mp_grid::PhononFineKpointsMpGrid;
 pub use phonon_fine_kpoints_mp_offset::PhononFineKpointsMpOffset;
 pub use phonon_fine_kpoint_path_spacing::PhononFineKpointPathSpacing;
 pub use phonon_supercell_matrix::PhononSupercellMatrix;
+pub use supercell_search::{PhononSupercell, PhononSupercellSuggestion, SupercellTarget};
@@ -1,13 +1,16 @@
 use castep_cell_fmt::{Cell, CellValue, CResult, FromBlock, FromCellValue, ToCell, ToCellValue};
 use super::phonon_kpoint_path::PhononKpointPathEntry;
+use crate::cell::bz_sampling_kpoints::{path_from_rows, path_segments, path_to_rows};
 
 /// Represents the PHONON_FINE_KPOINT_PATH block.
 ///
 /// Contains a list of q-vectors that define a fine path through reciprocal space for phonon calculations.
 /// Format:
 /// %BLOCK PHONON_FINE_KPOINT_PATH
-/// R1i R1j R1k
+/// R1i R1j R1k ! label
 /// R2i R2j R2k
+/// BREAK
+/// R3i R3j R3k
 /// ...
 /// %ENDBLOCK PHONON_FINE_KPOINT_PATH
 #[derive(Debug, Clone, PartialEq, bon::Builder)]
@@ -15,30 +18,32 @@ pub struct PhononFineKpointPath {
     /// The list of q-vector entries.
     #[builder(default)]
     pub points: Vec<PhononKpointPathEntry>,
+    /// Indices of the points that start a new segment, written as `BREAK` lines.
+    #[builder(default)]
+    pub breaks: Vec<usize>,
 }
 
 impl FromBlock for PhononFineKpointPath {
     const BLOCK_NAME: &'static str = "PHONON_FINE_KPOINT_PATH";
     const BLOCK_ALIASES: &'static [&'static str] = &["PHONON_FINE_KPOINTS_PATH"];
+    const KEEP_COMMENTS: bool = true;
 
     fn from_block_rows(rows: &[CellValue<'_>]) -> CResult<Self> {
-        let points = rows
-            .iter()
-            .map(PhononKpointPathEntry::from_cell_value)
-            .collect::<CResult<Vec<_>>>()?;
-        Ok(PhononFineKpointPath { points })
+        let (points, breaks) = path_from_rows(rows, PhononKpointPathEntry::from_cell_value)?;
+        Ok(PhononFineKpointPath { points, breaks })
     }
 }
 
 impl ToCell for PhononFineKpointPath {
     fn to_cell(&self) -> Cell<'_> {
-        Cell::Block(
-            "PHONON_FINE_KPOINT_PATH",
-            self.points
-                .iter()
-                .map(|entry| entry.to_cell_value())
-                .collect::<Vec<CellValue>>(),
-        )
+        Cell::Block("PHONON_FINE_KPOINT_PATH", path_to_rows(&self.points, &self.breaks))
+    }
+}
+
+impl PhononFineKpointPath {
+    /// Returns the continuous runs of points between `BREAK` lines.
+    pub fn segments(&self) -> impl Iterator<Item = &[PhononKpointPathEntry]> {
+        path_segments(&self.points, &self.breaks)
     }
 }
 
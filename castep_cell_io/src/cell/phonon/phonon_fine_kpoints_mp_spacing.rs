@@ -3,6 +3,9 @@ use castep_cell_fmt::parse::{FromCellValue, FromKeyValue};
 use castep_cell_fmt::query::value_as_f64;
 use castep_cell_fmt::{CResult, Error};
 use crate::units::InvLengthUnit;
+use super::PhononFineKpointsMpGrid;
+use crate::Lattice;
+use crate::cell::bz_sampling_kpoints::grid_from_spacing;
 
 /// Specifies the spacing of fine k-points in the Monkhorst-Pack grid for phonon calculations.
 ///
@@ -15,6 +18,16 @@ pub struct PhononFineKpointsMpSpacing {
     pub unit: Option<InvLengthUnit>,
 }
 
+impl PhononFineKpointsMpSpacing {
+    /// Returns the `PHONON_FINE_KPOINT_MP_GRID` CASTEP derives from this spacing in `lattice`.
+    ///
+    /// Fails unless the spacing is positive and the lattice vectors are linearly
+    /// independent.
+    pub fn to_grid(&self, lattice: &Lattice) -> CResult<PhononFineKpointsMpGrid> {
+        grid_from_spacing(lattice, self.value, self.unit).map(PhononFineKpointsMpGrid)
+    }
+}
+
 impl FromCellValue for PhononFineKpointsMpSpacing {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value {
@@ -79,6 +92,7 @@ impl ToCellValue for PhononFineKpointsMpSpacing {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_fixtures::orthorhombic_lattice;
 
     #[test]
     fn test_phonon_fine_kpoints_mp_spacing_scalar() {
@@ -87,4 +101,17 @@ mod tests {
         assert_eq!(result.value, 0.15);
         assert_eq!(result.unit, None);
     }
+
+    #[test]
+    fn test_phonon_fine_kpoints_mp_spacing_to_grid() {
+        let lattice = orthorhombic_lattice(4.0, 6.0, 10.0);
+        let spacing = PhononFineKpointsMpSpacing {
+            value: 0.03,
+            unit: None,
+        };
+        assert_eq!(
+            spacing.to_grid(&lattice).unwrap(),
+            PhononFineKpointsMpGrid([9, 6, 4])
+        );
+    }
 }
@@ -1,5 +1,7 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromBlock, FromCellValue, CResult, query::value_as_f64};
 
+use crate::cell::bz_sampling_kpoints::KpointsList;
+
 #[derive(Debug, Clone, Copy, PartialEq, bon::Builder)]
 /// A line of block `PhononKpointList`
 /// The first three entries on a line are the fractional positions of the
@@ -58,6 +60,23 @@ pub struct PhononKpointList {
     pub kpoints: Vec<PhononKpointListEntry>,
 }
 
+impl From<KpointsList> for PhononKpointList {
+    /// Reuses the points and weights of a `KPOINTS_LIST`, e.g. one from
+    /// [`CellDocument::mp_kpoints_list`](crate::CellDocument::mp_kpoints_list).
+    fn from(list: KpointsList) -> Self {
+        PhononKpointList {
+            kpoints: list
+                .kpts
+                .into_iter()
+                .map(|k| PhononKpointListEntry {
+                    coord: k.coord,
+                    weight: k.weight,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl FromBlock for PhononKpointList {
     const BLOCK_NAME: &'static str = "PHONON_KPOINT_LIST";
     const BLOCK_ALIASES: &'static [&'static str] = &["PHONON_KPOINTS_LIST"];
@@ -276,4 +295,18 @@ mod tests {
         assert_eq!(list.kpoints[0].weight, 0.5);
         assert_eq!(list.kpoints[1].weight, 0.5);
     }
+
+    #[test]
+    fn test_phonon_kpoint_list_from_kpoints_list() {
+        let kpts = KpointsList {
+            kpts: vec![crate::cell::bz_sampling_kpoints::Kpoint {
+                coord: [0.25, 0.25, 0.25],
+                weight: 1.0,
+            }],
+        };
+        let list = PhononKpointList::from(kpts);
+        assert_eq!(list.kpoints.len(), 1);
+        assert_eq!(list.kpoints[0].coord, [0.25, 0.25, 0.25]);
+        assert_eq!(list.kpoints[0].weight, 1.0);
+    }
 }
@@ -1,83 +1,79 @@
-use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, query::value_as_f64};
+use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult};
+
+use crate::cell::bz_sampling_kpoints::{
+    path_from_rows, path_point_from_row, path_point_to_row, path_segments, path_to_rows,
+};
 
 /// Represents a single point entry within the PHONON_KPOINT_PATH block.
 ///
 /// Each entry contains three fractional q-vector coordinates relative to the reciprocal space lattice vectors.
-/// Format: <x> <y> <z>
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Format: <x> <y> <z> [! label]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PhononKpointPathEntry {
     /// Fractional q-vector coordinates [x, y, z].
     pub coord: [f64; 3],
+    /// Label written as a trailing comment, e.g. `X` in `0.5 0.0 0.5 ! X`.
+    pub label: Option<String>,
 }
 
 impl FromCellValue for PhononKpointPathEntry {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
-        match value {
-            CellValue::Array(arr) if arr.len() == 3 => {
-                let coord = [
-                    value_as_f64(&arr[0])?,
-                    value_as_f64(&arr[1])?,
-                    value_as_f64(&arr[2])?,
-                ];
-                Ok(PhononKpointPathEntry { coord })
-            }
-            _ => Err(castep_cell_fmt::Error::Message(
-                "PhononKpointPathEntry must be an array of 3 floats".into(),
-            )),
-        }
+        let (coord, label) = path_point_from_row(value).ok_or_else(|| {
+            castep_cell_fmt::Error::Message("PhononKpointPathEntry must be an array of 3 floats".into())
+        })?;
+        Ok(PhononKpointPathEntry { coord, label })
     }
 }
 
 impl ToCellValue for PhononKpointPathEntry {
     /// Converts the entry into a `CellValue::Array` representing one line of the block.
     fn to_cell_value(&self) -> CellValue<'_> {
-        CellValue::Array(
-            self.coord
-                .into_iter()
-                .map(CellValue::Float)
-                .collect(),
-        )
+        path_point_to_row(self.coord, self.label.as_deref())
     }
 }
 
 /// Represents the PHONON_KPOINT_PATH block.
 ///
 /// Contains a list of q-vectors that define a path through reciprocal space for phonon calculations.
+/// A `BREAK` line ends one segment of the path and starts the next.
 /// Format:
 /// %BLOCK PHONON_KPOINT_PATH
-/// R1i R1j R1k
+/// R1i R1j R1k ! label
 /// R2i R2j R2k
+/// BREAK
+/// R3i R3j R3k
 /// ...
 /// %ENDBLOCK PHONON_KPOINT_PATH
 #[derive(Debug, Clone, PartialEq)]
 pub struct PhononKpointPath {
     /// The list of q-vector entries.
     pub points: Vec<PhononKpointPathEntry>,
+    /// Indices of the points that start a new segment, written as `BREAK` lines.
+    pub breaks: Vec<usize>,
 }
 
 impl FromBlock for PhononKpointPath {
     const BLOCK_NAME: &'static str = "PHONON_KPOINT_PATH";
     const BLOCK_ALIASES: &'static [&'static str] = &["PHONON_KPOINTS_PATH"];
+    const KEEP_COMMENTS: bool = true;
 
     fn from_block_rows(rows: &[CellValue<'_>]) -> CResult<Self> {
-        let points = rows
-            .iter()
-            .map(PhononKpointPathEntry::from_cell_value)
-            .collect::<CResult<Vec<_>>>()?;
-        Ok(PhononKpointPath { points })
+        let (points, breaks) = path_from_rows(rows, PhononKpointPathEntry::from_cell_value)?;
+        Ok(PhononKpointPath { points, breaks })
     }
 }
 
 impl ToCell for PhononKpointPath {
     /// Converts the block into the intermediate `Cell` representation for serialization.
     fn to_cell(&self) -> Cell<'_> {
-        Cell::Block(
-            "PHONON_KPOINT_PATH",
-            self.points
-                .iter()
-                .map(|entry| entry.to_cell_value())
-                .collect::<Vec<CellValue>>(),
-        )
+        Cell::Block("PHONON_KPOINT_PATH", path_to_rows(&self.points, &self.breaks))
+    }
+}
+
+impl PhononKpointPath {
+    /// Returns the continuous runs of points between `BREAK` lines.
+    pub fn segments(&self) -> impl Iterator<Item = &[PhononKpointPathEntry]> {
+        path_segments(&self.points, &self.breaks)
     }
 }
 
@@ -122,6 +118,7 @@ mod tests {
     fn test_phonon_kpoint_path_entry_to_cell_value() {
         let entry = PhononKpointPathEntry {
             coord: [0.3333333333, 0.3750000000, 0.3333333333],
+            label: None,
         };
         let val = entry.to_cell_value();
         match val {
@@ -167,7 +164,9 @@ mod tests {
         let path = PhononKpointPath {
             points: vec![PhononKpointPathEntry {
                 coord: [0.3333333333, 0.3750000000, 0.3333333333],
+                label: None,
             }],
+            breaks: vec![],
         };
         let cell = path.to_cell();
         match cell {
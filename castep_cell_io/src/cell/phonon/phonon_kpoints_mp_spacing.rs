@@ -3,6 +3,9 @@ use castep_cell_fmt::parse::{FromCellValue, FromKeyValue};
 use castep_cell_fmt::query::value_as_f64;
 
 use crate::units::InvLengthUnit;
+use super::PhononKpointsMpGrid;
+use crate::Lattice;
+use crate::cell::bz_sampling_kpoints::grid_from_spacing;
 
 /// Specifies the spacing of k-points for the Monkhorst-Pack phonon grid.
 ///
@@ -20,6 +23,16 @@ pub struct PhononKpointsMpSpacing {
     pub unit: Option<InvLengthUnit>,
 }
 
+impl PhononKpointsMpSpacing {
+    /// Returns the `PHONON_KPOINT_MP_GRID` CASTEP derives from this spacing in `lattice`.
+    ///
+    /// Fails unless the spacing is positive and the lattice vectors are linearly
+    /// independent.
+    pub fn to_grid(&self, lattice: &Lattice) -> CResult<PhononKpointsMpGrid> {
+        grid_from_spacing(lattice, self.value, self.unit).map(PhononKpointsMpGrid)
+    }
+}
+
 impl FromCellValue for PhononKpointsMpSpacing {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value {
@@ -1,5 +1,7 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, parse::FromBlock, CResult, Error, query::row_as_i32_n};
 
+use crate::math;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, bon::Builder)]
 /// 3×3 integer matrix for phonon supercell
 /// %BLOCK PHONON_SUPERCELL_MATRIX
@@ -7,10 +9,23 @@ use castep_cell_fmt::{Cell, CellValue, ToCell, parse::FromBlock, CResult, Error,
 ///     m21 m22 m23
 ///     m31 m32 m33
 /// %ENDBLOCK PHONON_SUPERCELL_MATRIX
+///
+/// Like `LATTICE_CART`, each row is one supercell vector, here in fractional
+/// coordinates of the cell. [`CellDocument::transform`](crate::CellDocument::transform)
+/// takes the vectors as columns; use [`transform_matrix`](Self::transform_matrix) there.
 pub struct PhononSupercellMatrix {
     pub matrix: [[i32; 3]; 3],
 }
 
+impl PhononSupercellMatrix {
+    /// Returns the supercell vectors as columns, as taken by
+    /// [`CellDocument::transform`](crate::CellDocument::transform) and
+    /// [`MagneticOrderings::supercells`](crate::magnetism::MagneticOrderings::supercells).
+    pub fn transform_matrix(&self) -> [[i32; 3]; 3] {
+        math::itranspose(self.matrix)
+    }
+}
+
 impl FromBlock for PhononSupercellMatrix {
     const BLOCK_NAME: &'static str = "PHONON_SUPERCELL_MATRIX";
 
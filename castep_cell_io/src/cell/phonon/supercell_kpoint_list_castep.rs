@@ -1,5 +1,7 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromBlock, FromCellValue, CResult, query::value_as_f64};
 
+use crate::cell::bz_sampling_kpoints::KpointsList;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 /// A line of block `SupercellKpointListCastep`
 /// The first three entries on a line are the fractional positions of the
@@ -58,6 +60,23 @@ pub struct SupercellKpointListCastep {
     pub kpoints: Vec<SupercellKpointListCastepEntry>,
 }
 
+impl From<KpointsList> for SupercellKpointListCastep {
+    /// Reuses the points and weights of a `KPOINTS_LIST`, which must already be in the
+    /// reciprocal basis of the supercell.
+    fn from(list: KpointsList) -> Self {
+        SupercellKpointListCastep {
+            kpoints: list
+                .kpts
+                .into_iter()
+                .map(|k| SupercellKpointListCastepEntry {
+                    coord: k.coord,
+                    weight: k.weight,
+                })
+                .collect(),
+        }
+    }
+}
+
 impl FromBlock for SupercellKpointListCastep {
     const BLOCK_NAME: &'static str = "SUPERCELL_KPOINT_LIST_CASTEP";
     const BLOCK_ALIASES: &'static [&'static str] = &["SUPERCELL_KPOINTS_LIST_CASTEP"];
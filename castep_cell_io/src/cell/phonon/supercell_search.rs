@@ -0,0 +1,435 @@
+//! Choice of `PHONON_SUPERCELL_MATRIX` from a target image distance or atom count.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::CellDocument;
+use crate::math::{self, IMat3, Mat3, Vec3};
+
+use super::{PhononSupercellMatrix, SupercellKpointListCastep};
+
+/// Image distances closer than this, in Å, count as equal when ranking supercells.
+const DISTANCE_TOL: f64 = 1e-6;
+
+/// Largest number of copies of the cell searched for a diagonal supercell.
+const MAX_DIAGONAL_CELLS: u32 = 10_000;
+
+/// Largest number of copies of the cell searched for a non-diagonal supercell.
+const MAX_NONDIAGONAL_CELLS: u32 = 500;
+
+/// Requirement a suggested phonon supercell has to meet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SupercellTarget {
+    /// The smallest supercell in which the periodic images of every atom are at least
+    /// this far apart, in Å.
+    MinImageDistance(f64),
+    /// The supercell with the largest image distance holding at most this many atoms.
+    MaxAtoms(usize),
+}
+
+/// A `PHONON_SUPERCELL_MATRIX` with the size and k-point sampling of its supercell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhononSupercell {
+    /// Supercell vectors as rows, in fractional coordinates of the cell; see
+    /// [`PhononSupercellMatrix::transform_matrix`] for building the supercell.
+    pub matrix: PhononSupercellMatrix,
+    /// Number of copies of the cell, `det(matrix)`.
+    pub cells: u32,
+    /// Number of atoms in the supercell.
+    pub atoms: usize,
+    /// Shortest distance between periodic images of an atom, in Å.
+    pub image_distance: f64,
+    /// The cell's Monkhorst-Pack set folded into the Brillouin zone of the supercell.
+    pub kpoints: SupercellKpointListCastep,
+}
+
+/// Result of [`CellDocument::suggest_phonon_supercell`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhononSupercellSuggestion {
+    /// Best supercell with a diagonal matrix.
+    pub diagonal: PhononSupercell,
+    /// Best supercell over integer matrices of up to 500 copies of the cell, at least as
+    /// good as `diagonal`.
+    pub nondiagonal: PhononSupercell,
+}
+
+impl CellDocument {
+    /// Searches for the phonon supercells that best meet `target`.
+    ///
+    /// The image distance of a supercell is the length of its shortest lattice vector.
+    /// For [`SupercellTarget::MinImageDistance`] the fewest copies of the cell win and
+    /// ties go to the larger image distance; for [`SupercellTarget::MaxAtoms`] the
+    /// largest image distance wins and ties go to the fewer copies. Non-diagonal
+    /// candidates are enumerated as Hermite normal forms of at most 500 copies of the
+    /// cell, beyond which the diagonal supercell is kept, and the winner is returned
+    /// Niggli-reduced. Sizes and partial matrices whose image distance is bounded below
+    /// the distance to beat (the target, or the best supercell found so far) are
+    /// skipped.
+    ///
+    /// The k-points are the full Monkhorst-Pack set of `KPOINT_MP_GRID` (or of the grid
+    /// derived from `KPOINT_MP_SPACING`) shifted by `KPOINT_MP_OFFSET`, folded into the
+    /// supercell so that both calculations sample the same points.
+    ///
+    /// Fails if the target allows supercells of more than 10 000 copies of the cell,
+    /// which are too many to enumerate.
+    pub fn suggest_phonon_supercell(
+        &self,
+        target: SupercellTarget,
+    ) -> CResult<PhononSupercellSuggestion> {
+        let a = self.lattice.vectors();
+        let max_cells = match target {
+            SupercellTarget::MinImageDistance(r) => {
+                if r <= 0.0 || !r.is_finite() {
+                    return Err(Error::Message(format!(
+                        "image distance must be positive, got {r}"
+                    )));
+                }
+                // A diagonal supercell at least `r` thick along every axis always works.
+                self.lattice
+                    .reciprocal_vectors()?
+                    .iter()
+                    .map(|b| ((r * math::norm(*b) - 1e-8).ceil() as u32).max(1))
+                    .try_fold(1u32, u32::checked_mul)
+                    .filter(|&cells| cells <= MAX_DIAGONAL_CELLS)
+                    .ok_or_else(|| {
+                        Error::Message(format!(
+                            "an image distance of {r} Å needs more than \
+                             {MAX_DIAGONAL_CELLS} copies of the cell"
+                        ))
+                    })?
+            }
+            SupercellTarget::MaxAtoms(n) => {
+                let cells = n / self.positions.len().max(1);
+                if cells == 0 {
+                    return Err(Error::Message(format!(
+                        "{n} atoms do not fit a single cell of {} atoms",
+                        self.positions.len()
+                    )));
+                }
+                u32::try_from(cells)
+                    .ok()
+                    .filter(|&cells| cells <= MAX_DIAGONAL_CELLS)
+                    .ok_or_else(|| {
+                        Error::Message(format!(
+                            "{n} atoms allow more than {MAX_DIAGONAL_CELLS} copies of the cell"
+                        ))
+                    })?
+            }
+        };
+
+        let diagonal = best(a, target, diagonal_matrices(max_cells))
+            .ok_or_else(|| Error::Message("no diagonal supercell meets the target".into()))?;
+        // Under a distance target no larger cell can beat the diagonal one.
+        let bound = match target {
+            SupercellTarget::MinImageDistance(_) => diagonal.1,
+            SupercellTarget::MaxAtoms(_) => max_cells,
+        }
+        .min(MAX_NONDIAGONAL_CELLS);
+        // A distance target takes the first size that reaches it; an atom target visits
+        // the largest sizes first, whose image distances prune most smaller ones.
+        let sizes: Vec<u32> = match target {
+            SupercellTarget::MinImageDistance(_) => (1..=bound).collect(),
+            SupercellTarget::MaxAtoms(_) => (1..=bound).rev().collect(),
+        };
+        let mut nondiagonal = diagonal;
+        for n in sizes {
+            let floor = match target {
+                SupercellTarget::MinImageDistance(r) => r,
+                SupercellTarget::MaxAtoms(_) => nondiagonal.2,
+            } - DISTANCE_TOL;
+            // By Hermite's bound no sublattice of index n has a shortest vector longer
+            // than (√2 n V)^(1/3).
+            let volume = f64::from(n) * math::det(a).abs();
+            if (2.0_f64.sqrt() * volume).cbrt() < floor {
+                continue;
+            }
+            if let Some(candidate) = best(a, target, hermite_normal_forms(a, n, floor)) {
+                if better(target, &candidate, &nondiagonal) {
+                    nondiagonal = candidate;
+                }
+                // Sizes are visited in increasing order, so the first hit is the smallest.
+                if matches!(target, SupercellTarget::MinImageDistance(_)) {
+                    break;
+                }
+            }
+        }
+        let nondiagonal = (
+            niggli_reduced(a, nondiagonal.0),
+            nondiagonal.1,
+            nondiagonal.2,
+        );
+
+        let kpoints = self.mp_grid()?.kpoints(self.kpoints_mp_offset);
+        let supercell = |(matrix, cells, image_distance): Candidate| {
+            let matrix = PhononSupercellMatrix { matrix };
+            PhononSupercell {
+                matrix,
+                cells,
+                atoms: self.positions.len() * cells as usize,
+                image_distance,
+                kpoints: kpoints.fold(matrix.transform_matrix()).into(),
+            }
+        };
+        Ok(PhononSupercellSuggestion {
+            diagonal: supercell(diagonal),
+            nondiagonal: supercell(nondiagonal),
+        })
+    }
+}
+
+/// Supercell matrix, number of cells and image distance.
+type Candidate = (IMat3, u32, f64);
+
+/// Returns the best candidate among `matrices` that meets `target`.
+fn best(
+    a: Mat3,
+    target: SupercellTarget,
+    matrices: impl Iterator<Item = IMat3>,
+) -> Option<Candidate> {
+    matrices
+        .map(|m| {
+            let cells = math::imat_det(m).unsigned_abs();
+            (m, cells, image_distance(math::mat_mul(math::to_f64(m), a)))
+        })
+        .filter(|c| match target {
+            SupercellTarget::MinImageDistance(r) => c.2 >= r - DISTANCE_TOL,
+            SupercellTarget::MaxAtoms(_) => true,
+        })
+        .reduce(|best, candidate| {
+            if better(target, &candidate, &best) {
+                candidate
+            } else {
+                best
+            }
+        })
+}
+
+/// Whether `candidate` ranks above `best` for `target`.
+fn better(target: SupercellTarget, candidate: &Candidate, best: &Candidate) -> bool {
+    let farther = candidate.2 > best.2 + DISTANCE_TOL;
+    let level = (candidate.2 - best.2).abs() <= DISTANCE_TOL;
+    match target {
+        SupercellTarget::MinImageDistance(_) => {
+            candidate.1 < best.1 || (candidate.1 == best.1 && farther)
+        }
+        SupercellTarget::MaxAtoms(_) => farther || (level && candidate.1 < best.1),
+    }
+}
+
+/// Diagonal matrices with at most `max_cells` cells.
+fn diagonal_matrices(max_cells: u32) -> impl Iterator<Item = IMat3> {
+    (1..=max_cells).flat_map(move |i| {
+        (1..=max_cells / i).flat_map(move |j| {
+            (1..=max_cells / (i * j)).map(move |k| {
+                let [i, j, k] = [i, j, k].map(|n| n as i32);
+                [[i, 0, 0], [0, j, 0], [0, 0, k]]
+            })
+        })
+    })
+}
+
+/// Upper-triangular Hermite normal forms with determinant `n`, one per sublattice of
+/// index `n` of the lattice with rows `lattice`.
+///
+/// Forms holding a vector shorter than `floor` Å cannot reach that image distance and
+/// are skipped: the last two rows are checked as a plane lattice first, then the first
+/// three multiples of the first row against their nearest points in that plane.
+fn hermite_normal_forms(lattice: Mat3, n: u32, floor: f64) -> impl Iterator<Item = IMat3> {
+    let n = n as i32;
+    let row = move |m: [i32; 3]| math::vec_mat(m.map(f64::from), lattice);
+    (1..=n).filter(move |a| n % a == 0).flat_map(move |a| {
+        (1..=n / a)
+            .filter(move |d| (n / a) % d == 0)
+            .flat_map(move |d| {
+                let f = n / (a * d);
+                (0..f)
+                    .map(move |e| (e, reduce_plane(row([0, d, e]), row([0, 0, f]))))
+                    .filter(move |(_, plane)| math::norm(plane.0) >= floor)
+                    .flat_map(move |(e, plane)| {
+                        (0..d)
+                            .flat_map(move |b| (0..f).map(move |c| [a, b, c]))
+                            .filter(move |&first| {
+                                (1..=3).all(|k| {
+                                    let w = math::scale(row(first), f64::from(k));
+                                    distance_to_plane_lattice(w, plane) >= floor
+                                })
+                            })
+                            .map(move |first| [first, [0, d, e], [0, 0, f]])
+                    })
+            })
+    })
+}
+
+/// Lagrange-Gauss reduced basis of the plane lattice spanned by `u` and `v`, shortest
+/// vector first.
+fn reduce_plane(mut u: Vec3, mut v: Vec3) -> (Vec3, Vec3) {
+    loop {
+        if math::norm(u) > math::norm(v) {
+            std::mem::swap(&mut u, &mut v);
+        }
+        let m = (math::dot(u, v) / math::dot(u, u)).round();
+        v = math::sub(v, math::scale(u, m));
+        if math::norm(v) >= math::norm(u) {
+            return (u, v);
+        }
+    }
+}
+
+/// Upper bound on the distance from `w` to the reduced plane lattice `(u, v)`: the
+/// shortest of `w - i·u - j·v` around the rounded projection of `w`.
+fn distance_to_plane_lattice(w: Vec3, (u, v): (Vec3, Vec3)) -> f64 {
+    let (uu, uv, vv) = (math::dot(u, u), math::dot(u, v), math::dot(v, v));
+    let (wu, wv) = (math::dot(w, u), math::dot(w, v));
+    let det = uu * vv - uv * uv;
+    let i0 = ((wu * vv - wv * uv) / det).round();
+    let j0 = ((wv * uu - wu * uv) / det).round();
+    let mut shortest = f64::INFINITY;
+    for i in [i0 - 1.0, i0, i0 + 1.0] {
+        for j in [j0 - 1.0, j0, j0 + 1.0] {
+            let r = math::sub(w, math::add(math::scale(u, i), math::scale(v, j)));
+            shortest = shortest.min(math::norm(r));
+        }
+    }
+    shortest
+}
+
+/// Length of the shortest lattice vector of the lattice with rows `vectors`.
+fn image_distance(vectors: Mat3) -> f64 {
+    let reduced = math::change_basis(vectors, niggli(vectors));
+    let mut shortest = f64::INFINITY;
+    for i in -1..=1 {
+        for j in -1..=1 {
+            for k in -1..=1 {
+                if (i, j, k) != (0, 0, 0) {
+                    let [i, j, k] = [i, j, k].map(f64::from);
+                    let v = math::add(
+                        math::add(math::scale(reduced[0], i), math::scale(reduced[1], j)),
+                        math::scale(reduced[2], k),
+                    );
+                    shortest = shortest.min(math::norm(v));
+                }
+            }
+        }
+    }
+    shortest
+}
+
+/// Rewrites the supercell matrix `m` so that its rows span the Niggli-reduced basis of
+/// the same supercell, keeping the determinant positive.
+fn niggli_reduced(a: Mat3, m: IMat3) -> IMat3 {
+    let p = niggli(math::mat_mul(math::to_f64(m), a));
+    let reduced = math::imat_mul(math::itranspose(p), m);
+    if math::imat_det(reduced) < 0 {
+        reduced.map(|row| row.map(|x| -x))
+    } else {
+        reduced
+    }
+}
+
+fn niggli(vectors: Mat3) -> IMat3 {
+    let eps = 1e-5 * math::det(vectors).abs().powf(2.0 / 3.0);
+    math::niggli_reduce(vectors, eps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::bz_sampling_kpoints::KpointsMpGrid;
+    use crate::test_fixtures::cubic_cell;
+
+    fn simple_cubic(a: f64) -> CellDocument {
+        CellDocument {
+            kpoints_mp_grid: Some(KpointsMpGrid([4, 4, 4])),
+            ..cubic_cell(a, &[("Po", [0.0; 3])])
+        }
+    }
+
+    #[test]
+    fn distance_target_prefers_fewest_cells() {
+        let suggestion = simple_cubic(3.0)
+            .suggest_phonon_supercell(SupercellTarget::MinImageDistance(5.1))
+            .unwrap();
+        assert_eq!(
+            suggestion.diagonal.matrix.matrix,
+            [[2, 0, 0], [0, 2, 0], [0, 0, 2]]
+        );
+        assert_eq!(suggestion.diagonal.cells, 8);
+        // A body-centred supercell of four cells already has images 3√3 Å apart; no
+        // sublattice of index 3 can, by Hermite's bound.
+        let nondiagonal = &suggestion.nondiagonal;
+        assert_eq!(nondiagonal.cells, 4);
+        assert!(nondiagonal.image_distance >= 5.1);
+        assert_eq!(math::imat_det(nondiagonal.matrix.matrix), 4);
+        assert_eq!(nondiagonal.atoms, 4);
+    }
+
+    #[test]
+    fn transform_builds_the_suggested_supercell() {
+        let doc = simple_cubic(3.0);
+        let suggestion = doc
+            .suggest_phonon_supercell(SupercellTarget::MinImageDistance(5.1))
+            .unwrap();
+        let nondiagonal = &suggestion.nondiagonal;
+        let supercell = doc
+            .transform(nondiagonal.matrix.transform_matrix())
+            .unwrap();
+        assert_eq!(supercell.positions.len(), nondiagonal.atoms);
+        let distance = image_distance(supercell.lattice.vectors());
+        assert!((distance - nondiagonal.image_distance).abs() < 1e-9);
+        // The rows of the block are the supercell vectors.
+        let rows = math::mat_mul(
+            math::to_f64(nondiagonal.matrix.matrix),
+            doc.lattice.vectors(),
+        );
+        let vectors = supercell.lattice.vectors();
+        assert!(
+            rows.iter()
+                .flatten()
+                .zip(vectors.iter().flatten())
+                .all(|(x, y)| (x - y).abs() < 1e-9)
+        );
+    }
+
+    #[test]
+    fn atom_target_maximises_distance() {
+        let suggestion = simple_cubic(3.0)
+            .suggest_phonon_supercell(SupercellTarget::MaxAtoms(30))
+            .unwrap();
+        assert_eq!(
+            suggestion.diagonal.matrix.matrix,
+            [[3, 0, 0], [0, 3, 0], [0, 0, 3]]
+        );
+        assert!((suggestion.diagonal.image_distance - 9.0).abs() < 1e-9);
+        assert!(suggestion.nondiagonal.image_distance >= 9.0 - 1e-9);
+        assert!(suggestion.nondiagonal.atoms <= 30);
+    }
+
+    #[test]
+    fn kpoints_are_folded_into_the_supercell() {
+        let suggestion = simple_cubic(3.0)
+            .suggest_phonon_supercell(SupercellTarget::MinImageDistance(6.0))
+            .unwrap();
+        // ±1/8 and ±3/8 along each axis double to 1/4 and 3/4.
+        let kpoints = &suggestion.diagonal.kpoints.kpoints;
+        assert_eq!(kpoints.len(), 8);
+        assert!(kpoints.iter().all(|k| (k.weight - 0.125).abs() < 1e-12));
+        assert!(
+            simple_cubic(3.0)
+                .suggest_phonon_supercell(SupercellTarget::MaxAtoms(0))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn oversized_targets_fail() {
+        let doc = simple_cubic(3.0);
+        let err = doc
+            .suggest_phonon_supercell(SupercellTarget::MinImageDistance(1e4))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("more than 10000 copies"), "{err}");
+        assert!(
+            doc.suggest_phonon_supercell(SupercellTarget::MaxAtoms(usize::MAX))
+                .is_err()
+        );
+    }
+}
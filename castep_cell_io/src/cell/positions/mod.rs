@@ -1,3 +1,6 @@
+mod qualifiers;
+pub use qualifiers::Spin;
+
 mod positions_frac;
 pub use positions_frac::PositionsFrac;
 pub use positions_frac::PositionFracEntry;
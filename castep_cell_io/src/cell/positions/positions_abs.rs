@@ -2,20 +2,22 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, query::value_as_f64, query::value_as_str};
 
 use crate::cell::species::Species;
+use super::qualifiers::{Spin, parse_qualifiers, push_qualifiers};
 use crate::units::LengthUnit;
 
 /// Represents a single atom entry within the POSITIONS_ABS block.
 ///
 /// Consists of the element symbol/number, absolute coordinates, and optional spin/mixture qualifiers.
-/// Format: <element> <x> <y> <z> [SPIN <value>] [MIXTURE <index> <weight>]
+/// Format: <element> <x> <y> <z> [SPIN <value>|<x> <y> <z>] [MIXTURE <index> <weight>]
 #[derive(Debug, Clone, PartialEq, bon::Builder)]
 pub struct PositionAbsEntry {
     /// The chemical element symbol (e.g., "Fe") or atomic number as a string (e.g., "26").
     pub species: Species,
     /// Absolute coordinates [x, y, z].
     pub coord: [f64; 3],
-    /// Optional initial spin polarization for the atom.
-    pub spin: Option<f64>,
+    /// Optional initial spin (`SPIN` or `MAGMOM` qualifier), scalar or vector.
+    #[builder(into)]
+    pub spin: Option<Spin>,
     /// Optional mixture specification: (index, weight) for disordered systems.
     pub mixture: Option<(u32, f64)>,
 }
@@ -31,48 +33,7 @@ impl FromCellValue for PositionAbsEntry {
                     value_as_f64(&arr[3])?,
                 ];
 
-                let mut spin = None;
-                let mut mixture = None;
-                let mut idx = 4;
-
-                // Parse optional qualifiers (SPIN, MIXTURE) in any order
-                while idx < arr.len() {
-                    let keyword = value_as_str(&arr[idx])?.to_ascii_uppercase();
-
-                    if keyword == "SPIN" || keyword == "SPIN=" {
-                        if idx + 1 < arr.len() {
-                            spin = Some(value_as_f64(&arr[idx + 1])?);
-                            idx += 2;
-                        } else {
-                            return Err(castep_cell_fmt::Error::Message(
-                                "SPIN qualifier requires a value".into(),
-                            ));
-                        }
-                    } else if keyword == "MIXTURE" || keyword == "MIXTURE=" {
-                        if idx + 2 < arr.len() {
-                            let mix_idx = match &arr[idx + 1] {
-                                CellValue::UInt(u) => *u,
-                                CellValue::Int(i) if *i >= 0 => *i as u32,
-                                _ => {
-                                    return Err(castep_cell_fmt::Error::Message(
-                                        "MIXTURE index must be a positive integer".into(),
-                                    ))
-                                }
-                            };
-                            let mix_weight = value_as_f64(&arr[idx + 2])?;
-                            mixture = Some((mix_idx, mix_weight));
-                            idx += 3;
-                        } else {
-                            return Err(castep_cell_fmt::Error::Message(
-                                "MIXTURE qualifier requires index and weight".into(),
-                            ));
-                        }
-                    } else {
-                        return Err(castep_cell_fmt::Error::Message(
-                            format!("unknown qualifier: {keyword}"),
-                        ));
-                    }
-                }
+                let (spin, mixture) = parse_qualifiers(&arr[4..])?;
 
                 Ok(PositionAbsEntry {
                     species,
@@ -94,18 +55,7 @@ impl ToCellValue for PositionAbsEntry {
         let mut arr = vec![self.species.to_cell_value()];
         arr.extend(self.coord.into_iter().map(CellValue::Float));
 
-        // Append SPIN qualifier if present
-        if let Some(spin) = self.spin {
-            arr.push(CellValue::String("SPIN=".to_string()));
-            arr.push(CellValue::Float(spin));
-        }
-
-        // Append MIXTURE qualifier if present
-        if let Some((mix_idx, mix_weight)) = self.mixture {
-            arr.push(CellValue::String("MIXTURE=".to_string()));
-            arr.push(CellValue::UInt(mix_idx));
-            arr.push(CellValue::Float(mix_weight));
-        }
+        push_qualifiers(&mut arr, self.spin, self.mixture);
 
         CellValue::Array(arr)
     }
@@ -206,7 +156,7 @@ mod tests {
         ]);
         let entry = PositionAbsEntry::from_cell_value(&val).unwrap();
         assert_eq!(entry.coord, [1.5, 2.5, 3.5]);
-        assert_eq!(entry.spin, Some(2.0));
+        assert_eq!(entry.spin, Some(Spin::Scalar(2.0)));
         assert!(entry.mixture.is_none());
     }
 
@@ -242,7 +192,7 @@ mod tests {
         ]);
         let entry = PositionAbsEntry::from_cell_value(&val).unwrap();
         assert_eq!(entry.coord, [1.0, 2.0, 3.0]);
-        assert_eq!(entry.spin, Some(1.5));
+        assert_eq!(entry.spin, Some(Spin::Scalar(1.5)));
         assert_eq!(entry.mixture, Some((2, 0.6)));
     }
 
@@ -349,7 +299,7 @@ mod tests {
             positions: vec![PositionAbsEntry {
                 species: Species::from_cell_value(&CellValue::Str("Fe")).unwrap(),
                 coord: [1.0, 2.0, 3.0],
-                spin: Some(1.5),
+                spin: Some(Spin::Scalar(1.5)),
                 mixture: None,
             }],
         };
@@ -368,7 +318,7 @@ mod tests {
         let entry = PositionAbsEntry {
             species: Species::from_cell_value(&CellValue::Str("Fe")).unwrap(),
             coord: [0.1, 0.2, 0.3],
-            spin: Some(1.5),
+            spin: Some(Spin::Scalar(1.5)),
             mixture: Some((1, 0.7)),
         };
         let val = entry.to_cell_value();
@@ -406,7 +356,7 @@ mod tests {
 
         assert_eq!(entry.species, species);
         assert_eq!(entry.coord, [1.5, 2.5, 3.5]);
-        assert_eq!(entry.spin, Some(2.0));
+        assert_eq!(entry.spin, Some(Spin::Scalar(2.0)));
         assert_eq!(entry.mixture, None);
     }
 
@@ -437,7 +387,7 @@ mod tests {
 
         assert_eq!(entry.species, species);
         assert_eq!(entry.coord, [1.0, 2.0, 3.0]);
-        assert_eq!(entry.spin, Some(1.5));
+        assert_eq!(entry.spin, Some(Spin::Scalar(1.5)));
         assert_eq!(entry.mixture, Some((2, 0.6)));
     }
 
@@ -526,7 +476,7 @@ mod tests {
 
         assert_eq!(positions.unit, Some(LengthUnit::Ang));
         assert_eq!(positions.positions.len(), 2);
-        assert_eq!(positions.positions[0].spin, Some(2.0));
+        assert_eq!(positions.positions[0].spin, Some(Spin::Scalar(2.0)));
         assert_eq!(positions.positions[1].mixture, Some((1, 0.8)));
     }
 }
@@ -2,19 +2,21 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, query::value_as_f64, query::value_as_str, query::value_as_string};
 
 use crate::cell::species::Species;
+use super::qualifiers::{Spin, parse_qualifiers, push_qualifiers};
 
 /// Represents a single atom entry within the POSITIONS_FRAC block.
 ///
 /// Consists of the element symbol/number, fractional coordinates, and optional spin/mixture qualifiers.
-/// Format: <element> <x> <y> <z> [SPIN <value>] [MIXTURE <index> <weight>]
+/// Format: <element> <x> <y> <z> [SPIN <value>|<x> <y> <z>] [MIXTURE <index> <weight>]
 #[derive(Debug, Clone, PartialEq, bon::Builder)]
 pub struct PositionFracEntry {
     /// The chemical element symbol (e.g., "Fe") or atomic number as a string (e.g., "26").
     pub species: Species,
     /// Fractional coordinates [x, y, z].
     pub coord: [f64; 3],
-    /// Optional initial spin polarization for the atom.
-    pub spin: Option<f64>,
+    /// Optional initial spin (`SPIN` or `MAGMOM` qualifier), scalar or vector.
+    #[builder(into)]
+    pub spin: Option<Spin>,
     /// Optional mixture specification: (index, weight) for disordered systems.
     pub mixture: Option<(u32, f64)>,
 }
@@ -30,48 +32,7 @@ impl FromCellValue for PositionFracEntry {
                     value_as_f64(&arr[3])?,
                 ];
 
-                let mut spin = None;
-                let mut mixture = None;
-                let mut idx = 4;
-
-                // Parse optional qualifiers (SPIN, MIXTURE) in any order
-                while idx < arr.len() {
-                    let keyword = value_as_string(&arr[idx])?.to_ascii_uppercase();
-
-                    if keyword == "SPIN" || keyword == "SPIN=" {
-                        if idx + 1 < arr.len() {
-                            spin = Some(value_as_f64(&arr[idx + 1])?);
-                            idx += 2;
-                        } else {
-                            return Err(castep_cell_fmt::Error::Message(
-                                "SPIN qualifier requires a value".into(),
-                            ));
-                        }
-                    } else if keyword == "MIXTURE" || keyword == "MIXTURE=" {
-                        if idx + 2 < arr.len() {
-                            let mix_idx = match &arr[idx + 1] {
-                                CellValue::UInt(u) => *u,
-                                CellValue::Int(i) if *i >= 0 => *i as u32,
-                                _ => {
-                                    return Err(castep_cell_fmt::Error::Message(
-                                        "MIXTURE index must be a positive integer".into(),
-                                    ))
-                                }
-                            };
-                            let mix_weight = value_as_f64(&arr[idx + 2])?;
-                            mixture = Some((mix_idx, mix_weight));
-                            idx += 3;
-                        } else {
-                            return Err(castep_cell_fmt::Error::Message(
-                                "MIXTURE qualifier requires index and weight".into(),
-                            ));
-                        }
-                    } else {
-                        return Err(castep_cell_fmt::Error::Message(
-                            format!("unknown qualifier: {keyword}"),
-                        ));
-                    }
-                }
+                let (spin, mixture) = parse_qualifiers(&arr[4..])?;
 
                 Ok(PositionFracEntry {
                     species,
@@ -93,18 +54,7 @@ impl ToCellValue for PositionFracEntry {
         let mut arr = vec![self.species.to_cell_value()];
         arr.extend(self.coord.into_iter().map(CellValue::Float));
 
-        // Append SPIN qualifier if present
-        if let Some(spin) = self.spin {
-            arr.push(CellValue::String("SPIN=".to_string()));
-            arr.push(CellValue::Float(spin));
-        }
-
-        // Append MIXTURE qualifier if present
-        if let Some((mix_idx, mix_weight)) = self.mixture {
-            arr.push(CellValue::String("MIXTURE=".to_string()));
-            arr.push(CellValue::UInt(mix_idx));
-            arr.push(CellValue::Float(mix_weight));
-        }
+        push_qualifiers(&mut arr, self.spin, self.mixture);
 
         CellValue::Array(arr)
     }
@@ -179,7 +129,7 @@ mod tests {
         ]);
         let entry = PositionFracEntry::from_cell_value(&val).unwrap();
         assert_eq!(entry.coord, [0.5, 0.5, 0.5]);
-        assert_eq!(entry.spin, Some(2.0));
+        assert_eq!(entry.spin, Some(Spin::Scalar(2.0)));
     }
 
     #[test]
@@ -193,7 +143,7 @@ mod tests {
             CellValue::Float(1.5),
         ]);
         let entry = PositionFracEntry::from_cell_value(&val).unwrap();
-        assert_eq!(entry.spin, Some(1.5));
+        assert_eq!(entry.spin, Some(Spin::Scalar(1.5)));
     }
 
     #[test]
@@ -262,7 +212,7 @@ mod tests {
         let entry = PositionFracEntry {
             species: Species::from_cell_value(&CellValue::Str("Fe")).unwrap(),
             coord: [0.1, 0.2, 0.3],
-            spin: Some(1.5),
+            spin: Some(Spin::Scalar(1.5)),
             mixture: None,
         };
         let val = entry.to_cell_value();
@@ -320,7 +270,7 @@ mod tests {
             CellValue::Float(0.5),
         ]);
         let entry = PositionFracEntry::from_cell_value(&val).unwrap();
-        assert_eq!(entry.spin, Some(2.0));
+        assert_eq!(entry.spin, Some(Spin::Scalar(2.0)));
         assert_eq!(entry.mixture, Some((1, 0.5)));
     }
 
@@ -339,7 +289,7 @@ mod tests {
         ]);
         let entry = PositionFracEntry::from_cell_value(&val).unwrap();
         assert_eq!(entry.mixture, Some((3, 0.75)));
-        assert_eq!(entry.spin, Some(1.5));
+        assert_eq!(entry.spin, Some(Spin::Scalar(1.5)));
     }
 
     #[test]
@@ -400,7 +350,7 @@ mod tests {
         let entry = PositionFracEntry {
             species: Species::from_cell_value(&CellValue::Str("Fe")).unwrap(),
             coord: [0.5, 0.5, 0.5],
-            spin: Some(2.0),
+            spin: Some(Spin::Scalar(2.0)),
             mixture: Some((1, 0.5)),
         };
         let val = entry.to_cell_value();
@@ -433,7 +383,7 @@ mod tests {
         let original = PositionFracEntry {
             species: Species::from_cell_value(&CellValue::Str("Fe")).unwrap(),
             coord: [0.1, 0.2, 0.3],
-            spin: Some(1.5),
+            spin: Some(Spin::Scalar(1.5)),
             mixture: Some((1, 0.75)),
         };
         let cell_value = original.to_cell_value();
@@ -462,7 +412,7 @@ mod tests {
             .coord([0.5, 0.5, 0.5])
             .spin(2.0)
             .build();
-        assert_eq!(entry.spin, Some(2.0));
+        assert_eq!(entry.spin, Some(Spin::Scalar(2.0)));
     }
 
     #[test]
@@ -484,7 +434,7 @@ mod tests {
             .mixture((1, 0.5))
             .build();
         assert_eq!(entry.coord, [0.5, 0.5, 0.5]);
-        assert_eq!(entry.spin, Some(2.0));
+        assert_eq!(entry.spin, Some(Spin::Scalar(2.0)));
         assert_eq!(entry.mixture, Some((1, 0.5)));
     }
 
@@ -50,6 +50,7 @@ mod tests {
     use super::*;
     use castep_cell_fmt::CellValue;
     use crate::cell::species::Species;
+    use crate::cell::positions::Spin;
 
     #[test]
     fn test_positions_frac_intermediate_multiple_entries() {
@@ -116,7 +117,7 @@ mod tests {
         ];
         let result = PositionsFracIntermediate::from_block_rows(&rows).unwrap();
         assert_eq!(result.positions.len(), 1);
-        assert_eq!(result.positions[0].spin, Some(2.0));
+        assert_eq!(result.positions[0].spin, Some(Spin::Scalar(2.0)));
     }
 
     #[test]
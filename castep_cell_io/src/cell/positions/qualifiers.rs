@@ -0,0 +1,267 @@
+use castep_cell_fmt::{CResult, CellValue, Error};
+
+/// Initial spin of an atom, from the `SPIN` or deprecated `MAGMOM` qualifier of a
+/// position row.
+///
+/// `SPIN` gives Nup − Ndown and `MAGMOM` the polarisation (Nup − Ndown)/(Nup + Ndown).
+/// Either takes one value for collinear calculations or three Cartesian components for
+/// non-collinear ones (`SPIN_TREATMENT : VECTOR`).
+///
+/// Format: `SPIN= s`, `SPIN= sx sy sz`, `MAGMOM= m`, `MAGMOM= mx my mz`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spin {
+    /// Collinear `SPIN`.
+    Scalar(f64),
+    /// Non-collinear `SPIN` vector.
+    Vector([f64; 3]),
+    /// Collinear `MAGMOM`.
+    Magmom(f64),
+    /// Non-collinear `MAGMOM` vector.
+    MagmomVector([f64; 3]),
+}
+
+impl Spin {
+    /// Returns `true` for the three-component forms.
+    pub fn is_vector(&self) -> bool {
+        matches!(self, Spin::Vector(_) | Spin::MagmomVector(_))
+    }
+
+    /// Returns the collinear value, if this is one.
+    pub fn scalar(&self) -> Option<f64> {
+        match self {
+            Spin::Scalar(s) | Spin::Magmom(s) => Some(*s),
+            _ => None,
+        }
+    }
+
+    /// Returns the Cartesian components, if this is a vector.
+    pub fn vector(&self) -> Option<[f64; 3]> {
+        match self {
+            Spin::Vector(v) | Spin::MagmomVector(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the size of the moment: the absolute value or the vector length.
+    pub fn magnitude(&self) -> f64 {
+        match self {
+            Spin::Scalar(s) | Spin::Magmom(s) => s.abs(),
+            Spin::Vector(v) | Spin::MagmomVector(v) => v.iter().map(|x| x * x).sum::<f64>().sqrt(),
+        }
+    }
+}
+
+impl From<f64> for Spin {
+    fn from(value: f64) -> Self {
+        Spin::Scalar(value)
+    }
+}
+
+impl From<[f64; 3]> for Spin {
+    fn from(value: [f64; 3]) -> Self {
+        Spin::Vector(value)
+    }
+}
+
+enum Token {
+    Word(String),
+    Number(f64),
+}
+
+/// Splits qualifier values into words and numbers, dropping the `=`, `:` and
+/// parentheses CASTEP allows around qualifier values (`MIXTURE:( 1 0.5 )`).
+fn tokens(values: &[CellValue<'_>]) -> CResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    for value in values {
+        match value {
+            CellValue::Float(x) => tokens.push(Token::Number(*x)),
+            CellValue::Int(i) => tokens.push(Token::Number(f64::from(*i))),
+            CellValue::UInt(u) => tokens.push(Token::Number(f64::from(*u))),
+            CellValue::Str(s) => push_words(&mut tokens, s),
+            CellValue::String(s) => push_words(&mut tokens, s),
+            other => {
+                return Err(Error::Message(format!(
+                    "unexpected value in position qualifiers: {other:?}"
+                )));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn push_words(tokens: &mut Vec<Token>, text: &str) {
+    for part in text.replace(['=', ':', '(', ')'], " ").split_whitespace() {
+        tokens.push(match part.parse::<f64>() {
+            Ok(x) => Token::Number(x),
+            Err(_) => Token::Word(part.to_ascii_uppercase()),
+        });
+    }
+}
+
+/// The `SPIN`/`MAGMOM` and `MIXTURE` (index, weight) qualifiers of a position row.
+type Qualifiers = (Option<Spin>, Option<(u32, f64)>);
+
+/// Parses the `SPIN`/`MAGMOM` and `MIXTURE` qualifiers trailing a position row, in any
+/// order.
+pub(crate) fn parse_qualifiers(values: &[CellValue<'_>]) -> CResult<Qualifiers> {
+    let tokens = tokens(values)?;
+    let mut spin = None;
+    let mut mixture = None;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let keyword = match &tokens[idx] {
+            Token::Word(word) => word.as_str(),
+            Token::Number(x) => {
+                return Err(Error::Message(format!(
+                    "unexpected value in qualifiers: {x}"
+                )));
+            }
+        };
+        let numbers: Vec<f64> = tokens[idx + 1..]
+            .iter()
+            .map_while(|t| match t {
+                Token::Number(x) => Some(*x),
+                Token::Word(_) => None,
+            })
+            .collect();
+        match keyword {
+            "SPIN" | "MAGMOM" => {
+                let magmom = keyword == "MAGMOM";
+                spin = Some(match numbers.as_slice() {
+                    [s] if magmom => Spin::Magmom(*s),
+                    [s] => Spin::Scalar(*s),
+                    [x, y, z] if magmom => Spin::MagmomVector([*x, *y, *z]),
+                    [x, y, z] => Spin::Vector([*x, *y, *z]),
+                    [] => {
+                        return Err(Error::Message(format!(
+                            "{keyword} qualifier requires a value"
+                        )));
+                    }
+                    _ => {
+                        return Err(Error::Message(format!(
+                            "{keyword} qualifier takes one value or three components"
+                        )));
+                    }
+                });
+            }
+            "MIXTURE" => match numbers.as_slice() {
+                [index, weight] => {
+                    if index.fract() != 0.0 || *index < 0.0 {
+                        return Err(Error::Message(
+                            "MIXTURE index must be a positive integer".into(),
+                        ));
+                    }
+                    mixture = Some((*index as u32, *weight));
+                }
+                _ => {
+                    return Err(Error::Message(
+                        "MIXTURE qualifier requires index and weight".into(),
+                    ));
+                }
+            },
+            other => {
+                return Err(Error::Message(format!("unknown qualifier: {other}")));
+            }
+        }
+        idx += 1 + numbers.len();
+    }
+    Ok((spin, mixture))
+}
+
+/// Appends the qualifiers of a position row in the form CASTEP writes them.
+pub(crate) fn push_qualifiers(
+    row: &mut Vec<CellValue<'_>>,
+    spin: Option<Spin>,
+    mixture: Option<(u32, f64)>,
+) {
+    if let Some(spin) = spin {
+        let keyword = match spin {
+            Spin::Scalar(_) | Spin::Vector(_) => "SPIN=",
+            Spin::Magmom(_) | Spin::MagmomVector(_) => "MAGMOM=",
+        };
+        row.push(CellValue::String(keyword.to_string()));
+        match spin {
+            Spin::Scalar(s) | Spin::Magmom(s) => row.push(CellValue::Float(s)),
+            Spin::Vector(v) | Spin::MagmomVector(v) => {
+                row.extend(v.into_iter().map(CellValue::Float))
+            }
+        }
+    }
+    if let Some((index, weight)) = mixture {
+        row.push(CellValue::String("MIXTURE=".to_string()));
+        row.push(CellValue::UInt(index));
+        row.push(CellValue::Float(weight));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spin_forms() {
+        let cases: [(&[CellValue], Spin); 5] = [
+            (
+                &[CellValue::Str("SPIN="), CellValue::Float(4.0)],
+                Spin::Scalar(4.0),
+            ),
+            (
+                &[
+                    CellValue::Str("SPIN"),
+                    CellValue::Str(":"),
+                    CellValue::Int(-2),
+                ],
+                Spin::Scalar(-2.0),
+            ),
+            (&[CellValue::Str("SPIN=4")], Spin::Scalar(4.0)),
+            (
+                &[CellValue::Str("MAGMOM"), CellValue::Float(0.5)],
+                Spin::Magmom(0.5),
+            ),
+            (
+                &[
+                    CellValue::Str("SPIN=("),
+                    CellValue::Float(0.0),
+                    CellValue::Float(1.0),
+                    CellValue::Str("-1.5)"),
+                ],
+                Spin::Vector([0.0, 1.0, -1.5]),
+            ),
+        ];
+        for (values, expected) in cases {
+            assert_eq!(parse_qualifiers(values).unwrap(), (Some(expected), None));
+        }
+    }
+
+    #[test]
+    fn test_mixture_after_vector_spin() {
+        let values = [
+            CellValue::Str("MAGMOM"),
+            CellValue::Float(0.1),
+            CellValue::Float(0.2),
+            CellValue::Float(0.3),
+            CellValue::Str("MIXTURE:("),
+            CellValue::UInt(1),
+            CellValue::Str("0.5)"),
+        ];
+        let (spin, mixture) = parse_qualifiers(&values).unwrap();
+        assert_eq!(spin, Some(Spin::MagmomVector([0.1, 0.2, 0.3])));
+        assert_eq!(mixture, Some((1, 0.5)));
+
+        let mut row = Vec::new();
+        push_qualifiers(&mut row, spin, mixture);
+        assert_eq!(parse_qualifiers(&row).unwrap(), (spin, mixture));
+    }
+
+    #[test]
+    fn test_invalid_qualifiers() {
+        let two = [
+            CellValue::Str("SPIN"),
+            CellValue::Float(1.0),
+            CellValue::Float(2.0),
+        ];
+        assert!(parse_qualifiers(&two).is_err());
+        assert!(parse_qualifiers(&[CellValue::Str("SPIN")]).is_err());
+        assert!(parse_qualifiers(&[CellValue::Str("CHARGE"), CellValue::Float(1.0)]).is_err());
+    }
+}
@@ -3,6 +3,8 @@ use castep_cell_fmt::parse::FromCellValue;
 use castep_cell_fmt::{CResult, Error};
 use serde::{Deserialize, Serialize};
 
+use crate::periodic_table::Element;
+
 mod hubbard_u;
 mod quantization_axis;
 mod sedc_custom_params;
@@ -46,25 +48,57 @@ impl Species {
             None
         }
     }
+
+    /// Returns the label that tells species apart, so that `Fe`, `fe` and `26` all give
+    /// `Fe`. Custom labels such as `Fe1` are returned as written.
+    pub fn canonical_label(&self) -> String {
+        match self {
+            Species::AtomicNumber(n) => {
+                Element::from_number(*n).map_or_else(|| n.to_string(), |e| e.symbol.to_string())
+            }
+            Species::Symbol(s) => {
+                Element::from_symbol(s).map_or_else(|| s.clone(), |e| e.symbol.to_string())
+            }
+        }
+    }
+
+    /// Returns the element of this species, resolving custom labels such as `Fe:up` or
+    /// `Fe1` to `Fe`. Returns `None` for unknown symbols and atomic numbers.
+    pub fn element(&self) -> Option<&'static Element> {
+        match self {
+            Species::AtomicNumber(n) => Element::from_number(*n),
+            Species::Symbol(s) => Element::from_label(s),
+        }
+    }
+
+    /// Returns `true` if both spellings name the same species.
+    pub fn same_species(&self, other: &Species) -> bool {
+        self.canonical_label() == other.canonical_label()
+    }
 }
 
 impl FromCellValue for Species {
+    /// Parses a species. Labels and atomic numbers that do not resolve to an element
+    /// (see [`Species::element`]) are kept as written; use
+    /// [`CellDocument::check_species`](crate::CellDocument::check_species) to find them.
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
-        match value {
+        Ok(match value {
             CellValue::Str(s) => {
                 if let Ok(n) = s.parse::<u32>() {
-                    Ok(Species::AtomicNumber(n))
+                    Species::AtomicNumber(n)
                 } else {
-                    Ok(Species::Symbol(s.to_string()))
+                    Species::Symbol(s.to_string())
                 }
             }
-            CellValue::UInt(n) => Ok(Species::AtomicNumber(*n)),
-            CellValue::String(s) => Ok(Species::Symbol(s.clone())),
-            other => Err(Error::UnexpectedType(
-                "Species".into(),
-                format!("{other:?}"),
-            )),
-        }
+            CellValue::UInt(n) => Species::AtomicNumber(*n),
+            CellValue::String(s) => Species::Symbol(s.clone()),
+            other => {
+                return Err(Error::UnexpectedType(
+                    "Species".into(),
+                    format!("{other:?}"),
+                ));
+            }
+        })
     }
 }
 
@@ -85,3 +119,32 @@ impl std::fmt::Display for Species {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_species_resolves_to_element() {
+        let fe = Species::from_cell_value(&CellValue::Str("Fe:up")).unwrap();
+        assert_eq!(fe, Species::Symbol("Fe:up".into()));
+        assert_eq!(fe.element().unwrap().atomic_number, 26);
+        assert_eq!(fe.canonical_label(), "Fe:up");
+        let z = Species::from_cell_value(&CellValue::Str("26")).unwrap();
+        assert_eq!(z.canonical_label(), "Fe");
+        assert!(z.same_species(&Species::Symbol("fe".into())));
+    }
+
+    #[test]
+    fn test_species_keeps_unknown_labels() {
+        let water = Species::from_cell_value(&CellValue::Str("O:w")).unwrap();
+        assert_eq!(water.element().unwrap().symbol, "O");
+        let typo = Species::from_cell_value(&CellValue::Str("Sx")).unwrap();
+        assert!(typo.element().is_none());
+        let dummy = Species::from_cell_value(&CellValue::Str("Q1")).unwrap();
+        assert_eq!(dummy, Species::Symbol("Q1".into()));
+        assert!(dummy.element().is_none());
+        let z = Species::from_cell_value(&CellValue::UInt(250)).unwrap();
+        assert!(z.element().is_none());
+    }
+}
@@ -0,0 +1,890 @@
+//! Space-group detection from lattice and atomic positions.
+//!
+//! The search follows the usual approach: find the pure translations and a reduced
+//! primitive cell, collect the lattice rotations that map the crystal onto itself,
+//! classify the point group, then match the operations against the built-in table
+//! of 230 space groups after moving to a conventional basis.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::math::{self, IMat3, Mat3, Vec3};
+use crate::{CellDocument, Positions};
+
+use super::point_group::{CrystalSystem, PointGroup, rotation_type};
+use super::space_group::{Centering, SpaceGroup};
+use super::symmetry_ops::{SymmetryOp, SymmetryOpFrac, SymmetryOps};
+use super::symmetry_tol::SymmetryTol;
+
+/// Result of a symmetry search on a [`CellDocument`].
+#[derive(Debug, Clone)]
+pub struct SymmetryDataset {
+    /// The space-group type of the crystal.
+    pub space_group: &'static SpaceGroup,
+    /// The crystallographic point group.
+    pub point_group: PointGroup,
+    /// Symmetry operations of the input cell in fractional coordinates, including
+    /// pure translations of supercells.
+    ///
+    /// Operations of the crystal whose rotation does not map the input lattice onto
+    /// itself are omitted.
+    pub operations: Vec<SymmetryOpFrac>,
+    /// The same operations as a ready-to-use `SYMMETRY_OPS` block.
+    pub symmetry_ops: SymmetryOps,
+    /// For every atom, the index of the first atom in its symmetry orbit.
+    pub equivalent_atoms: Vec<usize>,
+    /// Matrix `P` taking input fractional coordinates to the standard conventional
+    /// cell of [`space_group`](Self::space_group): `x_std = P · x + origin_shift`.
+    pub transformation_matrix: [[f64; 3]; 3],
+    /// Origin shift of the standard setting, see [`transformation_matrix`](Self::transformation_matrix).
+    pub origin_shift: [f64; 3],
+}
+
+impl CellDocument {
+    /// Detects the symmetry of the structure using `SYMMETRY_TOL` if set, or the
+    /// CASTEP default of 0.01 Å otherwise.
+    pub fn find_symmetry(&self) -> CResult<SymmetryDataset> {
+        find_symmetry(self, &self.symmetry_tol.unwrap_or_default())
+    }
+}
+
+/// Detects the space group, point group and symmetry operations of `doc`.
+///
+/// Atoms are only considered equivalent if they share the same species label,
+/// `SPIN` value and `MIXTURE` weight, so magnetic orderings and partially occupied
+/// sites lower the symmetry as they do in CASTEP.
+pub fn find_symmetry(doc: &CellDocument, tolerance: &SymmetryTol) -> CResult<SymmetryDataset> {
+    let (input, tol) = Crystal::from_document(doc, tolerance)?;
+    let n_atoms = input.coords.len();
+    let (translations, primitive) = primitive_basis(&input, tol)?;
+    let order = translations.len();
+    let primitive_inv = math::inverse(primitive).ok_or_else(inconsistent)?;
+
+    let mut prim = Crystal::new(
+        math::mat_mul(math::transpose(primitive), input.lattice),
+        Vec::new(),
+        Vec::new(),
+    )?;
+    for (x, kind) in input.coords.iter().zip(&input.kinds) {
+        let y = math::wrap(math::mat_vec(primitive_inv, *x));
+        let seen = prim
+            .coords
+            .iter()
+            .zip(&prim.kinds)
+            .any(|(c, k)| k == kind && prim.within(*c, y, tol));
+        if !seen {
+            prim.coords.push(y);
+            prim.kinds.push(*kind);
+        }
+    }
+    if prim.coords.len() * order != n_atoms {
+        return Err(inconsistent());
+    }
+    let prim_reference = prim.reference_atom();
+
+    let ops: Vec<(IMat3, Vec3)> = lattice_rotations(prim.lattice, tol)
+        .into_iter()
+        .filter_map(|w| {
+            prim.find_translation(math::to_f64(w), prim_reference, tol)
+                .map(|t| (w, t))
+        })
+        .collect();
+    let rotations: Vec<IMat3> = ops.iter().map(|(w, _)| *w).collect();
+    let point_group = PointGroup::from_rotations(&rotations).ok_or_else(|| {
+        Error::Message(
+            "symmetry operations found at this tolerance do not form a group; try a smaller SYMMETRY_TOL"
+                .into(),
+        )
+    })?;
+    let (space_group, conventional, origin_shift) = identify(prim.lattice, &ops, point_group, tol)
+        .ok_or_else(|| {
+            Error::Message("could not identify the space group; try a different SYMMETRY_TOL".into())
+        })?;
+
+    let mut operations = Vec::new();
+    let mut generators: Vec<(Mat3, Vec3)> =
+        translations.iter().map(|tau| (math::IDENTITY, *tau)).collect();
+    for (w, t) in &ops {
+        let w_input = math::mat_mul(math::mat_mul(primitive, math::to_f64(*w)), primitive_inv);
+        let Some(rotation) = math::to_int(w_input, 1e-6) else {
+            continue;
+        };
+        let t_input = math::mat_vec(primitive, *t);
+        generators.push((math::to_f64(rotation), t_input));
+        operations.extend(translations.iter().map(|tau| SymmetryOpFrac {
+            rotation,
+            translation: math::wrap(math::add(t_input, *tau)),
+        }));
+    }
+
+    // Orbits under the generating operations, each labelled by its lowest index.
+    let mut parent: Vec<usize> = (0..n_atoms).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for (w, t) in generators {
+        if let Some(perm) = input.mapping(w, t, tol) {
+            for (i, j) in perm.into_iter().enumerate() {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+    let equivalent_atoms: Vec<usize> = (0..n_atoms).map(|i| root(&mut parent, i)).collect();
+
+    let symmetry_ops = SymmetryOps {
+        ops: operations
+            .iter()
+            .map(|op| SymmetryOp::from_frac(op, &doc.lattice))
+            .collect::<CResult<_>>()?,
+    };
+    let conventional_input = math::mat_mul(primitive, math::to_f64(conventional));
+    Ok(SymmetryDataset {
+        space_group,
+        point_group,
+        operations,
+        symmetry_ops,
+        equivalent_atoms,
+        transformation_matrix: math::inverse(conventional_input).ok_or_else(inconsistent)?,
+        origin_shift,
+    })
+}
+
+fn inconsistent() -> Error {
+    Error::Message("atomic positions are inconsistent at this SYMMETRY_TOL".into())
+}
+
+/// Finds the pure translations of `doc` and the Delaunay-reduced primitive basis they
+/// span, as columns in fractional coordinates of the input cell.
+pub(crate) fn primitive_transformation(
+    doc: &CellDocument,
+    tolerance: &SymmetryTol,
+) -> CResult<(Vec<Vec3>, Mat3)> {
+    let (input, tol) = Crystal::from_document(doc, tolerance)?;
+    primitive_basis(&input, tol)
+}
+
+fn primitive_basis(input: &Crystal, tol: f64) -> CResult<(Vec<Vec3>, Mat3)> {
+    let n_atoms = input.coords.len();
+    let reference = input.reference_atom();
+    let translations: Vec<Vec3> = (0..n_atoms)
+        .filter(|&j| input.kinds[j] == input.kinds[reference])
+        .filter_map(|j| {
+            let t = math::wrap(math::sub(input.coords[j], input.coords[reference]));
+            input
+                .mapping(math::IDENTITY, t, tol)
+                .map(|perm| input.refine(math::IDENTITY, t, &perm))
+        })
+        .collect();
+    let order = translations.len();
+    if !n_atoms.is_multiple_of(order) {
+        return Err(inconsistent());
+    }
+    let mut generators: Vec<[i64; 3]> = (0..3)
+        .map(|i| [0, 1, 2].map(|j| if i == j { order as i64 } else { 0 }))
+        .collect();
+    generators.extend(
+        translations
+            .iter()
+            .map(|t| t.map(|x| (x * order as f64).round() as i64)),
+    );
+    let basis = math::lattice_basis(&generators).ok_or_else(inconsistent)?;
+    let mut primitive = math::transpose(basis.map(|row| row.map(|x| x as f64 / order as f64)));
+    if math::det(primitive) < 0.0 {
+        primitive = primitive.map(|row| row.map(|x| -x));
+    }
+    let unreduced = math::mat_mul(math::transpose(primitive), input.lattice);
+    let eps = 1e-8 * unreduced.iter().map(|v| math::dot(*v, *v)).fold(0.0, f64::max);
+    let primitive = math::mat_mul(primitive, math::to_f64(math::delaunay_reduce(unreduced, eps)));
+    Ok((translations, primitive))
+}
+
+/// Assigns every atom an integer kind; atoms of different kinds are never equivalent.
+///
+/// Spin vectors are compared component by component, so non-collinear moments only
+/// match when they are parallel in Cartesian space.
+pub(crate) fn atom_kinds(positions: &Positions) -> Vec<usize> {
+    let mut keys: Vec<(String, Option<Vec<i64>>, Option<i64>)> = Vec::new();
+    let species = positions.species();
+    let spins = positions.spins();
+    let mixtures = positions.mixtures();
+    species
+        .iter()
+        .zip(spins)
+        .zip(mixtures)
+        .map(|((species, spin), mixture)| {
+            let key = (
+                species.canonical_label(),
+                spin.map(|s| {
+                    let components = match s.vector() {
+                        Some(v) => v.to_vec(),
+                        None => vec![s.scalar().unwrap_or_default()],
+                    };
+                    components.iter().map(|x| (x * 1e6).round() as i64).collect()
+                }),
+                mixture.map(|(_, w)| (w * 1e6).round() as i64),
+            );
+            keys.iter().position(|k| *k == key).unwrap_or_else(|| {
+                keys.push(key);
+                keys.len() - 1
+            })
+        })
+        .collect()
+}
+
+/// A periodic arrangement of typed points in fractional coordinates.
+struct Crystal {
+    lattice: Mat3,
+    /// Spacing of the lattice planes normal to each reciprocal vector, for quick rejection.
+    spacings: Vec3,
+    coords: Vec<Vec3>,
+    kinds: Vec<usize>,
+}
+
+impl Crystal {
+    /// The wrapped atoms of `doc`, with the tolerance converted to Å.
+    fn from_document(doc: &CellDocument, tolerance: &SymmetryTol) -> CResult<(Self, f64)> {
+        let tol = tolerance.value * tolerance.unit.in_angstrom();
+        if tol.is_nan() || tol <= 0.0 {
+            return Err(Error::Message("symmetry tolerance must be positive".into()));
+        }
+        if doc.positions.is_empty() {
+            return Err(Error::Message("cannot find the symmetry of an empty cell".into()));
+        }
+        let lattice = doc.lattice.vectors();
+        if math::det(lattice).abs() < 1e-8 {
+            return Err(Error::Message("lattice vectors are linearly dependent".into()));
+        }
+        let crystal = Crystal::new(
+            lattice,
+            doc.positions
+                .frac_coords(&doc.lattice)?
+                .into_iter()
+                .map(math::wrap)
+                .collect(),
+            atom_kinds(&doc.positions),
+        )?;
+        Ok((crystal, tol))
+    }
+
+    fn new(lattice: Mat3, coords: Vec<Vec3>, kinds: Vec<usize>) -> CResult<Self> {
+        let spacings = math::reciprocal(lattice)?.map(|b| 1.0 / math::norm(b));
+        Ok(Self {
+            lattice,
+            spacings,
+            coords,
+            kinds,
+        })
+    }
+
+    fn within(&self, a: Vec3, b: Vec3, tol: f64) -> bool {
+        let d = math::min_image(math::sub(a, b));
+        (0..3).all(|i| d[i].abs() * self.spacings[i] < tol)
+            && math::norm(math::frac_to_cart(self.lattice, d)) < tol
+    }
+
+    /// First atom of the least populated kind, used to generate candidate translations.
+    fn reference_atom(&self) -> usize {
+        let count = |k: usize| self.kinds.iter().filter(|x| **x == k).count();
+        (0..self.kinds.len())
+            .min_by_key(|&i| count(self.kinds[i]))
+            .unwrap_or(0)
+    }
+
+    /// Atom permutation induced by `x -> w · x + t`, if every image lands on an atom.
+    fn mapping(&self, w: Mat3, t: Vec3, tol: f64) -> Option<Vec<usize>> {
+        self.coords
+            .iter()
+            .zip(&self.kinds)
+            .map(|(x, kind)| {
+                let y = math::add(math::mat_vec(w, *x), t);
+                (0..self.coords.len())
+                    .find(|&j| self.kinds[j] == *kind && self.within(y, self.coords[j], tol))
+            })
+            .collect()
+    }
+
+    /// Adjusts `t` so that the mapped atoms sit on average exactly on their images.
+    fn refine(&self, w: Mat3, t: Vec3, perm: &[usize]) -> Vec3 {
+        let mut shift = [0.0; 3];
+        for (x, &j) in self.coords.iter().zip(perm) {
+            let y = math::add(math::mat_vec(w, *x), t);
+            shift = math::add(shift, math::min_image(math::sub(self.coords[j], y)));
+        }
+        math::wrap(math::add(t, math::scale(shift, 1.0 / perm.len() as f64)))
+    }
+
+    /// Finds a translation completing the rotation `w` into a symmetry operation.
+    fn find_translation(&self, w: Mat3, reference: usize, tol: f64) -> Option<Vec3> {
+        let image = math::mat_vec(w, self.coords[reference]);
+        (0..self.coords.len())
+            .filter(|&j| self.kinds[j] == self.kinds[reference])
+            .find_map(|j| {
+                let t = math::sub(self.coords[j], image);
+                self.mapping(w, t, tol).map(|perm| self.refine(w, t, &perm))
+            })
+    }
+}
+
+/// Integer vectors in `[-r, r]³` without the origin, sorted by Cartesian length.
+fn short_vectors(lattice: Mat3, r: i32) -> Vec<[i32; 3]> {
+    let mut out = Vec::new();
+    for i in -r..=r {
+        for j in -r..=r {
+            for k in -r..=r {
+                if (i, j, k) != (0, 0, 0) {
+                    out.push([i, j, k]);
+                }
+            }
+        }
+    }
+    let length = |n: &[i32; 3]| math::norm(math::frac_to_cart(lattice, n.map(f64::from)));
+    out.sort_by(|a, b| length(a).total_cmp(&length(b)));
+    out
+}
+
+/// Rotations (in fractional coordinates) that preserve the metric of a reduced lattice.
+fn lattice_rotations(lattice: Mat3, tol: f64) -> Vec<IMat3> {
+    let metric = math::mat_mul(lattice, math::transpose(lattice));
+    let lengths = [0, 1, 2].map(|i| metric[i][i].sqrt());
+    let mut candidates: [Vec<([i32; 3], Vec3)>; 3] = Default::default();
+    for n in short_vectors(lattice, 2) {
+        let v = math::frac_to_cart(lattice, n.map(f64::from));
+        for (i, list) in candidates.iter_mut().enumerate() {
+            if (math::norm(v) - lengths[i]).abs() < tol {
+                list.push((n, v));
+            }
+        }
+    }
+    let mut out = Vec::new();
+    for (n0, v0) in &candidates[0] {
+        for (n1, v1) in &candidates[1] {
+            for (n2, v2) in &candidates[2] {
+                let w = math::itranspose([*n0, *n1, *n2]);
+                if math::imat_det(w).abs() != 1 {
+                    continue;
+                }
+                let images = [v0, v1, v2];
+                let preserved = [(0, 1), (0, 2), (1, 2)].iter().all(|&(i, j)| {
+                    (math::dot(*images[i], *images[j]) - metric[i][j]).abs()
+                        < tol * (lengths[i] + lengths[j])
+                });
+                if preserved {
+                    out.push(w);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Matches the primitive-cell operations against the space-group table.
+///
+/// Returns the group, the conventional basis (columns in primitive coordinates) and
+/// the origin shift of the standard setting.
+fn identify(
+    lattice: Mat3,
+    ops: &[(IMat3, Vec3)],
+    point_group: PointGroup,
+    tol: f64,
+) -> Option<(&'static SpaceGroup, IMat3, Vec3)> {
+    'bases: for conventional in conventional_bases(lattice, ops, point_group) {
+        let conv = math::to_f64(conventional);
+        let Some(conv_inv) = math::inverse(conv) else {
+            continue;
+        };
+        let mut conv_ops = Vec::with_capacity(ops.len());
+        for (w, t) in ops {
+            let w_conv = math::mat_mul(math::mat_mul(conv_inv, math::to_f64(*w)), conv);
+            let Some(w_conv) = math::to_int(w_conv, 1e-6) else {
+                continue 'bases;
+            };
+            conv_ops.push((w_conv, math::mat_vec(conv_inv, *t)));
+        }
+        let Some(centering) = centering_of(conv_inv) else {
+            continue;
+        };
+        let conv_lattice = math::change_basis(lattice, conventional);
+        let candidates = SpaceGroup::all()
+            .iter()
+            .filter(|sg| sg.centering() == centering && sg.point_group() == point_group);
+        for sg in candidates {
+            let reps = sg.coset_representatives();
+            if !reps
+                .iter()
+                .all(|op| conv_ops.iter().any(|(w, _)| *w == op.rotation))
+            {
+                continue;
+            }
+            if let Some(shift) =
+                origin_shift(reps, &conv_ops, &centering.vectors(), conv_lattice, tol)
+            {
+                return Some((sg, conventional, shift));
+            }
+        }
+    }
+    None
+}
+
+/// Candidate conventional bases for the crystal system, as integer column matrices.
+fn conventional_bases(lattice: Mat3, ops: &[(IMat3, Vec3)], point_group: PointGroup) -> Vec<IMat3> {
+    let proper: Vec<IMat3> = ops
+        .iter()
+        .map(|(w, _)| if math::imat_det(*w) < 0 { negate(*w) } else { *w })
+        .collect();
+    let of_type = |n: i32| -> Vec<IMat3> {
+        proper
+            .iter()
+            .filter(|w| rotation_type(**w) == Some(n))
+            .copied()
+            .collect()
+    };
+    let vectors = short_vectors(lattice, 3);
+    let in_plane = |powers: &[IMat3]| -> Vec<[i32; 3]> {
+        vectors
+            .iter()
+            .filter(|n| {
+                let sum = powers
+                    .iter()
+                    .fold([0; 3], |acc, w| add_i(acc, math::imat_vec(*w, **n)));
+                sum == [0; 3]
+            })
+            .take(12)
+            .copied()
+            .collect()
+    };
+    match point_group.crystal_system() {
+        CrystalSystem::Triclinic => vec![math::IIDENTITY],
+        CrystalSystem::Monoclinic => {
+            let Some(&r2) = of_type(2).first() else {
+                return Vec::new();
+            };
+            let b = axis(r2);
+            let plane = in_plane(&[math::IIDENTITY, r2]);
+            let mut out = Vec::new();
+            for a in &plane {
+                for c in &plane {
+                    out.push(columns(*a, b, *c));
+                }
+            }
+            smallest_cells(out)
+        }
+        CrystalSystem::Orthorhombic | CrystalSystem::Cubic => {
+            let fourfold = of_type(4);
+            let source = if fourfold.is_empty() { of_type(2) } else { fourfold };
+            let mut axes: Vec<[i32; 3]> = Vec::new();
+            for w in source {
+                let v = axis(w);
+                if !axes.contains(&v) {
+                    axes.push(v);
+                }
+            }
+            if axes.len() != 3 {
+                return Vec::new();
+            }
+            [[0, 1, 2], [1, 2, 0], [2, 0, 1], [1, 0, 2], [0, 2, 1], [2, 1, 0]]
+                .iter()
+                .map(|p| columns(axes[p[0]], axes[p[1]], axes[p[2]]))
+                .collect()
+        }
+        CrystalSystem::Tetragonal => {
+            let Some(&r4) = of_type(4).first() else {
+                return Vec::new();
+            };
+            let c = axis(r4);
+            let r4_2 = math::imat_mul(r4, r4);
+            let plane = in_plane(&[math::IIDENTITY, r4, r4_2, math::imat_mul(r4_2, r4)]);
+            smallest_cells(plane.iter().map(|a| columns(*a, math::imat_vec(r4, *a), c)).collect())
+        }
+        CrystalSystem::Trigonal | CrystalSystem::Hexagonal => {
+            let r3 = of_type(3)
+                .first()
+                .copied()
+                .or_else(|| of_type(6).first().map(|w| math::imat_mul(*w, *w)));
+            let Some(r3) = r3 else {
+                return Vec::new();
+            };
+            let c = axis(r3);
+            let plane = in_plane(&[math::IIDENTITY, r3, math::imat_mul(r3, r3)]);
+            smallest_cells(plane.iter().map(|a| columns(*a, math::imat_vec(r3, *a), c)).collect())
+        }
+    }
+}
+
+fn negate(w: IMat3) -> IMat3 {
+    w.map(|row| row.map(|x| -x))
+}
+
+fn add_i(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+/// Column matrix `(a, b, c)`, with `c` flipped if needed to make it right-handed.
+fn columns(a: [i32; 3], b: [i32; 3], c: [i32; 3]) -> IMat3 {
+    let m = math::itranspose([a, b, c]);
+    if math::imat_det(m) < 0 {
+        math::itranspose([a, b, c.map(|x| -x)])
+    } else {
+        m
+    }
+}
+
+/// Keeps the non-degenerate cells with the fewest lattice points.
+fn smallest_cells(cells: Vec<IMat3>) -> Vec<IMat3> {
+    let min = cells
+        .iter()
+        .map(|m| math::imat_det(*m))
+        .filter(|d| *d != 0)
+        .min();
+    cells
+        .into_iter()
+        .filter(|m| Some(math::imat_det(*m)) == min)
+        .collect()
+}
+
+/// Shortest lattice vector along the rotation axis of the proper rotation `w`.
+fn axis(w: IMat3) -> [i32; 3] {
+    let m = [0, 1, 2].map(|i| [0, 1, 2].map(|j| w[i][j] - i32::from(i == j)));
+    let cross = |a: [i32; 3], b: [i32; 3]| {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    };
+    let v = [(0, 1), (0, 2), (1, 2)]
+        .iter()
+        .map(|&(i, j)| cross(m[i], m[j]))
+        .find(|v| *v != [0; 3])
+        .unwrap_or([0, 0, 1]);
+    let gcd = v.iter().fold(0, |g, x| gcd(g, x.abs()));
+    let v = v.map(|x| x / gcd);
+    let first = v.iter().find(|x| **x != 0).copied().unwrap_or(1);
+    if first < 0 { v.map(|x| -x) } else { v }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Identifies the centering of a conventional cell from its inverse basis matrix.
+fn centering_of(conv_inv: Mat3) -> Option<Centering> {
+    let generators: Vec<Vec3> = (0..3)
+        .map(|i| math::wrap([conv_inv[0][i], conv_inv[1][i], conv_inv[2][i]]))
+        .collect();
+    let same = |a: Vec3, b: Vec3| {
+        math::min_image(math::sub(a, b))
+            .iter()
+            .all(|x| x.abs() < 1e-6)
+    };
+    let mut points = vec![[0.0; 3]];
+    loop {
+        let mut added = false;
+        for i in 0..points.len() {
+            for g in &generators {
+                let v = math::wrap(math::add(points[i], *g));
+                if !points.iter().any(|p| same(*p, v)) {
+                    points.push(v);
+                    added = true;
+                }
+            }
+        }
+        if !added {
+            break;
+        }
+        if points.len() > 4 {
+            return None;
+        }
+    }
+    [
+        Centering::P,
+        Centering::A,
+        Centering::B,
+        Centering::C,
+        Centering::I,
+        Centering::F,
+        Centering::R,
+    ]
+    .into_iter()
+    .find(|c| {
+        let vectors = c.vectors();
+        vectors.len() == points.len() && vectors.iter().all(|v| points.iter().any(|p| same(*p, *v)))
+    })
+}
+
+/// A minimal set of operations whose rotations generate all of `reps`.
+fn generators(reps: &[SymmetryOpFrac]) -> Vec<SymmetryOpFrac> {
+    let mut gens: Vec<SymmetryOpFrac> = Vec::new();
+    let mut group = vec![math::IIDENTITY];
+    for op in reps {
+        if group.contains(&op.rotation) {
+            continue;
+        }
+        gens.push(*op);
+        loop {
+            let mut added = false;
+            for i in 0..group.len() {
+                for g in &gens {
+                    let w = math::imat_mul(group[i], g.rotation);
+                    if !group.contains(&w) {
+                        group.push(w);
+                        added = true;
+                    }
+                }
+            }
+            if !added {
+                break;
+            }
+        }
+    }
+    gens
+}
+
+/// Solves for the origin shift `p` that turns `conv_ops` into the tabulated `reps`,
+/// i.e. `t + (I - W) p ≡ s` modulo the lattice and centering for every rotation `W`.
+fn origin_shift(
+    reps: &[SymmetryOpFrac],
+    conv_ops: &[(IMat3, Vec3)],
+    centering: &[Vec3],
+    lattice: Mat3,
+    tol: f64,
+) -> Option<Vec3> {
+    let translation_of = |w: IMat3| conv_ops.iter().find(|(x, _)| *x == w).map(|(_, t)| *t);
+    let gens = generators(reps);
+    let choices = centering.len().pow(gens.len() as u32);
+    for choice in 0..choices {
+        let mut rows: Vec<[i64; 3]> = Vec::new();
+        let mut delta: Vec<f64> = Vec::new();
+        let mut c = choice;
+        for op in &gens {
+            let centre = centering[c % centering.len()];
+            c /= centering.len();
+            let t = translation_of(op.rotation)?;
+            for i in 0..3 {
+                rows.push([0, 1, 2].map(|j| i64::from(i == j) - i64::from(op.rotation[i][j])));
+                delta.push(op.translation[i] - t[i] + centre[i]);
+            }
+        }
+        let (l, r, d) = math::diagonalize(&rows);
+        let mut q = [0.0; 3];
+        let mut consistent = true;
+        for (i, row) in l.iter().enumerate() {
+            let v: f64 = row.iter().zip(&delta).map(|(a, b)| *a as f64 * b).sum();
+            if i < 3 && d[i][i] != 0 {
+                q[i] = v / d[i][i] as f64;
+            } else if (v - v.round()).abs() > 0.1 {
+                consistent = false;
+                break;
+            }
+        }
+        if !consistent {
+            continue;
+        }
+        let p = [0, 1, 2].map(|i| (0..3).map(|j| r[i][j] as f64 * q[j]).sum::<f64>());
+        let matches = reps.iter().all(|op| {
+            let Some(t) = translation_of(op.rotation) else {
+                return false;
+            };
+            let moved = math::add(t, math::sub(p, math::mat_vec(math::to_f64(op.rotation), p)));
+            centering.iter().any(|centre| {
+                let residual =
+                    math::min_image(math::sub(math::sub(moved, op.translation), *centre));
+                math::norm(math::frac_to_cart(lattice, residual)) < 4.0 * tol
+            })
+        });
+        if matches {
+            return Some(math::wrap(p));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::lattice_param::{LatticeABC, LatticeCart};
+    use crate::cell::positions::{PositionFracEntry, PositionsFrac};
+    use crate::test_fixtures::{
+        atom, cell, cubic_cell, cubic_lattice, frac_atoms, orthorhombic_lattice,
+    };
+    use crate::units::LengthUnit;
+
+    fn tol() -> SymmetryTol {
+        SymmetryTol::default()
+    }
+
+    #[test]
+    fn test_diamond_primitive_cell() {
+        let fcc = LatticeCart {
+            unit: None,
+            a: [0.0, 2.715, 2.715],
+            b: [2.715, 0.0, 2.715],
+            c: [2.715, 2.715, 0.0],
+        };
+        let si = cell(fcc, frac_atoms(&[("Si", [0.0; 3]), ("Si", [0.25; 3])]));
+        let ds = si.find_symmetry().unwrap();
+        assert_eq!(ds.space_group.number, 227);
+        assert_eq!(ds.point_group, PointGroup::Oh);
+        assert_eq!(ds.symmetry_ops.ops.len(), 48);
+        assert_eq!(ds.equivalent_atoms, vec![0, 0]);
+        // Every Cartesian rotation is orthogonal.
+        for op in &ds.symmetry_ops.ops {
+            let r = op.rotation;
+            let rrt = math::mat_mul(r, math::transpose(r));
+            for (row, expected) in rrt.iter().zip(math::IDENTITY) {
+                for (x, y) in row.iter().zip(expected) {
+                    assert!((x - y).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rocksalt_conventional_cell() {
+        let mut atoms = Vec::new();
+        for f in [[0.0, 0.0, 0.0], [0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]] {
+            atoms.push(atom("Na", f));
+            atoms.push(atom("Cl", math::wrap(math::add(f, [0.5, 0.0, 0.0]))));
+        }
+        let ds = cell(cubic_lattice(5.64), PositionsFrac { positions: atoms })
+            .find_symmetry()
+            .unwrap();
+        assert_eq!(ds.space_group.international, "Fm-3m");
+        assert_eq!(ds.operations.len(), 192);
+        assert_eq!(ds.equivalent_atoms, vec![0, 1, 0, 1, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_spins_lower_symmetry() {
+        let iron = |spins: [f64; 2]| {
+            let mut positions = frac_atoms(&[("Fe", [0.0; 3]), ("Fe", [0.5; 3])]);
+            for (entry, spin) in positions.positions.iter_mut().zip(spins) {
+                entry.spin = Some(spin.into());
+            }
+            cell(cubic_lattice(2.87), positions)
+        };
+        let ferro = iron([2.0, 2.0]);
+        assert_eq!(ferro.find_symmetry().unwrap().space_group.number, 229);
+        let antiferro = iron([2.0, -2.0]);
+        let ds = antiferro.find_symmetry().unwrap();
+        assert_eq!(ds.space_group.number, 221);
+        assert_eq!(ds.equivalent_atoms, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_mixture_weights_are_respected() {
+        let mut atoms = frac_atoms(&[
+            ("Si", [0.0, 0.0, 0.0]),
+            ("Ge", [0.0, 0.0, 0.0]),
+            ("Si", [0.5, 0.5, 0.5]),
+            ("Ge", [0.5, 0.5, 0.5]),
+        ]);
+        for (i, w) in [(1, 0.5), (1, 0.5), (2, 0.5), (2, 0.5)].into_iter().enumerate() {
+            atoms.positions[i].mixture = Some(w);
+        }
+        assert_eq!(cell(cubic_lattice(3.0), atoms.clone()).find_symmetry().unwrap().space_group.number, 229);
+        atoms.positions[2].mixture = Some((2, 0.3));
+        atoms.positions[3].mixture = Some((2, 0.7));
+        assert_eq!(cell(cubic_lattice(3.0), atoms).find_symmetry().unwrap().space_group.number, 221);
+    }
+
+    #[test]
+    fn test_tolerance_units_and_noise() {
+        let noisy = cubic_cell(4.0, &[("Po", [0.001, 0.0, 0.0])]);
+        let ds = find_symmetry(&noisy, &SymmetryTol { value: 0.1, unit: LengthUnit::Bohr }).unwrap();
+        assert_eq!(ds.space_group.number, 221);
+        assert!(find_symmetry(&noisy, &SymmetryTol { value: 0.0, unit: LengthUnit::Ang }).is_err());
+    }
+
+    #[test]
+    fn test_supercell_reports_all_translations() {
+        let ds = cell(
+            orthorhombic_lattice(8.0, 4.0, 4.0),
+            frac_atoms(&[("Po", [0.0, 0.0, 0.0]), ("Po", [0.5, 0.0, 0.0])]),
+        )
+        .find_symmetry()
+        .unwrap();
+        assert_eq!(ds.space_group.number, 221);
+        assert_eq!(ds.point_group, PointGroup::Oh);
+        // Only the tetragonal subgroup keeps the 2x1x1 cell, times two translations.
+        assert_eq!(ds.operations.len(), 32);
+    }
+
+    fn system_lattice(sg: &SpaceGroup) -> LatticeABC {
+        let (abc, angles) = match sg.crystal_system() {
+            CrystalSystem::Triclinic => ([5.0, 6.0, 7.0], [80.0, 85.0, 95.0]),
+            CrystalSystem::Monoclinic => ([5.0, 6.0, 7.0], [90.0, 100.0, 90.0]),
+            CrystalSystem::Orthorhombic => ([5.0, 6.0, 7.0], [90.0; 3]),
+            CrystalSystem::Tetragonal => ([5.0, 5.0, 7.0], [90.0; 3]),
+            CrystalSystem::Trigonal | CrystalSystem::Hexagonal => {
+                ([5.0, 5.0, 7.0], [90.0, 90.0, 120.0])
+            }
+            CrystalSystem::Cubic => ([6.0; 3], [90.0; 3]),
+        };
+        LatticeABC {
+            unit: None,
+            abc,
+            angles,
+        }
+    }
+
+    fn orbit(sg: &SpaceGroup, point: Vec3, shift: Vec3) -> Vec<Vec3> {
+        let mut out: Vec<Vec3> = Vec::new();
+        for op in sg.operations() {
+            let x = math::wrap(math::add(op.apply(point), shift));
+            if !out
+                .iter()
+                .any(|y| math::min_image(math::sub(*y, x)).iter().all(|d| d.abs() < 1e-6))
+            {
+                out.push(x);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_identifies_every_space_group() {
+        let shift = [0.137, 0.271, 0.419];
+        for sg in SpaceGroup::all() {
+            let mut atoms: Vec<PositionFracEntry> = orbit(sg, [0.1123, 0.2071, 0.3313], shift)
+                .into_iter()
+                .map(|x| atom("A", x))
+                .collect();
+            atoms.extend(
+                orbit(sg, [0.3791, 0.0547, 0.1931], shift)
+                    .into_iter()
+                    .map(|x| atom("B", x)),
+            );
+            let n = atoms.len();
+            let ds = cell(system_lattice(sg), PositionsFrac { positions: atoms })
+                .find_symmetry()
+                .unwrap();
+            assert_eq!(ds.space_group.number, sg.number, "{sg}");
+            assert_eq!(ds.operations.len() * 2, n, "{sg}");
+        }
+    }
+
+    #[test]
+    fn test_identifies_permuted_settings() {
+        // Cyclic permutation of the axes gives non-standard settings, e.g. Pnma as Pbnm.
+        for sg in SpaceGroup::all().iter().filter(|sg| sg.number < 75) {
+            let atoms: Vec<PositionFracEntry> = orbit(sg, [0.1123, 0.2071, 0.3313], [0.0; 3])
+                .into_iter()
+                .map(|x| atom("A", [x[1], x[2], x[0]]))
+                .chain(
+                    orbit(sg, [0.3791, 0.0547, 0.1931], [0.0; 3])
+                        .into_iter()
+                        .map(|x| atom("B", [x[1], x[2], x[0]])),
+                )
+                .collect();
+            let mut lattice = system_lattice(sg);
+            lattice.abc = [lattice.abc[1], lattice.abc[2], lattice.abc[0]];
+            lattice.angles = [lattice.angles[1], lattice.angles[2], lattice.angles[0]];
+            let ds = cell(lattice, PositionsFrac { positions: atoms }).find_symmetry().unwrap();
+            assert_eq!(ds.space_group.number, sg.number, "{sg}");
+        }
+    }
+}
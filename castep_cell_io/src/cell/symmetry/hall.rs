@@ -0,0 +1,177 @@
+//! Hall symbol interpreter used to generate the built-in space-group table.
+//!
+//! Only the subset of the notation needed for the 230 standard settings is supported:
+//! lattice symbol with optional `-` (centrosymmetric), up to three matrix symbols with
+//! default or explicit axes, screw digits, translation letters and an optional origin
+//! shift `(vx vy vz)` in units of 1/12.
+
+use crate::math::{self, IMat3, Vec3};
+
+use super::space_group::Centering;
+
+/// Coset representatives of the group described by `hall`, one per rotation,
+/// together with its lattice centering.
+pub(crate) fn generate(hall: &str) -> (Centering, Vec<(IMat3, Vec3)>) {
+    let (body, shift) = match hall.split_once('(') {
+        Some((body, shift)) => {
+            let v: Vec<f64> = shift
+                .trim_end_matches(')')
+                .split_whitespace()
+                .map(|x| x.parse::<f64>().expect("origin shift component") / 12.0)
+                .collect();
+            (body, [v[0], v[1], v[2]])
+        }
+        None => (hall, [0.0; 3]),
+    };
+    let mut tokens = body.split_whitespace();
+    let lattice = tokens.next().expect("lattice symbol");
+    let centrosymmetric = lattice.starts_with('-');
+    let centering = match lattice.trim_start_matches('-') {
+        "P" => Centering::P,
+        "A" => Centering::A,
+        "B" => Centering::B,
+        "C" => Centering::C,
+        "I" => Centering::I,
+        "F" => Centering::F,
+        "R" => Centering::R,
+        other => panic!("unsupported lattice symbol {other}"),
+    };
+
+    let mut generators = Vec::new();
+    if centrosymmetric {
+        generators.push((neg(math::IIDENTITY), [0.0; 3]));
+    }
+    let mut previous: Option<(u32, char)> = None;
+    for (position, token) in tokens.enumerate() {
+        let (op, order, axis) = parse_matrix_symbol(token, position, previous);
+        previous = Some((order, axis));
+        generators.push(op);
+    }
+
+    let mut ops: Vec<(IMat3, Vec3)> = vec![(math::IIDENTITY, [0.0; 3])];
+    loop {
+        let mut added = false;
+        for i in 0..ops.len() {
+            for g in &generators {
+                let (w1, t1) = ops[i];
+                let w = math::imat_mul(w1, g.0);
+                if ops.iter().any(|(x, _)| *x == w) {
+                    continue;
+                }
+                let t = math::wrap(math::add(math::mat_vec(math::to_f64(w1), g.1), t1));
+                ops.push((w, t));
+                added = true;
+            }
+        }
+        if !added {
+            break;
+        }
+    }
+
+    for (w, t) in ops.iter_mut() {
+        let moved = math::sub(shift, math::mat_vec(math::to_f64(*w), shift));
+        *t = math::wrap(math::add(*t, moved));
+    }
+    (centering, ops)
+}
+
+fn neg(w: IMat3) -> IMat3 {
+    w.map(|row| row.map(|x| -x))
+}
+
+fn parse_matrix_symbol(
+    token: &str,
+    position: usize,
+    previous: Option<(u32, char)>,
+) -> ((IMat3, Vec3), u32, char) {
+    let mut chars = token.chars().peekable();
+    let improper = chars.next_if_eq(&'-').is_some();
+    let order = chars.next().and_then(|c| c.to_digit(10)).expect("rotation order");
+    let screw = chars.next_if(|c| c.is_ascii_digit()).and_then(|c| c.to_digit(10));
+    let mut axis = None;
+    let mut translation = [0.0; 3];
+    for c in chars {
+        match c {
+            'x' | 'y' | 'z' | '\'' | '"' | '*' => axis = Some(c),
+            'a' => translation[0] += 0.5,
+            'b' => translation[1] += 0.5,
+            'c' => translation[2] += 0.5,
+            'n' => translation = math::add(translation, [0.5; 3]),
+            'u' => translation[0] += 0.25,
+            'v' => translation[1] += 0.25,
+            'w' => translation[2] += 0.25,
+            'd' => translation = math::add(translation, [0.25; 3]),
+            other => panic!("unsupported Hall symbol character {other}"),
+        }
+    }
+    let axis = axis.unwrap_or(match (position, previous) {
+        (0, _) => 'z',
+        (1, Some((2 | 4, _))) if order == 2 => 'x',
+        (1, Some((3 | 6, _))) if order == 2 => '\'',
+        (2, _) if order == 3 => '*',
+        _ => 'z',
+    });
+    let w = rotation(order, axis);
+    if let Some(s) = screw {
+        let fraction = f64::from(s) / f64::from(order);
+        match axis {
+            'x' => translation[0] += fraction,
+            'y' => translation[1] += fraction,
+            _ => translation[2] += fraction,
+        }
+    }
+    let w = if improper { neg(w) } else { w };
+    ((w, translation), order, axis)
+}
+
+/// Proper rotation of the given order about a Hall axis, acting on column vectors.
+fn rotation(order: u32, axis: char) -> IMat3 {
+    match (order, axis) {
+        (1, _) => math::IIDENTITY,
+        (2, 'x') => [[1, 0, 0], [0, -1, 0], [0, 0, -1]],
+        (2, 'y') => [[-1, 0, 0], [0, 1, 0], [0, 0, -1]],
+        (2, 'z') => [[-1, 0, 0], [0, -1, 0], [0, 0, 1]],
+        (2, '\'') => [[0, -1, 0], [-1, 0, 0], [0, 0, -1]],
+        (2, '"') => [[0, 1, 0], [1, 0, 0], [0, 0, -1]],
+        (3, 'x') => [[1, 0, 0], [0, 0, -1], [0, 1, -1]],
+        (3, 'y') => [[-1, 0, 1], [0, 1, 0], [-1, 0, 0]],
+        (3, 'z') => [[0, -1, 0], [1, -1, 0], [0, 0, 1]],
+        (3, '*') => [[0, 0, 1], [1, 0, 0], [0, 1, 0]],
+        (4, 'x') => [[1, 0, 0], [0, 0, -1], [0, 1, 0]],
+        (4, 'y') => [[0, 0, 1], [0, 1, 0], [-1, 0, 0]],
+        (4, 'z') => [[0, -1, 0], [1, 0, 0], [0, 0, 1]],
+        (6, 'x') => [[1, 0, 0], [0, 1, -1], [0, 1, 0]],
+        (6, 'y') => [[0, 0, 1], [0, 1, 0], [-1, 0, 1]],
+        (6, 'z') => [[1, -1, 0], [1, 0, 0], [0, 0, 1]],
+        other => panic!("unsupported Hall rotation {other:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_p21_c() {
+        let (centering, ops) = generate("-P 2ybc");
+        assert_eq!(centering, Centering::P);
+        assert_eq!(ops.len(), 4);
+        let screw = ops
+            .iter()
+            .find(|(w, _)| *w == [[-1, 0, 0], [0, 1, 0], [0, 0, -1]])
+            .unwrap();
+        assert_eq!(screw.1, [0.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_origin_shift() {
+        // P3_1 12: the two-fold along a-b sits at z = 1/3 after the shift.
+        let (_, ops) = generate("P 31 2c (0 0 1)");
+        assert_eq!(ops.len(), 6);
+        let two_fold = ops
+            .iter()
+            .find(|(w, _)| *w == [[0, -1, 0], [-1, 0, 0], [0, 0, -1]])
+            .unwrap();
+        assert!((two_fold.1[2] - 2.0 / 3.0).abs() < 1e-12);
+    }
+}
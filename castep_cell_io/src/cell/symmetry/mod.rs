@@ -1,7 +1,17 @@
+mod finder;
+mod hall;
+mod point_group;
+mod space_group;
 mod symmetry_ops;
 mod symmetry_tol;
 mod symmetry_generate;
+mod wyckoff;
 
-pub use symmetry_ops::{SymmetryOp, SymmetryOps};
+pub use finder::{SymmetryDataset, find_symmetry};
+pub(crate) use finder::{atom_kinds, primitive_transformation};
+pub use point_group::{CrystalSystem, PointGroup};
+pub use space_group::{Centering, SpaceGroup};
+pub use symmetry_ops::{SymmetryOp, SymmetryOpFrac, SymmetryOps, SymmetryViolation};
 pub use symmetry_tol::SymmetryTol;
 pub use symmetry_generate::SymmetryGenerate;
+pub use wyckoff::AsymmetricUnit;
@@ -0,0 +1,239 @@
+use crate::math::{self, IMat3};
+
+/// The seven crystal systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CrystalSystem {
+    Triclinic,
+    Monoclinic,
+    Orthorhombic,
+    Tetragonal,
+    Trigonal,
+    Hexagonal,
+    Cubic,
+}
+
+/// The 32 crystallographic point groups, named by their Schoenflies symbol.
+///
+/// Use [`PointGroup::symbol`] for the Hermann–Mauguin (international) symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PointGroup {
+    C1,
+    Ci,
+    C2,
+    Cs,
+    C2h,
+    D2,
+    C2v,
+    D2h,
+    C4,
+    S4,
+    C4h,
+    D4,
+    C4v,
+    D2d,
+    D4h,
+    C3,
+    C3i,
+    D3,
+    C3v,
+    D3d,
+    C6,
+    C3h,
+    C6h,
+    D6,
+    C6v,
+    D3h,
+    D6h,
+    T,
+    Th,
+    O,
+    Td,
+    Oh,
+}
+
+/// Counts of rotation types `[-6, -4, -3, -2, -1, 1, 2, 3, 4, 6]` for every point group.
+const ROTATION_TYPE_COUNTS: [(PointGroup, [usize; 10]); 32] = [
+    (PointGroup::C1, [0, 0, 0, 0, 0, 1, 0, 0, 0, 0]),
+    (PointGroup::Ci, [0, 0, 0, 0, 1, 1, 0, 0, 0, 0]),
+    (PointGroup::C2, [0, 0, 0, 0, 0, 1, 1, 0, 0, 0]),
+    (PointGroup::Cs, [0, 0, 0, 1, 0, 1, 0, 0, 0, 0]),
+    (PointGroup::C2h, [0, 0, 0, 1, 1, 1, 1, 0, 0, 0]),
+    (PointGroup::D2, [0, 0, 0, 0, 0, 1, 3, 0, 0, 0]),
+    (PointGroup::C2v, [0, 0, 0, 2, 0, 1, 1, 0, 0, 0]),
+    (PointGroup::D2h, [0, 0, 0, 3, 1, 1, 3, 0, 0, 0]),
+    (PointGroup::C4, [0, 0, 0, 0, 0, 1, 1, 0, 2, 0]),
+    (PointGroup::S4, [0, 2, 0, 0, 0, 1, 1, 0, 0, 0]),
+    (PointGroup::C4h, [0, 2, 0, 1, 1, 1, 1, 0, 2, 0]),
+    (PointGroup::D4, [0, 0, 0, 0, 0, 1, 5, 0, 2, 0]),
+    (PointGroup::C4v, [0, 0, 0, 4, 0, 1, 1, 0, 2, 0]),
+    (PointGroup::D2d, [0, 2, 0, 2, 0, 1, 3, 0, 0, 0]),
+    (PointGroup::D4h, [0, 2, 0, 5, 1, 1, 5, 0, 2, 0]),
+    (PointGroup::C3, [0, 0, 0, 0, 0, 1, 0, 2, 0, 0]),
+    (PointGroup::C3i, [0, 0, 2, 0, 1, 1, 0, 2, 0, 0]),
+    (PointGroup::D3, [0, 0, 0, 0, 0, 1, 3, 2, 0, 0]),
+    (PointGroup::C3v, [0, 0, 0, 3, 0, 1, 0, 2, 0, 0]),
+    (PointGroup::D3d, [0, 0, 2, 3, 1, 1, 3, 2, 0, 0]),
+    (PointGroup::C6, [0, 0, 0, 0, 0, 1, 1, 2, 0, 2]),
+    (PointGroup::C3h, [2, 0, 0, 1, 0, 1, 0, 2, 0, 0]),
+    (PointGroup::C6h, [2, 0, 2, 1, 1, 1, 1, 2, 0, 2]),
+    (PointGroup::D6, [0, 0, 0, 0, 0, 1, 7, 2, 0, 2]),
+    (PointGroup::C6v, [0, 0, 0, 6, 0, 1, 1, 2, 0, 2]),
+    (PointGroup::D3h, [2, 0, 0, 4, 0, 1, 3, 2, 0, 0]),
+    (PointGroup::D6h, [2, 0, 2, 7, 1, 1, 7, 2, 0, 2]),
+    (PointGroup::T, [0, 0, 0, 0, 0, 1, 3, 8, 0, 0]),
+    (PointGroup::Th, [0, 0, 8, 3, 1, 1, 3, 8, 0, 0]),
+    (PointGroup::O, [0, 0, 0, 0, 0, 1, 9, 8, 6, 0]),
+    (PointGroup::Td, [0, 6, 0, 6, 0, 1, 3, 8, 0, 0]),
+    (PointGroup::Oh, [0, 6, 8, 9, 1, 1, 9, 8, 6, 0]),
+];
+
+impl PointGroup {
+    /// Identifies the point group from its set of integer rotation matrices.
+    ///
+    /// Returns `None` if the rotation-type census matches no crystallographic point group.
+    pub fn from_rotations(rotations: &[[[i32; 3]; 3]]) -> Option<Self> {
+        let mut counts = [0usize; 10];
+        for w in rotations {
+            let index = match rotation_type(*w)? {
+                -6 => 0,
+                -4 => 1,
+                -3 => 2,
+                -2 => 3,
+                -1 => 4,
+                1 => 5,
+                2 => 6,
+                3 => 7,
+                4 => 8,
+                _ => 9,
+            };
+            counts[index] += 1;
+        }
+        ROTATION_TYPE_COUNTS
+            .iter()
+            .find(|(_, c)| *c == counts)
+            .map(|(pg, _)| *pg)
+    }
+
+    /// Hermann–Mauguin symbol, e.g. `"m-3m"`.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PointGroup::C1 => "1",
+            PointGroup::Ci => "-1",
+            PointGroup::C2 => "2",
+            PointGroup::Cs => "m",
+            PointGroup::C2h => "2/m",
+            PointGroup::D2 => "222",
+            PointGroup::C2v => "mm2",
+            PointGroup::D2h => "mmm",
+            PointGroup::C4 => "4",
+            PointGroup::S4 => "-4",
+            PointGroup::C4h => "4/m",
+            PointGroup::D4 => "422",
+            PointGroup::C4v => "4mm",
+            PointGroup::D2d => "-42m",
+            PointGroup::D4h => "4/mmm",
+            PointGroup::C3 => "3",
+            PointGroup::C3i => "-3",
+            PointGroup::D3 => "32",
+            PointGroup::C3v => "3m",
+            PointGroup::D3d => "-3m",
+            PointGroup::C6 => "6",
+            PointGroup::C3h => "-6",
+            PointGroup::C6h => "6/m",
+            PointGroup::D6 => "622",
+            PointGroup::C6v => "6mm",
+            PointGroup::D3h => "-6m2",
+            PointGroup::D6h => "6/mmm",
+            PointGroup::T => "23",
+            PointGroup::Th => "m-3",
+            PointGroup::O => "432",
+            PointGroup::Td => "-43m",
+            PointGroup::Oh => "m-3m",
+        }
+    }
+
+    /// Number of operations in the point group.
+    pub fn order(&self) -> usize {
+        ROTATION_TYPE_COUNTS
+            .iter()
+            .find(|(pg, _)| pg == self)
+            .map(|(_, c)| c.iter().sum())
+            .unwrap_or(1)
+    }
+
+    /// Returns `true` if the group contains the inversion.
+    pub fn is_centrosymmetric(&self) -> bool {
+        ROTATION_TYPE_COUNTS
+            .iter()
+            .any(|(pg, c)| pg == self && c[4] == 1)
+    }
+
+    /// The crystal system the point group belongs to.
+    pub fn crystal_system(&self) -> CrystalSystem {
+        use PointGroup::*;
+        match self {
+            C1 | Ci => CrystalSystem::Triclinic,
+            C2 | Cs | C2h => CrystalSystem::Monoclinic,
+            D2 | C2v | D2h => CrystalSystem::Orthorhombic,
+            C4 | S4 | C4h | D4 | C4v | D2d | D4h => CrystalSystem::Tetragonal,
+            C3 | C3i | D3 | C3v | D3d => CrystalSystem::Trigonal,
+            C6 | C3h | C6h | D6 | C6v | D3h | D6h => CrystalSystem::Hexagonal,
+            T | Th | O | Td | Oh => CrystalSystem::Cubic,
+        }
+    }
+}
+
+impl std::fmt::Display for PointGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// Rotation type of an integer rotation: the order of the rotation, negated for
+/// improper rotations (`-2` is a mirror).
+pub(crate) fn rotation_type(w: IMat3) -> Option<i32> {
+    let trace = w[0][0] + w[1][1] + w[2][2];
+    match (math::imat_det(w), trace) {
+        (1, 3) => Some(1),
+        (1, -1) => Some(2),
+        (1, 0) => Some(3),
+        (1, 1) => Some(4),
+        (1, 2) => Some(6),
+        (-1, -3) => Some(-1),
+        (-1, 1) => Some(-2),
+        (-1, 0) => Some(-3),
+        (-1, -1) => Some(-4),
+        (-1, -2) => Some(-6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_types() {
+        assert_eq!(rotation_type(math::IIDENTITY), Some(1));
+        assert_eq!(rotation_type([[-1, 0, 0], [0, -1, 0], [0, 0, -1]]), Some(-1));
+        assert_eq!(rotation_type([[0, -1, 0], [1, 0, 0], [0, 0, 1]]), Some(4));
+        assert_eq!(rotation_type([[1, 0, 0], [0, 1, 0], [0, 0, -1]]), Some(-2));
+    }
+
+    #[test]
+    fn test_from_rotations() {
+        let mirror = [[1, 0, 0], [0, 1, 0], [0, 0, -1]];
+        assert_eq!(PointGroup::from_rotations(&[math::IIDENTITY, mirror]), Some(PointGroup::Cs));
+        assert_eq!(PointGroup::from_rotations(&[mirror]), None);
+    }
+
+    #[test]
+    fn test_orders_and_systems() {
+        assert_eq!(PointGroup::Oh.order(), 48);
+        assert_eq!(PointGroup::D6h.order(), 24);
+        assert!(PointGroup::C2h.is_centrosymmetric());
+        assert!(!PointGroup::Td.is_centrosymmetric());
+        assert_eq!(PointGroup::D3d.crystal_system(), CrystalSystem::Trigonal);
+        assert_eq!(PointGroup::D3h.to_string(), "-6m2");
+    }
+}
@@ -0,0 +1,475 @@
+use std::sync::OnceLock;
+
+use crate::math::{self, IMat3, Vec3};
+
+use super::hall;
+use super::point_group::{CrystalSystem, PointGroup};
+use super::symmetry_ops::SymmetryOpFrac;
+
+/// Lattice centering of a conventional cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Centering {
+    /// Primitive.
+    P,
+    /// Centred on the A face.
+    A,
+    /// Centred on the B face.
+    B,
+    /// Centred on the C face.
+    C,
+    /// Body centred.
+    I,
+    /// All faces centred.
+    F,
+    /// Rhombohedral in hexagonal axes (obverse setting).
+    R,
+}
+
+impl Centering {
+    /// Centering translations, including the zero vector.
+    pub fn vectors(&self) -> Vec<[f64; 3]> {
+        let mut v = vec![[0.0; 3]];
+        match self {
+            Centering::P => {}
+            Centering::A => v.push([0.0, 0.5, 0.5]),
+            Centering::B => v.push([0.5, 0.0, 0.5]),
+            Centering::C => v.push([0.5, 0.5, 0.0]),
+            Centering::I => v.push([0.5, 0.5, 0.5]),
+            Centering::F => v.extend([[0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]]),
+            Centering::R => v.extend([[2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0], [1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0]]),
+        }
+        v
+    }
+}
+
+/// One of the 230 space-group types in its standard ITA setting.
+///
+/// Monoclinic groups use the unique axis b (cell choice 1), rhombohedral groups use
+/// hexagonal axes, and groups with two origin choices use origin choice 2.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SpaceGroup {
+    /// ITA number, 1 to 230.
+    pub number: u32,
+    /// Short Hermann–Mauguin symbol, with screw axes written as `2_1`.
+    pub international: &'static str,
+    /// Hall symbol of the tabulated setting.
+    pub hall: &'static str,
+}
+
+impl SpaceGroup {
+    /// Looks up a space group by its ITA number.
+    pub fn from_number(number: u32) -> Option<&'static SpaceGroup> {
+        number
+            .checked_sub(1)
+            .and_then(|i| SPACE_GROUPS.get(i as usize))
+    }
+
+    /// Looks up a space group by its short Hermann–Mauguin symbol.
+    ///
+    /// Spaces and underscores are ignored, so `"P 21/c"` and `"P2_1/c"` both match.
+    pub fn from_symbol(symbol: &str) -> Option<&'static SpaceGroup> {
+        let key = |s: &str| s.chars().filter(|c| !matches!(c, ' ' | '_')).collect::<String>();
+        let wanted = key(symbol);
+        SPACE_GROUPS.iter().find(|sg| key(sg.international) == wanted)
+    }
+
+    /// All 230 space groups, ordered by number.
+    pub fn all() -> &'static [SpaceGroup] {
+        &SPACE_GROUPS
+    }
+
+    /// Lattice centering of the tabulated setting.
+    pub fn centering(&self) -> Centering {
+        tables()[self.index()].0
+    }
+
+    /// The crystal system of the group.
+    pub fn crystal_system(&self) -> CrystalSystem {
+        match self.number {
+            1..=2 => CrystalSystem::Triclinic,
+            3..=15 => CrystalSystem::Monoclinic,
+            16..=74 => CrystalSystem::Orthorhombic,
+            75..=142 => CrystalSystem::Tetragonal,
+            143..=167 => CrystalSystem::Trigonal,
+            168..=194 => CrystalSystem::Hexagonal,
+            _ => CrystalSystem::Cubic,
+        }
+    }
+
+    /// The point group of the space group.
+    pub fn point_group(&self) -> PointGroup {
+        let rotations: Vec<IMat3> = self.coset_representatives().iter().map(|op| op.rotation).collect();
+        PointGroup::from_rotations(&rotations).expect("tabulated groups are closed")
+    }
+
+    /// All operations of the conventional cell, including centering translations.
+    pub fn operations(&self) -> Vec<SymmetryOpFrac> {
+        let centering = self.centering().vectors();
+        centering
+            .iter()
+            .flat_map(|c| {
+                self.coset_representatives().iter().map(move |op| SymmetryOpFrac {
+                    rotation: op.rotation,
+                    translation: math::wrap(math::add(op.translation, *c)),
+                })
+            })
+            .collect()
+    }
+
+    /// One operation per rotation; the full group is these combined with the centering.
+    pub(crate) fn coset_representatives(&self) -> &'static [SymmetryOpFrac] {
+        &tables()[self.index()].1
+    }
+
+    fn index(&self) -> usize {
+        self.number as usize - 1
+    }
+}
+
+impl std::fmt::Display for SpaceGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.international, self.number)
+    }
+}
+
+fn tables() -> &'static [(Centering, Vec<SymmetryOpFrac>)] {
+    static TABLES: OnceLock<Vec<(Centering, Vec<SymmetryOpFrac>)>> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        SPACE_GROUPS
+            .iter()
+            .map(|sg| {
+                let (centering, ops) = hall::generate(sg.hall);
+                let ops = ops
+                    .into_iter()
+                    .map(|(rotation, translation): (IMat3, Vec3)| SymmetryOpFrac { rotation, translation })
+                    .collect();
+                (centering, ops)
+            })
+            .collect()
+    })
+}
+
+const fn sg(number: u32, international: &'static str, hall: &'static str) -> SpaceGroup {
+    SpaceGroup {
+        number,
+        international,
+        hall,
+    }
+}
+
+static SPACE_GROUPS: [SpaceGroup; 230] = [
+    sg(1, "P1", "P 1"),
+    sg(2, "P-1", "-P 1"),
+    sg(3, "P2", "P 2y"),
+    sg(4, "P2_1", "P 2yb"),
+    sg(5, "C2", "C 2y"),
+    sg(6, "Pm", "P -2y"),
+    sg(7, "Pc", "P -2yc"),
+    sg(8, "Cm", "C -2y"),
+    sg(9, "Cc", "C -2yc"),
+    sg(10, "P2/m", "-P 2y"),
+    sg(11, "P2_1/m", "-P 2yb"),
+    sg(12, "C2/m", "-C 2y"),
+    sg(13, "P2/c", "-P 2yc"),
+    sg(14, "P2_1/c", "-P 2ybc"),
+    sg(15, "C2/c", "-C 2yc"),
+    sg(16, "P222", "P 2 2"),
+    sg(17, "P222_1", "P 2c 2"),
+    sg(18, "P2_12_12", "P 2 2ab"),
+    sg(19, "P2_12_12_1", "P 2ac 2ab"),
+    sg(20, "C222_1", "C 2c 2"),
+    sg(21, "C222", "C 2 2"),
+    sg(22, "F222", "F 2 2"),
+    sg(23, "I222", "I 2 2"),
+    sg(24, "I2_12_12_1", "I 2b 2c"),
+    sg(25, "Pmm2", "P 2 -2"),
+    sg(26, "Pmc2_1", "P 2c -2"),
+    sg(27, "Pcc2", "P 2 -2c"),
+    sg(28, "Pma2", "P 2 -2a"),
+    sg(29, "Pca2_1", "P 2c -2ac"),
+    sg(30, "Pnc2", "P 2 -2bc"),
+    sg(31, "Pmn2_1", "P 2ac -2"),
+    sg(32, "Pba2", "P 2 -2ab"),
+    sg(33, "Pna2_1", "P 2c -2n"),
+    sg(34, "Pnn2", "P 2 -2n"),
+    sg(35, "Cmm2", "C 2 -2"),
+    sg(36, "Cmc2_1", "C 2c -2"),
+    sg(37, "Ccc2", "C 2 -2c"),
+    sg(38, "Amm2", "A 2 -2"),
+    sg(39, "Aem2", "A 2 -2c"),
+    sg(40, "Ama2", "A 2 -2a"),
+    sg(41, "Aea2", "A 2 -2ac"),
+    sg(42, "Fmm2", "F 2 -2"),
+    sg(43, "Fdd2", "F 2 -2d"),
+    sg(44, "Imm2", "I 2 -2"),
+    sg(45, "Iba2", "I 2 -2c"),
+    sg(46, "Ima2", "I 2 -2a"),
+    sg(47, "Pmmm", "-P 2 2"),
+    sg(48, "Pnnn", "-P 2ab 2bc"),
+    sg(49, "Pccm", "-P 2 2c"),
+    sg(50, "Pban", "-P 2ab 2b"),
+    sg(51, "Pmma", "-P 2a 2a"),
+    sg(52, "Pnna", "-P 2a 2bc"),
+    sg(53, "Pmna", "-P 2ac 2"),
+    sg(54, "Pcca", "-P 2a 2ac"),
+    sg(55, "Pbam", "-P 2 2ab"),
+    sg(56, "Pccn", "-P 2ab 2ac"),
+    sg(57, "Pbcm", "-P 2c 2b"),
+    sg(58, "Pnnm", "-P 2 2n"),
+    sg(59, "Pmmn", "-P 2ab 2a"),
+    sg(60, "Pbcn", "-P 2n 2ab"),
+    sg(61, "Pbca", "-P 2ac 2ab"),
+    sg(62, "Pnma", "-P 2ac 2n"),
+    sg(63, "Cmcm", "-C 2c 2"),
+    sg(64, "Cmce", "-C 2bc 2"),
+    sg(65, "Cmmm", "-C 2 2"),
+    sg(66, "Cccm", "-C 2 2c"),
+    sg(67, "Cmme", "-C 2b 2"),
+    sg(68, "Ccce", "-C 2a 2ac"),
+    sg(69, "Fmmm", "-F 2 2"),
+    sg(70, "Fddd", "-F 2uv 2vw"),
+    sg(71, "Immm", "-I 2 2"),
+    sg(72, "Ibam", "-I 2 2c"),
+    sg(73, "Ibca", "-I 2b 2c"),
+    sg(74, "Imma", "-I 2b 2"),
+    sg(75, "P4", "P 4"),
+    sg(76, "P4_1", "P 4w"),
+    sg(77, "P4_2", "P 4c"),
+    sg(78, "P4_3", "P 4cw"),
+    sg(79, "I4", "I 4"),
+    sg(80, "I4_1", "I 4bw"),
+    sg(81, "P-4", "P -4"),
+    sg(82, "I-4", "I -4"),
+    sg(83, "P4/m", "-P 4"),
+    sg(84, "P4_2/m", "-P 4c"),
+    sg(85, "P4/n", "-P 4a"),
+    sg(86, "P4_2/n", "-P 4bc"),
+    sg(87, "I4/m", "-I 4"),
+    sg(88, "I4_1/a", "-I 4ad"),
+    sg(89, "P422", "P 4 2"),
+    sg(90, "P42_12", "P 4ab 2ab"),
+    sg(91, "P4_122", "P 4w 2c"),
+    sg(92, "P4_12_12", "P 4abw 2nw"),
+    sg(93, "P4_222", "P 4c 2"),
+    sg(94, "P4_22_12", "P 4n 2n"),
+    sg(95, "P4_322", "P 4cw 2c"),
+    sg(96, "P4_32_12", "P 4nw 2abw"),
+    sg(97, "I422", "I 4 2"),
+    sg(98, "I4_122", "I 4bw 2bw"),
+    sg(99, "P4mm", "P 4 -2"),
+    sg(100, "P4bm", "P 4 -2ab"),
+    sg(101, "P4_2cm", "P 4c -2c"),
+    sg(102, "P4_2nm", "P 4n -2n"),
+    sg(103, "P4cc", "P 4 -2c"),
+    sg(104, "P4nc", "P 4 -2n"),
+    sg(105, "P4_2mc", "P 4c -2"),
+    sg(106, "P4_2bc", "P 4c -2ab"),
+    sg(107, "I4mm", "I 4 -2"),
+    sg(108, "I4cm", "I 4 -2c"),
+    sg(109, "I4_1md", "I 4bw -2"),
+    sg(110, "I4_1cd", "I 4bw -2c"),
+    sg(111, "P-42m", "P -4 2"),
+    sg(112, "P-42c", "P -4 2c"),
+    sg(113, "P-42_1m", "P -4 2ab"),
+    sg(114, "P-42_1c", "P -4 2n"),
+    sg(115, "P-4m2", "P -4 -2"),
+    sg(116, "P-4c2", "P -4 -2c"),
+    sg(117, "P-4b2", "P -4 -2ab"),
+    sg(118, "P-4n2", "P -4 -2n"),
+    sg(119, "I-4m2", "I -4 -2"),
+    sg(120, "I-4c2", "I -4 -2c"),
+    sg(121, "I-42m", "I -4 2"),
+    sg(122, "I-42d", "I -4 2bw"),
+    sg(123, "P4/mmm", "-P 4 2"),
+    sg(124, "P4/mcc", "-P 4 2c"),
+    sg(125, "P4/nbm", "-P 4a 2b"),
+    sg(126, "P4/nnc", "-P 4a 2bc"),
+    sg(127, "P4/mbm", "-P 4 2ab"),
+    sg(128, "P4/mnc", "-P 4 2n"),
+    sg(129, "P4/nmm", "-P 4a 2a"),
+    sg(130, "P4/ncc", "-P 4a 2ac"),
+    sg(131, "P4_2/mmc", "-P 4c 2"),
+    sg(132, "P4_2/mcm", "-P 4c 2c"),
+    sg(133, "P4_2/nbc", "-P 4ac 2b"),
+    sg(134, "P4_2/nnm", "-P 4ac 2bc"),
+    sg(135, "P4_2/mbc", "-P 4c 2ab"),
+    sg(136, "P4_2/mnm", "-P 4n 2n"),
+    sg(137, "P4_2/nmc", "-P 4ac 2a"),
+    sg(138, "P4_2/ncm", "-P 4ac 2ac"),
+    sg(139, "I4/mmm", "-I 4 2"),
+    sg(140, "I4/mcm", "-I 4 2c"),
+    sg(141, "I4_1/amd", "-I 4bd 2"),
+    sg(142, "I4_1/acd", "-I 4bd 2c"),
+    sg(143, "P3", "P 3"),
+    sg(144, "P3_1", "P 31"),
+    sg(145, "P3_2", "P 32"),
+    sg(146, "R3", "R 3"),
+    sg(147, "P-3", "-P 3"),
+    sg(148, "R-3", "-R 3"),
+    sg(149, "P312", "P 3 2"),
+    sg(150, "P321", "P 3 2\""),
+    sg(151, "P3_112", "P 31 2c (0 0 1)"),
+    sg(152, "P3_121", "P 31 2\""),
+    sg(153, "P3_212", "P 32 2c (0 0 -1)"),
+    sg(154, "P3_221", "P 32 2\""),
+    sg(155, "R32", "R 3 2\""),
+    sg(156, "P3m1", "P 3 -2\""),
+    sg(157, "P31m", "P 3 -2"),
+    sg(158, "P3c1", "P 3 -2\"c"),
+    sg(159, "P31c", "P 3 -2c"),
+    sg(160, "R3m", "R 3 -2\""),
+    sg(161, "R3c", "R 3 -2\"c"),
+    sg(162, "P-31m", "-P 3 2"),
+    sg(163, "P-31c", "-P 3 2c"),
+    sg(164, "P-3m1", "-P 3 2\""),
+    sg(165, "P-3c1", "-P 3 2\"c"),
+    sg(166, "R-3m", "-R 3 2\""),
+    sg(167, "R-3c", "-R 3 2\"c"),
+    sg(168, "P6", "P 6"),
+    sg(169, "P6_1", "P 61"),
+    sg(170, "P6_5", "P 65"),
+    sg(171, "P6_2", "P 62"),
+    sg(172, "P6_4", "P 64"),
+    sg(173, "P6_3", "P 6c"),
+    sg(174, "P-6", "P -6"),
+    sg(175, "P6/m", "-P 6"),
+    sg(176, "P6_3/m", "-P 6c"),
+    sg(177, "P622", "P 6 2"),
+    sg(178, "P6_122", "P 61 2 (0 0 -1)"),
+    sg(179, "P6_522", "P 65 2 (0 0 1)"),
+    sg(180, "P6_222", "P 62 2c (0 0 1)"),
+    sg(181, "P6_422", "P 64 2c (0 0 -1)"),
+    sg(182, "P6_322", "P 6c 2c"),
+    sg(183, "P6mm", "P 6 -2"),
+    sg(184, "P6cc", "P 6 -2c"),
+    sg(185, "P6_3cm", "P 6c -2"),
+    sg(186, "P6_3mc", "P 6c -2c"),
+    sg(187, "P-6m2", "P -6 2"),
+    sg(188, "P-6c2", "P -6c 2"),
+    sg(189, "P-62m", "P -6 -2"),
+    sg(190, "P-62c", "P -6c -2c"),
+    sg(191, "P6/mmm", "-P 6 2"),
+    sg(192, "P6/mcc", "-P 6 2c"),
+    sg(193, "P6_3/mcm", "-P 6c 2"),
+    sg(194, "P6_3/mmc", "-P 6c 2c"),
+    sg(195, "P23", "P 2 2 3"),
+    sg(196, "F23", "F 2 2 3"),
+    sg(197, "I23", "I 2 2 3"),
+    sg(198, "P2_13", "P 2ac 2ab 3"),
+    sg(199, "I2_13", "I 2b 2c 3"),
+    sg(200, "Pm-3", "-P 2 2 3"),
+    sg(201, "Pn-3", "-P 2ab 2bc 3"),
+    sg(202, "Fm-3", "-F 2 2 3"),
+    sg(203, "Fd-3", "-F 2uv 2vw 3"),
+    sg(204, "Im-3", "-I 2 2 3"),
+    sg(205, "Pa-3", "-P 2ac 2ab 3"),
+    sg(206, "Ia-3", "-I 2b 2c 3"),
+    sg(207, "P432", "P 4 2 3"),
+    sg(208, "P4_232", "P 4n 2 3"),
+    sg(209, "F432", "F 4 2 3"),
+    sg(210, "F4_132", "F 4d 2 3"),
+    sg(211, "I432", "I 4 2 3"),
+    sg(212, "P4_332", "P 4acd 2ab 3"),
+    sg(213, "P4_132", "P 4bd 2ab 3"),
+    sg(214, "I4_132", "I 4bd 2c 3"),
+    sg(215, "P-43m", "P -4 2 3"),
+    sg(216, "F-43m", "F -4 2 3"),
+    sg(217, "I-43m", "I -4 2 3"),
+    sg(218, "P-43n", "P -4n 2 3"),
+    sg(219, "F-43c", "F -4c 2 3"),
+    sg(220, "I-43d", "I -4bd 2c 3"),
+    sg(221, "Pm-3m", "-P 4 2 3"),
+    sg(222, "Pn-3n", "-P 4a 2bc 3"),
+    sg(223, "Pm-3n", "-P 4n 2 3"),
+    sg(224, "Pn-3m", "-P 4bc 2bc 3"),
+    sg(225, "Fm-3m", "-F 4 2 3"),
+    sg(226, "Fm-3c", "-F 4c 2 3"),
+    sg(227, "Fd-3m", "-F 4vw 2vw 3"),
+    sg(228, "Fd-3c", "-F 4cvw 2vw 3"),
+    sg(229, "Im-3m", "-I 4 2 3"),
+    sg(230, "Ia-3d", "-I 4bd 2c 3"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected_point_group(number: u32) -> PointGroup {
+        use PointGroup::*;
+        match number {
+            1 => C1,
+            2 => Ci,
+            3..=5 => C2,
+            6..=9 => Cs,
+            10..=15 => C2h,
+            16..=24 => D2,
+            25..=46 => C2v,
+            47..=74 => D2h,
+            75..=80 => C4,
+            81..=82 => S4,
+            83..=88 => C4h,
+            89..=98 => D4,
+            99..=110 => C4v,
+            111..=122 => D2d,
+            123..=142 => D4h,
+            143..=146 => C3,
+            147..=148 => C3i,
+            149..=155 => D3,
+            156..=161 => C3v,
+            162..=167 => D3d,
+            168..=173 => C6,
+            174 => C3h,
+            175..=176 => C6h,
+            177..=182 => D6,
+            183..=186 => C6v,
+            187..=190 => D3h,
+            191..=194 => D6h,
+            195..=199 => T,
+            200..=206 => Th,
+            207..=214 => O,
+            215..=220 => Td,
+            _ => Oh,
+        }
+    }
+
+    #[test]
+    fn test_table_is_consistent() {
+        for (i, sg) in SpaceGroup::all().iter().enumerate() {
+            assert_eq!(sg.number as usize, i + 1);
+            let pg = sg.point_group();
+            assert_eq!(pg, expected_point_group(sg.number), "{sg}");
+            assert_eq!(pg.crystal_system(), sg.crystal_system(), "{sg}");
+            assert_eq!(sg.coset_representatives().len(), pg.order(), "{sg}");
+            let letter = sg.international.chars().next().unwrap();
+            assert_eq!(format!("{:?}", sg.centering()), letter.to_string(), "{sg}");
+        }
+    }
+
+    #[test]
+    fn test_operations_form_a_group() {
+        for sg in SpaceGroup::all() {
+            let ops = sg.operations();
+            for a in &ops {
+                for b in &ops {
+                    let w = math::imat_mul(a.rotation, b.rotation);
+                    let t = a.apply(b.translation);
+                    let found = ops.iter().any(|c| {
+                        c.rotation == w
+                            && math::min_image(math::sub(c.translation, t))
+                                .iter()
+                                .all(|x| x.abs() < 1e-9)
+                    });
+                    assert!(found, "{sg} is not closed");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(SpaceGroup::from_number(225).unwrap().international, "Fm-3m");
+        assert_eq!(SpaceGroup::from_symbol("P 21/c").unwrap().number, 14);
+        assert!(SpaceGroup::from_number(0).is_none());
+        assert!(SpaceGroup::from_number(231).is_none());
+    }
+}
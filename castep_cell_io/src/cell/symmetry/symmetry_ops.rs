@@ -1,6 +1,19 @@
 use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue, FromCellValue, FromBlock, CResult, query::value_as_f64};
 
+use crate::math::{self, Mat3};
+use crate::{CellDocument, Lattice, Positions};
+
+use super::finder::atom_kinds;
+use super::symmetry_tol::SymmetryTol;
+
+/// Element-wise tolerance used when comparing Cartesian rotation matrices.
+const ROTATION_TOL: f64 = 1e-4;
+
 /// Represents a single symmetry operation, consisting of a 3x3 rotation matrix and a 3-element translation vector.
+///
+/// The rotation acts on Cartesian row vectors, `r' = r · rotation + translation · A`,
+/// where `A` holds the lattice vectors as rows; each stored row is one line of the block.
+/// The translation is fractional.
 #[derive(Debug, Clone, PartialEq, bon::Builder)]
 pub struct SymmetryOp {
     /// The 3x3 rotation matrix (stored as 3 rows of 3 elements each).
@@ -9,6 +22,139 @@ pub struct SymmetryOp {
     pub translation: [f64; 3],
 }
 
+impl SymmetryOp {
+    /// Converts a fractional operation into the Cartesian form used by `SYMMETRY_OPS`.
+    ///
+    /// Fails if the lattice vectors are linearly dependent.
+    pub fn from_frac(op: &SymmetryOpFrac, lattice: &Lattice) -> CResult<Self> {
+        let a = lattice.vectors();
+        let a_inv = math::lattice_inverse(a)?;
+        let w_t = math::transpose(math::to_f64(op.rotation));
+        let rotation = math::mat_mul(math::mat_mul(a_inv, w_t), a).map(|row| row.map(clean));
+        Ok(Self {
+            rotation,
+            translation: op.translation,
+        })
+    }
+
+    /// Converts the operation to fractional coordinates of `lattice`.
+    ///
+    /// Fails if the rotation does not map the lattice onto itself or the lattice vectors
+    /// are linearly dependent.
+    pub fn to_frac(&self, lattice: &Lattice) -> CResult<SymmetryOpFrac> {
+        let a = lattice.vectors();
+        let a_inv = math::lattice_inverse(a)?;
+        let w = math::transpose(math::mat_mul(math::mat_mul(a, self.rotation), a_inv));
+        let rotation = math::to_int(w, 1e-4).ok_or_else(|| {
+            castep_cell_fmt::Error::Message(
+                "symmetry operation rotation is not a lattice symmetry".into(),
+            )
+        })?;
+        Ok(SymmetryOpFrac {
+            rotation,
+            translation: self.translation,
+        })
+    }
+
+    /// The identity operation.
+    pub fn identity() -> Self {
+        Self {
+            rotation: math::IDENTITY,
+            translation: [0.0; 3],
+        }
+    }
+
+    /// Returns the operation that applies `other` first and then `self`.
+    ///
+    /// The translations are fractional, so the lattice is needed to carry the
+    /// translation of `other` through the rotation of `self`. Fails if the lattice
+    /// vectors are linearly dependent.
+    pub fn compose(&self, other: &SymmetryOp, lattice: &Lattice) -> CResult<SymmetryOp> {
+        let a = lattice.vectors();
+        let a_inv = math::lattice_inverse(a)?;
+        let carried = math::vec_mat(
+            math::vec_mat(math::vec_mat(other.translation, a), self.rotation),
+            a_inv,
+        );
+        Ok(SymmetryOp {
+            rotation: math::mat_mul(other.rotation, self.rotation).map(|row| row.map(clean)),
+            translation: math::add(carried, self.translation),
+        })
+    }
+
+    /// Returns the inverse operation.
+    ///
+    /// Fails if the rotation matrix is singular or the lattice vectors are linearly
+    /// dependent.
+    pub fn inverse(&self, lattice: &Lattice) -> CResult<SymmetryOp> {
+        let a = lattice.vectors();
+        let a_inv = math::lattice_inverse(a)?;
+        let r_inv = math::inverse(self.rotation).ok_or_else(|| {
+            castep_cell_fmt::Error::Message("symmetry operation rotation is singular".into())
+        })?;
+        let t = math::vec_mat(math::vec_mat(math::vec_mat(self.translation, a), r_inv), a_inv);
+        Ok(SymmetryOp {
+            rotation: r_inv.map(|row| row.map(clean)),
+            translation: math::scale(t, -1.0),
+        })
+    }
+
+    /// Returns `true` if the rotation preserves the lattice metric `G = A·Aᵀ`,
+    /// i.e. `Wᵀ·G·W = G` for its fractional form `W`, within a relative tolerance `tol`.
+    pub fn is_orthogonal(&self, lattice: &Lattice, tol: f64) -> bool {
+        let a = lattice.vectors();
+        let Some(a_inv) = math::inverse(a) else {
+            return false;
+        };
+        let w = math::transpose(math::mat_mul(math::mat_mul(a, self.rotation), a_inv));
+        let g = math::mat_mul(a, math::transpose(a));
+        let rotated = math::mat_mul(math::mat_mul(math::transpose(w), g), w);
+        let scale = g.iter().flatten().fold(0.0_f64, |m, x| m.max(x.abs()));
+        g.iter()
+            .flatten()
+            .zip(rotated.iter().flatten())
+            .all(|(x, y)| (x - y).abs() <= tol * scale)
+    }
+
+    /// Returns `true` if `self` and `other` are the same operation, with translations
+    /// compared modulo lattice vectors to within `tol` Å.
+    fn matches(&self, other: &SymmetryOp, a: Mat3, tol: f64) -> bool {
+        let same_rotation = self
+            .rotation
+            .iter()
+            .flatten()
+            .zip(other.rotation.iter().flatten())
+            .all(|(x, y)| (x - y).abs() < ROTATION_TOL);
+        let shift = math::sub(self.translation, other.translation);
+        same_rotation && math::periodic_distance(a, shift) < tol
+    }
+}
+
+/// Snaps round-off noise on the common rotation matrix values.
+fn clean(x: f64) -> f64 {
+    let r = (x * 2.0).round() / 2.0;
+    if (x - r).abs() < 1e-10 { r } else { x }
+}
+
+/// A symmetry operation in fractional coordinates, `x' = rotation · x + translation`.
+///
+/// This is the form used by the space-group tables and the symmetry finder; use
+/// [`SymmetryOp::from_frac`] to obtain the `SYMMETRY_OPS` entry for a given lattice.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymmetryOpFrac {
+    /// Integer rotation acting on fractional column vectors.
+    pub rotation: [[i32; 3]; 3],
+    /// Fractional translation.
+    pub translation: [f64; 3],
+}
+
+impl SymmetryOpFrac {
+    /// Applies the operation to a fractional coordinate.
+    pub fn apply(&self, x: [f64; 3]) -> [f64; 3] {
+        math::add(math::mat_vec(math::to_f64(self.rotation), x), self.translation)
+    }
+}
+
 /// Represents the SYMMETRY_OPS block.
 ///
 /// Contains a list of symmetry operations under which the unit cell is invariant.
@@ -29,6 +175,105 @@ pub struct SymmetryOps {
     pub ops: Vec<SymmetryOp>,
 }
 
+/// An atom that a symmetry operation does not map onto an equivalent atom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymmetryViolation {
+    /// Index of the operation in [`SymmetryOps::ops`].
+    pub op: usize,
+    /// Index of the atom in the positions block.
+    pub atom: usize,
+    /// Distance in Å from the image of the atom to the closest equivalent atom.
+    pub distance: f64,
+}
+
+impl SymmetryOps {
+    /// Checks that the operations form a group: every product of two operations must
+    /// be in the set, with translations compared modulo lattice vectors within `tolerance`.
+    pub fn check_closure(&self, lattice: &Lattice, tolerance: &SymmetryTol) -> CResult<()> {
+        if self.ops.is_empty() {
+            return Err(castep_cell_fmt::Error::Message("SYMMETRY_OPS contains no operations".into()));
+        }
+        let a = lattice.vectors();
+        let tol = tolerance.value * tolerance.unit.in_angstrom();
+        for (i, op_i) in self.ops.iter().enumerate() {
+            for (j, op_j) in self.ops.iter().enumerate() {
+                let product = op_i.compose(op_j, lattice)?;
+                if !self.ops.iter().any(|op| op.matches(&product, a, tol)) {
+                    return Err(castep_cell_fmt::Error::Message(format!(
+                        "SYMMETRY_OPS is not closed: the product of operations {} and {} is missing",
+                        i + 1,
+                        j + 1
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies every operation to `positions` and reports the atoms whose image is
+    /// further than `tolerance` from any equivalent atom.
+    ///
+    /// Atoms are equivalent if they share the same species, `SPIN` and `MIXTURE` weight.
+    /// Fails if the lattice vectors are linearly dependent.
+    pub fn violations(
+        &self,
+        lattice: &Lattice,
+        positions: &Positions,
+        tolerance: &SymmetryTol,
+    ) -> CResult<Vec<SymmetryViolation>> {
+        let a = lattice.vectors();
+        let a_inv = math::lattice_inverse(a)?;
+        let tol = tolerance.value * tolerance.unit.in_angstrom();
+        let coords = positions.frac_coords(lattice)?;
+        let kinds = atom_kinds(positions);
+        let mut violations = Vec::new();
+        for (op_index, op) in self.ops.iter().enumerate() {
+            for (atom, x) in coords.iter().enumerate() {
+                let r = math::vec_mat(math::frac_to_cart(a, *x), op.rotation);
+                let image = math::add(math::vec_mat(r, a_inv), op.translation);
+                let closest = coords
+                    .iter()
+                    .zip(&kinds)
+                    .filter(|(_, kind)| **kind == kinds[atom])
+                    .map(|(y, _)| math::periodic_distance(a, math::sub(image, *y)))
+                    .fold(f64::INFINITY, f64::min);
+                if closest > tol {
+                    violations.push(SymmetryViolation {
+                        op: op_index,
+                        atom,
+                        distance: closest,
+                    });
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
+
+impl CellDocument {
+    /// Validates `SYMMETRY_OPS`, if present, against the structure using `SYMMETRY_TOL`
+    /// or the CASTEP default of 0.01 Å.
+    ///
+    /// Fails if the operations are not closed under composition or if any operation
+    /// fails to map an atom onto an equivalent atom.
+    pub fn check_symmetry_ops(&self) -> CResult<()> {
+        let Some(ops) = &self.symmetry_ops else {
+            return Ok(());
+        };
+        let tolerance = self.symmetry_tol.unwrap_or_default();
+        ops.check_closure(&self.lattice, &tolerance)?;
+        match ops.violations(&self.lattice, &self.positions, &tolerance)?.first() {
+            Some(v) => Err(castep_cell_fmt::Error::Message(format!(
+                "symmetry operation {} maps atom {} {:.4} ang away from any equivalent atom",
+                v.op + 1,
+                v.atom + 1,
+                v.distance
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
 impl FromBlock for SymmetryOps {
     const BLOCK_NAME: &'static str = "SYMMETRY_OPS";
 
@@ -91,8 +336,179 @@ impl ToCell for SymmetryOps {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cell::positions::{PositionAbsEntry, PositionFracEntry, PositionsAbs, PositionsFrac};
+    use crate::cell::species::Species;
+    use crate::test_fixtures::{abc_lattice, atom};
     use castep_cell_fmt::CellValue;
 
+    fn monoclinic() -> Lattice {
+        abc_lattice([5.0, 6.0, 7.0], [90.0, 100.0, 90.0])
+    }
+
+    /// The four operations of P2_1/c (unique axis b) in the Cartesian form.
+    fn p21c_ops(lattice: &Lattice) -> SymmetryOps {
+        let ops = [
+            ([[1, 0, 0], [0, 1, 0], [0, 0, 1]], [0.0, 0.0, 0.0]),
+            ([[-1, 0, 0], [0, 1, 0], [0, 0, -1]], [0.0, 0.5, 0.5]),
+            ([[-1, 0, 0], [0, -1, 0], [0, 0, -1]], [0.0, 0.0, 0.0]),
+            ([[1, 0, 0], [0, -1, 0], [0, 0, 1]], [0.0, 0.5, 0.5]),
+        ];
+        SymmetryOps {
+            ops: ops
+                .iter()
+                .map(|&(rotation, translation)| {
+                    SymmetryOp::from_frac(&SymmetryOpFrac { rotation, translation }, lattice)
+                        .unwrap()
+                })
+                .collect(),
+        }
+    }
+
+    fn assert_same(a: &SymmetryOp, b: &SymmetryOp, lattice: &Lattice) {
+        assert!(a.matches(b, lattice.vectors(), 1e-8), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_compose_and_inverse() {
+        let lattice = monoclinic();
+        let ops = p21c_ops(&lattice);
+        let screw = &ops.ops[1];
+        let inversion = &ops.ops[2];
+        // 2_1 followed by -1 is the glide plane c.
+        assert_same(&inversion.compose(screw, &lattice).unwrap(), &ops.ops[3], &lattice);
+        // The screw applied twice is a lattice translation.
+        assert_same(&screw.compose(screw, &lattice).unwrap(), &SymmetryOp::identity(), &lattice);
+        let tetragonal = abc_lattice([4.0, 4.0, 9.0], [90.0, 90.0, 90.0]);
+        let quarter = SymmetryOp::from_frac(
+            &SymmetryOpFrac {
+                rotation: [[0, -1, 0], [1, 0, 0], [0, 0, 1]],
+                translation: [0.0, 0.0, 0.25],
+            },
+            &tetragonal,
+        )
+        .unwrap();
+        let inverse = quarter.inverse(&tetragonal).unwrap();
+        assert_same(
+            &quarter.compose(&inverse, &tetragonal).unwrap(),
+            &SymmetryOp::identity(),
+            &tetragonal,
+        );
+        let t = inverse.to_frac(&tetragonal).unwrap().translation;
+        assert!(math::norm(math::sub(t, [0.0, 0.0, -0.25])) < 1e-12);
+    }
+
+    #[test]
+    fn test_degenerate_lattice_fails() {
+        let flat = abc_lattice([5.0, 5.0, 5.0], [90.0, 90.0, 180.0]);
+        let identity = SymmetryOpFrac {
+            rotation: math::IIDENTITY,
+            translation: [0.0; 3],
+        };
+        assert!(SymmetryOp::from_frac(&identity, &flat).is_err());
+        assert!(SymmetryOp::identity().to_frac(&flat).is_err());
+        let identity = SymmetryOp::identity();
+        assert!(identity.compose(&identity, &flat).is_err());
+        assert!(identity.inverse(&flat).is_err());
+    }
+
+    #[test]
+    fn test_inverse_of_singular_rotation_fails() {
+        let op = SymmetryOp {
+            rotation: [[1.0, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+            translation: [0.0; 3],
+        };
+        assert!(op.inverse(&monoclinic()).is_err());
+    }
+
+    #[test]
+    fn test_is_orthogonal() {
+        let lattice = monoclinic();
+        for op in &p21c_ops(&lattice).ops {
+            assert!(op.is_orthogonal(&lattice, 1e-8));
+        }
+        // A four-fold about c does not preserve the monoclinic metric.
+        let four_fold = SymmetryOp::from_frac(
+            &SymmetryOpFrac {
+                rotation: [[0, -1, 0], [1, 0, 0], [0, 0, 1]],
+                translation: [0.0; 3],
+            },
+            &lattice,
+        )
+        .unwrap();
+        assert!(!four_fold.is_orthogonal(&lattice, 1e-6));
+        let shear = SymmetryOp {
+            rotation: [[1.0, 0.1, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            translation: [0.0; 3],
+        };
+        assert!(!shear.is_orthogonal(&lattice, 1e-6));
+    }
+
+    #[test]
+    fn test_check_closure() {
+        let lattice = monoclinic();
+        let tol = SymmetryTol::default();
+        let mut ops = p21c_ops(&lattice);
+        ops.check_closure(&lattice, &tol).unwrap();
+        // Translations that differ by a lattice vector still close the group.
+        ops.ops[3].translation = [1.0, -0.5, 0.5];
+        ops.check_closure(&lattice, &tol).unwrap();
+        ops.ops.pop();
+        let err = ops.check_closure(&lattice, &tol).unwrap_err();
+        assert!(err.to_string().contains("not closed"));
+        assert!(SymmetryOps::builder().build().check_closure(&lattice, &tol).is_err());
+    }
+
+    #[test]
+    fn test_violations() {
+        let lattice = monoclinic();
+        let general = [0.1, 0.2, 0.3];
+        let positions: Vec<PositionFracEntry> = p21c_ops(&lattice)
+            .ops
+            .iter()
+            .map(|op| atom("O", math::wrap(op.to_frac(&lattice).unwrap().apply(general))))
+            .collect();
+        let mut doc = CellDocument::builder()
+            .lattice(lattice.clone())
+            .positions(PositionsFrac { positions })
+            .symmetry_ops(p21c_ops(&lattice))
+            .build()
+            .unwrap();
+        let ops = doc.symmetry_ops.clone().unwrap();
+        let tol = SymmetryTol::default();
+        assert!(ops.violations(&doc.lattice, &doc.positions, &tol).unwrap().is_empty());
+        doc.check_symmetry_ops().unwrap();
+
+        if let Positions::Frac(p) = &mut doc.positions {
+            p.positions[0].coord[0] += 0.01;
+        }
+        let violations = ops.violations(&doc.lattice, &doc.positions, &tol).unwrap();
+        // Every non-identity operation moves atom 1 and its partner away from each other.
+        assert!(violations.iter().all(|v| v.op != 0 && v.distance > 0.01));
+        assert!(violations.iter().any(|v| v.atom == 0));
+        assert!(doc.check_symmetry_ops().is_err());
+        let loose = SymmetryTol {
+            value: 0.2,
+            unit: crate::units::LengthUnit::Ang,
+        };
+        assert!(ops.violations(&doc.lattice, &doc.positions, &loose).unwrap().is_empty());
+
+        // A flat cell is reported rather than panicking on absolute positions.
+        let flat = abc_lattice([5.0, 5.0, 5.0], [90.0, 90.0, 180.0]);
+        let abs = Positions::Abs(PositionsAbs {
+            unit: None,
+            positions: vec![
+                PositionAbsEntry::builder()
+                    .species(Species::Symbol("O".into()))
+                    .coord([1.0, 2.0, 3.0])
+                    .build(),
+            ],
+        });
+        let identity = SymmetryOps {
+            ops: vec![SymmetryOp::identity()],
+        };
+        assert!(identity.violations(&flat, &abs, &tol).is_err());
+    }
+
     #[test]
     fn test_symmetry_ops_empty() {
         let result = SymmetryOps::from_block_rows(&[]).unwrap();
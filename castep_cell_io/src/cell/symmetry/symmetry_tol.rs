@@ -19,6 +19,16 @@ pub struct SymmetryTol {
     pub unit: LengthUnit,
 }
 
+impl Default for SymmetryTol {
+    /// The CASTEP default of 0.01 Å.
+    fn default() -> Self {
+        Self {
+            value: 0.01,
+            unit: LengthUnit::Ang,
+        }
+    }
+}
+
 impl FromCellValue for SymmetryTol {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value {
@@ -0,0 +1,377 @@
+//! Building a full cell from a space group and its asymmetric unit.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::lattice_param::LatticeABC;
+use crate::cell::positions::{PositionFracEntry, PositionsFrac};
+use crate::math::{self, Vec3};
+use crate::{CellDocument, Lattice};
+
+use super::point_group::CrystalSystem;
+use super::space_group::SpaceGroup;
+use super::symmetry_ops::{SymmetryOp, SymmetryOps};
+use super::symmetry_tol::SymmetryTol;
+
+/// Relative tolerance on lattice lengths when checking them against the crystal system.
+const LENGTH_TOL: f64 = 1e-4;
+/// Tolerance in degrees on lattice angles when checking them against the crystal system.
+const ANGLE_TOL: f64 = 1e-3;
+
+/// A space group, its conventional lattice parameters and the unique sites of the
+/// structure, from which the full cell is generated.
+///
+/// Sites are given in fractional coordinates of the tabulated setting of
+/// `space_group` (see [`SpaceGroup`]); rhombohedral groups therefore use hexagonal axes.
+///
+/// # Example
+///
+/// ```
+/// use castep_cell_io::cell::lattice_param::LatticeABC;
+/// use castep_cell_io::cell::positions::PositionFracEntry;
+/// use castep_cell_io::cell::species::Species;
+/// use castep_cell_io::cell::symmetry::{AsymmetricUnit, SpaceGroup};
+///
+/// let site = |symbol: &str, coord| PositionFracEntry::builder()
+///     .species(Species::Symbol(symbol.into()))
+///     .coord(coord)
+///     .build();
+/// let nacl = AsymmetricUnit::builder()
+///     .space_group(SpaceGroup::from_number(225).unwrap())
+///     .lattice(LatticeABC::builder().abc([5.64; 3]).angles([90.0; 3]).build())
+///     .sites(vec![site("Na", [0.0; 3]), site("Cl", [0.5, 0.0, 0.0])])
+///     .build()
+///     .to_cell_document()
+///     .unwrap();
+/// assert_eq!(nacl.positions.len(), 8);
+/// ```
+#[derive(Debug, Clone, bon::Builder)]
+pub struct AsymmetricUnit {
+    /// The space group in its tabulated setting.
+    pub space_group: &'static SpaceGroup,
+    /// Conventional lattice parameters; they must agree with the crystal system.
+    pub lattice: LatticeABC,
+    /// The symmetry-unique sites.
+    #[builder(default)]
+    pub sites: Vec<PositionFracEntry>,
+    /// Images closer than this distance are merged into one atom.
+    #[builder(default)]
+    pub tolerance: SymmetryTol,
+    /// Whether to fill `SYMMETRY_OPS` with the operations of the conventional cell.
+    #[builder(default)]
+    pub symmetry_ops: bool,
+}
+
+impl AsymmetricUnit {
+    /// Expands the unique sites to all equivalent positions and builds the cell.
+    ///
+    /// Images of a site that fall within `tolerance` of each other are merged, so sites
+    /// on special positions yield the correct multiplicity. Fails if the lattice
+    /// parameters do not fit the crystal system, or if two sites of different
+    /// species overlap without being `MIXTURE` components.
+    pub fn to_cell_document(&self) -> CResult<CellDocument> {
+        let lattice = conform_lattice(self.space_group.crystal_system(), &self.lattice)?;
+        let a = lattice.vectors();
+        let tol = self.tolerance.value * self.tolerance.unit.in_angstrom();
+        if tol.is_nan() || tol <= 0.0 {
+            return Err(Error::Message("merging tolerance must be positive".into()));
+        }
+
+        let operations = self.space_group.operations();
+        let mut positions: Vec<PositionFracEntry> = Vec::new();
+        for (index, site) in self.sites.iter().enumerate() {
+            for op in &operations {
+                let coord = snap(op.apply(site.coord));
+                let overlap = positions
+                    .iter()
+                    .find(|p| math::periodic_distance(a, math::sub(p.coord, coord)) < tol);
+                match overlap {
+                    None => positions.push(PositionFracEntry {
+                        coord,
+                        ..site.clone()
+                    }),
+                    Some(p) if same_kind(p, site) => {}
+                    Some(p) if p.mixture.is_some() && site.mixture.is_some() => {
+                        if !positions.iter().any(|q| {
+                            same_kind(q, site)
+                                && math::periodic_distance(a, math::sub(q.coord, coord)) < tol
+                        }) {
+                            positions.push(PositionFracEntry {
+                                coord: p.coord,
+                                ..site.clone()
+                            });
+                        }
+                    }
+                    Some(p) => {
+                        return Err(Error::Message(format!(
+                            "site {} ({}) overlaps with an image of {} at {:?}",
+                            index + 1,
+                            site.species,
+                            p.species,
+                            p.coord
+                        )));
+                    }
+                }
+            }
+        }
+
+        let lattice = Lattice::Abc(lattice);
+        let symmetry_ops = self
+            .symmetry_ops
+            .then(|| -> CResult<SymmetryOps> {
+                Ok(SymmetryOps {
+                    ops: operations
+                        .iter()
+                        .map(|op| SymmetryOp::from_frac(op, &lattice))
+                        .collect::<CResult<_>>()?,
+                })
+            })
+            .transpose()?;
+        CellDocument::builder()
+            .lattice(lattice)
+            .positions(PositionsFrac { positions })
+            .maybe_symmetry_ops(symmetry_ops)
+            .build()
+    }
+}
+
+fn same_kind(a: &PositionFracEntry, b: &PositionFracEntry) -> bool {
+    a.species.same_species(&b.species) && a.spin == b.spin && a.mixture == b.mixture
+}
+
+/// Wraps into `[0, 1)` and removes round-off noise on twelfths, which covers every
+/// special-position coordinate of the tabulated settings.
+fn snap(x: Vec3) -> Vec3 {
+    math::wrap(x.map(|v| {
+        let r = (v * 12.0).round() / 12.0;
+        if (v - r).abs() < 1e-10 { r } else { v }
+    }))
+}
+
+/// Checks the lattice parameters against the crystal system and returns them with the
+/// constrained values set exactly.
+fn conform_lattice(system: CrystalSystem, lattice: &LatticeABC) -> CResult<LatticeABC> {
+    let [a, b, c] = lattice.abc;
+    let [alpha, beta, gamma] = lattice.angles;
+    if lattice.abc.iter().any(|x| x.is_nan() || *x <= 0.0) {
+        return Err(Error::Message("lattice lengths must be positive".into()));
+    }
+    let equal_length = |x: f64, y: f64| (x - y).abs() <= LENGTH_TOL * x.max(y);
+    let angle_is = |x: f64, y: f64| (x - y).abs() <= ANGLE_TOL;
+    let (consistent, abc, angles) = match system {
+        CrystalSystem::Triclinic => (true, [a, b, c], [alpha, beta, gamma]),
+        CrystalSystem::Monoclinic => (
+            angle_is(alpha, 90.0) && angle_is(gamma, 90.0),
+            [a, b, c],
+            [90.0, beta, 90.0],
+        ),
+        CrystalSystem::Orthorhombic => (
+            [alpha, beta, gamma].iter().all(|x| angle_is(*x, 90.0)),
+            [a, b, c],
+            [90.0; 3],
+        ),
+        CrystalSystem::Tetragonal => (
+            equal_length(a, b) && [alpha, beta, gamma].iter().all(|x| angle_is(*x, 90.0)),
+            [a, a, c],
+            [90.0; 3],
+        ),
+        CrystalSystem::Trigonal | CrystalSystem::Hexagonal => (
+            equal_length(a, b)
+                && angle_is(alpha, 90.0)
+                && angle_is(beta, 90.0)
+                && angle_is(gamma, 120.0),
+            [a, a, c],
+            [90.0, 90.0, 120.0],
+        ),
+        CrystalSystem::Cubic => (
+            equal_length(a, b)
+                && equal_length(a, c)
+                && [alpha, beta, gamma].iter().all(|x| angle_is(*x, 90.0)),
+            [a; 3],
+            [90.0; 3],
+        ),
+    };
+    if !consistent {
+        return Err(Error::Message(format!(
+            "lattice parameters {:?} {:?} are inconsistent with the {:?} crystal system",
+            lattice.abc, lattice.angles, system
+        )));
+    }
+    Ok(LatticeABC {
+        unit: lattice.unit,
+        abc,
+        angles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::species::Species;
+    use crate::test_fixtures::atom;
+    use crate::units::LengthUnit;
+
+    fn unit(
+        number: u32,
+        abc: [f64; 3],
+        angles: [f64; 3],
+        sites: Vec<PositionFracEntry>,
+    ) -> AsymmetricUnit {
+        AsymmetricUnit::builder()
+            .space_group(SpaceGroup::from_number(number).unwrap())
+            .lattice(LatticeABC {
+                unit: None,
+                abc,
+                angles,
+            })
+            .sites(sites)
+            .build()
+    }
+
+    #[test]
+    fn test_rocksalt() {
+        let doc = unit(
+            225,
+            [5.64; 3],
+            [90.0; 3],
+            vec![atom("Na", [0.0; 3]), atom("Cl", [0.5, 0.5, 0.5])],
+        )
+        .to_cell_document()
+        .unwrap();
+        assert_eq!(doc.positions.len(), 8);
+        let ds = doc.find_symmetry().unwrap();
+        assert_eq!(ds.space_group.number, 225);
+        assert!(doc.symmetry_ops.is_none());
+    }
+
+    #[test]
+    fn test_special_and_general_positions() {
+        // Rutile: Ti on 2a, O on 4f.
+        let doc = unit(
+            136,
+            [4.594, 4.594, 2.959],
+            [90.0; 3],
+            vec![atom("Ti", [0.0; 3]), atom("O", [0.3048, 0.3048, 0.0])],
+        )
+        .to_cell_document()
+        .unwrap();
+        assert_eq!(doc.positions.len(), 6);
+        assert_eq!(doc.find_symmetry().unwrap().space_group.number, 136);
+
+        // A general position of Pm-3m has multiplicity 48.
+        let doc = unit(221, [4.0; 3], [90.0; 3], vec![atom("C", [0.1, 0.2, 0.3])])
+            .to_cell_document()
+            .unwrap();
+        assert_eq!(doc.positions.len(), 48);
+    }
+
+    #[test]
+    fn test_rhombohedral_and_symmetry_ops() {
+        // Corundum, R-3c in hexagonal axes: Al on 12c, O on 18e.
+        let mut corundum = unit(
+            167,
+            [4.759, 4.759, 12.99],
+            [90.0, 90.0, 120.0],
+            vec![
+                atom("Al", [0.0, 0.0, 0.3523]),
+                atom("O", [0.3064, 0.0, 0.25]),
+            ],
+        );
+        corundum.symmetry_ops = true;
+        let doc = corundum.to_cell_document().unwrap();
+        assert_eq!(doc.positions.len(), 30);
+        assert_eq!(doc.symmetry_ops.as_ref().unwrap().ops.len(), 36);
+        doc.check_symmetry_ops().unwrap();
+        assert_eq!(doc.find_symmetry().unwrap().space_group.number, 167);
+    }
+
+    #[test]
+    fn test_lattice_is_conformed() {
+        let doc = unit(
+            194,
+            [3.21, 3.2100001, 5.21],
+            [90.0, 90.0, 120.0],
+            vec![atom("Mg", [1.0 / 3.0, 2.0 / 3.0, 0.25])],
+        )
+        .to_cell_document()
+        .unwrap();
+        let Lattice::Abc(abc) = &doc.lattice else {
+            panic!("expected LATTICE_ABC");
+        };
+        assert_eq!(abc.abc, [3.21, 3.21, 5.21]);
+        assert_eq!(doc.positions.len(), 2);
+
+        let err = unit(221, [4.0, 4.1, 4.0], [90.0; 3], vec![atom("C", [0.0; 3])])
+            .to_cell_document()
+            .unwrap_err();
+        assert!(err.to_string().contains("Cubic"));
+        assert!(
+            unit(14, [5.0, 6.0, 7.0], [90.0, 100.0, 95.0], vec![])
+                .to_cell_document()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_overlapping_sites() {
+        let err = unit(
+            221,
+            [4.0; 3],
+            [90.0; 3],
+            vec![atom("Na", [0.0; 3]), atom("Cl", [0.001, 0.0, 0.0])],
+        )
+        .to_cell_document()
+        .unwrap_err();
+        assert!(err.to_string().contains("overlaps"));
+
+        // Mixture components may share a site; repeated symmetry-equivalent sites are merged.
+        let mixed = |symbol: &str, index, weight| PositionFracEntry {
+            mixture: Some((index, weight)),
+            ..atom(symbol, [0.5; 3])
+        };
+        let doc = unit(
+            221,
+            [4.0; 3],
+            [90.0; 3],
+            vec![
+                atom("Sr", [0.0; 3]),
+                mixed("Ti", 1, 0.5),
+                mixed("Zr", 1, 0.5),
+                atom("Sr", [0.0; 3]),
+            ],
+        )
+        .to_cell_document()
+        .unwrap();
+        assert_eq!(doc.positions.len(), 3);
+
+        // So are sites naming the same species in different spellings.
+        let strontium = PositionFracEntry {
+            species: Species::AtomicNumber(38),
+            ..atom("Sr", [0.0; 3])
+        };
+        let doc = unit(
+            221,
+            [4.0; 3],
+            [90.0; 3],
+            vec![atom("Sr", [0.0; 3]), atom("sr", [0.0; 3]), strontium],
+        )
+        .to_cell_document()
+        .unwrap();
+        assert_eq!(doc.positions.len(), 1);
+    }
+
+    #[test]
+    fn test_tolerance_merges_near_special_positions() {
+        let mut near = unit(
+            221,
+            [4.0; 3],
+            [90.0; 3],
+            vec![atom("C", [0.0005, 0.0, 0.0])],
+        );
+        assert_eq!(near.to_cell_document().unwrap().positions.len(), 1);
+        near.tolerance = SymmetryTol {
+            value: 0.0001,
+            unit: LengthUnit::Ang,
+        };
+        assert_eq!(near.to_cell_document().unwrap().positions.len(), 6);
+    }
+}
@@ -82,11 +82,17 @@ use crate::cell::{
         PhononKpointsMpGrid, PhononKpointsMpOffset, PhononKpointsMpSpacing,
         PhononSupercellMatrix, SupercellKpointListCastep,
     },
-    positions::{PositionsAbs, PositionsFrac},
-    species::{HubbardU, SedcCustomParams, SpeciesLcaoStates, SpeciesMass, SpeciesPot, SpeciesQ},
+    positions::{
+        PositionAbsEntry, PositionAbsIntermediateEntry, PositionAbsProductEntry, PositionsAbs,
+        PositionsAbsIntermediate, PositionsAbsProduct, PositionsFrac, PositionsFracIntermediate,
+        PositionsFracProduct, Spin,
+    },
+    species::{HubbardU, QuantizationAxis, Species, SedcCustomParams, SpeciesLcaoStates, SpeciesMass, SpeciesPot, SpeciesQ},
     symmetry::{SymmetryGenerate, SymmetryOps, SymmetryTol},
     velocities::IonicVelocities,
 };
+use crate::math;
+use crate::IonIndex;
 use cell_document_builder::IsComplete;
 
 /// Lattice vector specification for the simulation cell.
@@ -123,6 +129,29 @@ impl ToCell for Lattice {
     }
 }
 
+impl Lattice {
+    /// Returns the lattice vectors `[a, b, c]` as Cartesian rows in Å.
+    pub fn vectors(&self) -> [[f64; 3]; 3] {
+        match self {
+            Lattice::Cart(cart) => cart.vectors(),
+            Lattice::Abc(abc) => abc.vectors(),
+        }
+    }
+
+    /// Returns the cell volume in Å³.
+    pub fn volume(&self) -> f64 {
+        math::det(self.vectors()).abs()
+    }
+
+    /// Returns the reciprocal lattice vectors as Cartesian rows in Å⁻¹, without the
+    /// factor 2π, so that `a_i · b_j = δ_ij`.
+    ///
+    /// Fails if the lattice vectors are linearly dependent.
+    pub fn reciprocal_vectors(&self) -> CResult<[[f64; 3]; 3]> {
+        math::reciprocal(self.vectors())
+    }
+}
+
 impl From<LatticeCart> for Lattice {
     fn from(v: LatticeCart) -> Self {
         Lattice::Cart(v)
@@ -164,6 +193,94 @@ pub enum Positions {
     Abs(PositionsAbs),
 }
 
+impl Positions {
+    /// Returns the number of atoms (including every entry of a mixture site).
+    pub fn len(&self) -> usize {
+        match self {
+            Positions::Frac(frac) => frac.positions.len(),
+            Positions::Abs(abs) => abs.positions.len(),
+        }
+    }
+
+    /// Returns `true` if the block lists no atoms.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the species of every atom, in block order.
+    pub fn species(&self) -> Vec<&Species> {
+        match self {
+            Positions::Frac(frac) => frac.positions.iter().map(|p| &p.species).collect(),
+            Positions::Abs(abs) => abs.positions.iter().map(|p| &p.species).collect(),
+        }
+    }
+
+    /// Returns the 1-based ion number of every atom within its species, as used by
+    /// per-ion blocks such as `IONIC_CONSTRAINTS`.
+    pub fn ion_numbers(&self) -> Vec<u32> {
+        IonIndex::new(self).ion_numbers().to_vec()
+    }
+
+    /// Returns the index of ion `ion_number` (1-based) of `species`, if it exists.
+    ///
+    /// Numbers the whole block on each call; build an [`IonIndex`] once to resolve many
+    /// ions.
+    pub fn ion_index(&self, species: &Species, ion_number: u32) -> Option<usize> {
+        IonIndex::new(self).index(species, ion_number)
+    }
+
+    /// Returns the `SPIN` or `MAGMOM` qualifier of every atom, in block order.
+    pub fn spins(&self) -> Vec<Option<Spin>> {
+        match self {
+            Positions::Frac(frac) => frac.positions.iter().map(|p| p.spin).collect(),
+            Positions::Abs(abs) => abs.positions.iter().map(|p| p.spin).collect(),
+        }
+    }
+
+    /// Returns the `MIXTURE` qualifier of every atom, in block order.
+    pub fn mixtures(&self) -> Vec<Option<(u32, f64)>> {
+        match self {
+            Positions::Frac(frac) => frac.positions.iter().map(|p| p.mixture).collect(),
+            Positions::Abs(abs) => abs.positions.iter().map(|p| p.mixture).collect(),
+        }
+    }
+
+    /// Returns the fractional coordinates of every atom in `lattice`, in block order.
+    ///
+    /// Fails for `POSITIONS_ABS` if the lattice vectors are linearly dependent.
+    pub fn frac_coords(&self, lattice: &Lattice) -> CResult<Vec<[f64; 3]>> {
+        match self {
+            Positions::Frac(frac) => Ok(frac.positions.iter().map(|p| p.coord).collect()),
+            Positions::Abs(abs) => {
+                let f = abs.unit.unwrap_or_default().in_angstrom();
+                let inverse = math::lattice_inverse(lattice.vectors())?;
+                Ok(abs
+                    .positions
+                    .iter()
+                    .map(|p| math::vec_mat(p.coord.map(|x| x * f), inverse))
+                    .collect())
+            }
+        }
+    }
+
+    /// Returns the Cartesian coordinates of every atom in Å, in block order.
+    pub fn cart_coords(&self, lattice: &Lattice) -> Vec<[f64; 3]> {
+        match self {
+            Positions::Frac(frac) => {
+                let vectors = lattice.vectors();
+                frac.positions
+                    .iter()
+                    .map(|p| math::frac_to_cart(vectors, p.coord))
+                    .collect()
+            }
+            Positions::Abs(abs) => {
+                let f = abs.unit.unwrap_or_default().in_angstrom();
+                abs.positions.iter().map(|p| p.coord.map(|x| x * f)).collect()
+            }
+        }
+    }
+}
+
 impl ToCell for Positions {
     fn to_cell(&self) -> Cell<'_> {
         match self {
@@ -173,6 +290,179 @@ impl ToCell for Positions {
     }
 }
 
+impl From<PositionsFrac> for Positions {
+    fn from(v: PositionsFrac) -> Self {
+        Positions::Frac(v)
+    }
+}
+
+impl From<PositionsAbs> for Positions {
+    fn from(v: PositionsAbs) -> Self {
+        Positions::Abs(v)
+    }
+}
+
+/// Intermediate geometry of a transition-state search.
+///
+/// Lists the same atoms in the same order and coordinate mode as [`Positions`].
+/// Corresponds to `%BLOCK POSITIONS_FRAC_INTERMEDIATE` or
+/// `%BLOCK POSITIONS_ABS_INTERMEDIATE`.
+#[derive(Debug, Clone)]
+pub enum PositionsIntermediate {
+    /// Fractional coordinates relative to lattice vectors.
+    Frac(PositionsFracIntermediate),
+    /// Absolute Cartesian coordinates.
+    Abs(PositionsAbsIntermediate),
+}
+
+impl PositionsIntermediate {
+    /// Returns the geometry as a [`Positions`] block, to use its accessors.
+    pub fn to_positions(&self) -> Positions {
+        match self {
+            PositionsIntermediate::Frac(frac) => Positions::Frac(PositionsFrac {
+                positions: frac.positions.clone(),
+            }),
+            PositionsIntermediate::Abs(abs) => Positions::Abs(PositionsAbs {
+                unit: abs.unit,
+                positions: abs
+                    .positions
+                    .iter()
+                    .map(|p| PositionAbsEntry {
+                        species: p.species.clone(),
+                        coord: p.coord,
+                        spin: p.spin,
+                        mixture: p.mixture,
+                    })
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl ToCell for PositionsIntermediate {
+    fn to_cell(&self) -> Cell<'_> {
+        match self {
+            PositionsIntermediate::Frac(frac) => frac.to_cell(),
+            PositionsIntermediate::Abs(abs) => abs.to_cell(),
+        }
+    }
+}
+
+impl From<Positions> for PositionsIntermediate {
+    fn from(v: Positions) -> Self {
+        match v {
+            Positions::Frac(frac) => PositionsIntermediate::Frac(PositionsFracIntermediate {
+                positions: frac.positions,
+            }),
+            Positions::Abs(abs) => PositionsIntermediate::Abs(PositionsAbsIntermediate {
+                unit: abs.unit,
+                positions: abs
+                    .positions
+                    .into_iter()
+                    .map(|p| PositionAbsIntermediateEntry {
+                        species: p.species,
+                        coord: p.coord,
+                        spin: p.spin,
+                        mixture: p.mixture,
+                    })
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl From<PositionsFracIntermediate> for PositionsIntermediate {
+    fn from(v: PositionsFracIntermediate) -> Self {
+        PositionsIntermediate::Frac(v)
+    }
+}
+
+impl From<PositionsAbsIntermediate> for PositionsIntermediate {
+    fn from(v: PositionsAbsIntermediate) -> Self {
+        PositionsIntermediate::Abs(v)
+    }
+}
+
+/// Product geometry of a transition-state search.
+///
+/// Lists the same atoms in the same order and coordinate mode as [`Positions`].
+/// Corresponds to `%BLOCK POSITIONS_FRAC_PRODUCT` or `%BLOCK POSITIONS_ABS_PRODUCT`.
+#[derive(Debug, Clone)]
+pub enum PositionsProduct {
+    /// Fractional coordinates relative to lattice vectors.
+    Frac(PositionsFracProduct),
+    /// Absolute Cartesian coordinates.
+    Abs(PositionsAbsProduct),
+}
+
+impl PositionsProduct {
+    /// Returns the geometry as a [`Positions`] block, to use its accessors.
+    pub fn to_positions(&self) -> Positions {
+        match self {
+            PositionsProduct::Frac(frac) => Positions::Frac(PositionsFrac {
+                positions: frac.positions.clone(),
+            }),
+            PositionsProduct::Abs(abs) => Positions::Abs(PositionsAbs {
+                unit: abs.unit,
+                positions: abs
+                    .positions
+                    .iter()
+                    .map(|p| PositionAbsEntry {
+                        species: p.species.clone(),
+                        coord: p.coord,
+                        spin: p.spin,
+                        mixture: p.mixture,
+                    })
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl ToCell for PositionsProduct {
+    fn to_cell(&self) -> Cell<'_> {
+        match self {
+            PositionsProduct::Frac(frac) => frac.to_cell(),
+            PositionsProduct::Abs(abs) => abs.to_cell(),
+        }
+    }
+}
+
+impl From<Positions> for PositionsProduct {
+    fn from(v: Positions) -> Self {
+        match v {
+            Positions::Frac(frac) => PositionsProduct::Frac(PositionsFracProduct {
+                positions: frac.positions,
+            }),
+            Positions::Abs(abs) => PositionsProduct::Abs(PositionsAbsProduct {
+                unit: abs.unit,
+                positions: abs
+                    .positions
+                    .into_iter()
+                    .map(|p| PositionAbsProductEntry {
+                        species: p.species,
+                        coord: p.coord,
+                        spin: p.spin,
+                        mixture: p.mixture,
+                    })
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl From<PositionsFracProduct> for PositionsProduct {
+    fn from(v: PositionsFracProduct) -> Self {
+        PositionsProduct::Frac(v)
+    }
+}
+
+impl From<PositionsAbsProduct> for PositionsProduct {
+    fn from(v: PositionsAbsProduct) -> Self {
+        PositionsProduct::Abs(v)
+    }
+}
+
 /// Complete representation of a CASTEP `.cell` file.
 ///
 /// This is the primary type for working with CASTEP cell files. It contains all
@@ -229,15 +519,23 @@ impl ToCell for Positions {
 /// - **External fields**: [`external_efield`](Self::external_efield), [`external_pressure`](Self::external_pressure)
 /// - **Species properties**: [`species_mass`](Self::species_mass), [`species_pot`](Self::species_pot),
 ///   [`species_lcao_states`](Self::species_lcao_states), [`species_q`](Self::species_q),
-///   [`hubbard_u`](Self::hubbard_u), [`sedc_custom_params`](Self::sedc_custom_params)
+///   [`hubbard_u`](Self::hubbard_u), [`quantization_axis`](Self::quantization_axis),
+///   [`sedc_custom_params`](Self::sedc_custom_params)
 /// - **Phonon calculations**: [`phonon_kpoint_list`](Self::phonon_kpoint_list), [`phonon_kpoint_path`](Self::phonon_kpoint_path),
 ///   [`phonon_gamma_directions`](Self::phonon_gamma_directions), [`phonon_fine_kpoint_list`](Self::phonon_fine_kpoint_list),
 ///   [`phonon_supercell_matrix`](Self::phonon_supercell_matrix), [`supercell_kpoint_list`](Self::supercell_kpoint_list)
 /// - **Dynamics**: [`ionic_velocities`](Self::ionic_velocities)
+/// - **Transition states**: [`positions_intermediate`](Self::positions_intermediate),
+///   [`positions_product`](Self::positions_product)
 /// - **Symmetry**: [`symmetry_ops`](Self::symmetry_ops)
 #[allow(clippy::duplicated_attributes)]
 #[derive(Debug, Clone, Builder)]
-#[builder(on(Lattice, into), on(Positions, into), finish_fn(vis = "", name = build_internal))]
+#[builder(
+    on(Lattice, into),
+    on(Positions, into),
+    on(PositionsIntermediate, into),
+    on(PositionsProduct, into),
+    finish_fn(vis = "", name = build_internal))]
 pub struct CellDocument {
     /// Lattice vectors defining the simulation cell.
     ///
@@ -247,6 +545,16 @@ pub struct CellDocument {
     ///
     /// Required field. Can be fractional or absolute coordinates.
     pub positions: Positions,
+    /// Intermediate geometry for a transition-state search.
+    ///
+    /// Corresponds to `%BLOCK POSITIONS_FRAC_INTERMEDIATE` or
+    /// `%BLOCK POSITIONS_ABS_INTERMEDIATE` in CASTEP.
+    pub positions_intermediate: Option<PositionsIntermediate>,
+    /// Product geometry for a transition-state search.
+    ///
+    /// Corresponds to `%BLOCK POSITIONS_FRAC_PRODUCT` or `%BLOCK POSITIONS_ABS_PRODUCT`
+    /// in CASTEP.
+    pub positions_product: Option<PositionsProduct>,
     /// K-point sampling grid for electronic structure calculations.
     ///
     /// Corresponds to `%BLOCK KPOINTS_LIST` in CASTEP.
@@ -375,6 +683,10 @@ pub struct CellDocument {
     ///
     /// Corresponds to `%BLOCK HUBBARD_U` in CASTEP.
     pub hubbard_u: Option<HubbardU>,
+    /// Quantization (magnetization) axis for DFT+U calculations, in fractional coordinates.
+    ///
+    /// Corresponds to `QUANTIZATION_AXIS` in CASTEP.
+    pub quantization_axis: Option<QuantizationAxis>,
     /// Custom parameters for semi-empirical dispersion correction.
     ///
     /// Corresponds to `%BLOCK SEDC_CUSTOM_PARAMS` in CASTEP.
@@ -441,6 +753,132 @@ pub struct CellDocument {
     pub ionic_velocities: Option<IonicVelocities>,
 }
 
+impl CellDocument {
+    /// Checks the transition-state geometries against the reactant.
+    ///
+    /// `POSITIONS_*_INTERMEDIATE` needs a `POSITIONS_*_PRODUCT` block, and both must list
+    /// the same atoms in the same order as the reactant, in the same coordinate mode.
+    pub fn check_transition_state(&self) -> CResult<()> {
+        if self.positions_intermediate.is_some() && self.positions_product.is_none() {
+            return Err(Error::Message(
+                "POSITIONS_*_INTERMEDIATE requires a POSITIONS_*_PRODUCT block".into(),
+            ));
+        }
+        let images = [
+            self.positions_intermediate
+                .as_ref()
+                .map(|p| (p.to_positions(), "INTERMEDIATE")),
+            self.positions_product
+                .as_ref()
+                .map(|p| (p.to_positions(), "PRODUCT")),
+        ];
+        let reactant = block_name(&self.positions, "");
+        for (image, suffix) in images.into_iter().flatten() {
+            let name = block_name(&image, suffix);
+            if std::mem::discriminant(&image) != std::mem::discriminant(&self.positions) {
+                return Err(Error::Message(format!(
+                    "{name} must use the same coordinate mode as {reactant}"
+                )));
+            }
+            if image.len() != self.positions.len() {
+                return Err(Error::Message(format!(
+                    "{name} lists {} atoms but {reactant} lists {}",
+                    image.len(),
+                    self.positions.len()
+                )));
+            }
+            let mismatch = self
+                .positions
+                .species()
+                .into_iter()
+                .zip(image.species())
+                .position(|(a, b)| !a.same_species(b));
+            if let Some(i) = mismatch {
+                return Err(Error::Message(format!(
+                    "atom {} is {} in {reactant} but {} in {name}",
+                    i + 1,
+                    self.positions.species()[i],
+                    image.species()[i]
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the virtual-crystal (`MIXTURE`) sites.
+    ///
+    /// All components sharing a mixture index must sit at the same position, be distinct
+    /// species and carry positive fractions adding up to 1. Mixtures are not checked by
+    /// [`CellDocumentBuilder::build`], so partially edited cells can still be built.
+    pub fn check_mixtures(&self) -> CResult<()> {
+        let mixtures = self.positions.mixtures();
+        let species = self.positions.species();
+        let frac = self.positions.frac_coords(&self.lattice)?;
+        let vectors = self.lattice.vectors();
+        let mut ids: Vec<u32> = mixtures.iter().flatten().map(|m| m.0).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        for id in ids {
+            let members: Vec<usize> = (0..mixtures.len())
+                .filter(|&i| mixtures[i].is_some_and(|m| m.0 == id))
+                .collect();
+            let first = members[0];
+            if let Some(&i) = members.iter().find(|&&i| {
+                math::periodic_distance(vectors, math::sub(frac[i], frac[first]))
+                    > MIXTURE_POSITION_TOL
+            }) {
+                return Err(Error::Message(format!(
+                    "MIXTURE {id} components {} and {} are at different positions",
+                    first + 1,
+                    i + 1
+                )));
+            }
+            let members_species: Vec<&Species> = members.iter().map(|&i| species[i]).collect();
+            let weights: Vec<f64> = members.iter().map(|&i| mixtures[i].unwrap().1).collect();
+            crate::edit::check_mixture(id, &members_species, &weights)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every species in the positions block resolves to an element (see
+    /// [`Species::element`]).
+    ///
+    /// Parsing keeps unknown labels, so this is the place to catch typos such as `Sx`.
+    pub fn check_species(&self) -> CResult<()> {
+        let mut unknown: Vec<String> = Vec::new();
+        for species in self.positions.species() {
+            let label = species.to_string();
+            if species.element().is_none() && !unknown.contains(&label) {
+                unknown.push(label);
+            }
+        }
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Message(format!(
+                "species {} do not name a known element",
+                unknown.join(", ")
+            )))
+        }
+    }
+}
+
+/// Largest distance in Å between components of one `MIXTURE` site.
+const MIXTURE_POSITION_TOL: f64 = 1e-4;
+
+/// Block name of `positions` with an optional `_INTERMEDIATE`/`_PRODUCT` suffix.
+fn block_name(positions: &Positions, suffix: &str) -> String {
+    let base = match positions {
+        Positions::Frac(_) => "POSITIONS_FRAC",
+        Positions::Abs(_) => "POSITIONS_ABS",
+    };
+    if suffix.is_empty() {
+        base.to_string()
+    } else {
+        format!("{base}_{suffix}")
+    }
+}
+
 impl<S: cell_document_builder::IsComplete> CellDocumentBuilder<S> {
     pub fn build(self) -> CResult<CellDocument> {
         let doc = self.build_internal();
@@ -475,6 +913,8 @@ impl<S: cell_document_builder::IsComplete> CellDocumentBuilder<S> {
             return Err(Error::Message("At most one of symmetry_generate, symmetry_ops may be specified".into()));
         }
 
+        doc.check_transition_state()?;
+
         Ok(doc)
     }
 }
@@ -496,6 +936,7 @@ impl FromCellFile for CellDocument {
     /// - Required blocks are missing
     /// - Block content is malformed
     /// - Multiple position blocks are present
+    /// - Transition-state geometries do not match the reactant atoms
     /// - Any block fails to parse according to its schema
     ///
     /// # Example
@@ -529,40 +970,77 @@ impl FromCellFile for CellDocument {
             ));
         }
         let lattice = if has_lattice_cart {
-            Lattice::Cart(LatticeCart::from_block_rows(find_block(
+            Lattice::Cart(LatticeCart::from_file_rows(find_block(
                 cells,
                 "LATTICE_CART",
             )?)?)
         } else {
             let rows = find_block(cells, "LATTICE_ABC")?;
-            Lattice::Abc(LatticeABC::from_block_rows(rows)?)
+            Lattice::Abc(LatticeABC::from_file_rows(rows)?)
         };
 
         let positions = if find_block(cells, "POSITIONS_FRAC").is_ok() {
-            Positions::Frac(PositionsFrac::from_block_rows(find_block(
+            Positions::Frac(PositionsFrac::from_file_rows(find_block(
                 cells,
                 "POSITIONS_FRAC",
             )?)?)
         } else {
-            Positions::Abs(PositionsAbs::from_block_rows(find_block(
+            Positions::Abs(PositionsAbs::from_file_rows(find_block(
                 cells,
                 "POSITIONS_ABS",
             )?)?)
         };
 
+        let positions_intermediate = match (
+            find_block(cells, "POSITIONS_FRAC_INTERMEDIATE"),
+            find_block(cells, "POSITIONS_ABS_INTERMEDIATE"),
+        ) {
+            (Ok(_), Ok(_)) => {
+                return Err(Error::Message(
+                    "Both POSITIONS_FRAC_INTERMEDIATE and POSITIONS_ABS_INTERMEDIATE are specified."
+                        .into(),
+                ));
+            }
+            (Ok(rows), _) => Some(PositionsIntermediate::Frac(
+                PositionsFracIntermediate::from_file_rows(rows)?,
+            )),
+            (_, Ok(rows)) => Some(PositionsIntermediate::Abs(
+                PositionsAbsIntermediate::from_file_rows(rows)?,
+            )),
+            _ => None,
+        };
+
+        let positions_product = match (
+            find_block(cells, "POSITIONS_FRAC_PRODUCT"),
+            find_block(cells, "POSITIONS_ABS_PRODUCT"),
+        ) {
+            (Ok(_), Ok(_)) => {
+                return Err(Error::Message(
+                    "Both POSITIONS_FRAC_PRODUCT and POSITIONS_ABS_PRODUCT are specified.".into(),
+                ));
+            }
+            (Ok(rows), _) => Some(PositionsProduct::Frac(PositionsFracProduct::from_file_rows(
+                rows,
+            )?)),
+            (_, Ok(rows)) => Some(PositionsProduct::Abs(PositionsAbsProduct::from_file_rows(
+                rows,
+            )?)),
+            _ => None,
+        };
+
         let kpoints_list = find_block_any(cells, &["KPOINT_LIST", "KPOINTS_LIST"])
             .ok()
-            .map(|rows| KpointsList::from_block_rows(rows))
+            .map(|rows| KpointsList::from_file_rows(rows))
             .transpose()?;
 
         let optics_kpoints_list = find_block_any(cells, &["OPTICS_KPOINT_LIST", "OPTICS_KPOINTS_LIST"])
             .ok()
-            .map(|rows| OpticsKpointsList::from_block_rows(rows))
+            .map(|rows| OpticsKpointsList::from_file_rows(rows))
             .transpose()?;
 
         let magres_kpoints_list = find_block_any(cells, &["MAGRES_KPOINT_LIST", "MAGRES_KPOINTS_LIST"])
             .ok()
-            .map(|rows| MagresKpointsList::from_block_rows(rows))
+            .map(|rows| MagresKpointsList::from_file_rows(rows))
             .transpose()?;
 
         let spectral_kpoint_path = find_block_any(
@@ -570,7 +1048,7 @@ impl FromCellFile for CellDocument {
             &["SPECTRAL_KPOINT_PATH", "SPECTRAL_KPOINTS_PATH", "BS_KPOINT_PATH", "BS_KPOINTS_PATH"],
         )
         .ok()
-        .map(|rows| SpectralKpointPath::from_block_rows(rows))
+        .map(|rows| SpectralKpointPath::from_file_rows(rows))
         .transpose()?;
 
         let spectral_kpoints_list = find_block_any(
@@ -578,7 +1056,7 @@ impl FromCellFile for CellDocument {
             &["SPECTRAL_KPOINT_LIST", "SPECTRAL_KPOINTS_LIST", "BS_KPOINT_LIST", "BS_KPOINTS_LIST"],
         )
         .ok()
-        .map(|rows| SpectralKpointsList::from_block_rows(rows))
+        .map(|rows| SpectralKpointsList::from_file_rows(rows))
         .transpose()?;
 
         let bs_kpoint_path = if spectral_kpoint_path.is_some() {
@@ -586,7 +1064,7 @@ impl FromCellFile for CellDocument {
         } else {
             find_block_any(cells, &["BS_KPOINT_PATH", "BS_KPOINTS_PATH"])
                 .ok()
-                .map(|rows| BsKpointPath::from_block_rows(rows))
+                .map(|rows| BsKpointPath::from_file_rows(rows))
                 .transpose()?
         };
 
@@ -595,7 +1073,7 @@ impl FromCellFile for CellDocument {
         } else {
             find_block_any(cells, &["BS_KPOINT_LIST", "BS_KPOINTS_LIST"])
                 .ok()
-                .map(|rows| BSKpointList::from_block_rows(rows))
+                .map(|rows| BSKpointList::from_file_rows(rows))
                 .transpose()?
         };
 
@@ -612,7 +1090,7 @@ impl FromCellFile for CellDocument {
 
         let symmetry_ops = find_block(cells, "SYMMETRY_OPS")
             .ok()
-            .map(|rows| SymmetryOps::from_block_rows(rows))
+            .map(|rows| SymmetryOps::from_file_rows(rows))
             .transpose()?;
 
         let symmetry_tol = SymmetryTol::from_cells(cells)?;
@@ -634,12 +1112,12 @@ impl FromCellFile for CellDocument {
 
         let ionic_constraints = find_block(cells, "IONIC_CONSTRAINTS")
             .ok()
-            .map(|rows| IonicConstraints::from_block_rows(rows))
+            .map(|rows| IonicConstraints::from_file_rows(rows))
             .transpose()?;
 
         let nonlinear_constraints = find_block(cells, "NONLINEAR_CONSTRAINTS")
             .ok()
-            .map(|rows| NonlinearConstraints::from_block_rows(rows))
+            .map(|rows| NonlinearConstraints::from_file_rows(rows))
             .transpose()?;
 
         let fix_all_ions = cells.iter().find_map(|c| {
@@ -663,57 +1141,59 @@ impl FromCellFile for CellDocument {
         let fix_vol = FixVOL::from_cells(cells)?;
         let cell_constraints = find_block(cells, "CELL_CONSTRAINTS")
             .ok()
-            .map(|rows| CellConstraints::from_block_rows(rows))
+            .map(|rows| CellConstraints::from_file_rows(rows))
             .transpose()?;
 
         let external_efield = find_block(cells, "EXTERNAL_EFIELD")
             .ok()
-            .map(|rows| ExternalEfield::from_block_rows(rows))
+            .map(|rows| ExternalEfield::from_file_rows(rows))
             .transpose()?;
 
         let external_pressure = find_block(cells, "EXTERNAL_PRESSURE")
             .ok()
-            .map(|rows| ExternalPressure::from_block_rows(rows))
+            .map(|rows| ExternalPressure::from_file_rows(rows))
             .transpose()?;
 
         let species_mass = find_block(cells, "SPECIES_MASS")
             .ok()
-            .map(|rows| SpeciesMass::from_block_rows(rows))
+            .map(|rows| SpeciesMass::from_file_rows(rows))
             .transpose()?;
 
         let species_pot = find_block(cells, "SPECIES_POT")
             .ok()
-            .map(|rows| SpeciesPot::from_block_rows(rows))
+            .map(|rows| SpeciesPot::from_file_rows(rows))
             .transpose()?;
 
         let species_lcao_states = find_block(cells, "SPECIES_LCAO_STATES")
             .ok()
-            .map(|rows| SpeciesLcaoStates::from_block_rows(rows))
+            .map(|rows| SpeciesLcaoStates::from_file_rows(rows))
             .transpose()?;
 
         let species_q = find_block(cells, "SPECIES_Q")
             .ok()
-            .map(|rows| SpeciesQ::from_block_rows(rows))
+            .map(|rows| SpeciesQ::from_file_rows(rows))
             .transpose()?;
 
         let hubbard_u = find_block(cells, "HUBBARD_U")
             .ok()
-            .map(|rows| HubbardU::from_block_rows(rows))
+            .map(|rows| HubbardU::from_file_rows(rows))
             .transpose()?;
 
+        let quantization_axis = QuantizationAxis::from_cells(cells)?;
+
         let sedc_custom_params = find_block(cells, "SEDC_CUSTOM_PARAMS")
             .ok()
-            .map(|rows| SedcCustomParams::from_block_rows(rows))
+            .map(|rows| SedcCustomParams::from_file_rows(rows))
             .transpose()?;
 
         let phonon_kpoint_list = find_block_any(cells, &["PHONON_KPOINT_LIST", "PHONON_KPOINTS_LIST"])
             .ok()
-            .map(|rows| PhononKpointList::from_block_rows(rows))
+            .map(|rows| PhononKpointList::from_file_rows(rows))
             .transpose()?;
 
         let phonon_kpoint_path = find_block_any(cells, &["PHONON_KPOINT_PATH", "PHONON_KPOINTS_PATH"])
             .ok()
-            .map(|rows| PhononKpointPath::from_block_rows(rows))
+            .map(|rows| PhononKpointPath::from_file_rows(rows))
             .transpose()?;
 
         let phonon_kpoints_mp_grid = PhononKpointsMpGrid::from_cells(cells)?;
@@ -725,7 +1205,7 @@ impl FromCellFile for CellDocument {
             &["PHONON_FINE_KPOINT_PATH", "PHONON_FINE_KPOINTS_PATH"],
         )
         .ok()
-        .map(|rows| PhononFineKpointPath::from_block_rows(rows))
+        .map(|rows| PhononFineKpointPath::from_file_rows(rows))
         .transpose()?;
 
         let phonon_fine_kpoint_path_spacing = PhononFineKpointPathSpacing::from_cells(cells)?;
@@ -735,32 +1215,34 @@ impl FromCellFile for CellDocument {
 
         let phonon_gamma_directions = find_block(cells, "PHONON_GAMMA_DIRECTIONS")
             .ok()
-            .map(|rows| PhononGammaDirections::from_block_rows(rows))
+            .map(|rows| PhononGammaDirections::from_file_rows(rows))
             .transpose()?;
 
         let phonon_fine_kpoint_list = find_block_any(cells, &["PHONON_FINE_KPOINT_LIST", "PHONON_FINE_KPOINTS_LIST"])
             .ok()
-            .map(|rows| PhononFineKpointList::from_block_rows(rows))
+            .map(|rows| PhononFineKpointList::from_file_rows(rows))
             .transpose()?;
 
         let phonon_supercell_matrix = find_block(cells, "PHONON_SUPERCELL_MATRIX")
             .ok()
-            .map(|rows| PhononSupercellMatrix::from_block_rows(rows))
+            .map(|rows| PhononSupercellMatrix::from_file_rows(rows))
             .transpose()?;
 
         let supercell_kpoint_list = find_block_any(cells, &["SUPERCELL_KPOINT_LIST", "SUPERCELL_KPOINTS_LIST"])
             .ok()
-            .map(|rows| SupercellKpointListCastep::from_block_rows(rows))
+            .map(|rows| SupercellKpointListCastep::from_file_rows(rows))
             .transpose()?;
 
         let ionic_velocities = find_block(cells, "IONIC_VELOCITIES")
             .ok()
-            .map(|rows| IonicVelocities::from_block_rows(rows))
+            .map(|rows| IonicVelocities::from_file_rows(rows))
             .transpose()?;
 
         CellDocument::builder()
             .lattice(lattice)
             .positions(positions)
+            .maybe_positions_intermediate(positions_intermediate)
+            .maybe_positions_product(positions_product)
             .maybe_kpoints_list(kpoints_list)
             .maybe_bs_kpoint_path(bs_kpoint_path)
             .maybe_bs_kpoints_list(bs_kpoints_list)
@@ -793,6 +1275,7 @@ impl FromCellFile for CellDocument {
             .maybe_species_lcao_states(species_lcao_states)
             .maybe_species_q(species_q)
             .maybe_hubbard_u(hubbard_u)
+            .maybe_quantization_axis(quantization_axis)
             .maybe_sedc_custom_params(sedc_custom_params)
             .maybe_phonon_kpoint_list(phonon_kpoint_list)
             .maybe_phonon_kpoint_path(phonon_kpoint_path)
@@ -823,7 +1306,7 @@ impl ToCellFile for CellDocument {
     /// # Block Order
     ///
     /// Blocks are emitted in a standard order:
-    /// 1. Lattice and positions (required)
+    /// 1. Lattice and positions (required), then transition-state geometries
     /// 2. K-point sampling blocks
     /// 3. Constraints and flags
     /// 4. External fields
@@ -846,6 +1329,12 @@ impl ToCellFile for CellDocument {
     /// ```
     fn to_cell_file(&self) -> Vec<Cell<'_>> {
         let mut cells = vec![self.lattice.to_cell(), self.positions.to_cell()];
+        if let Some(pi) = &self.positions_intermediate {
+            cells.push(pi.to_cell());
+        }
+        if let Some(pp) = &self.positions_product {
+            cells.push(pp.to_cell());
+        }
 
         if let Some(kp) = &self.kpoints_list {
             cells.push(kp.to_cell());
@@ -943,6 +1432,9 @@ impl ToCellFile for CellDocument {
         if let Some(hu) = &self.hubbard_u {
             cells.push(hu.to_cell());
         }
+        if let Some(qa) = &self.quantization_axis {
+            cells.push(qa.to_cell());
+        }
         if let Some(sc) = &self.sedc_custom_params {
             cells.push(sc.to_cell());
         }
@@ -1000,7 +1492,8 @@ impl ToCellFile for CellDocument {
 mod tests {
     use super::*;
     use crate::cell::species::Species;
-    use crate::cell::positions::PositionFracEntry;
+    use crate::cell::positions::{PositionAbsEntry, PositionFracEntry};
+    use crate::test_fixtures::orthorhombic_lattice;
     use crate::cell::bz_sampling_kpoints::{Kpoint, SpectralKpointPathEntry, BsKpointPathEntry};
     use crate::cell::phonon::{PhononKpointPathEntry, PhononKpointListEntry};
     use crate::cell::symmetry::SymmetryOp;
@@ -1044,6 +1537,38 @@ mod tests {
         })
     }
 
+    #[test]
+    fn check_species_reports_unknown_labels() {
+        let doc = CellDocument::builder()
+            .lattice(minimal_lattice())
+            .positions(minimal_positions())
+            .build()
+            .unwrap();
+        assert!(doc.check_species().is_ok());
+        let input = "%BLOCK LATTICE_CART\n5 0 0\n0 5 0\n0 0 5\n%ENDBLOCK LATTICE_CART\n\
+                     %BLOCK POSITIONS_FRAC\nO:w 0 0 0\nSx 0.5 0 0\nSx 0 0.5 0\n\
+                     %ENDBLOCK POSITIONS_FRAC\n";
+        let parsed = castep_cell_fmt::parse::<CellDocument>(input).unwrap();
+        let err = parsed.check_species().unwrap_err().to_string();
+        assert!(err.contains("species Sx do not"), "{err}");
+    }
+
+    #[test]
+    fn frac_coords_fails_on_a_degenerate_lattice() {
+        let positions = Positions::Abs(PositionsAbs {
+            unit: None,
+            positions: vec![PositionAbsEntry {
+                species: Species::Symbol("Si".to_string()),
+                coord: [1.0, 1.0, 0.0],
+                spin: None,
+                mixture: None,
+            }],
+        });
+        assert!(positions.frac_coords(&minimal_lattice()).is_ok());
+        let flat = orthorhombic_lattice(10.0, 10.0, 0.0);
+        assert!(positions.frac_coords(&flat).is_err());
+    }
+
     #[test]
     fn build_rejects_multiple_kpoint_specs() {
         let result = CellDocument::builder()
@@ -1063,7 +1588,7 @@ mod tests {
             .lattice(minimal_lattice())
             .positions(minimal_positions())
             .maybe_spectral_kpoint_path(Some(SpectralKpointPath::builder()
-                .points(vec![SpectralKpointPathEntry { coord: [0.0, 0.0, 0.0] }])
+                .points(vec![SpectralKpointPathEntry { coord: [0.0, 0.0, 0.0], label: None }])
                 .build()))
             .maybe_spectral_kpoints_mp_grid(Some(SpectralKpointsMpGrid([2, 2, 2])))
             .build();
@@ -1076,10 +1601,10 @@ mod tests {
             .lattice(minimal_lattice())
             .positions(minimal_positions())
             .maybe_spectral_kpoint_path(Some(SpectralKpointPath::builder()
-                .points(vec![SpectralKpointPathEntry { coord: [0.0, 0.0, 0.0] }])
+                .points(vec![SpectralKpointPathEntry { coord: [0.0, 0.0, 0.0], label: None }])
                 .build()))
             .maybe_bs_kpoint_path(Some(BsKpointPath::builder()
-                .points(vec![BsKpointPathEntry { coord: [0.0, 0.0, 0.0] }])
+                .points(vec![BsKpointPathEntry { coord: [0.0, 0.0, 0.0], label: None }])
                 .build()))
             .build();
         assert!(result.is_err());
@@ -1091,7 +1616,8 @@ mod tests {
             .lattice(minimal_lattice())
             .positions(minimal_positions())
             .maybe_phonon_kpoint_path(Some(PhononKpointPath {
-                points: vec![PhononKpointPathEntry { coord: [0.0, 0.0, 0.0] }],
+                points: vec![PhononKpointPathEntry { coord: [0.0, 0.0, 0.0], label: None }],
+                breaks: vec![],
             }))
             .maybe_phonon_kpoint_list(Some(PhononKpointList::builder()
                 .kpoints(vec![PhononKpointListEntry { coord: [0.0, 0.0, 0.0], weight: 1.0 }])
@@ -1150,7 +1676,7 @@ mod tests {
             .lattice(minimal_lattice())
             .positions(minimal_positions())
             .maybe_spectral_kpoint_path(Some(SpectralKpointPath::builder()
-                .points(vec![SpectralKpointPathEntry { coord: [0.0, 0.0, 0.0] }])
+                .points(vec![SpectralKpointPathEntry { coord: [0.0, 0.0, 0.0], label: None }])
                 .build()))
             .build();
         assert!(r2.is_ok());
@@ -1158,7 +1684,8 @@ mod tests {
             .lattice(minimal_lattice())
             .positions(minimal_positions())
             .maybe_phonon_kpoint_path(Some(PhononKpointPath {
-                points: vec![PhononKpointPathEntry { coord: [0.0, 0.0, 0.0] }],
+                points: vec![PhononKpointPathEntry { coord: [0.0, 0.0, 0.0], label: None }],
+                breaks: vec![],
             }))
             .build();
         assert!(r3.is_ok());
@@ -1189,6 +1716,104 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn round_trips_vector_spins_and_quantization_axis() {
+        let input = "%BLOCK LATTICE_CART\n2.87 0 0\n0 2.87 0\n0 0 2.87\n%ENDBLOCK LATTICE_CART\n\
+            %BLOCK POSITIONS_FRAC\n\
+            Fe 0.0 0.0 0.0 SPIN=( 0.0 0.0 2.2 )\n\
+            Fe 0.5 0.5 0.5 SPIN : 1.1 1.1 0.0\n\
+            %ENDBLOCK POSITIONS_FRAC\n\
+            QUANTIZATION_AXIS : 0 0 1\n";
+        let doc = castep_cell_fmt::parse::<CellDocument>(input).unwrap();
+        assert_eq!(
+            doc.positions.spins(),
+            vec![Some(Spin::Vector([0.0, 0.0, 2.2])), Some(Spin::Vector([1.1, 1.1, 0.0]))]
+        );
+        assert_eq!(doc.quantization_axis.unwrap().direction, [0.0, 0.0, 1.0]);
+
+        let output = castep_cell_fmt::format::to_string_many_spaced(&doc.to_cell_file());
+        let reparsed = castep_cell_fmt::parse::<CellDocument>(&output).unwrap();
+        assert_eq!(reparsed.positions.spins(), doc.positions.spins());
+        assert_eq!(reparsed.quantization_axis, doc.quantization_axis);
+    }
+
+    const TS_LATTICE: &str = "%BLOCK LATTICE_CART\n5 0 0\n0 5 0\n0 0 5\n%ENDBLOCK LATTICE_CART\n";
+
+    fn ts_block(name: &str, rows: &[&str]) -> String {
+        format!("%BLOCK {name}\n{}\n%ENDBLOCK {name}\n", rows.join("\n"))
+    }
+
+    #[test]
+    fn round_trips_transition_state_geometries() {
+        let input = [
+            TS_LATTICE.to_string(),
+            ts_block("POSITIONS_FRAC", &["H 0.1 0.0 0.0", "O 0.0 0.0 0.0", "H 0.9 0.0 0.0"]),
+            ts_block(
+                "POSITIONS_FRAC_INTERMEDIATE",
+                &["H 0.2 0.0 0.0", "O 0.0 0.0 0.0", "H 0.9 0.0 0.0"],
+            ),
+            ts_block(
+                "POSITIONS_FRAC_PRODUCT",
+                &["H 0.3 0.0 0.0", "O 0.0 0.0 0.0", "H 0.9 0.0 0.0"],
+            ),
+        ]
+        .concat();
+        let doc = castep_cell_fmt::parse::<CellDocument>(&input).unwrap();
+        assert!(matches!(doc.positions_intermediate, Some(PositionsIntermediate::Frac(_))));
+        assert!(matches!(doc.positions_product, Some(PositionsProduct::Frac(_))));
+
+        let output = castep_cell_fmt::format::to_string_many_spaced(&doc.to_cell_file());
+        let reparsed = castep_cell_fmt::parse::<CellDocument>(&output).unwrap();
+        let product = |d: &CellDocument| {
+            d.positions_product.as_ref().unwrap().to_positions().frac_coords(&d.lattice).unwrap()
+        };
+        assert_eq!(product(&reparsed), product(&doc));
+        assert_eq!(product(&doc)[0], [0.3, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn drops_trailing_comments_outside_path_blocks() {
+        let input = "%BLOCK LATTICE_CART\n5 0 0 ! a\n0 5 0\n0 0 5\n%ENDBLOCK LATTICE_CART\n\
+                     %BLOCK POSITIONS_FRAC\nSi 0 0 0 # origin\n%ENDBLOCK POSITIONS_FRAC\n\
+                     %BLOCK KPOINT_LIST\n0 0 0 1!G\n%ENDBLOCK KPOINT_LIST\n\
+                     %BLOCK BS_KPOINT_PATH\n0 0 0 ! G\n0.5 0 0!X\n%ENDBLOCK BS_KPOINT_PATH\n";
+        let doc = castep_cell_fmt::parse::<CellDocument>(input).unwrap();
+        assert_eq!(doc.lattice.vectors()[0], [5.0, 0.0, 0.0]);
+        assert_eq!(doc.positions.len(), 1);
+        assert_eq!(doc.kpoints_list.unwrap().kpts[0].weight, 1.0);
+        let path = doc.spectral_kpoint_path.unwrap();
+        assert_eq!(path.points[1].label.as_deref(), Some("X"));
+    }
+
+    #[test]
+    fn rejects_mismatched_transition_state_geometries() {
+        let reactant = ts_block("POSITIONS_FRAC", &["H 0.1 0.0 0.0", "O 0.0 0.0 0.0"]);
+        let cases = [
+            // Different coordinate mode.
+            ts_block("POSITIONS_ABS_PRODUCT", &["H 0.5 0.0 0.0", "O 0.0 0.0 0.0"]),
+            // Missing atom.
+            ts_block("POSITIONS_FRAC_PRODUCT", &["H 0.2 0.0 0.0"]),
+            // Atoms in a different order.
+            ts_block("POSITIONS_FRAC_PRODUCT", &["O 0.0 0.0 0.0", "H 0.2 0.0 0.0"]),
+            // Intermediate without a product.
+            ts_block("POSITIONS_FRAC_INTERMEDIATE", &["H 0.2 0.0 0.0", "O 0.0 0.0 0.0"]),
+            // Both product blocks.
+            [
+                ts_block("POSITIONS_FRAC_PRODUCT", &["H 0.2 0.0 0.0", "O 0.0 0.0 0.0"]),
+                ts_block("POSITIONS_ABS_PRODUCT", &["H 1.0 0.0 0.0", "O 0.0 0.0 0.0"]),
+            ]
+            .concat(),
+        ];
+        for case in cases {
+            let input = [TS_LATTICE, &reactant, &case].concat();
+            assert!(castep_cell_fmt::parse::<CellDocument>(&input).is_err(), "{case}");
+        }
+
+        let same_species = ts_block("POSITIONS_FRAC_PRODUCT", &["1 0.2 0.0 0.0", "o 0.0 0.0 0.0"]);
+        let input = [TS_LATTICE, &reactant, &same_species].concat();
+        assert!(castep_cell_fmt::parse::<CellDocument>(&input).is_ok());
+    }
+
     #[test]
     fn build_allows_empty_document() {
         let result = CellDocument::builder()
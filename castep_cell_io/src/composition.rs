@@ -0,0 +1,269 @@
+//! Chemical composition, formulae, mass and density of a cell.
+//!
+//! Every atom counts with its `MIXTURE` weight, so a site shared by Si and Ge with
+//! weights 0.5 adds half an atom of each. Masses come from `SPECIES_MASS` where it lists
+//! the species, and from the standard atomic weights otherwise.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::CellDocument;
+use crate::periodic_table::Element;
+use crate::units::AMU_IN_G;
+
+/// Number of atoms of each element in a cell.
+///
+/// Elements are kept in Hill order: carbon first and hydrogen second if carbon is
+/// present, everything else alphabetically by symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Composition {
+    amounts: Vec<(&'static Element, f64)>,
+}
+
+impl Composition {
+    /// Builds a composition from element amounts, merging repeated elements.
+    pub fn new(amounts: impl IntoIterator<Item = (&'static Element, f64)>) -> Self {
+        let mut merged: Vec<(&'static Element, f64)> = Vec::new();
+        for (element, amount) in amounts {
+            match merged.iter_mut().find(|(e, _)| *e == element) {
+                Some((_, total)) => *total += amount,
+                None => merged.push((element, amount)),
+            }
+        }
+        let has_carbon = merged.iter().any(|(e, _)| e.symbol == "C");
+        let rank = |e: &Element| match e.symbol {
+            "C" if has_carbon => 0,
+            "H" if has_carbon => 1,
+            _ => 2,
+        };
+        merged.sort_by(|(a, _), (b, _)| rank(a).cmp(&rank(b)).then(a.symbol.cmp(b.symbol)));
+        Self { amounts: merged }
+    }
+
+    /// Returns the elements and their amounts, in Hill order.
+    pub fn amounts(&self) -> &[(&'static Element, f64)] {
+        &self.amounts
+    }
+
+    /// Returns the amount of `element`, zero if it is absent.
+    pub fn amount(&self, element: &Element) -> f64 {
+        self.amounts
+            .iter()
+            .find(|(e, _)| *e == element)
+            .map_or(0.0, |(_, n)| *n)
+    }
+
+    /// Returns the total number of atoms.
+    pub fn total(&self) -> f64 {
+        self.amounts.iter().map(|(_, n)| n).sum()
+    }
+
+    /// Returns the formula of the whole cell, e.g. `Fe4O6`.
+    pub fn formula(&self) -> String {
+        formula(self.amounts.iter().map(|(e, n)| (*e, *n)))
+    }
+
+    /// Returns the number of formula units: the largest factor that divides every
+    /// amount. Mixtures can make this fractional.
+    pub fn formula_units(&self) -> f64 {
+        // Amounts are taken as rationals with a denominator of at most 1000.
+        const DENOMINATOR: f64 = 1000.0;
+        let divisor = self
+            .amounts
+            .iter()
+            .map(|(_, n)| (n * DENOMINATOR).round() as u64)
+            .fold(0, gcd);
+        if divisor == 0 {
+            1.0
+        } else {
+            divisor as f64 / DENOMINATOR
+        }
+    }
+
+    /// Returns the formula of one formula unit, e.g. `Fe2O3`.
+    pub fn reduced_formula(&self) -> String {
+        let units = self.formula_units();
+        formula(self.amounts.iter().map(|(e, n)| (*e, n / units)))
+    }
+}
+
+impl std::fmt::Display for Composition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.formula())
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn formula(amounts: impl Iterator<Item = (&'static Element, f64)>) -> String {
+    amounts
+        .map(|(element, n)| {
+            if (n - 1.0).abs() < 1e-6 {
+                element.symbol.to_string()
+            } else if (n - n.round()).abs() < 1e-6 {
+                format!("{}{}", element.symbol, n.round())
+            } else {
+                let digits = format!("{n:.3}");
+                format!("{}{}", element.symbol, digits.trim_end_matches('0'))
+            }
+        })
+        .collect()
+}
+
+impl CellDocument {
+    /// Returns the composition of the cell, with `MIXTURE` components weighted.
+    ///
+    /// Fails if a species does not resolve to an element.
+    pub fn composition(&self) -> CResult<Composition> {
+        let weights = self.mixture_weights();
+        let amounts = self
+            .positions
+            .species()
+            .into_iter()
+            .zip(weights)
+            .map(|(species, w)| {
+                let element = species.element().ok_or_else(|| {
+                    Error::Message(format!("species {species} is not a known element"))
+                })?;
+                Ok((element, w))
+            })
+            .collect::<CResult<Vec<_>>>()?;
+        Ok(Composition::new(amounts))
+    }
+
+    /// Returns the mass of the cell contents in amu, using `SPECIES_MASS` where it lists
+    /// a species and `MIXTURE` weights for shared sites.
+    pub fn total_mass(&self) -> CResult<f64> {
+        Ok(self
+            .atom_masses()?
+            .iter()
+            .zip(self.mixture_weights())
+            .map(|(m, w)| w * m)
+            .sum())
+    }
+
+    /// Returns the mass of every atom in amu, from `SPECIES_MASS` where it lists the
+    /// species and the standard atomic weight otherwise. `MIXTURE` weights are not
+    /// applied.
+    pub(crate) fn atom_masses(&self) -> CResult<Vec<f64>> {
+        let unit = self
+            .species_mass
+            .as_ref()
+            .map_or(1.0, |sm| sm.unit.unwrap_or_default().in_amu());
+        self.positions
+            .species()
+            .into_iter()
+            .map(|species| {
+                let listed = self.species_mass.as_ref().and_then(|sm| {
+                    sm.masses
+                        .iter()
+                        .find(|entry| entry.species.same_species(species))
+                });
+                match listed {
+                    Some(entry) => Ok(entry.mass * unit),
+                    None => species.element().map(|e| e.mass).ok_or_else(|| {
+                        Error::Message(format!("species {species} is not a known element"))
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the density in g/cm³.
+    pub fn density(&self) -> CResult<f64> {
+        // amu/Å³ to g/cm³: AMU_IN_G g per 1e-24 cm³.
+        Ok(self.total_mass()? * AMU_IN_G * 1e24 / self.lattice.volume())
+    }
+
+    /// Returns the weight each atom contributes: its `MIXTURE` weight, or 1.
+    fn mixture_weights(&self) -> Vec<f64> {
+        self.positions
+            .mixtures()
+            .into_iter()
+            .map(|m| m.map_or(1.0, |(_, w)| w))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::positions::{PositionFracEntry, PositionsFrac};
+    use crate::cell::species::{Species, SpeciesMass, SpeciesMassEntry};
+    use crate::test_fixtures::{atom, cell, cubic_lattice};
+    use crate::units::MassUnit;
+
+    fn doc(a: f64, atoms: &[(&str, Option<(u32, f64)>)]) -> CellDocument {
+        let positions = atoms
+            .iter()
+            .enumerate()
+            .map(|(i, (symbol, mixture))| PositionFracEntry {
+                mixture: *mixture,
+                ..atom(symbol, [0.1 * i as f64, 0.0, 0.0])
+            })
+            .collect();
+        cell(cubic_lattice(a), PositionsFrac { positions })
+    }
+
+    #[test]
+    fn test_formulae() {
+        let atoms = [("O", None), ("Fe:up", None), ("O", None), ("Fe1", None)];
+        let cell = doc(
+            5.0,
+            &[&atoms[..], &atoms[..], &[("O", None), ("O", None)]].concat(),
+        );
+        let composition = cell.composition().unwrap();
+        assert_eq!(composition.formula(), "Fe4O6");
+        assert_eq!(composition.reduced_formula(), "Fe2O3");
+        assert_eq!(composition.formula_units(), 2.0);
+        assert_eq!(composition.total(), 10.0);
+
+        let organic = Composition::new([
+            (Element::from_symbol("O").unwrap(), 1.0),
+            (Element::from_symbol("H").unwrap(), 6.0),
+            (Element::from_symbol("C").unwrap(), 2.0),
+        ]);
+        assert_eq!(organic.formula(), "C2H6O");
+        let inorganic = Composition::new([
+            (Element::from_symbol("O").unwrap(), 1.0),
+            (Element::from_symbol("H").unwrap(), 2.0),
+        ]);
+        assert_eq!(inorganic.formula(), "H2O");
+    }
+
+    #[test]
+    fn test_mixture_weighting() {
+        let cell = doc(
+            5.43,
+            &[
+                ("Si", Some((1, 0.75))),
+                ("Ge", Some((1, 0.25))),
+                ("Si", None),
+            ],
+        );
+        let composition = cell.composition().unwrap();
+        assert_eq!(composition.formula(), "Ge0.25Si1.75");
+        assert_eq!(composition.formula_units(), 0.25);
+        assert_eq!(composition.reduced_formula(), "GeSi7");
+        let mass = 1.75 * 28.085 + 0.25 * 72.630;
+        assert!((cell.total_mass().unwrap() - mass).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mass_and_density() {
+        // Eight Si atoms in the conventional diamond cell.
+        let mut cell = doc(5.431, &[("Si", None); 8]);
+        assert!((cell.total_mass().unwrap() - 8.0 * 28.085).abs() < 1e-9);
+        assert!((cell.density().unwrap() - 2.329).abs() < 1e-3);
+
+        cell.species_mass = Some(SpeciesMass {
+            unit: Some(MassUnit::Gram),
+            masses: vec![SpeciesMassEntry {
+                species: Species::AtomicNumber(14),
+                mass: 30.0 * AMU_IN_G,
+            }],
+        });
+        assert!((cell.total_mass().unwrap() - 240.0).abs() < 1e-9);
+    }
+}
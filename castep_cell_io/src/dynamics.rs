@@ -0,0 +1,417 @@
+//! Maxwell–Boltzmann initial velocities for molecular dynamics.
+//!
+//! Velocities are drawn per particle from a Gaussian of variance k_B T / m, projected
+//! onto the motions allowed by `IONIC_CONSTRAINTS` with zero centre-of-mass momentum,
+//! and rescaled so the kinetic temperature over the remaining degrees of freedom equals
+//! the target exactly. A `MIXTURE` site is a single particle whose mass is the weighted
+//! sum of its components, and every component receives the site velocity.
+
+use std::collections::HashMap;
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::velocities::{IonicVelocities, IonicVelocityEntry};
+use crate::math::{self, Vec3};
+use crate::units::VelocityUnit;
+use crate::{CellDocument, ParamDocument};
+
+/// Boltzmann constant in amu Å² ps⁻² K⁻¹.
+const BOLTZMANN: f64 = 0.831_446_261_815_324;
+
+/// Rows shorter than this after orthogonalisation are linearly dependent.
+const RANK_TOL: f64 = 1e-10;
+
+/// Settings for drawing initial ionic velocities at a target temperature.
+///
+/// # Example
+///
+/// ```no_run
+/// use castep_cell_io::CellDocument;
+/// use castep_cell_io::dynamics::MaxwellBoltzmann;
+///
+/// # fn run(doc: &mut CellDocument) -> castep_cell_fmt::CResult<()> {
+/// let velocities = MaxwellBoltzmann::builder()
+///     .temperature(300.0)
+///     .seed(42)
+///     .build()
+///     .generate(doc)?;
+/// doc.ionic_velocities = Some(velocities);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, bon::Builder)]
+pub struct MaxwellBoltzmann {
+    /// Target temperature in K.
+    pub temperature: f64,
+    /// Random seed; without one the generator is seeded from the clock.
+    pub seed: Option<u64>,
+    /// Unit of the generated block (default: Å/ps).
+    pub unit: Option<VelocityUnit>,
+}
+
+impl MaxwellBoltzmann {
+    /// Takes the temperature from `MD_TEMPERATURE` (default 300 K) and the seed from
+    /// `RAND_SEED`, where 0 or an absent keyword means a clock seed as in CASTEP.
+    pub fn from_param(param: &ParamDocument) -> Self {
+        let temperature = param
+            .molecular_dynamics
+            .md_temperature
+            .as_ref()
+            .map_or(300.0, |t| t.value);
+        let seed = param
+            .general
+            .rand_seed
+            .filter(|s| s.0 != 0)
+            .map(|s| i64::from(s.0) as u64);
+        MaxwellBoltzmann {
+            temperature,
+            seed,
+            unit: None,
+        }
+    }
+
+    /// Returns an `IONIC_VELOCITIES` block for `doc` at the target temperature.
+    ///
+    /// Fails if the temperature is negative, if `FIX_ALL_IONS` is set or the constraints
+    /// leave no degree of freedom at a non-zero temperature, or if a mass is unknown.
+    pub fn generate(&self, doc: &CellDocument) -> CResult<IonicVelocities> {
+        if self.temperature.is_nan() || self.temperature < 0.0 {
+            return Err(Error::Message(format!(
+                "temperature must be non-negative, got {} K",
+                self.temperature
+            )));
+        }
+        if doc.fix_all_ions.is_some_and(|f| f.0) {
+            return Err(Error::Message(
+                "FIX_ALL_IONS is set, so no ion can be given a velocity".into(),
+            ));
+        }
+        let particles = Particles::new(doc)?;
+        let constraints = particles.constraint_basis(doc)?;
+        let freedom = 3 * particles.len() - constraints.len();
+        if freedom == 0 && self.temperature > 0.0 {
+            return Err(Error::Message(
+                "the constraints leave no degree of freedom to carry a temperature".into(),
+            ));
+        }
+
+        let mut rng = SplitMix64::new(self.seed.unwrap_or_else(clock_seed));
+        let mut v: Vec<f64> = particles
+            .masses
+            .iter()
+            .flat_map(|&m| [m; 3])
+            .map(|m| rng.normal() * (BOLTZMANN * self.temperature / m).sqrt())
+            .collect();
+        project_out(&mut v, &constraints);
+
+        let current = particles.temperature(&v, freedom);
+        let factor = if current > 0.0 {
+            (self.temperature / current).sqrt()
+        } else {
+            0.0
+        };
+        let unit = self.unit.unwrap_or_default();
+        let to_unit = factor / unit.in_ang_per_ps();
+        let velocities = doc
+            .positions
+            .species()
+            .into_iter()
+            .zip(&particles.of_atom)
+            .map(|(species, &p)| IonicVelocityEntry {
+                species: species.clone(),
+                velocity: [0, 1, 2].map(|k| v[3 * p + k] * to_unit),
+            })
+            .collect();
+        Ok(IonicVelocities {
+            unit: self.unit,
+            velocities,
+        })
+    }
+}
+
+impl CellDocument {
+    /// Returns the kinetic temperature in K of `IONIC_VELOCITIES`, counting the degrees
+    /// of freedom left by `IONIC_CONSTRAINTS` and zero centre-of-mass momentum.
+    ///
+    /// Returns `None` without an `IONIC_VELOCITIES` block. Fails if the block does not
+    /// have one row per atom.
+    pub fn kinetic_temperature(&self) -> CResult<Option<f64>> {
+        let Some(block) = &self.ionic_velocities else {
+            return Ok(None);
+        };
+        if block.velocities.len() != self.positions.len() {
+            return Err(Error::Message(format!(
+                "IONIC_VELOCITIES has {} rows but the cell has {} atoms",
+                block.velocities.len(),
+                self.positions.len()
+            )));
+        }
+        let particles = Particles::new(self)?;
+        let freedom = 3 * particles.len() - particles.constraint_basis(self)?.len();
+        if freedom == 0 {
+            return Ok(Some(0.0));
+        }
+        let scale = block.unit.unwrap_or_default().in_ang_per_ps();
+        let mut v = vec![0.0; 3 * particles.len()];
+        // Components of a mixture site share its velocity; take the first row.
+        for (i, &p) in particles.of_atom.iter().enumerate().rev() {
+            for k in 0..3 {
+                v[3 * p + k] = block.velocities[i].velocity[k] * scale;
+            }
+        }
+        Ok(Some(particles.temperature(&v, freedom)))
+    }
+}
+
+/// Atoms grouped into independently moving particles.
+struct Particles {
+    /// Mass of each particle in amu.
+    masses: Vec<f64>,
+    /// Particle of each atom, in positions order.
+    of_atom: Vec<usize>,
+}
+
+impl Particles {
+    fn new(doc: &CellDocument) -> CResult<Self> {
+        let atom_masses = doc.atom_masses()?;
+        let mut masses = Vec::new();
+        let mut of_atom = Vec::with_capacity(atom_masses.len());
+        let mut sites: HashMap<u32, usize> = HashMap::new();
+        for (mass, mixture) in atom_masses.into_iter().zip(doc.positions.mixtures()) {
+            let (p, weight) = match mixture {
+                Some((site, weight)) => match sites.get(&site) {
+                    Some(&p) => (p, weight),
+                    None => {
+                        sites.insert(site, masses.len());
+                        masses.push(0.0);
+                        (masses.len() - 1, weight)
+                    }
+                },
+                None => {
+                    masses.push(0.0);
+                    (masses.len() - 1, 1.0)
+                }
+            };
+            masses[p] += weight * mass;
+            of_atom.push(p);
+        }
+        Ok(Particles { masses, of_atom })
+    }
+
+    fn len(&self) -> usize {
+        self.masses.len()
+    }
+
+    /// Returns an orthonormal basis of the velocity directions removed by
+    /// `IONIC_CONSTRAINTS` and by fixing the centre-of-mass momentum.
+    fn constraint_basis(&self, doc: &CellDocument) -> CResult<Vec<Vec<f64>>> {
+        let n = 3 * self.len();
+        let mut rows: Vec<(u32, Vec<f64>)> = Vec::new();
+        if let Some(block) = &doc.ionic_constraints {
+            let index = doc.ion_index();
+            for entry in &block.constraints {
+                let atom = index.resolve(&entry.species, entry.ion_number)?;
+                let p = self.of_atom[atom];
+                let row = match rows.iter().position(|(c, _)| *c == entry.constraint_number) {
+                    Some(r) => r,
+                    None => {
+                        rows.push((entry.constraint_number, vec![0.0; n]));
+                        rows.len() - 1
+                    }
+                };
+                for k in 0..3 {
+                    rows[row].1[3 * p + k] += entry.coefficients[k];
+                }
+            }
+        }
+        let mut candidates: Vec<Vec<f64>> = rows.into_iter().map(|(_, row)| row).collect();
+        for k in 0..3 {
+            let mut row = vec![0.0; n];
+            for (p, &m) in self.masses.iter().enumerate() {
+                row[3 * p + k] = m;
+            }
+            candidates.push(row);
+        }
+
+        let mut basis: Vec<Vec<f64>> = Vec::new();
+        for mut row in candidates {
+            let length = norm(&row);
+            if length == 0.0 {
+                continue;
+            }
+            row.iter_mut().for_each(|x| *x /= length);
+            project_out(&mut row, &basis);
+            let residual = norm(&row);
+            if residual > RANK_TOL {
+                row.iter_mut().for_each(|x| *x /= residual);
+                basis.push(row);
+            }
+        }
+        Ok(basis)
+    }
+
+    /// Kinetic temperature in K of particle velocities `v` in Å/ps.
+    fn temperature(&self, v: &[f64], freedom: usize) -> f64 {
+        let twice_kinetic: f64 = self
+            .masses
+            .iter()
+            .enumerate()
+            .map(|(p, &m)| {
+                let vp: Vec3 = [v[3 * p], v[3 * p + 1], v[3 * p + 2]];
+                m * math::dot(vp, vp)
+            })
+            .sum();
+        twice_kinetic / (freedom as f64 * BOLTZMANN)
+    }
+}
+
+/// Removes from `v` its components along the orthonormal `basis`.
+fn project_out(v: &mut [f64], basis: &[Vec<f64>]) {
+    for b in basis {
+        let overlap: f64 = v.iter().zip(b).map(|(x, y)| x * y).sum();
+        v.iter_mut().zip(b).for_each(|(x, y)| *x -= overlap * y);
+    }
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+fn clock_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// SplitMix64 generator (Steele, Lea and Flood, 2014), ample for initial velocities.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in (0, 1].
+    fn uniform(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal by the Box–Muller transform.
+    fn normal(&mut self) -> f64 {
+        let r = (-2.0 * self.uniform().ln()).sqrt();
+        r * (std::f64::consts::TAU * self.uniform()).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::constraints::{FixAllIons, IonicConstraintEntry, IonicConstraints};
+    use crate::cell::species::Species;
+    use crate::test_fixtures::cubic_cell;
+
+    fn doc() -> CellDocument {
+        cubic_cell(
+            10.0,
+            &[
+                ("O", [0.0, 0.0, 0.0]),
+                ("H", [0.1, 0.0, 0.0]),
+                ("H", [0.0, 0.1, 0.0]),
+                ("Fe", [0.5, 0.5, 0.5]),
+            ],
+        )
+    }
+
+    fn momentum(doc: &CellDocument, block: &IonicVelocities) -> Vec3 {
+        doc.atom_masses()
+            .unwrap()
+            .iter()
+            .zip(&block.velocities)
+            .fold([0.0; 3], |acc, (m, e)| {
+                math::add(acc, math::scale(e.velocity, *m))
+            })
+    }
+
+    #[test]
+    fn seeded_velocities_are_reproducible_and_exact() {
+        let mut doc = doc();
+        let generator = MaxwellBoltzmann::builder()
+            .temperature(500.0)
+            .seed(7)
+            .build();
+        let first = generator.generate(&doc).unwrap();
+        assert_eq!(first, generator.generate(&doc).unwrap());
+        assert!(math::norm(momentum(&doc, &first)) < 1e-9);
+
+        doc.ionic_velocities = Some(first);
+        let t = doc.kinetic_temperature().unwrap().unwrap();
+        assert!((t - 500.0).abs() < 1e-9, "{t}");
+    }
+
+    #[test]
+    fn unit_is_respected() {
+        let doc = doc();
+        let ang_ps = MaxwellBoltzmann::builder()
+            .temperature(300.0)
+            .seed(1)
+            .build()
+            .generate(&doc)
+            .unwrap();
+        let ang_fs = MaxwellBoltzmann::builder()
+            .temperature(300.0)
+            .seed(1)
+            .unit(VelocityUnit::AngPerFs)
+            .build()
+            .generate(&doc)
+            .unwrap();
+        let ratio = ang_ps.velocities[0].velocity[0] / ang_fs.velocities[0].velocity[0];
+        assert!((ratio - 1e3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fixed_atoms_stay_still() {
+        let mut doc = doc();
+        doc.ionic_constraints = Some(IonicConstraints {
+            constraints: (0..3)
+                .map(|k| {
+                    let mut coefficients = [0.0; 3];
+                    coefficients[k] = 1.0;
+                    IonicConstraintEntry {
+                        constraint_number: k as u32 + 1,
+                        species: Species::Symbol("Fe".into()),
+                        ion_number: 1,
+                        coefficients,
+                    }
+                })
+                .collect(),
+        });
+        let block = MaxwellBoltzmann::builder()
+            .temperature(300.0)
+            .seed(3)
+            .build()
+            .generate(&doc)
+            .unwrap();
+        assert!(math::norm(block.velocities[3].velocity) < 1e-12);
+        assert!(math::norm(momentum(&doc, &block)) < 1e-9);
+
+        doc.ionic_velocities = Some(block);
+        let t = doc.kinetic_temperature().unwrap().unwrap();
+        assert!((t - 300.0).abs() < 1e-9, "{t}");
+    }
+
+    #[test]
+    fn fix_all_ions_is_rejected() {
+        let mut doc = doc();
+        doc.fix_all_ions = Some(FixAllIons(true));
+        let generator = MaxwellBoltzmann::builder().temperature(300.0).build();
+        assert!(generator.generate(&doc).is_err());
+    }
+}
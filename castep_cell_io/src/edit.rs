@@ -0,0 +1,939 @@
+//! Adding, removing, replacing and reordering atoms, and building and collapsing
+//! `MIXTURE` sites.
+//!
+//! CASTEP addresses ions by species and 1-based number within that species, counted in
+//! positions order, so most edits renumber ions. Every edit rewrites the per-ion blocks
+//! (`IONIC_CONSTRAINTS`, `NONLINEAR_CONSTRAINTS`, `HUBBARD_U`, `IONIC_VELOCITIES`) and the
+//! transition-state geometries to follow the atoms, and fails without touching the document when an edit would leave
+//! one of them referring to an atom that no longer exists. `SYMMETRY_OPS` keeps only the
+//! operations that still map the edited cell onto itself.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::constraints::{
+    AtomSite, IonicConstraintEntry, IonicConstraints, NonlinearConstraint, NonlinearConstraints,
+    extend_span,
+};
+use crate::cell::positions::{PositionAbsEntry, PositionFracEntry, PositionsAbs, PositionsFrac};
+use crate::cell::species::{AtomHubbardU, HubbardU, Species};
+use crate::cell::symmetry::SymmetryOps;
+use crate::cell::velocities::{IonicVelocities, IonicVelocityEntry};
+use crate::geometry::check_indices;
+use crate::math::{self, Vec3};
+use crate::IonIndex;
+use crate::{CellDocument, Lattice, Positions};
+
+/// Allowed deviation of the summed `MIXTURE` fractions from 1, enough for fractions
+/// written to a few decimals.
+const MIXTURE_WEIGHT_TOL: f64 = 1e-4;
+
+/// Where an atom of the edited cell comes from.
+enum Source {
+    /// Old atom, unchanged.
+    Old(usize),
+    /// Old atom with a new species.
+    Replaced(usize, Species),
+    /// New atom at fractional coordinates.
+    New(Species, Vec3),
+    /// Component of a new `MIXTURE` site in place of an old atom.
+    Component(usize, Species, (u32, f64)),
+    /// Old atom taken out of its `MIXTURE` site, with the dropped components whose
+    /// per-ion entries move to it, `IONIC_CONSTRAINTS` rows only where they add to the
+    /// atom's own.
+    Unmixed(usize, Vec<usize>),
+}
+
+impl CellDocument {
+    /// Appends an atom of `species` at fractional coordinates `coord` and returns its
+    /// index.
+    ///
+    /// Appending never renumbers existing ions. If `IONIC_VELOCITIES` is present the new
+    /// atom starts at rest.
+    pub fn add_atom(&mut self, species: Species, coord: [f64; 3]) -> CResult<usize> {
+        let mut sources: Vec<Source> = (0..self.positions.len()).map(Source::Old).collect();
+        sources.push(Source::New(species, coord));
+        *self = edit(self, &sources)?;
+        Ok(self.positions.len() - 1)
+    }
+
+    /// Removes the atoms at `indices`, e.g. to create vacancies.
+    ///
+    /// Constraints acting only on removed atoms and per-ion `HUBBARD_U` entries of removed
+    /// atoms are dropped. Fails if a constraint couples a removed atom to one that stays,
+    /// or if only part of a `MIXTURE` site is removed.
+    pub fn remove_atoms(&mut self, indices: &[usize]) -> CResult<()> {
+        let n = self.positions.len();
+        check_indices(n, indices)?;
+        let mixtures = self.positions.mixtures();
+        for &i in indices {
+            if let Some((id, _)) = mixtures[i]
+                && let Some(j) =
+                    (0..n).find(|&j| !indices.contains(&j) && mixtures[j].map(|m| m.0) == Some(id))
+            {
+                return Err(Error::Message(format!(
+                    "atom {i} shares MIXTURE {id} with atom {j}; remove the whole site"
+                )));
+            }
+        }
+        let sources = (0..n)
+            .filter(|i| !indices.contains(i))
+            .map(Source::Old)
+            .collect::<Vec<_>>();
+        *self = edit(self, &sources)?;
+        Ok(())
+    }
+
+    /// Changes the species of the atom at `index`, e.g. to substitute a dopant.
+    ///
+    /// Constraints and velocities follow the atom under its new label. Fails if the atom
+    /// has its own `HUBBARD_U` entry, since those values belong to the old species.
+    pub fn replace_species(&mut self, index: usize, species: Species) -> CResult<()> {
+        let n = self.positions.len();
+        check_indices(n, &[index])?;
+        let sources = (0..n)
+            .map(|i| {
+                if i == index {
+                    Source::Replaced(i, species.clone())
+                } else {
+                    Source::Old(i)
+                }
+            })
+            .collect::<Vec<_>>();
+        *self = edit(self, &sources)?;
+        Ok(())
+    }
+
+    /// Reorders the atoms so that the atom now at `order[k]` moves to index `k`.
+    ///
+    /// `order` must be a permutation of all atom indices.
+    pub fn reorder_atoms(&mut self, order: &[usize]) -> CResult<()> {
+        let n = self.positions.len();
+        let mut seen = vec![false; n];
+        for &i in order {
+            if i >= n || std::mem::replace(&mut seen[i], true) {
+                return Err(Error::Message(format!(
+                    "atom order is not a permutation of 0..{n}"
+                )));
+            }
+        }
+        if order.len() != n {
+            return Err(Error::Message(format!(
+                "atom order lists {} atoms but the cell has {n}",
+                order.len()
+            )));
+        }
+        let sources = order.iter().map(|&i| Source::Old(i)).collect::<Vec<_>>();
+        *self = edit(self, &sources)?;
+        Ok(())
+    }
+
+    /// Turns the atom at `index` into a `MIXTURE` site of `components`, species with
+    /// their fractions, and returns the new mixture index.
+    ///
+    /// The components take the place of the atom in the given order and share its
+    /// position in every geometry, its spin and its velocity; constraints on the atom
+    /// move to the first component. Fails if the atom already belongs to a mixture, has
+    /// its own `HUBBARD_U` entry, or the fractions are not positive or do not add up
+    /// to 1.
+    pub fn mix_site(&mut self, index: usize, components: &[(Species, f64)]) -> CResult<u32> {
+        let n = self.positions.len();
+        check_indices(n, &[index])?;
+        let mixtures = self.positions.mixtures();
+        if let Some((id, _)) = mixtures[index] {
+            return Err(Error::Message(format!(
+                "atom {index} already belongs to MIXTURE {id}"
+            )));
+        }
+        let id = mixtures.iter().flatten().map(|m| m.0).max().unwrap_or(0) + 1;
+        let species: Vec<&Species> = components.iter().map(|(s, _)| s).collect();
+        let weights: Vec<f64> = components.iter().map(|(_, w)| *w).collect();
+        check_mixture(id, &species, &weights)?;
+        let sources = (0..n)
+            .flat_map(|i| {
+                if i == index {
+                    components
+                        .iter()
+                        .map(|(s, w)| Source::Component(i, s.clone(), (id, *w)))
+                        .collect()
+                } else {
+                    vec![Source::Old(i)]
+                }
+            })
+            .collect::<Vec<_>>();
+        *self = edit(self, &sources)?;
+        Ok(id)
+    }
+
+    /// Replaces `MIXTURE` site `mixture` by its component with the largest fraction,
+    /// the first one on a tie, as a plain atom.
+    ///
+    /// The other components are removed and their constraints move to the kept atom,
+    /// so a site built by [`mix_site`](Self::mix_site) collapses with the constraints of
+    /// the original atom. An `IONIC_CONSTRAINTS` row of a removed component is dropped
+    /// instead when it only restricts directions the kept atom is already constrained
+    /// in. Fails if no atom carries the mixture index.
+    pub fn collapse_mixture(&mut self, mixture: u32) -> CResult<()> {
+        if !self.positions.mixtures().iter().flatten().any(|m| m.0 == mixture) {
+            return Err(Error::Message(format!(
+                "MIXTURE {mixture} does not occur in the cell"
+            )));
+        }
+        self.collapse(|id| id == mixture)
+    }
+
+    /// Replaces every `MIXTURE` site by its majority component, see
+    /// [`collapse_mixture`](Self::collapse_mixture).
+    pub fn collapse_mixtures(&mut self) -> CResult<()> {
+        self.collapse(|_| true)
+    }
+
+    fn collapse(&mut self, selected: impl Fn(u32) -> bool) -> CResult<()> {
+        let mixtures = self.positions.mixtures();
+        // The kept component and its weight for every selected mixture.
+        let mut majority: Vec<(u32, usize, f64)> = Vec::new();
+        for (i, mixture) in mixtures.iter().enumerate() {
+            let Some((id, weight)) = *mixture else { continue };
+            if !selected(id) {
+                continue;
+            }
+            match majority.iter_mut().find(|m| m.0 == id) {
+                Some(m) if weight > m.2 => *m = (id, i, weight),
+                Some(_) => {}
+                None => majority.push((id, i, weight)),
+            }
+        }
+        let sources = mixtures
+            .iter()
+            .enumerate()
+            .filter_map(|(i, mixture)| match mixture {
+                Some((id, _)) if selected(*id) => majority.iter().any(|m| m.1 == i).then(|| {
+                    let dropped = (0..mixtures.len())
+                        .filter(|&j| j != i && mixtures[j].is_some_and(|m| m.0 == *id))
+                        .collect();
+                    Source::Unmixed(i, dropped)
+                }),
+                _ => Some(Source::Old(i)),
+            })
+            .collect::<Vec<_>>();
+        *self = edit(self, &sources)?;
+        Ok(())
+    }
+}
+
+/// Checks the components of `MIXTURE` site `id`: distinct species with positive
+/// fractions adding up to 1.
+pub(crate) fn check_mixture(id: u32, species: &[&Species], weights: &[f64]) -> CResult<()> {
+    if let Some(w) = weights.iter().find(|w| !(w.is_finite() && **w > 0.0)) {
+        return Err(Error::Message(format!(
+            "MIXTURE {id} has a non-positive fraction {w}"
+        )));
+    }
+    for (k, s) in species.iter().enumerate() {
+        if species[..k].iter().any(|t| t.same_species(s)) {
+            return Err(Error::Message(format!("MIXTURE {id} lists {s} twice")));
+        }
+    }
+    let total: f64 = weights.iter().sum();
+    if (total - 1.0).abs() > MIXTURE_WEIGHT_TOL {
+        return Err(Error::Message(format!(
+            "MIXTURE {id} fractions add up to {total}, not 1"
+        )));
+    }
+    Ok(())
+}
+
+/// Builds the positions block of the edited cell from `sources`, keeping the coordinate
+/// mode of `positions`; new atoms take the same place in every geometry.
+fn edit_positions(positions: &Positions, lattice: &Lattice, sources: &[Source]) -> Positions {
+    match positions {
+        Positions::Frac(frac) => Positions::Frac(PositionsFrac {
+            positions: sources
+                .iter()
+                .map(|source| match source {
+                    Source::Old(i) => frac.positions[*i].clone(),
+                    Source::Replaced(i, species) => PositionFracEntry {
+                        species: species.clone(),
+                        ..frac.positions[*i].clone()
+                    },
+                    Source::New(species, coord) => PositionFracEntry {
+                        species: species.clone(),
+                        coord: *coord,
+                        spin: None,
+                        mixture: None,
+                    },
+                    Source::Component(i, species, mixture) => PositionFracEntry {
+                        species: species.clone(),
+                        mixture: Some(*mixture),
+                        ..frac.positions[*i].clone()
+                    },
+                    Source::Unmixed(i, _) => PositionFracEntry {
+                        mixture: None,
+                        ..frac.positions[*i].clone()
+                    },
+                })
+                .collect(),
+        }),
+        Positions::Abs(abs) => {
+            let f = abs.unit.unwrap_or_default().in_angstrom();
+            let a = lattice.vectors();
+            Positions::Abs(PositionsAbs {
+                unit: abs.unit,
+                positions: sources
+                    .iter()
+                    .map(|source| match source {
+                        Source::Old(i) => abs.positions[*i].clone(),
+                        Source::Replaced(i, species) => PositionAbsEntry {
+                            species: species.clone(),
+                            ..abs.positions[*i].clone()
+                        },
+                        Source::New(species, coord) => PositionAbsEntry {
+                            species: species.clone(),
+                            coord: math::frac_to_cart(a, *coord).map(|x| x / f),
+                            spin: None,
+                            mixture: None,
+                        },
+                        Source::Component(i, species, mixture) => PositionAbsEntry {
+                            species: species.clone(),
+                            mixture: Some(*mixture),
+                            ..abs.positions[*i].clone()
+                        },
+                        Source::Unmixed(i, _) => PositionAbsEntry {
+                            mixture: None,
+                            ..abs.positions[*i].clone()
+                        },
+                    })
+                    .collect(),
+            })
+        }
+    }
+}
+
+/// Rebuilds `doc` with the atoms listed in `sources`, carrying the per-ion blocks along.
+fn edit(doc: &CellDocument, sources: &[Source]) -> CResult<CellDocument> {
+    let old = IonIndex::new(&doc.positions);
+    let mut moved: Vec<Option<usize>> = vec![None; old.len()];
+    let mut replaced = vec![false; old.len()];
+    let mut unmixed = vec![false; old.len()];
+    for (k, source) in sources.iter().enumerate() {
+        match source {
+            Source::Old(i) => moved[*i] = Some(k),
+            Source::Unmixed(i, dropped) => {
+                moved[*i] = Some(k);
+                for &d in dropped {
+                    moved[d] = Some(k);
+                    replaced[d] = true;
+                    unmixed[d] = true;
+                }
+            }
+            Source::Replaced(i, _) => {
+                moved[*i] = Some(k);
+                replaced[*i] = true;
+            }
+            // Per-ion blocks follow the first component.
+            Source::Component(i, ..) => {
+                moved[*i] = moved[*i].or(Some(k));
+                replaced[*i] = true;
+            }
+            Source::New(..) => {}
+        }
+    }
+
+    let positions = edit_positions(&doc.positions, &doc.lattice, sources);
+    let positions_intermediate = doc
+        .positions_intermediate
+        .as_ref()
+        .map(|p| edit_positions(&p.to_positions(), &doc.lattice, sources).into());
+    let positions_product = doc
+        .positions_product
+        .as_ref()
+        .map(|p| edit_positions(&p.to_positions(), &doc.lattice, sources).into());
+    let new = IonIndex::new(&positions);
+
+    // Maps an old ion to its new index, `None` if it was removed.
+    let locate = |species: &Species, ion_number: u32| -> CResult<Option<usize>> {
+        Ok(moved[old.resolve(species, ion_number)?])
+    };
+
+    let ionic_constraints = doc
+        .ionic_constraints
+        .as_ref()
+        .map(|ic| -> CResult<IonicConstraints> {
+            let mut numbers: Vec<u32> = Vec::new();
+            for entry in &ic.constraints {
+                if !numbers.contains(&entry.constraint_number) {
+                    numbers.push(entry.constraint_number);
+                }
+            }
+            // Every surviving constraint as coefficients per new atom, and whether it
+            // involves a component dropped from a mixture.
+            let mut rows: Vec<(Vec<(usize, Vec3)>, bool)> = Vec::new();
+            for number in numbers {
+                let entries = ic
+                    .constraints
+                    .iter()
+                    .filter(|e| e.constraint_number == number)
+                    .map(|e| Ok((e, old.resolve(&e.species, e.ion_number)?)))
+                    .collect::<CResult<Vec<_>>>()?;
+                if entries.iter().all(|(_, i)| moved[*i].is_none()) {
+                    continue;
+                }
+                if let Some((e, _)) = entries.iter().find(|(_, i)| moved[*i].is_none()) {
+                    return Err(Error::Message(format!(
+                        "IONIC_CONSTRAINTS constraint {number} couples removed ion {} {} to \
+                         ions that remain",
+                        e.species, e.ion_number
+                    )));
+                }
+                // Components of a collapsed mixture land on the same atom; their
+                // coefficients add up since they share its position.
+                let mut row: Vec<(usize, Vec3)> = Vec::new();
+                for (e, j) in entries
+                    .iter()
+                    .filter_map(|(e, i)| moved[*i].map(|j| (e, j)))
+                {
+                    match row.iter_mut().find(|(k, _)| *k == j) {
+                        Some((_, c)) => *c = math::add(*c, e.coefficients),
+                        None => row.push((j, e.coefficients)),
+                    }
+                }
+                rows.push((row, entries.iter().any(|(_, i)| unmixed[*i])));
+            }
+            // A single-atom row from a dropped component is kept only if it constrains a
+            // direction the kept atom's own rows leave free.
+            let mut spans: Vec<Vec<Vec3>> = vec![Vec::new(); new.len()];
+            for (row, from_dropped) in &rows {
+                if let [(j, c)] = row.as_slice()
+                    && !from_dropped
+                {
+                    extend_span(&mut spans[*j], *c);
+                }
+            }
+            let mut constraints = Vec::new();
+            let mut next = 0;
+            for (row, from_dropped) in rows {
+                if let [(j, c)] = row.as_slice()
+                    && from_dropped
+                    && !extend_span(&mut spans[*j], *c)
+                {
+                    continue;
+                }
+                next += 1;
+                for (j, coefficients) in row {
+                    constraints.push(IonicConstraintEntry {
+                        constraint_number: next,
+                        species: new.species(j).clone(),
+                        ion_number: new.ion_number(j),
+                        coefficients,
+                    });
+                }
+            }
+            Ok(IonicConstraints { constraints })
+        })
+        .transpose()?;
+
+    let nonlinear_constraints = doc
+        .nonlinear_constraints
+        .as_ref()
+        .map(|nc| -> CResult<NonlinearConstraints> {
+            let mut constraints = Vec::new();
+            for constraint in &nc.constraints {
+                let sites = constraint
+                    .atom_sites
+                    .iter()
+                    .map(|site| Ok((site, locate(&site.species, site.ion_number)?)))
+                    .collect::<CResult<Vec<_>>>()?;
+                if sites.iter().all(|(_, j)| j.is_none()) {
+                    continue;
+                }
+                if let Some((site, _)) = sites.iter().find(|(_, j)| j.is_none()) {
+                    return Err(Error::Message(format!(
+                        "NONLINEAR_CONSTRAINTS refers to removed ion {} {}",
+                        site.species, site.ion_number
+                    )));
+                }
+                constraints.push(NonlinearConstraint {
+                    constraint_type: constraint.constraint_type.clone(),
+                    atom_sites: sites
+                        .iter()
+                        .filter_map(|(site, j)| {
+                            j.map(|j| {
+                                AtomSite::new(
+                                    new.species(j).clone(),
+                                    new.ion_number(j),
+                                    site.image_indices,
+                                )
+                            })
+                        })
+                        .collect(),
+                });
+            }
+            Ok(NonlinearConstraints { constraints })
+        })
+        .transpose()?;
+
+    let hubbard_u = doc
+        .hubbard_u
+        .as_ref()
+        .map(|hu| -> CResult<HubbardU> {
+            let mut atom_u_values = Vec::new();
+            for entry in &hu.atom_u_values {
+                match entry.ion_number {
+                    // Species-wide values stay while the species is present.
+                    None => {
+                        if new.count(&entry.species) > 0 {
+                            atom_u_values.push(entry.clone());
+                        }
+                    }
+                    Some(n) => {
+                        let i = old.resolve(&entry.species, n)?;
+                        if replaced[i] {
+                            return Err(Error::Message(format!(
+                                "HUBBARD_U sets values for ion {} {n}, whose species is \
+                                 being replaced",
+                                entry.species
+                            )));
+                        }
+                        if let Some(j) = moved[i] {
+                            atom_u_values.push(AtomHubbardU {
+                                species: new.species(j).clone(),
+                                ion_number: Some(new.ion_number(j)),
+                                orbitals: entry.orbitals.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+            Ok(HubbardU {
+                unit: hu.unit,
+                atom_u_values,
+            })
+        })
+        .transpose()?;
+
+    let ionic_velocities = doc
+        .ionic_velocities
+        .as_ref()
+        .map(|iv| -> CResult<IonicVelocities> {
+            if iv.velocities.len() != old.len() {
+                return Err(Error::Message(format!(
+                    "IONIC_VELOCITIES lists {} ions but the cell has {}",
+                    iv.velocities.len(),
+                    old.len()
+                )));
+            }
+            Ok(IonicVelocities {
+                unit: iv.unit,
+                velocities: sources
+                    .iter()
+                    .enumerate()
+                    .map(|(k, source)| IonicVelocityEntry {
+                        species: new.species(k).clone(),
+                        velocity: match source {
+                            Source::Old(i)
+                            | Source::Replaced(i, _)
+                            | Source::Component(i, ..)
+                            | Source::Unmixed(i, _) => iv.velocities[*i].velocity,
+                            Source::New(..) => [0.0; 3],
+                        },
+                    })
+                    .collect(),
+            })
+        })
+        .transpose()?;
+
+    // The surviving operations are the symmetries shared with the old cell, so they
+    // still form a group.
+    let symmetry_ops = doc
+        .symmetry_ops
+        .as_ref()
+        .map(|ops| -> CResult<SymmetryOps> {
+            let tolerance = doc.symmetry_tol.unwrap_or_default();
+            let broken: Vec<usize> = ops
+                .violations(&doc.lattice, &positions, &tolerance)?
+                .iter()
+                .map(|v| v.op)
+                .collect();
+            Ok(SymmetryOps {
+                ops: ops
+                    .ops
+                    .iter()
+                    .enumerate()
+                    .filter(|(k, _)| !broken.contains(k))
+                    .map(|(_, op)| op.clone())
+                    .collect(),
+            })
+        })
+        .transpose()?;
+
+    Ok(CellDocument {
+        positions,
+        positions_intermediate,
+        positions_product,
+        ionic_constraints,
+        nonlinear_constraints,
+        hubbard_u,
+        ionic_velocities,
+        symmetry_ops,
+        ..doc.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::constraints::ConstraintType;
+    use crate::cell::species::OrbitalU;
+    use crate::cell::symmetry::SymmetryOp;
+    use crate::test_fixtures::{cubic_lattice, frac_atoms};
+
+    fn label(species: &Species, ion_number: u32) -> String {
+        format!("{species} {ion_number}")
+    }
+
+    /// Four atoms, Fe Fe O Fe, with every per-ion block set.
+    fn cell() -> CellDocument {
+        let sym = |s: &str| Species::Symbol(s.into());
+        CellDocument::builder()
+            .lattice(cubic_lattice(4.0))
+            .positions(frac_atoms(&[
+                ("Fe", [0.0, 0.0, 0.0]),
+                ("Fe", [0.5, 0.0, 0.0]),
+                ("O", [0.25, 0.25, 0.0]),
+                ("Fe", [0.0, 0.5, 0.0]),
+            ]))
+            .ionic_constraints(IonicConstraints {
+                constraints: vec![
+                    IonicConstraintEntry {
+                        constraint_number: 1,
+                        species: sym("Fe"),
+                        ion_number: 3,
+                        coefficients: [0.0, 0.0, 1.0],
+                    },
+                    IonicConstraintEntry {
+                        constraint_number: 2,
+                        species: sym("O"),
+                        ion_number: 1,
+                        coefficients: [1.0, 0.0, 0.0],
+                    },
+                ],
+            })
+            .nonlinear_constraints(NonlinearConstraints {
+                constraints: vec![NonlinearConstraint {
+                    constraint_type: ConstraintType::Distance,
+                    atom_sites: vec![
+                        AtomSite::new(sym("Fe"), 3, [0, 0, 0]),
+                        AtomSite::new(sym("O"), 1, [0, 0, 0]),
+                    ],
+                }],
+            })
+            .hubbard_u(HubbardU {
+                unit: None,
+                atom_u_values: vec![AtomHubbardU {
+                    species: sym("Fe"),
+                    ion_number: Some(2),
+                    orbitals: vec![OrbitalU::D(4.0)],
+                }],
+            })
+            .ionic_velocities(IonicVelocities {
+                unit: None,
+                velocities: (0..4)
+                    .map(|k| IonicVelocityEntry {
+                        species: sym(if k == 2 { "O" } else { "Fe" }),
+                        velocity: [k as f64, 0.0, 0.0],
+                    })
+                    .collect(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_remove_renumbers_dependent_blocks() {
+        let mut doc = cell();
+        doc.remove_atoms(&[0]).unwrap();
+        assert_eq!(doc.positions.len(), 3);
+        let ic = &doc.ionic_constraints.as_ref().unwrap().constraints;
+        assert_eq!(label(&ic[0].species, ic[0].ion_number), "Fe 2");
+        let nc = &doc.nonlinear_constraints.as_ref().unwrap().constraints[0];
+        assert_eq!(
+            label(&nc.atom_sites[0].species, nc.atom_sites[0].ion_number),
+            "Fe 2"
+        );
+        let hu = &doc.hubbard_u.as_ref().unwrap().atom_u_values[0];
+        assert_eq!(hu.ion_number, Some(1));
+        let velocities = &doc.ionic_velocities.as_ref().unwrap().velocities;
+        assert_eq!(velocities[0].velocity, [1.0, 0.0, 0.0]);
+
+        // Removing the Hubbard ion drops its entry.
+        doc.remove_atoms(&[0]).unwrap();
+        assert!(doc.hubbard_u.as_ref().unwrap().atom_u_values.is_empty());
+    }
+
+    #[test]
+    fn test_remove_fails_on_coupled_constraint() {
+        let mut doc = cell();
+        let err = doc.remove_atoms(&[2]).unwrap_err().to_string();
+        assert!(err.contains("O 1"), "{err}");
+        assert_eq!(doc.positions.len(), 4);
+        assert_eq!(doc.ionic_constraints.as_ref().unwrap().constraints.len(), 2);
+
+        // Dropping both atoms of the distance constraint removes it entirely.
+        doc.remove_atoms(&[2, 3]).unwrap();
+        assert!(doc.nonlinear_constraints.unwrap().constraints.is_empty());
+        assert!(doc.ionic_constraints.unwrap().constraints.is_empty());
+    }
+
+    #[test]
+    fn test_replace_and_add() {
+        let mut doc = cell();
+        doc.replace_species(3, Species::Symbol("Mn".into()))
+            .unwrap();
+        let ic = &doc.ionic_constraints.as_ref().unwrap().constraints;
+        assert_eq!(label(&ic[0].species, ic[0].ion_number), "Mn 1");
+        assert_eq!(
+            doc.ionic_velocities.as_ref().unwrap().velocities[3].species,
+            Species::Symbol("Mn".into())
+        );
+        assert!(
+            doc.replace_species(1, Species::Symbol("Co".into()))
+                .is_err()
+        );
+
+        let index = doc
+            .add_atom(Species::Symbol("O".into()), [0.5, 0.5, 0.5])
+            .unwrap();
+        assert_eq!(index, 4);
+        assert_eq!(doc.positions.ion_numbers()[4], 2);
+        assert_eq!(
+            doc.ionic_velocities.unwrap().velocities[4].velocity,
+            [0.0; 3]
+        );
+    }
+
+    #[test]
+    fn test_reorder_atoms() {
+        let mut doc = cell();
+        doc.reorder_atoms(&[2, 3, 1, 0]).unwrap();
+        let species = doc
+            .positions
+            .species()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(species, ["O", "Fe", "Fe", "Fe"]);
+        let ic = &doc.ionic_constraints.as_ref().unwrap().constraints;
+        assert_eq!(label(&ic[0].species, ic[0].ion_number), "Fe 1");
+        assert_eq!(
+            doc.hubbard_u.as_ref().unwrap().atom_u_values[0].ion_number,
+            Some(2)
+        );
+        assert_eq!(
+            doc.ionic_velocities.as_ref().unwrap().velocities[0].velocity[0],
+            2.0
+        );
+
+        assert!(doc.reorder_atoms(&[0, 0, 1, 2]).is_err());
+        assert!(doc.reorder_atoms(&[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_edits_drop_broken_symmetry_ops() {
+        let mut doc = cell();
+        let op = |rotation: [[f64; 3]; 3]| SymmetryOp {
+            rotation,
+            translation: [0.0; 3],
+        };
+        doc.symmetry_ops = Some(SymmetryOps {
+            ops: vec![
+                SymmetryOp::identity(),
+                op([[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]),
+                op([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, -1.0]]),
+                op([[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]]),
+            ],
+        });
+        doc.check_symmetry_ops().unwrap();
+
+        doc.reorder_atoms(&[3, 2, 1, 0]).unwrap();
+        assert_eq!(doc.symmetry_ops.as_ref().unwrap().ops.len(), 4);
+        // Without the Fe at (0.5, 0, 0) the x-y swap is no longer a symmetry.
+        doc.remove_atoms(&[2]).unwrap();
+        let ops = &doc.symmetry_ops.as_ref().unwrap().ops;
+        assert_eq!(ops.len(), 2);
+        assert_eq!(ops[1].rotation[2][2], -1.0);
+        doc.check_symmetry_ops().unwrap();
+    }
+
+    #[test]
+    fn test_edits_follow_transition_state() {
+        let mut doc = cell();
+        let product = match &doc.positions {
+            Positions::Frac(frac) => PositionsFrac {
+                positions: frac
+                    .positions
+                    .iter()
+                    .map(|p| PositionFracEntry {
+                        coord: math::add(p.coord, [0.0, 0.0, 0.1]),
+                        ..p.clone()
+                    })
+                    .collect(),
+            },
+            Positions::Abs(_) => unreachable!(),
+        };
+        doc.positions_product = Some(Positions::Frac(product).into());
+
+        doc.remove_atoms(&[1]).unwrap();
+        doc.replace_species(0, Species::Symbol("Co".into())).unwrap();
+        doc.add_atom(Species::Symbol("H".into()), [0.5, 0.5, 0.5]).unwrap();
+        doc.check_transition_state().unwrap();
+        let product = doc.positions_product.as_ref().unwrap().to_positions();
+        let coords = product.frac_coords(&doc.lattice).unwrap();
+        assert_eq!(coords[1], [0.25, 0.25, 0.1]);
+        assert_eq!(coords[3], [0.5, 0.5, 0.5]);
+        assert_eq!(product.species()[0].to_string(), "Co");
+    }
+
+    #[test]
+    fn test_mix_site() {
+        let mut doc = cell();
+        let sym = |s: &str| Species::Symbol(s.into());
+        let id = doc
+            .mix_site(2, &[(sym("O"), 0.75), (sym("F"), 0.25)])
+            .unwrap();
+        assert_eq!(id, 1);
+        doc.check_mixtures().unwrap();
+        assert_eq!(doc.positions.len(), 5);
+        assert_eq!(
+            doc.positions.mixtures()[2..4],
+            [Some((1, 0.75)), Some((1, 0.25))]
+        );
+        assert_eq!(doc.positions.frac_coords(&doc.lattice).unwrap()[3], [0.25, 0.25, 0.0]);
+        // Constraints stay on the first component, velocities are shared.
+        let ic = &doc.ionic_constraints.as_ref().unwrap().constraints;
+        assert_eq!(label(&ic[1].species, ic[1].ion_number), "O 1");
+        let velocities = &doc.ionic_velocities.as_ref().unwrap().velocities;
+        assert_eq!(velocities[3].species, sym("F"));
+        assert_eq!(velocities[3].velocity, [2.0, 0.0, 0.0]);
+        assert_eq!(label(&ic[0].species, ic[0].ion_number), "Fe 3");
+
+        assert!(doc.mix_site(2, &[(sym("S"), 1.0)]).is_err());
+        assert!(doc.mix_site(0, &[(sym("Fe"), 0.5), (sym("Co"), 0.4)]).is_err());
+        assert!(doc.mix_site(0, &[(sym("Co"), 0.5), (sym("Co"), 0.5)]).is_err());
+        assert!(doc.mix_site(0, &[(sym("Co"), 1.5), (sym("Ni"), -0.5)]).is_err());
+        // Per-ion HUBBARD_U entries cannot be shared between components.
+        assert!(doc.mix_site(1, &[(sym("Fe"), 0.5), (sym("Co"), 0.5)]).is_err());
+        assert_eq!(doc.positions.len(), 5);
+    }
+
+    #[test]
+    fn test_check_mixtures() {
+        let mut doc = cell();
+        let sym = |s: &str| Species::Symbol(s.into());
+        doc.mix_site(0, &[(sym("Fe"), 0.5), (sym("Co"), 0.5)])
+            .unwrap();
+        doc.check_mixtures().unwrap();
+
+        let mut misplaced = doc.clone();
+        if let Positions::Frac(frac) = &mut misplaced.positions {
+            frac.positions[1].coord = [0.1, 0.0, 0.0];
+        }
+        let err = misplaced.check_mixtures().unwrap_err().to_string();
+        assert!(err.contains("different positions"), "{err}");
+
+        // A periodic image of the same site is accepted.
+        let mut image = doc.clone();
+        if let Positions::Frac(frac) = &mut image.positions {
+            frac.positions[1].coord = [1.0, 0.0, -1.0];
+        }
+        image.check_mixtures().unwrap();
+
+        let mut unbalanced = doc.clone();
+        if let Positions::Frac(frac) = &mut unbalanced.positions {
+            frac.positions[1].mixture = Some((1, 0.4));
+        }
+        let err = unbalanced.check_mixtures().unwrap_err().to_string();
+        assert!(err.contains("add up to 0.9"), "{err}");
+    }
+
+    #[test]
+    fn test_collapse_mixtures() {
+        let mut doc = cell();
+        let sym = |s: &str| Species::Symbol(s.into());
+        doc.mix_site(2, &[(sym("F"), 0.25), (sym("O"), 0.75)])
+            .unwrap();
+        doc.mix_site(4, &[(sym("Fe"), 0.5), (sym("Co"), 0.5)])
+            .unwrap();
+        assert_eq!(doc.positions.len(), 6);
+
+        doc.collapse_mixture(1).unwrap();
+        assert_eq!(doc.positions.len(), 5);
+        assert_eq!(doc.positions.species()[2], &sym("O"));
+        assert_eq!(doc.positions.mixtures()[2], None);
+        let ic = &doc.ionic_constraints.as_ref().unwrap().constraints;
+        assert_eq!(label(&ic[1].species, ic[1].ion_number), "O 1");
+        assert!(doc.collapse_mixture(1).is_err());
+
+        // Ties keep the first component.
+        doc.collapse_mixtures().unwrap();
+        assert_eq!(doc.positions.len(), 4);
+        assert!(doc.positions.mixtures().iter().all(Option::is_none));
+        let species = doc
+            .positions
+            .species()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(species, ["Fe", "Fe", "O", "Fe"]);
+        let velocities = &doc.ionic_velocities.as_ref().unwrap().velocities;
+        assert_eq!(velocities[2].velocity, [2.0, 0.0, 0.0]);
+        assert_eq!(velocities[3].velocity, [3.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_collapse_keeps_independent_constraints() {
+        let mut doc = cell();
+        let sym = |s: &str| Species::Symbol(s.into());
+        doc.mix_site(2, &[(sym("F"), 0.25), (sym("O"), 0.75)])
+            .unwrap();
+        let entry = |number, species: &str, coefficients| IonicConstraintEntry {
+            constraint_number: number,
+            species: sym(species),
+            ion_number: if species == "Fe" { 3 } else { 1 },
+            coefficients,
+        };
+        // Both components are constrained, partly in the same directions.
+        doc.ionic_constraints = Some(IonicConstraints {
+            constraints: vec![
+                entry(1, "Fe", [0.0, 0.0, 1.0]),
+                entry(2, "F", [1.0, 0.0, 0.0]),
+                entry(3, "O", [1.0, 0.0, 0.0]),
+                entry(4, "O", [0.0, 1.0, 0.0]),
+                entry(5, "F", [1.0, 1.0, 0.0]),
+                entry(6, "F", [0.0, 0.0, 1.0]),
+                entry(7, "F", [0.0, 0.0, 1.0]),
+                entry(7, "O", [0.0, 0.0, 1.0]),
+            ],
+        });
+        doc.collapse_mixture(1).unwrap();
+        let ic = &doc.ionic_constraints.as_ref().unwrap().constraints;
+        let rows = ic
+            .iter()
+            .map(|e| {
+                (
+                    e.constraint_number,
+                    label(&e.species, e.ion_number),
+                    e.coefficients,
+                )
+            })
+            .collect::<Vec<_>>();
+        // Only the z row of F adds to the kept O atom's own x and y rows.
+        assert_eq!(
+            rows,
+            [
+                (1, "Fe 3".to_string(), [0.0, 0.0, 1.0]),
+                (2, "O 1".to_string(), [1.0, 0.0, 0.0]),
+                (3, "O 1".to_string(), [0.0, 1.0, 0.0]),
+                (4, "O 1".to_string(), [0.0, 0.0, 1.0]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! Periodic neighbour lists and geometry analysis.
+//!
+//! Neighbours are found among all periodic images. An image is identified by the
+//! integer lattice translation added to the coordinates as written in the positions
+//! block, the same convention as [`AtomSite::image_indices`], so a [`Neighbour`] can be
+//! turned directly into a `NONLINEAR_CONSTRAINTS` site.
+//!
+//! [`AtomSite::image_indices`]: crate::cell::constraints::AtomSite::image_indices
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::CellDocument;
+use crate::math::{self, Mat3, Vec3};
+
+/// A periodic image of an atom within the cutoff of another atom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbour {
+    /// Index of the neighbouring atom in the positions block.
+    pub index: usize,
+    /// Lattice translation of the neighbouring image, in units of the lattice vectors.
+    pub image: [i32; 3],
+    /// Cartesian vector from the central atom to the image, in Å.
+    pub vector: [f64; 3],
+    /// Length of [`vector`](Self::vector), in Å.
+    pub distance: f64,
+}
+
+/// All neighbours of every atom within a cutoff distance, including periodic images.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeighbourList {
+    cutoff: f64,
+    neighbours: Vec<Vec<Neighbour>>,
+}
+
+impl NeighbourList {
+    /// Builds the neighbour list of `doc` with a cutoff in Å.
+    ///
+    /// Neighbours of each atom are sorted by distance. An atom's own periodic images
+    /// are included when the cell is shorter than the cutoff.
+    pub fn new(doc: &CellDocument, cutoff: f64) -> CResult<Self> {
+        if cutoff.is_nan() || cutoff <= 0.0 {
+            return Err(Error::Message("neighbour cutoff must be positive".into()));
+        }
+        let a = doc.lattice.vectors();
+        if math::det(a).abs() < 1e-8 {
+            return Err(Error::Message(
+                "lattice vectors are linearly dependent".into(),
+            ));
+        }
+        let coords = doc.positions.frac_coords(&doc.lattice)?;
+        // |Δf_k| ≤ |r| · |b_k| bounds the translations that can be within the cutoff.
+        let reach = math::reciprocal(a)?.map(|b| cutoff * math::norm(b));
+        let neighbours = (0..coords.len())
+            .map(|i| {
+                let mut list: Vec<Neighbour> = (0..coords.len())
+                    .flat_map(|j| images_within(a, reach, (i, coords[i]), (j, coords[j]), cutoff))
+                    .collect();
+                list.sort_by(|x, y| x.distance.total_cmp(&y.distance));
+                list
+            })
+            .collect();
+        Ok(Self { cutoff, neighbours })
+    }
+
+    /// The cutoff distance in Å.
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff
+    }
+
+    /// Number of atoms in the list.
+    pub fn len(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    /// Returns `true` if the cell has no atoms.
+    pub fn is_empty(&self) -> bool {
+        self.neighbours.is_empty()
+    }
+
+    /// Neighbours of `atom`, nearest first.
+    pub fn neighbours(&self, atom: usize) -> CResult<&[Neighbour]> {
+        check_indices(self.len(), &[atom])?;
+        Ok(&self.neighbours[atom])
+    }
+
+    /// Number of neighbours of `atom` within the cutoff.
+    pub fn coordination_number(&self, atom: usize) -> CResult<usize> {
+        Ok(self.neighbours(atom)?.len())
+    }
+
+    /// Every bond within the cutoff, listed once as `(atom, neighbour)` with the
+    /// central atom in the home cell.
+    pub fn pairs(&self) -> impl Iterator<Item = (usize, &Neighbour)> {
+        self.neighbours.iter().enumerate().flat_map(|(i, list)| {
+            list.iter()
+                .filter(move |n| (i, [0; 3]) < (n.index, n.image))
+                .map(move |n| (i, n))
+        })
+    }
+
+    /// The shortest interatomic distance within the cutoff, if any.
+    pub fn shortest(&self) -> Option<(usize, &Neighbour)> {
+        self.pairs()
+            .min_by(|x, y| x.1.distance.total_cmp(&y.1.distance))
+    }
+}
+
+/// Images of atom `j` within `cutoff` of atom `i`, both given with their fractional
+/// coordinates, searching `reach` lattice translations either way along each axis.
+fn images_within(
+    a: Mat3,
+    reach: Vec3,
+    (i, xi): (usize, Vec3),
+    (j, xj): (usize, Vec3),
+    cutoff: f64,
+) -> Vec<Neighbour> {
+    let d = math::sub(xj, xi);
+    let range = |k: usize| {
+        let lo = (-d[k] - reach[k]).ceil() as i32;
+        let hi = (-d[k] + reach[k]).floor() as i32;
+        lo..=hi
+    };
+    let mut images = Vec::new();
+    for n0 in range(0) {
+        for n1 in range(1) {
+            for n2 in range(2) {
+                let image = [n0, n1, n2];
+                if i == j && image == [0; 3] {
+                    continue;
+                }
+                let f = math::add(d, image.map(f64::from));
+                let vector = math::frac_to_cart(a, f);
+                let distance = math::norm(vector);
+                if distance <= cutoff {
+                    images.push(Neighbour {
+                        index: j,
+                        image,
+                        vector,
+                        distance,
+                    });
+                }
+            }
+        }
+    }
+    images
+}
+
+/// A bond between an atom in the home cell and an image of another atom.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bond {
+    /// Index of the first atom, taken in the home cell.
+    pub atom: usize,
+    /// Index of the second atom.
+    pub other: usize,
+    /// Lattice translation of the second atom.
+    pub image: [i32; 3],
+    /// Bond length in Å.
+    pub length: f64,
+}
+
+/// The angle at a central atom between two of its bonds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BondAngle {
+    /// Index of the central atom, taken in the home cell.
+    pub centre: usize,
+    /// The two outer atoms with their lattice translations.
+    pub ends: [(usize, [i32; 3]); 2],
+    /// Angle in degrees.
+    pub angle: f64,
+}
+
+/// Bond lengths, bond angles and coordination numbers of a structure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometryReport {
+    /// The bonding cutoff used, in Å.
+    pub cutoff: f64,
+    /// All bonds no longer than the cutoff, each listed once.
+    pub bonds: Vec<Bond>,
+    /// All angles between pairs of bonds sharing a central atom.
+    pub angles: Vec<BondAngle>,
+    /// Coordination number of every atom.
+    pub coordination: Vec<usize>,
+    /// The shortest interatomic distance in the structure, regardless of the cutoff.
+    pub shortest: Option<Bond>,
+}
+
+impl GeometryReport {
+    /// Bonds shorter than `min_distance` (Å), e.g. to catch overlapping atoms.
+    pub fn short_contacts(&self, min_distance: f64) -> Vec<Bond> {
+        self.bonds
+            .iter()
+            .filter(|b| b.length < min_distance)
+            .copied()
+            .collect()
+    }
+}
+
+impl CellDocument {
+    /// Builds the periodic neighbour list with a cutoff in Å.
+    pub fn neighbour_list(&self, cutoff: f64) -> CResult<NeighbourList> {
+        NeighbourList::new(self, cutoff)
+    }
+
+    /// Lattice translation of the image of atom `j` closest to atom `i`.
+    pub fn nearest_image(&self, i: usize, j: usize) -> CResult<[i32; 3]> {
+        let (a, coords) = self.atom_coords(&[i, j])?;
+        let start = math::sub(coords[i], coords[j]).map(|x| x.round() as i32);
+        let mut best = (f64::INFINITY, start);
+        for n0 in -1..=1 {
+            for n1 in -1..=1 {
+                for n2 in -1..=1 {
+                    let image = [start[0] + n0, start[1] + n1, start[2] + n2];
+                    let d = math::norm(bond_vector(a, &coords, i, j, image));
+                    if d < best.0 - 1e-12 {
+                        best = (d, image);
+                    }
+                }
+            }
+        }
+        Ok(best.1)
+    }
+
+    /// Distance in Å between atom `i` and atom `j` translated by `image`.
+    pub fn distance(&self, i: usize, j: usize, image: [i32; 3]) -> CResult<f64> {
+        let (a, coords) = self.atom_coords(&[i, j])?;
+        Ok(math::norm(bond_vector(a, &coords, i, j, image)))
+    }
+
+    /// Angle in degrees at atom `centre` between the images `a` and `b`, each given
+    /// as an atom index and lattice translation.
+    pub fn angle(
+        &self,
+        centre: usize,
+        a: (usize, [i32; 3]),
+        b: (usize, [i32; 3]),
+    ) -> CResult<f64> {
+        let (vectors, coords) = self.atom_coords(&[centre, a.0, b.0])?;
+        let u = bond_vector(vectors, &coords, centre, a.0, a.1);
+        let v = bond_vector(vectors, &coords, centre, b.0, b.1);
+        Ok(vector_angle(u, v))
+    }
+
+    /// Dihedral angle in degrees about the bond `b`–`c` for the chain `a`–`b`–`c`–`d`,
+    /// each given as an atom index and lattice translation.
+    pub fn torsion(
+        &self,
+        a: (usize, [i32; 3]),
+        b: (usize, [i32; 3]),
+        c: (usize, [i32; 3]),
+        d: (usize, [i32; 3]),
+    ) -> CResult<f64> {
+        let (vectors, coords) = self.atom_coords(&[a.0, b.0, c.0, d.0])?;
+        let b1 = bond_vector(vectors, &coords, a.0, b.0, sub_image(b.1, a.1));
+        let b2 = bond_vector(vectors, &coords, b.0, c.0, sub_image(c.1, b.1));
+        let b3 = bond_vector(vectors, &coords, c.0, d.0, sub_image(d.1, c.1));
+        let n1 = math::cross(b1, b2);
+        let n2 = math::cross(b2, b3);
+        Ok((math::norm(b2) * math::dot(b1, n2))
+            .atan2(math::dot(n1, n2))
+            .to_degrees())
+    }
+
+    /// The shortest distance between any two atoms, including an atom and its own
+    /// periodic images.
+    pub fn shortest_distance(&self) -> CResult<Option<Bond>> {
+        // The self-image along the shortest lattice vector bounds the answer.
+        let cutoff = self
+            .lattice
+            .vectors()
+            .iter()
+            .map(|v| math::norm(*v))
+            .fold(f64::INFINITY, f64::min);
+        Ok(NeighbourList::new(self, cutoff * (1.0 + 1e-9))?
+            .shortest()
+            .map(|(i, n)| bond(i, n)))
+    }
+
+    /// Reports bonds, angles and coordination numbers for the bonding cutoff `cutoff`
+    /// (Å), together with the shortest interatomic distance.
+    ///
+    /// The shortest distance comes from the bonds when there are any; only a cell with
+    /// no bond within the cutoff needs the wider search of
+    /// [`shortest_distance`](Self::shortest_distance).
+    pub fn geometry_report(&self, cutoff: f64) -> CResult<GeometryReport> {
+        let list = NeighbourList::new(self, cutoff)?;
+        let bonds: Vec<Bond> = list.pairs().map(|(i, n)| bond(i, n)).collect();
+        let mut angles = Vec::new();
+        for (centre, neighbours) in list.neighbours.iter().enumerate() {
+            for (k, first) in neighbours.iter().enumerate() {
+                for second in &neighbours[k + 1..] {
+                    angles.push(BondAngle {
+                        centre,
+                        ends: [(first.index, first.image), (second.index, second.image)],
+                        angle: vector_angle(first.vector, second.vector),
+                    });
+                }
+            }
+        }
+        let shortest = match list.shortest() {
+            Some((i, n)) => Some(bond(i, n)),
+            None => self.shortest_distance()?,
+        };
+        Ok(GeometryReport {
+            cutoff,
+            coordination: list.neighbours.iter().map(Vec::len).collect(),
+            bonds,
+            angles,
+            shortest,
+        })
+    }
+
+    /// Lattice vectors and fractional coordinates of every atom, after checking that
+    /// `atoms` are valid indices.
+    fn atom_coords(&self, atoms: &[usize]) -> CResult<(Mat3, Vec<Vec3>)> {
+        check_indices(self.positions.len(), atoms)?;
+        Ok((
+            self.lattice.vectors(),
+            self.positions.frac_coords(&self.lattice)?,
+        ))
+    }
+}
+
+/// Cartesian vector from atom `i` to atom `j` translated by `image`.
+fn bond_vector(a: Mat3, coords: &[Vec3], i: usize, j: usize, image: [i32; 3]) -> Vec3 {
+    let f = math::add(math::sub(coords[j], coords[i]), image.map(f64::from));
+    math::frac_to_cart(a, f)
+}
+
+/// Fails if any of `atoms` is not an index into a cell of `n` atoms.
+pub(crate) fn check_indices(n: usize, atoms: &[usize]) -> CResult<()> {
+    match atoms.iter().find(|&&i| i >= n) {
+        Some(i) => Err(Error::Message(format!(
+            "atom index {i} is out of range for a cell with {n} atoms"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn bond(atom: usize, n: &Neighbour) -> Bond {
+    Bond {
+        atom,
+        other: n.index,
+        image: n.image,
+        length: n.distance,
+    }
+}
+
+fn sub_image(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn vector_angle(u: Vec3, v: Vec3) -> f64 {
+    let cos = math::dot(u, v) / (math::norm(u) * math::norm(v));
+    cos.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::cubic_cell;
+
+    fn rocksalt() -> CellDocument {
+        let mut atoms = Vec::new();
+        for f in [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.5, 0.5],
+            [0.5, 0.0, 0.5],
+            [0.5, 0.5, 0.0],
+        ] {
+            atoms.push(("Na", f));
+            atoms.push(("Cl", math::add(f, [0.5, 0.0, 0.0])));
+        }
+        cubic_cell(5.64, &atoms)
+    }
+
+    #[test]
+    fn test_rocksalt_coordination() {
+        let cell = rocksalt();
+        let list = cell.neighbour_list(3.0).unwrap();
+        assert_eq!(list.len(), 8);
+        for i in 0..8 {
+            assert_eq!(list.coordination_number(i).unwrap(), 6);
+            for n in list.neighbours(i).unwrap() {
+                assert!((n.distance - 2.82).abs() < 1e-9);
+                let distance = cell.distance(i, n.index, n.image).unwrap();
+                assert!((distance - n.distance).abs() < 1e-12);
+            }
+        }
+        // Each bond is listed once.
+        assert_eq!(list.pairs().count(), 8 * 6 / 2);
+        assert_eq!(cell.neighbour_list(4.0).unwrap().coordination_number(0).unwrap(), 18);
+        assert!(cell.neighbour_list(0.0).is_err());
+        assert!(list.neighbours(8).is_err());
+        assert!(cell.distance(0, 8, [0; 3]).is_err());
+        assert!(cell.nearest_image(8, 0).is_err());
+    }
+
+    #[test]
+    fn test_images_follow_written_coordinates() {
+        // The second atom is written one cell away; its image indices are relative to
+        // the coordinates as written, like NONLINEAR_CONSTRAINTS sites.
+        let cell = cubic_cell(10.0, &[("O", [0.0, 0.0, 0.0]), ("H", [1.1, 0.0, 0.0])]);
+        let list = cell.neighbour_list(1.5).unwrap();
+        let n = list.neighbours(0).unwrap()[0];
+        assert_eq!((n.index, n.image), (1, [-1, 0, 0]));
+        assert!((n.distance - 1.0).abs() < 1e-12);
+        assert_eq!(list.neighbours(1).unwrap()[0].image, [1, 0, 0]);
+        assert_eq!(cell.nearest_image(0, 1).unwrap(), [-1, 0, 0]);
+    }
+
+    #[test]
+    fn test_self_images_and_shortest_distance() {
+        let cell = cubic_cell(2.5, &[("Po", [0.0; 3])]);
+        let list = cell.neighbour_list(2.6).unwrap();
+        assert_eq!(list.coordination_number(0).unwrap(), 6);
+        assert_eq!(list.pairs().count(), 3);
+        assert_eq!(cell.nearest_image(0, 0).unwrap(), [0, 0, 0]);
+        let shortest = cell.shortest_distance().unwrap().unwrap();
+        assert!((shortest.length - 2.5).abs() < 1e-12);
+
+        let overlapping = cubic_cell(5.0, &[("C", [0.0; 3]), ("C", [0.05, 0.0, 0.0])]);
+        let report = overlapping.geometry_report(1.0).unwrap();
+        assert_eq!(report.short_contacts(0.5).len(), 1);
+        assert!((report.shortest.unwrap().length - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_geometry_report_angles() {
+        let report = rocksalt().geometry_report(3.0).unwrap();
+        assert_eq!(report.bonds.len(), 24);
+        assert_eq!(report.coordination, vec![6; 8]);
+        // Octahedral coordination: 12 right angles and 3 straight angles per atom.
+        assert_eq!(report.angles.len(), 8 * 15);
+        let straight = report
+            .angles
+            .iter()
+            .filter(|a| (a.angle - 180.0).abs() < 1e-9)
+            .count();
+        let right = report
+            .angles
+            .iter()
+            .filter(|a| (a.angle - 90.0).abs() < 1e-9)
+            .count();
+        assert_eq!((straight, right), (8 * 3, 8 * 12));
+    }
+
+    #[test]
+    fn test_angle_and_torsion() {
+        // H-O-O-H with a 90° dihedral, placed across a cell boundary.
+        let cell = cubic_cell(
+            10.0,
+            &[
+                ("H", [0.1, 0.1, 0.0]),
+                ("O", [0.0, 0.1, 0.0]),
+                ("O", [0.0, 0.0, 0.0]),
+                ("H", [0.0, 0.0, 0.9]),
+                ("H", [0.0, 0.0, 0.1]),
+            ],
+        );
+        let image = [0, 0, -1];
+        let angle = cell.angle(1, (0, [0; 3]), (2, [0; 3])).unwrap();
+        assert!((angle - 90.0).abs() < 1e-9);
+        let torsion = cell
+            .torsion((0, [0; 3]), (1, [0; 3]), (2, [0; 3]), (3, image))
+            .unwrap();
+        assert!((torsion.abs() - 90.0).abs() < 1e-9);
+        let flipped = cell
+            .torsion((0, [0; 3]), (1, [0; 3]), (2, [0; 3]), (4, [0; 3]))
+            .unwrap();
+        assert!((flipped + torsion).abs() < 1e-9);
+        assert!(cell.torsion((0, [0; 3]), (1, [0; 3]), (2, [0; 3]), (5, [0; 3])).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+//! Reaction-path interpolation between a reactant and a product cell.
+//!
+//! Every atom moves along its shortest periodic displacement from reactant to product,
+//! so an atom leaving through one face of the cell and entering through the opposite
+//! face travels the short way. Images are built either by linear interpolation of the
+//! Cartesian positions or with the image-dependent pair potential (IDPP) of Smidstrup
+//! et al., J. Chem. Phys. 140, 214106 (2014), which keeps interatomic distances close
+//! to a linear interpolation of the end-point distances instead of letting bonds shrink
+//! through rotations.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::positions::{PositionAbsEntry, PositionFracEntry, PositionsAbs, PositionsFrac};
+use crate::math::{self, Mat3, Vec3};
+use crate::{CellDocument, Lattice, Positions, PositionsIntermediate};
+
+/// How intermediate images are placed along the path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InterpolationScheme {
+    /// Linear interpolation of the Cartesian positions.
+    #[default]
+    Linear,
+    /// Image-dependent pair potential, starting from the linear path.
+    Idpp,
+}
+
+/// Settings for interpolating a reaction path.
+///
+/// # Example
+///
+/// ```no_run
+/// use castep_cell_io::CellDocument;
+/// use castep_cell_io::interpolation::{InterpolationScheme, PathInterpolation};
+///
+/// # fn run(reactant: &CellDocument, product: &CellDocument) -> castep_cell_fmt::CResult<()> {
+/// let images = PathInterpolation::builder()
+///     .images(5)
+///     .scheme(InterpolationScheme::Idpp)
+///     .build()
+///     .interpolate(reactant, product)?;
+/// assert_eq!(images.len(), 5);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, bon::Builder)]
+pub struct PathInterpolation {
+    /// Number of intermediate images, not counting the end points.
+    pub images: usize,
+    /// Interpolation scheme.
+    #[builder(default)]
+    pub scheme: InterpolationScheme,
+    /// Maximum number of IDPP relaxation steps.
+    #[builder(default = 1000)]
+    pub max_iterations: usize,
+    /// IDPP convergence threshold on the largest force on any atom, in Å⁻³.
+    #[builder(default = 1e-3)]
+    pub tolerance: f64,
+}
+
+/// Spring constant between neighbouring IDPP images, in Å⁻⁴.
+const SPRING: f64 = 5.0;
+/// Steepest-descent step length for IDPP relaxation, in Å⁴.
+const STEP: f64 = 0.05;
+/// Largest distance an atom moves in one IDPP step, in Å.
+const MAX_MOVE: f64 = 0.05;
+
+impl PathInterpolation {
+    /// Returns the intermediate cells between `reactant` and `product`, in path order.
+    ///
+    /// Both cells must share the lattice and list the same atoms in the same order. The
+    /// images are copies of `reactant` with new positions in its coordinate mode and
+    /// without transition-state blocks.
+    pub fn interpolate(
+        &self,
+        reactant: &CellDocument,
+        product: &CellDocument,
+    ) -> CResult<Vec<CellDocument>> {
+        check_endpoints(reactant, product)?;
+        let a = reactant.lattice.vectors();
+        let inverse = math::lattice_inverse(a)?;
+        let start = reactant.positions.cart_coords(&reactant.lattice);
+        let from = reactant.positions.frac_coords(&reactant.lattice)?;
+        let to = product.positions.frac_coords(&product.lattice)?;
+        let end: Vec<Vec3> = start
+            .iter()
+            .zip(from.iter().zip(&to))
+            .map(|(r, (x, y))| math::add(*r, math::shortest_vector(a, math::sub(*y, *x))))
+            .collect();
+
+        let segments = (self.images + 1) as f64;
+        let mut path: Vec<Vec<Vec3>> = (0..=self.images + 1)
+            .map(|k| {
+                let t = k as f64 / segments;
+                start
+                    .iter()
+                    .zip(&end)
+                    .map(|(r, s)| math::add(*r, math::scale(math::sub(*s, *r), t)))
+                    .collect()
+            })
+            .collect();
+        if self.scheme == InterpolationScheme::Idpp && self.images > 0 {
+            self.relax_idpp(a, inverse, &mut path)?;
+        }
+
+        Ok(path[1..=self.images]
+            .iter()
+            .map(|coords| CellDocument {
+                positions: place(&reactant.positions, inverse, coords),
+                positions_intermediate: None,
+                positions_product: None,
+                ..reactant.clone()
+            })
+            .collect())
+    }
+
+    /// Relaxes the inner images of `path` on the IDPP surface with nudged-elastic-band
+    /// forces: the IDPP gradient perpendicular to the path plus springs along it.
+    ///
+    /// `inverse` is the inverse of the lattice rows `a`.
+    fn relax_idpp(&self, a: Mat3, inverse: Mat3, path: &mut [Vec<Vec3>]) -> CResult<()> {
+        let last = path.len() - 1;
+        let start = pair_distances(a, inverse, &path[0]);
+        let end = pair_distances(a, inverse, &path[last]);
+        for _ in 0..self.max_iterations {
+            let mut largest: f64 = 0.0;
+            let mut moves: Vec<Vec<Vec3>> = Vec::with_capacity(last - 1);
+            for k in 1..last {
+                let t = k as f64 / last as f64;
+                let gradient = idpp_gradient((a, inverse), &path[k], (&start, &end), t);
+                let tangent = unit(&difference(&path[k + 1], &path[k - 1]));
+                let stretch = length(&difference(&path[k + 1], &path[k]))
+                    - length(&difference(&path[k], &path[k - 1]));
+                // Remove the gradient's component along the path and add the spring.
+                let along = dot(&gradient, &tangent) + SPRING * stretch;
+                let forces: Vec<Vec3> = gradient
+                    .iter()
+                    .zip(&tangent)
+                    .map(|(g, tau)| math::sub(math::scale(*tau, along), *g))
+                    .collect();
+                largest = forces
+                    .iter()
+                    .map(|f| math::norm(*f))
+                    .fold(largest, f64::max);
+                moves.push(
+                    forces
+                        .iter()
+                        .map(|f| {
+                            let step = math::scale(*f, STEP);
+                            let size = math::norm(step);
+                            if size > MAX_MOVE {
+                                math::scale(step, MAX_MOVE / size)
+                            } else {
+                                step
+                            }
+                        })
+                        .collect(),
+                );
+            }
+            if largest < self.tolerance {
+                return Ok(());
+            }
+            for (image, steps) in path[1..last].iter_mut().zip(moves) {
+                for (r, step) in image.iter_mut().zip(steps) {
+                    *r = math::add(*r, step);
+                }
+            }
+        }
+        Err(Error::Message(format!(
+            "IDPP interpolation did not converge in {} iterations",
+            self.max_iterations
+        )))
+    }
+}
+
+impl CellDocument {
+    /// Fills `POSITIONS_*_INTERMEDIATE` with the midpoint of the path from the reactant
+    /// positions to `POSITIONS_*_PRODUCT`, using default [`PathInterpolation`] settings
+    /// with `scheme`.
+    ///
+    /// Fails if there is no product geometry or it does not match the reactant.
+    pub fn fill_intermediate(&mut self, scheme: InterpolationScheme) -> CResult<()> {
+        let product = self.positions_product.as_ref().ok_or_else(|| {
+            Error::Message("POSITIONS_*_PRODUCT is required to interpolate an intermediate".into())
+        })?;
+        let product = CellDocument {
+            positions: product.to_positions(),
+            ..self.clone()
+        };
+        let midpoint = PathInterpolation::builder()
+            .images(1)
+            .scheme(scheme)
+            .build()
+            .interpolate(self, &product)?
+            .remove(0);
+        self.positions_intermediate = Some(PositionsIntermediate::from(midpoint.positions));
+        Ok(())
+    }
+}
+
+fn check_endpoints(reactant: &CellDocument, product: &CellDocument) -> CResult<()> {
+    let (a, b) = (reactant.lattice.vectors(), product.lattice.vectors());
+    let scale = a.iter().map(|v| math::norm(*v)).fold(0.0, f64::max);
+    let mismatch = a
+        .iter()
+        .zip(&b)
+        .any(|(u, v)| math::norm(math::sub(*u, *v)) > 1e-6 * scale);
+    if mismatch {
+        return Err(Error::Message(
+            "reactant and product must have the same lattice".into(),
+        ));
+    }
+    if reactant.positions.len() != product.positions.len() {
+        return Err(Error::Message(format!(
+            "reactant has {} atoms but product has {}",
+            reactant.positions.len(),
+            product.positions.len()
+        )));
+    }
+    let species = reactant.positions.species();
+    let other = product.positions.species();
+    match (0..species.len()).find(|&i| !species[i].same_species(other[i])) {
+        Some(i) => Err(Error::Message(format!(
+            "atom {} is {} in the reactant but {} in the product",
+            i + 1,
+            species[i],
+            other[i]
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Builds a positions block in the mode of `template` with atoms at Cartesian `coords`,
+/// with `inverse` the inverse of the lattice rows.
+fn place(template: &Positions, inverse: Mat3, coords: &[Vec3]) -> Positions {
+    match template {
+        Positions::Frac(frac) => Positions::Frac(PositionsFrac {
+            positions: frac
+                .positions
+                .iter()
+                .zip(coords)
+                .map(|(p, r)| PositionFracEntry {
+                    coord: math::vec_mat(*r, inverse),
+                    ..p.clone()
+                })
+                .collect(),
+        }),
+        Positions::Abs(abs) => {
+            let f = abs.unit.unwrap_or_default().in_angstrom();
+            Positions::Abs(PositionsAbs {
+                unit: abs.unit,
+                positions: abs
+                    .positions
+                    .iter()
+                    .zip(coords)
+                    .map(|(p, r)| PositionAbsEntry {
+                        coord: r.map(|x| x / f),
+                        ..p.clone()
+                    })
+                    .collect(),
+            })
+        }
+    }
+}
+
+/// Shortest periodic distance between every pair of atoms, with `inverse` the inverse
+/// of the lattice rows `a`.
+fn pair_distances(a: Mat3, inverse: Mat3, coords: &[Vec3]) -> Vec<Vec<f64>> {
+    coords
+        .iter()
+        .map(|ri| {
+            coords
+                .iter()
+                .map(|rj| {
+                    let d = math::vec_mat(math::sub(*rj, *ri), inverse);
+                    math::norm(math::shortest_vector(a, d))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Gradient of the IDPP objective `Σ (d - d_target)² / d⁴` over atom pairs, with the
+/// target distances interpolated to `t` between `start` and `end`. The lattice is
+/// given as its rows and their inverse.
+///
+/// Pairs that coincide at both end points, such as `MIXTURE` components, are skipped.
+fn idpp_gradient(
+    (a, inverse): (Mat3, Mat3),
+    coords: &[Vec3],
+    (start, end): (&[Vec<f64>], &[Vec<f64>]),
+    t: f64,
+) -> Vec<Vec3> {
+    let mut gradient = vec![[0.0; 3]; coords.len()];
+    for i in 0..coords.len() {
+        for j in i + 1..coords.len() {
+            if start[i][j] < 1e-6 && end[i][j] < 1e-6 {
+                continue;
+            }
+            let target = start[i][j] + t * (end[i][j] - start[i][j]);
+            let frac = math::vec_mat(math::sub(coords[j], coords[i]), inverse);
+            let r = math::shortest_vector(a, frac);
+            let d = math::norm(r);
+            let excess = d - target;
+            let slope = 2.0 * excess / d.powi(4) - 4.0 * excess * excess / d.powi(5);
+            let g = math::scale(r, slope / d);
+            gradient[j] = math::add(gradient[j], g);
+            gradient[i] = math::sub(gradient[i], g);
+        }
+    }
+    gradient
+}
+
+fn difference(x: &[Vec3], y: &[Vec3]) -> Vec<Vec3> {
+    x.iter().zip(y).map(|(a, b)| math::sub(*a, *b)).collect()
+}
+
+fn dot(x: &[Vec3], y: &[Vec3]) -> f64 {
+    x.iter().zip(y).map(|(a, b)| math::dot(*a, *b)).sum()
+}
+
+fn length(x: &[Vec3]) -> f64 {
+    dot(x, x).sqrt()
+}
+
+fn unit(x: &[Vec3]) -> Vec<Vec3> {
+    let l = length(x);
+    if l == 0.0 {
+        x.to_vec()
+    } else {
+        x.iter().map(|v| math::scale(*v, 1.0 / l)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PositionsProduct;
+    use crate::cell::lattice_param::LatticeCart;
+    use crate::test_fixtures::cubic_cell;
+
+    #[test]
+    fn test_linear_path_crosses_boundary() {
+        let reactant = cubic_cell(10.0, &[("H", [0.95, 0.5, 0.5]), ("O", [0.5, 0.5, 0.5])]);
+        let product = cubic_cell(10.0, &[("H", [0.15, 0.5, 0.5]), ("O", [0.5, 0.5, 0.5])]);
+        let images = PathInterpolation::builder()
+            .images(3)
+            .build()
+            .interpolate(&reactant, &product)
+            .unwrap();
+        assert_eq!(images.len(), 3);
+        let x: Vec<f64> = images
+            .iter()
+            .map(|d| d.positions.frac_coords(&d.lattice).unwrap()[0][0])
+            .collect();
+        for (got, want) in x.iter().zip([1.0, 1.05, 1.1]) {
+            assert!((got - want).abs() < 1e-9, "{x:?}");
+        }
+    }
+
+    #[test]
+    fn test_idpp_keeps_bond_length_through_rotation() {
+        // A 1 Å dimer turning by 90° about its centre.
+        let reactant = cubic_cell(10.0, &[("H", [0.45, 0.5, 0.5]), ("H", [0.55, 0.5, 0.5])]);
+        let product = cubic_cell(10.0, &[("H", [0.5, 0.45, 0.5]), ("H", [0.5, 0.55, 0.5])]);
+        let bond = |d: &CellDocument| d.distance(0, 1, [0, 0, 0]).unwrap();
+
+        let linear = PathInterpolation::builder()
+            .images(1)
+            .build()
+            .interpolate(&reactant, &product)
+            .unwrap();
+        assert!((bond(&linear[0]) - 0.5f64.sqrt()).abs() < 1e-9);
+
+        let idpp = PathInterpolation::builder()
+            .images(3)
+            .scheme(InterpolationScheme::Idpp)
+            .build()
+            .interpolate(&reactant, &product)
+            .unwrap();
+        for image in &idpp {
+            assert!((bond(image) - 1.0).abs() < 0.02, "{}", bond(image));
+        }
+    }
+
+    #[test]
+    fn test_fill_intermediate() {
+        let mut cell = cubic_cell(10.0, &[("H", [0.1, 0.5, 0.5]), ("O", [0.5, 0.5, 0.5])]);
+        assert!(cell.fill_intermediate(InterpolationScheme::Linear).is_err());
+        let product = cubic_cell(10.0, &[("H", [0.3, 0.5, 0.5]), ("O", [0.5, 0.5, 0.5])]);
+        cell.positions_product = Some(PositionsProduct::from(product.positions));
+        cell.fill_intermediate(InterpolationScheme::Idpp).unwrap();
+        cell.check_transition_state().unwrap();
+        let midpoint = cell
+            .positions_intermediate
+            .as_ref()
+            .unwrap()
+            .to_positions()
+            .frac_coords(&cell.lattice)
+            .unwrap();
+        assert!((midpoint[0][0] - 0.2).abs() < 1e-3, "{midpoint:?}");
+    }
+
+    #[test]
+    fn test_rejects_mismatched_endpoints() {
+        let reactant = cubic_cell(10.0, &[("H", [0.1, 0.5, 0.5]), ("O", [0.5, 0.5, 0.5])]);
+        let swapped = cubic_cell(10.0, &[("O", [0.5, 0.5, 0.5]), ("H", [0.1, 0.5, 0.5])]);
+        let shorter = cubic_cell(10.0, &[("H", [0.1, 0.5, 0.5])]);
+        let interpolation = PathInterpolation::builder().images(2).build();
+        assert!(interpolation.interpolate(&reactant, &swapped).is_err());
+        assert!(interpolation.interpolate(&reactant, &shorter).is_err());
+
+        let mut flat = reactant.clone();
+        flat.lattice = Lattice::Cart(LatticeCart {
+            unit: None,
+            a: [10.0, 0.0, 0.0],
+            b: [10.0, 0.0, 0.0],
+            c: [0.0, 0.0, 10.0],
+        });
+        let idpp = PathInterpolation::builder()
+            .images(1)
+            .scheme(InterpolationScheme::Idpp)
+            .build();
+        assert!(idpp.interpolate(&flat, &flat).is_err());
+    }
+}
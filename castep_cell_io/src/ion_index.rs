@@ -0,0 +1,289 @@
+//! Conversion between global atom indices and the `species ion_number` pairs used by
+//! per-ion blocks.
+
+use std::collections::HashMap;
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::species::Species;
+use crate::{CellDocument, Positions};
+
+/// Species and 1-based ion number of every atom in a positions block.
+///
+/// Ions are numbered within their species in block order. Species are compared by
+/// [`Species::canonical_label`], so `Fe`, `fe` and `26` address the same ions while a
+/// custom label such as `Fe1` is a species of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IonIndex {
+    species: Vec<Species>,
+    numbers: Vec<u32>,
+    /// Number of ions per canonical label.
+    counts: HashMap<String, u32>,
+    /// Atom index of every (canonical label, ion number) pair.
+    indices: HashMap<(String, u32), usize>,
+}
+
+/// A per-ion reference to an ion that does not exist in the cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DanglingIon {
+    /// Block holding the reference, e.g. `IONIC_CONSTRAINTS`.
+    pub block: &'static str,
+    /// Species as written in the block.
+    pub species: Species,
+    /// Ion number as written in the block.
+    pub ion_number: u32,
+}
+
+impl std::fmt::Display for DanglingIon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} refers to ion {} {}, which does not exist in the cell",
+            self.block, self.species, self.ion_number
+        )
+    }
+}
+
+impl IonIndex {
+    /// Numbers the ions of `positions`.
+    ///
+    /// Build the index once and reuse it when resolving many ions; lookups are
+    /// constant-time.
+    pub fn new(positions: &Positions) -> Self {
+        let species: Vec<Species> = positions.species().into_iter().cloned().collect();
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let mut indices = HashMap::with_capacity(species.len());
+        let numbers = species
+            .iter()
+            .enumerate()
+            .map(|(i, species)| {
+                let label = species.canonical_label();
+                let count = counts.entry(label.clone()).or_insert(0);
+                *count += 1;
+                indices.insert((label, *count), i);
+                *count
+            })
+            .collect();
+        Self {
+            species,
+            numbers,
+            counts,
+            indices,
+        }
+    }
+
+    /// Returns the number of atoms.
+    pub fn len(&self) -> usize {
+        self.species.len()
+    }
+
+    /// Returns `true` if there are no atoms.
+    pub fn is_empty(&self) -> bool {
+        self.species.is_empty()
+    }
+
+    /// Returns the ion number of every atom, in block order.
+    pub fn ion_numbers(&self) -> &[u32] {
+        &self.numbers
+    }
+
+    /// Returns the species of atom `index` as written in the positions block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn species(&self, index: usize) -> &Species {
+        &self.species[index]
+    }
+
+    /// Returns the ion number of atom `index` within its species.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn ion_number(&self, index: usize) -> u32 {
+        self.numbers[index]
+    }
+
+    /// Returns the species and ion number of atom `index`, if it exists.
+    pub fn ion(&self, index: usize) -> Option<(&Species, u32)> {
+        Some((self.species.get(index)?, *self.numbers.get(index)?))
+    }
+
+    /// Returns the number of ions of `species`.
+    pub fn count(&self, species: &Species) -> u32 {
+        self.counts
+            .get(&species.canonical_label())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the index of ion `ion_number` of `species`, if it exists.
+    pub fn index(&self, species: &Species, ion_number: u32) -> Option<usize> {
+        self.indices
+            .get(&(species.canonical_label(), ion_number))
+            .copied()
+    }
+
+    /// Like [`IonIndex::index`], but fails with a message naming the missing ion.
+    pub fn resolve(&self, species: &Species, ion_number: u32) -> CResult<usize> {
+        self.index(species, ion_number).ok_or_else(|| {
+            Error::Message(format!(
+                "ion {species} {ion_number} does not exist in the cell"
+            ))
+        })
+    }
+
+    /// Lists every reference in the per-ion blocks of `doc` (`IONIC_CONSTRAINTS`,
+    /// `NONLINEAR_CONSTRAINTS` and per-ion `HUBBARD_U`) to an ion that is not in this
+    /// index.
+    pub fn dangling_references(&self, doc: &CellDocument) -> Vec<DanglingIon> {
+        let mut references: Vec<(&'static str, &Species, u32)> = Vec::new();
+        if let Some(ic) = &doc.ionic_constraints {
+            references.extend(
+                ic.constraints
+                    .iter()
+                    .map(|e| ("IONIC_CONSTRAINTS", &e.species, e.ion_number)),
+            );
+        }
+        if let Some(nc) = &doc.nonlinear_constraints {
+            references.extend(
+                nc.constraints
+                    .iter()
+                    .flat_map(|c| &c.atom_sites)
+                    .map(|s| ("NONLINEAR_CONSTRAINTS", &s.species, s.ion_number)),
+            );
+        }
+        if let Some(hu) = &doc.hubbard_u {
+            references.extend(
+                hu.atom_u_values
+                    .iter()
+                    .filter_map(|e| e.ion_number.map(|n| ("HUBBARD_U", &e.species, n))),
+            );
+        }
+        references
+            .into_iter()
+            .filter(|(_, species, n)| self.index(species, *n).is_none())
+            .map(|(block, species, ion_number)| DanglingIon {
+                block,
+                species: species.clone(),
+                ion_number,
+            })
+            .collect()
+    }
+}
+
+impl CellDocument {
+    /// Returns the [`IonIndex`] of the positions block.
+    pub fn ion_index(&self) -> IonIndex {
+        IonIndex::new(&self.positions)
+    }
+
+    /// Checks that every per-ion reference names an ion in the positions block, and
+    /// that `IONIC_VELOCITIES` has one row per atom.
+    pub fn check_ion_references(&self) -> CResult<()> {
+        let index = self.ion_index();
+        let mut problems: Vec<String> = index
+            .dangling_references(self)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if let Some(iv) = &self.ionic_velocities
+            && iv.velocities.len() != index.len()
+        {
+            problems.push(format!(
+                "IONIC_VELOCITIES lists {} ions but the cell has {}",
+                iv.velocities.len(),
+                index.len()
+            ));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Message(problems.join("; ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::constraints::{IonicConstraintEntry, IonicConstraints};
+    use crate::cell::positions::{PositionFracEntry, PositionsFrac};
+    use crate::cell::species::{AtomHubbardU, HubbardU};
+    use crate::test_fixtures::{cell, cubic_lattice};
+
+    fn doc(species: &[Species]) -> CellDocument {
+        let positions = species
+            .iter()
+            .enumerate()
+            .map(|(i, species)| PositionFracEntry {
+                species: species.clone(),
+                coord: [0.1 * i as f64, 0.0, 0.0],
+                spin: None,
+                mixture: None,
+            })
+            .collect();
+        cell(cubic_lattice(3.0), PositionsFrac { positions })
+    }
+
+    #[test]
+    fn test_mixed_spellings_share_numbering() {
+        let sym = |s: &str| Species::Symbol(s.into());
+        let cell = doc(&[
+            sym("Fe"),
+            Species::AtomicNumber(26),
+            sym("Fe1"),
+            sym("fe"),
+            sym("O"),
+        ]);
+        let index = cell.ion_index();
+        assert_eq!(index.ion_numbers(), &[1, 2, 1, 3, 1]);
+        assert_eq!(index.count(&sym("Fe")), 3);
+        assert_eq!(index.count(&sym("Fe1")), 1);
+        assert_eq!(index.index(&Species::AtomicNumber(26), 3), Some(3));
+        assert_eq!(index.index(&sym("FE"), 2), Some(1));
+        assert_eq!(index.index(&sym("Fe1"), 2), None);
+        assert_eq!(index.ion(4), Some((&sym("O"), 1)));
+        assert_eq!(index.ion(5), None);
+        assert!(index.resolve(&Species::AtomicNumber(8), 2).is_err());
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let sym = |s: &str| Species::Symbol(s.into());
+        let mut cell = doc(&[sym("Fe"), sym("O")]);
+        assert!(cell.check_ion_references().is_ok());
+        cell.ionic_constraints = Some(IonicConstraints {
+            constraints: vec![
+                IonicConstraintEntry {
+                    constraint_number: 1,
+                    species: Species::AtomicNumber(26),
+                    ion_number: 1,
+                    coefficients: [1.0, 0.0, 0.0],
+                },
+                IonicConstraintEntry {
+                    constraint_number: 2,
+                    species: sym("O"),
+                    ion_number: 2,
+                    coefficients: [1.0, 0.0, 0.0],
+                },
+            ],
+        });
+        cell.hubbard_u = Some(HubbardU {
+            unit: None,
+            atom_u_values: vec![AtomHubbardU {
+                species: sym("Fe"),
+                ion_number: Some(4),
+                orbitals: Vec::new(),
+            }],
+        });
+        let dangling = cell.ion_index().dangling_references(&cell);
+        assert_eq!(dangling.len(), 2);
+        assert_eq!(dangling[0].block, "IONIC_CONSTRAINTS");
+        assert_eq!(dangling[1].block, "HUBBARD_U");
+        let err = cell.check_ion_references().unwrap_err().to_string();
+        assert!(err.contains("ion O 2"), "{err}");
+        assert!(err.contains("ion Fe 4"), "{err}");
+    }
+}
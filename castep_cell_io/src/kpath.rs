@@ -0,0 +1,1215 @@
+//! High-symmetry k-point paths after Setyawan and Curtarolo, Comput. Mater. Sci. 49,
+//! 299 (2010).
+//!
+//! The Bravais lattice and its variant are read from the standard conventional cell
+//! found by [`CellDocument::find_symmetry`]. Points are tabulated in the reciprocal
+//! basis of the Setyawan–Curtarolo primitive cell and carried to the reciprocal basis of
+//! the input cell through Cartesian space, so the path is valid for cells in any
+//! setting, including supercells, where the points simply fold back.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::bz_sampling_kpoints::{
+    BsKpointPath, BsKpointPathEntry, SpectralKpointPath, SpectralKpointPathEntry,
+};
+use crate::cell::phonon::{PhononFineKpointPath, PhononKpointPath, PhononKpointPathEntry};
+use crate::cell::positions::{PositionFracEntry, PositionsFrac};
+use crate::cell::species::Species;
+use crate::cell::symmetry::{Centering, CrystalSystem};
+use crate::math::{self, Mat3, Vec3};
+use crate::{CellDocument, Lattice};
+
+/// Relative tolerance for the equalities that separate lattice variants.
+const VARIANT_TOL: f64 = 1e-5;
+
+/// The 25 Bravais lattice variants distinguished by Setyawan and Curtarolo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatticeVariant {
+    /// Simple cubic.
+    Cub,
+    /// Face-centred cubic.
+    Fcc,
+    /// Body-centred cubic.
+    Bcc,
+    /// Simple tetragonal.
+    Tet,
+    /// Body-centred tetragonal with `c < a`.
+    Bct1,
+    /// Body-centred tetragonal with `c > a`.
+    Bct2,
+    /// Simple orthorhombic.
+    Orc,
+    /// Face-centred orthorhombic with `1/a² > 1/b² + 1/c²`.
+    Orcf1,
+    /// Face-centred orthorhombic with `1/a² < 1/b² + 1/c²`.
+    Orcf2,
+    /// Face-centred orthorhombic with `1/a² = 1/b² + 1/c²`.
+    Orcf3,
+    /// Body-centred orthorhombic.
+    Orci,
+    /// Base-centred orthorhombic.
+    Orcc,
+    /// Hexagonal.
+    Hex,
+    /// Rhombohedral with `α < 90°`.
+    Rhl1,
+    /// Rhombohedral with `α > 90°`.
+    Rhl2,
+    /// Simple monoclinic.
+    Mcl,
+    /// Base-centred monoclinic with `k_γ > 90°`.
+    Mclc1,
+    /// Base-centred monoclinic with `k_γ = 90°`.
+    Mclc2,
+    /// Base-centred monoclinic with `k_γ < 90°` and
+    /// `b cos α / c + b² sin² α / a² < 1`.
+    Mclc3,
+    /// Base-centred monoclinic with `k_γ < 90°` and
+    /// `b cos α / c + b² sin² α / a² = 1`.
+    Mclc4,
+    /// Base-centred monoclinic with `k_γ < 90°` and
+    /// `b cos α / c + b² sin² α / a² > 1`.
+    Mclc5,
+    /// Triclinic with all reciprocal angles above 90°.
+    Tri1a,
+    /// Triclinic with all reciprocal angles below 90°.
+    Tri1b,
+    /// Triclinic with `k_γ = 90°` and the other reciprocal angles above 90°.
+    Tri2a,
+    /// Triclinic with `k_γ = 90°` and the other reciprocal angles below 90°.
+    Tri2b,
+}
+
+impl LatticeVariant {
+    /// Returns the Setyawan–Curtarolo name, e.g. `BCT1`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LatticeVariant::Cub => "CUB",
+            LatticeVariant::Fcc => "FCC",
+            LatticeVariant::Bcc => "BCC",
+            LatticeVariant::Tet => "TET",
+            LatticeVariant::Bct1 => "BCT1",
+            LatticeVariant::Bct2 => "BCT2",
+            LatticeVariant::Orc => "ORC",
+            LatticeVariant::Orcf1 => "ORCF1",
+            LatticeVariant::Orcf2 => "ORCF2",
+            LatticeVariant::Orcf3 => "ORCF3",
+            LatticeVariant::Orci => "ORCI",
+            LatticeVariant::Orcc => "ORCC",
+            LatticeVariant::Hex => "HEX",
+            LatticeVariant::Rhl1 => "RHL1",
+            LatticeVariant::Rhl2 => "RHL2",
+            LatticeVariant::Mcl => "MCL",
+            LatticeVariant::Mclc1 => "MCLC1",
+            LatticeVariant::Mclc2 => "MCLC2",
+            LatticeVariant::Mclc3 => "MCLC3",
+            LatticeVariant::Mclc4 => "MCLC4",
+            LatticeVariant::Mclc5 => "MCLC5",
+            LatticeVariant::Tri1a => "TRI1a",
+            LatticeVariant::Tri1b => "TRI1b",
+            LatticeVariant::Tri2a => "TRI2a",
+            LatticeVariant::Tri2b => "TRI2b",
+        }
+    }
+}
+
+impl std::fmt::Display for LatticeVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A labelled high-symmetry point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPoint {
+    /// Label from the Setyawan–Curtarolo tables, e.g. `Γ` or `Σ1`.
+    pub label: &'static str,
+    /// Fractional coordinates in the reciprocal basis of the cell the path was made for.
+    pub coord: [f64; 3],
+}
+
+/// A high-symmetry path made of continuous segments.
+///
+/// Consecutive segments are not connected; the path jumps from the last point of one
+/// segment to the first point of the next.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KpointPath {
+    /// Bravais lattice variant the path was taken from.
+    pub variant: LatticeVariant,
+    /// Continuous runs of points, in path order.
+    pub segments: Vec<Vec<PathPoint>>,
+}
+
+impl KpointPath {
+    /// Returns the standard path for the crystal symmetry of `doc`, detected with
+    /// `SYMMETRY_TOL` or the CASTEP default of 0.01 Å.
+    pub fn for_cell(doc: &CellDocument) -> CResult<Self> {
+        let dataset = doc.find_symmetry()?;
+        let a = doc.lattice.vectors();
+        let to_conventional = math::inverse(dataset.transformation_matrix)
+            .ok_or_else(|| Error::Message("singular transformation to the standard cell".into()))?;
+        let conventional = math::mat_mul(math::transpose(to_conventional), a);
+        let group = dataset.space_group;
+        let table = Table::new(group.crystal_system(), group.centering(), conventional)?;
+
+        let point = |label: &str| -> CResult<PathPoint> {
+            let (label, k) = table
+                .points
+                .iter()
+                .find(|(name, _)| *name == label)
+                .copied()
+                .ok_or_else(|| Error::Message(format!("{} has no point {label}", table.variant)))?;
+            let cart = math::vec_mat(k, table.reciprocal);
+            let coord = math::mat_vec(a, cart).map(snap);
+            Ok(PathPoint { label, coord })
+        };
+        let segments = table
+            .path
+            .split('|')
+            .map(|segment| segment.split('-').map(point).collect::<CResult<Vec<_>>>())
+            .collect::<CResult<Vec<_>>>()?;
+        Ok(KpointPath {
+            variant: table.variant,
+            segments,
+        })
+    }
+
+    /// Returns every point of the path in order.
+    pub fn points(&self) -> impl Iterator<Item = &PathPoint> {
+        self.segments.iter().flatten()
+    }
+
+    /// Returns the path as a `BS_KPOINT_PATH` block.
+    ///
+    /// Every point carries its label, and the segments are separated by `BREAK` so the
+    /// jumps between them are not traversed.
+    pub fn to_bs_kpoint_path(&self) -> BsKpointPath {
+        BsKpointPath {
+            points: self
+                .points()
+                .map(|p| BsKpointPathEntry {
+                    coord: p.coord,
+                    label: Some(p.label.to_string()),
+                })
+                .collect(),
+            breaks: self.segment_starts(),
+        }
+    }
+
+    /// Returns the path as a `SPECTRAL_KPOINT_PATH` block, see
+    /// [`to_bs_kpoint_path`](Self::to_bs_kpoint_path).
+    pub fn to_spectral_kpoint_path(&self) -> SpectralKpointPath {
+        SpectralKpointPath {
+            points: self
+                .points()
+                .map(|p| SpectralKpointPathEntry {
+                    coord: p.coord,
+                    label: Some(p.label.to_string()),
+                })
+                .collect(),
+            breaks: self.segment_starts(),
+        }
+    }
+
+    /// Returns the path as a `PHONON_KPOINT_PATH` block, see
+    /// [`to_bs_kpoint_path`](Self::to_bs_kpoint_path).
+    pub fn to_phonon_kpoint_path(&self) -> PhononKpointPath {
+        PhononKpointPath {
+            points: self.phonon_entries(),
+            breaks: self.segment_starts(),
+        }
+    }
+
+    /// Returns the path as a `PHONON_FINE_KPOINT_PATH` block, see
+    /// [`to_bs_kpoint_path`](Self::to_bs_kpoint_path).
+    pub fn to_phonon_fine_kpoint_path(&self) -> PhononFineKpointPath {
+        PhononFineKpointPath {
+            points: self.phonon_entries(),
+            breaks: self.segment_starts(),
+        }
+    }
+
+    fn phonon_entries(&self) -> Vec<PhononKpointPathEntry> {
+        self.points()
+            .map(|p| PhononKpointPathEntry {
+                coord: p.coord,
+                label: Some(p.label.to_string()),
+            })
+            .collect()
+    }
+
+    /// Indices of the first point of every segment but the first.
+    fn segment_starts(&self) -> Vec<usize> {
+        self.segments
+            .iter()
+            .scan(0, |start, segment| {
+                *start += segment.len();
+                Some(*start)
+            })
+            .take(self.segments.len().saturating_sub(1))
+            .collect()
+    }
+}
+
+impl CellDocument {
+    /// Returns the standard high-symmetry path for the crystal, see
+    /// [`KpointPath::for_cell`].
+    pub fn high_symmetry_path(&self) -> CResult<KpointPath> {
+        KpointPath::for_cell(self)
+    }
+}
+
+impl Lattice {
+    /// Returns the standard high-symmetry path for the lattice alone, i.e. for a crystal
+    /// with one atom per cell and the full holohedry.
+    pub fn high_symmetry_path(&self) -> CResult<KpointPath> {
+        let doc = CellDocument::builder()
+            .lattice(self.clone())
+            .positions(PositionsFrac {
+                positions: vec![
+                    PositionFracEntry::builder()
+                        .species(Species::Symbol("H".into()))
+                        .coord([0.0; 3])
+                        .build(),
+                ],
+            })
+            .build()?;
+        KpointPath::for_cell(&doc)
+    }
+}
+
+/// Points and path of one lattice variant in its Setyawan–Curtarolo primitive cell.
+struct Table {
+    variant: LatticeVariant,
+    /// Reciprocal vectors of the primitive cell as Cartesian rows.
+    reciprocal: Mat3,
+    points: Vec<(&'static str, Vec3)>,
+    /// Labels joined by `-` within a segment and segments joined by `|`.
+    path: &'static str,
+}
+
+/// Primitive vectors of a face-centred cell, as rows in conventional coordinates.
+const FACE: Mat3 = [[0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]];
+/// Primitive vectors of a body-centred cell.
+const BODY: Mat3 = [[-0.5, 0.5, 0.5], [0.5, -0.5, 0.5], [0.5, 0.5, -0.5]];
+/// Primitive vectors of a C-centred orthorhombic cell.
+const BASE_ORC: Mat3 = [[0.5, -0.5, 0.0], [0.5, 0.5, 0.0], [0.0, 0.0, 1.0]];
+/// Primitive vectors of a C-centred monoclinic cell with unique axis a.
+const BASE_MCL: Mat3 = [[0.5, 0.5, 0.0], [-0.5, 0.5, 0.0], [0.0, 0.0, 1.0]];
+/// Rhombohedral primitive vectors of an obverse R cell in hexagonal axes.
+const RHOMBOHEDRAL: Mat3 = [
+    [2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0],
+    [-1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0],
+    [-1.0 / 3.0, -2.0 / 3.0, 1.0 / 3.0],
+];
+
+const G: (&str, Vec3) = ("Γ", [0.0, 0.0, 0.0]);
+
+impl Table {
+    /// Picks the variant for a standard conventional cell given as Cartesian rows.
+    fn new(system: CrystalSystem, centering: Centering, conv: Mat3) -> CResult<Self> {
+        use CrystalSystem as S;
+        let unsupported = || {
+            Error::Message(format!(
+                "no standard path for a {system:?} lattice with {centering:?} centering"
+            ))
+        };
+        match (system, centering) {
+            (S::Cubic, Centering::P) => cub(conv),
+            (S::Cubic, Centering::F) => fcc(conv),
+            (S::Cubic, Centering::I) => bcc(conv),
+            (S::Tetragonal, Centering::P) => tet(conv),
+            (S::Tetragonal, Centering::I) => bct(conv),
+            (S::Orthorhombic, Centering::P) => orc(sort_axes(conv)),
+            (S::Orthorhombic, Centering::F) => orcf(sort_axes(conv)),
+            (S::Orthorhombic, Centering::I) => orci(sort_axes(conv)),
+            (S::Orthorhombic, Centering::C) => orcc(conv),
+            // Cycle the axes so the centred face becomes the ab face.
+            (S::Orthorhombic, Centering::A) => orcc([conv[1], conv[2], conv[0]]),
+            (S::Orthorhombic, Centering::B) => orcc([conv[2], conv[0], conv[1]]),
+            (S::Hexagonal | S::Trigonal, Centering::P) => hex(conv),
+            (S::Trigonal, Centering::R) => rhl(conv),
+            (S::Monoclinic, Centering::P) => mcl(conv),
+            (S::Monoclinic, Centering::C) => mclc(conv),
+            (S::Triclinic, _) => tri(conv),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+/// Removes round-off from coordinates that are multiples of 1/24, which covers the
+/// fixed fractions of every table, so that e.g. ½ prints as `0.5`.
+fn snap(x: f64) -> f64 {
+    let r = (x * 24.0).round() / 24.0;
+    if (x - r).abs() < 1e-9 { r + 0.0 } else { x }
+}
+
+fn primitive(rows: Mat3, conv: Mat3) -> CResult<Mat3> {
+    math::reciprocal(math::mat_mul(rows, conv))
+}
+
+fn angle(u: Vec3, v: Vec3) -> f64 {
+    (math::dot(u, v) / (math::norm(u) * math::norm(v)))
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+fn lengths(conv: Mat3) -> [f64; 3] {
+    conv.map(math::norm)
+}
+
+/// Orders the axes of an orthorhombic cell by increasing length.
+fn sort_axes(conv: Mat3) -> Mat3 {
+    let mut rows = conv;
+    rows.sort_by(|u, v| math::norm(*u).total_cmp(&math::norm(*v)));
+    rows
+}
+
+fn cub(conv: Mat3) -> CResult<Table> {
+    Ok(Table {
+        variant: LatticeVariant::Cub,
+        reciprocal: math::reciprocal(conv)?,
+        points: vec![
+            G,
+            ("M", [0.5, 0.5, 0.0]),
+            ("R", [0.5, 0.5, 0.5]),
+            ("X", [0.0, 0.5, 0.0]),
+        ],
+        path: "Γ-X-M-Γ-R-X|M-R",
+    })
+}
+
+fn fcc(conv: Mat3) -> CResult<Table> {
+    Ok(Table {
+        variant: LatticeVariant::Fcc,
+        reciprocal: primitive(FACE, conv)?,
+        points: vec![
+            G,
+            ("K", [0.375, 0.375, 0.75]),
+            ("L", [0.5, 0.5, 0.5]),
+            ("U", [0.625, 0.25, 0.625]),
+            ("W", [0.5, 0.25, 0.75]),
+            ("X", [0.5, 0.0, 0.5]),
+        ],
+        path: "Γ-X-W-K-Γ-L-U-W-L-K|U-X",
+    })
+}
+
+fn bcc(conv: Mat3) -> CResult<Table> {
+    Ok(Table {
+        variant: LatticeVariant::Bcc,
+        reciprocal: primitive(BODY, conv)?,
+        points: vec![
+            G,
+            ("H", [0.5, -0.5, 0.5]),
+            ("P", [0.25, 0.25, 0.25]),
+            ("N", [0.0, 0.0, 0.5]),
+        ],
+        path: "Γ-H-N-Γ-P-H|P-N",
+    })
+}
+
+fn tet(conv: Mat3) -> CResult<Table> {
+    Ok(Table {
+        variant: LatticeVariant::Tet,
+        reciprocal: math::reciprocal(conv)?,
+        points: vec![
+            G,
+            ("A", [0.5, 0.5, 0.5]),
+            ("M", [0.5, 0.5, 0.0]),
+            ("R", [0.0, 0.5, 0.5]),
+            ("X", [0.0, 0.5, 0.0]),
+            ("Z", [0.0, 0.0, 0.5]),
+        ],
+        path: "Γ-X-M-Γ-Z-R-A-Z|X-R|M-A",
+    })
+}
+
+fn bct(conv: Mat3) -> CResult<Table> {
+    let [a, _, c] = lengths(conv);
+    let reciprocal = primitive(BODY, conv)?;
+    if c < a {
+        let eta = (1.0 + c * c / (a * a)) / 4.0;
+        Ok(Table {
+            variant: LatticeVariant::Bct1,
+            reciprocal,
+            points: vec![
+                G,
+                ("M", [-0.5, 0.5, 0.5]),
+                ("N", [0.0, 0.5, 0.0]),
+                ("P", [0.25, 0.25, 0.25]),
+                ("X", [0.0, 0.0, 0.5]),
+                ("Z", [eta, eta, -eta]),
+                ("Z1", [-eta, 1.0 - eta, eta]),
+            ],
+            path: "Γ-X-M-Γ-Z-P-N-Z1-M|X-P",
+        })
+    } else {
+        let eta = (1.0 + a * a / (c * c)) / 4.0;
+        let zeta = a * a / (2.0 * c * c);
+        Ok(Table {
+            variant: LatticeVariant::Bct2,
+            reciprocal,
+            points: vec![
+                G,
+                ("N", [0.0, 0.5, 0.0]),
+                ("P", [0.25, 0.25, 0.25]),
+                ("Σ", [-eta, eta, eta]),
+                ("Σ1", [eta, 1.0 - eta, -eta]),
+                ("X", [0.0, 0.0, 0.5]),
+                ("Y", [-zeta, zeta, 0.5]),
+                ("Y1", [0.5, 0.5, -zeta]),
+                ("Z", [0.5, 0.5, -0.5]),
+            ],
+            path: "Γ-X-Y-Σ-Γ-Z-Σ1-N-P-Y1-Z|X-P",
+        })
+    }
+}
+
+fn orc(conv: Mat3) -> CResult<Table> {
+    Ok(Table {
+        variant: LatticeVariant::Orc,
+        reciprocal: math::reciprocal(conv)?,
+        points: vec![
+            G,
+            ("R", [0.5, 0.5, 0.5]),
+            ("S", [0.5, 0.5, 0.0]),
+            ("T", [0.0, 0.5, 0.5]),
+            ("U", [0.5, 0.0, 0.5]),
+            ("X", [0.5, 0.0, 0.0]),
+            ("Y", [0.0, 0.5, 0.0]),
+            ("Z", [0.0, 0.0, 0.5]),
+        ],
+        path: "Γ-X-S-Y-Γ-Z-U-R-T-Z|Y-T|U-X|S-R",
+    })
+}
+
+fn orcf(conv: Mat3) -> CResult<Table> {
+    let [a, b, c] = lengths(conv);
+    let (a2, b2, c2) = (a * a, b * b, c * c);
+    let reciprocal = primitive(FACE, conv)?;
+    let lhs = 1.0 / a2;
+    let rhs = 1.0 / b2 + 1.0 / c2;
+    if (lhs - rhs).abs() > VARIANT_TOL * lhs && lhs < rhs {
+        let eta = (1.0 + a2 / b2 - a2 / c2) / 4.0;
+        let phi = (1.0 + c2 / b2 - c2 / a2) / 4.0;
+        let delta = (1.0 + b2 / a2 - b2 / c2) / 4.0;
+        return Ok(Table {
+            variant: LatticeVariant::Orcf2,
+            reciprocal,
+            points: vec![
+                G,
+                ("C", [0.5, 0.5 - eta, 1.0 - eta]),
+                ("C1", [0.5, 0.5 + eta, eta]),
+                ("D", [0.5 - delta, 0.5, 1.0 - delta]),
+                ("D1", [0.5 + delta, 0.5, delta]),
+                ("L", [0.5, 0.5, 0.5]),
+                ("H", [1.0 - phi, 0.5 - phi, 0.5]),
+                ("H1", [phi, 0.5 + phi, 0.5]),
+                ("X", [0.0, 0.5, 0.5]),
+                ("Y", [0.5, 0.0, 0.5]),
+                ("Z", [0.5, 0.5, 0.0]),
+            ],
+            path: "Γ-Y-C-D-X-Γ-Z-D1-H-C|C1-Z|X-H1|H-Y|L-Γ",
+        });
+    }
+    let zeta = (1.0 + a2 / b2 - a2 / c2) / 4.0;
+    let eta = (1.0 + a2 / b2 + a2 / c2) / 4.0;
+    let (variant, path) = if (lhs - rhs).abs() > VARIANT_TOL * lhs {
+        (LatticeVariant::Orcf1, "Γ-Y-T-Z-Γ-X-A1-Y|T-X1|X-A-Z|L-Γ")
+    } else {
+        (LatticeVariant::Orcf3, "Γ-Y-T-Z-Γ-X-A1-Y|X-A-Z|L-Γ")
+    };
+    Ok(Table {
+        variant,
+        reciprocal,
+        points: vec![
+            G,
+            ("A", [0.5, 0.5 + zeta, zeta]),
+            ("A1", [0.5, 0.5 - zeta, 1.0 - zeta]),
+            ("L", [0.5, 0.5, 0.5]),
+            ("T", [1.0, 0.5, 0.5]),
+            ("X", [0.0, eta, eta]),
+            ("X1", [1.0, 1.0 - eta, 1.0 - eta]),
+            ("Y", [0.5, 0.0, 0.5]),
+            ("Z", [0.5, 0.5, 0.0]),
+        ],
+        path,
+    })
+}
+
+fn orci(conv: Mat3) -> CResult<Table> {
+    let [a, b, c] = lengths(conv);
+    let (a2, b2, c2) = (a * a, b * b, c * c);
+    let zeta = (1.0 + a2 / c2) / 4.0;
+    let eta = (1.0 + b2 / c2) / 4.0;
+    let delta = (b2 - a2) / (4.0 * c2);
+    let mu = (a2 + b2) / (4.0 * c2);
+    Ok(Table {
+        variant: LatticeVariant::Orci,
+        reciprocal: primitive(BODY, conv)?,
+        points: vec![
+            G,
+            ("L", [-mu, mu, 0.5 - delta]),
+            ("L1", [mu, -mu, 0.5 + delta]),
+            ("L2", [0.5 - delta, 0.5 + delta, -mu]),
+            ("R", [0.0, 0.5, 0.0]),
+            ("S", [0.5, 0.0, 0.0]),
+            ("T", [0.0, 0.0, 0.5]),
+            ("W", [0.25, 0.25, 0.25]),
+            ("X", [-zeta, zeta, zeta]),
+            ("X1", [zeta, 1.0 - zeta, -zeta]),
+            ("Y", [eta, -eta, eta]),
+            ("Y1", [1.0 - eta, eta, -eta]),
+            ("Z", [0.5, 0.5, -0.5]),
+        ],
+        path: "Γ-X-L-T-W-R-X1-Z-Γ-Y-S-W|L1-Y|Y1-Z",
+    })
+}
+
+fn orcc(conv: Mat3) -> CResult<Table> {
+    let conv = if math::norm(conv[0]) > math::norm(conv[1]) {
+        [conv[1], conv[0], conv[2]]
+    } else {
+        conv
+    };
+    let [a, b, _] = lengths(conv);
+    let zeta = (1.0 + a * a / (b * b)) / 4.0;
+    Ok(Table {
+        variant: LatticeVariant::Orcc,
+        reciprocal: primitive(BASE_ORC, conv)?,
+        points: vec![
+            G,
+            ("A", [zeta, zeta, 0.5]),
+            ("A1", [-zeta, 1.0 - zeta, 0.5]),
+            ("R", [0.0, 0.5, 0.5]),
+            ("S", [0.0, 0.5, 0.0]),
+            ("T", [-0.5, 0.5, 0.5]),
+            ("X", [zeta, zeta, 0.0]),
+            ("X1", [-zeta, 1.0 - zeta, 0.0]),
+            ("Y", [-0.5, 0.5, 0.0]),
+            ("Z", [0.0, 0.0, 0.5]),
+        ],
+        path: "Γ-X-S-R-A-Z-Γ-Y-X1-A1-T-Y|Z-T",
+    })
+}
+
+fn hex(conv: Mat3) -> CResult<Table> {
+    Ok(Table {
+        variant: LatticeVariant::Hex,
+        reciprocal: math::reciprocal(conv)?,
+        points: vec![
+            G,
+            ("A", [0.0, 0.0, 0.5]),
+            ("H", [1.0 / 3.0, 1.0 / 3.0, 0.5]),
+            ("K", [1.0 / 3.0, 1.0 / 3.0, 0.0]),
+            ("L", [0.5, 0.0, 0.5]),
+            ("M", [0.5, 0.0, 0.0]),
+        ],
+        path: "Γ-M-K-Γ-A-L-H-A|L-M|K-H",
+    })
+}
+
+fn rhl(conv: Mat3) -> CResult<Table> {
+    let prim = math::mat_mul(RHOMBOHEDRAL, conv);
+    let alpha = angle(prim[1], prim[2]);
+    let reciprocal = math::reciprocal(prim)?;
+    if alpha < std::f64::consts::FRAC_PI_2 {
+        let eta = (1.0 + 4.0 * alpha.cos()) / (2.0 + 4.0 * alpha.cos());
+        let nu = 0.75 - eta / 2.0;
+        Ok(Table {
+            variant: LatticeVariant::Rhl1,
+            reciprocal,
+            points: vec![
+                G,
+                ("B", [eta, 0.5, 1.0 - eta]),
+                ("B1", [0.5, 1.0 - eta, eta - 1.0]),
+                ("F", [0.5, 0.5, 0.0]),
+                ("L", [0.5, 0.0, 0.0]),
+                ("L1", [0.0, 0.0, -0.5]),
+                ("P", [eta, nu, nu]),
+                ("P1", [1.0 - nu, 1.0 - nu, 1.0 - eta]),
+                ("P2", [nu, nu, eta - 1.0]),
+                ("Q", [1.0 - nu, nu, 0.0]),
+                ("X", [nu, 0.0, -nu]),
+                ("Z", [0.5, 0.5, 0.5]),
+            ],
+            path: "Γ-L-B1|B-Z-Γ-X|Q-F-P1-Z|L-P",
+        })
+    } else {
+        let eta = 1.0 / (2.0 * (alpha / 2.0).tan().powi(2));
+        let nu = 0.75 - eta / 2.0;
+        Ok(Table {
+            variant: LatticeVariant::Rhl2,
+            reciprocal,
+            points: vec![
+                G,
+                ("F", [0.5, -0.5, 0.0]),
+                ("L", [0.5, 0.0, 0.0]),
+                ("P", [1.0 - nu, -nu, 1.0 - nu]),
+                ("P1", [nu, nu - 1.0, nu - 1.0]),
+                ("Q", [eta, eta, eta]),
+                ("Q1", [1.0 - eta, -eta, -eta]),
+                ("Z", [0.5, -0.5, 0.5]),
+            ],
+            path: "Γ-P-Z-Q-Γ-F-P1-Q1-L-Z",
+        })
+    }
+}
+
+/// Makes the angle between the second and third axes acute by reversing the third.
+fn acute(conv: Mat3) -> Mat3 {
+    if math::dot(conv[1], conv[2]) < 0.0 {
+        [conv[0], conv[1], math::scale(conv[2], -1.0)]
+    } else {
+        conv
+    }
+}
+
+fn mcl(conv: Mat3) -> CResult<Table> {
+    // Setyawan–Curtarolo take the unique axis as a, with b ≤ c and α < 90°.
+    let (short, long) = if math::norm(conv[0]) <= math::norm(conv[2]) {
+        (conv[0], conv[2])
+    } else {
+        (conv[2], conv[0])
+    };
+    let conv = acute([conv[1], short, long]);
+    let [_, b, c] = lengths(conv);
+    let alpha = angle(conv[1], conv[2]);
+    let eta = (1.0 - b * alpha.cos() / c) / (2.0 * alpha.sin().powi(2));
+    let nu = 0.5 - eta * c * alpha.cos() / b;
+    Ok(Table {
+        variant: LatticeVariant::Mcl,
+        reciprocal: math::reciprocal(conv)?,
+        points: vec![
+            G,
+            ("A", [0.5, 0.5, 0.0]),
+            ("C", [0.0, 0.5, 0.5]),
+            ("D", [0.5, 0.0, 0.5]),
+            ("D1", [0.5, 0.0, -0.5]),
+            ("E", [0.5, 0.5, 0.5]),
+            ("H", [0.0, eta, 1.0 - nu]),
+            ("H1", [0.0, 1.0 - eta, nu]),
+            ("H2", [0.0, eta, -nu]),
+            ("M", [0.5, eta, 1.0 - nu]),
+            ("M1", [0.5, 1.0 - eta, nu]),
+            ("M2", [0.5, eta, -nu]),
+            ("X", [0.0, 0.5, 0.0]),
+            ("Y", [0.0, 0.0, 0.5]),
+            ("Y1", [0.0, 0.0, -0.5]),
+            ("Z", [0.5, 0.0, 0.0]),
+        ],
+        path: "Γ-Y-H-C-E-M1-A-X-H1|M-D-Z|Y-D",
+    })
+}
+
+fn mclc(conv: Mat3) -> CResult<Table> {
+    // The ITA C-centred cell has unique axis b in the centred face; Setyawan–Curtarolo
+    // take it as a, which keeps the centring on the ab face.
+    let conv = acute([conv[1], conv[0], conv[2]]);
+    let [a, b, c] = lengths(conv);
+    let alpha = angle(conv[1], conv[2]);
+    let (cos, sin2) = (alpha.cos(), alpha.sin().powi(2));
+    let reciprocal = primitive(BASE_MCL, conv)?;
+    let k_gamma = angle(reciprocal[0], reciprocal[1]);
+    let right = std::f64::consts::FRAC_PI_2;
+
+    if k_gamma > right - VARIANT_TOL {
+        let zeta = (2.0 - b * cos / c) / (4.0 * sin2);
+        let eta = 0.5 + 2.0 * zeta * c * cos / b;
+        let psi = 0.75 - a * a / (4.0 * b * b * sin2);
+        let phi = psi + (0.75 - psi) * b * cos / c;
+        let (variant, path) = if k_gamma > right + VARIANT_TOL {
+            (LatticeVariant::Mclc1, "Γ-Y-F-L-I|I1-Z-F1|Y-X1|X-Γ-N|M-Γ")
+        } else {
+            (LatticeVariant::Mclc2, "Γ-Y-F-L-I|I1-Z-F1|N-Γ-M")
+        };
+        return Ok(Table {
+            variant,
+            reciprocal,
+            points: vec![
+                G,
+                ("N", [0.5, 0.0, 0.0]),
+                ("N1", [0.0, -0.5, 0.0]),
+                ("F", [1.0 - zeta, 1.0 - zeta, 1.0 - eta]),
+                ("F1", [zeta, zeta, eta]),
+                ("F2", [-zeta, -zeta, 1.0 - eta]),
+                ("I", [phi, 1.0 - phi, 0.5]),
+                ("I1", [1.0 - phi, phi - 1.0, 0.5]),
+                ("L", [0.5, 0.5, 0.5]),
+                ("M", [0.5, 0.0, 0.5]),
+                ("X", [1.0 - psi, psi - 1.0, 0.0]),
+                ("X1", [psi, 1.0 - psi, 0.0]),
+                ("X2", [psi - 1.0, -psi, 0.0]),
+                ("Y", [0.5, 0.5, 0.0]),
+                ("Y1", [-0.5, -0.5, 0.0]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ],
+            path,
+        });
+    }
+
+    let test = b * cos / c + b * b * sin2 / (a * a);
+    if test < 1.0 + VARIANT_TOL {
+        let mu = (1.0 + b * b / (a * a)) / 4.0;
+        let delta = b * c * cos / (2.0 * a * a);
+        let zeta = mu - 0.25 + (1.0 - b * cos / c) / (4.0 * sin2);
+        let eta = 0.5 + 2.0 * zeta * c * cos / b;
+        let phi = 1.0 + zeta - 2.0 * mu;
+        let psi = eta - 2.0 * delta;
+        let (variant, path) = if test < 1.0 - VARIANT_TOL {
+            (LatticeVariant::Mclc3, "Γ-Y-F-H-Z-I-F1|H1-Y1-X-Γ-N|M-Γ")
+        } else {
+            (LatticeVariant::Mclc4, "Γ-Y-F-H-Z-I|H1-Y1-X-Γ-N|M-Γ")
+        };
+        return Ok(Table {
+            variant,
+            reciprocal,
+            points: vec![
+                G,
+                ("F", [1.0 - phi, 1.0 - phi, 1.0 - psi]),
+                ("F1", [phi, phi - 1.0, psi]),
+                ("F2", [1.0 - phi, -phi, 1.0 - psi]),
+                ("H", [zeta, zeta, eta]),
+                ("H1", [1.0 - zeta, -zeta, 1.0 - eta]),
+                ("H2", [-zeta, -zeta, 1.0 - eta]),
+                ("I", [0.5, -0.5, 0.5]),
+                ("M", [0.5, 0.0, 0.5]),
+                ("N", [0.5, 0.0, 0.0]),
+                ("N1", [0.0, -0.5, 0.0]),
+                ("X", [0.5, -0.5, 0.0]),
+                ("Y", [mu, mu, delta]),
+                ("Y1", [1.0 - mu, -mu, -delta]),
+                ("Y2", [-mu, -mu, -delta]),
+                ("Y3", [mu, mu - 1.0, delta]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ],
+            path,
+        });
+    }
+
+    let zeta = (b * b / (a * a) + (1.0 - b * cos / c) / sin2) / 4.0;
+    let eta = 0.5 + 2.0 * zeta * c * cos / b;
+    let mu = eta / 2.0 + b * b / (4.0 * a * a) - b * c * cos / (2.0 * a * a);
+    let nu = 2.0 * mu - zeta;
+    let omega = (4.0 * nu - 1.0 - b * b * sin2 / (a * a)) * c / (2.0 * b * cos);
+    let delta = zeta * c * cos / b + omega / 2.0 - 0.25;
+    let rho = 1.0 - zeta * a * a / (b * b);
+    Ok(Table {
+        variant: LatticeVariant::Mclc5,
+        reciprocal,
+        points: vec![
+            G,
+            ("F", [nu, nu, omega]),
+            ("F1", [1.0 - nu, 1.0 - nu, 1.0 - omega]),
+            ("F2", [nu, nu - 1.0, omega]),
+            ("H", [zeta, zeta, eta]),
+            ("H1", [1.0 - zeta, -zeta, 1.0 - eta]),
+            ("H2", [-zeta, -zeta, 1.0 - eta]),
+            ("I", [rho, 1.0 - rho, 0.5]),
+            ("I1", [1.0 - rho, rho - 1.0, 0.5]),
+            ("L", [0.5, 0.5, 0.5]),
+            ("M", [0.5, 0.0, 0.5]),
+            ("N", [0.5, 0.0, 0.0]),
+            ("N1", [0.0, -0.5, 0.0]),
+            ("X", [0.5, -0.5, 0.0]),
+            ("Y", [mu, mu, delta]),
+            ("Y1", [1.0 - mu, -mu, -delta]),
+            ("Y2", [-mu, -mu, -delta]),
+            ("Y3", [mu, mu - 1.0, delta]),
+            ("Z", [0.0, 0.0, 0.5]),
+        ],
+        path: "Γ-Y-F-L-I|I1-Z-H-F1|H1-Y1-X-Γ-N|M-Γ",
+    })
+}
+
+fn tri(conv: Mat3) -> CResult<Table> {
+    // The variants are defined on the Niggli-reduced reciprocal lattice, whose angles
+    // are either all acute or all non-acute.
+    let reciprocal = math::reciprocal(conv)?;
+    let eps = 1e-5 * math::det(reciprocal).abs().powf(2.0 / 3.0);
+    let reduced = math::change_basis(reciprocal, math::niggli_reduce(reciprocal, eps));
+    let angles = [(1, 2), (0, 2), (0, 1)].map(|(i, j)| angle(reduced[i], reduced[j]));
+    let right = std::f64::consts::FRAC_PI_2;
+    let obtuse = angles.iter().all(|&x| x > right - VARIANT_TOL);
+    // k_γ, between the first two vectors, is the smallest angle of an obtuse cell and
+    // the largest of an acute one.
+    let pick = (0..3)
+        .reduce(|i, j| {
+            if (angles[j] < angles[i]) == obtuse {
+                j
+            } else {
+                i
+            }
+        })
+        .unwrap_or(2);
+    let (first, second) = match pick {
+        0 => (1, 2),
+        1 => (2, 0),
+        _ => (0, 1),
+    };
+    let reciprocal = [reduced[first], reduced[second], reduced[pick]];
+    let k_gamma = angles[pick];
+    let right_gamma = (k_gamma - right).abs() < VARIANT_TOL;
+    let path = "X-Γ-Y|L-Γ-Z|N-Γ-M|R-Γ";
+    if obtuse {
+        Ok(Table {
+            variant: if right_gamma {
+                LatticeVariant::Tri2a
+            } else {
+                LatticeVariant::Tri1a
+            },
+            reciprocal,
+            points: vec![
+                G,
+                ("L", [0.5, 0.5, 0.0]),
+                ("M", [0.0, 0.5, 0.5]),
+                ("N", [0.5, 0.0, 0.5]),
+                ("R", [0.5, 0.5, 0.5]),
+                ("X", [0.5, 0.0, 0.0]),
+                ("Y", [0.0, 0.5, 0.0]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ],
+            path,
+        })
+    } else {
+        Ok(Table {
+            variant: if right_gamma {
+                LatticeVariant::Tri2b
+            } else {
+                LatticeVariant::Tri1b
+            },
+            reciprocal,
+            points: vec![
+                G,
+                ("L", [0.5, -0.5, 0.0]),
+                ("M", [0.0, 0.0, 0.5]),
+                ("N", [-0.5, -0.5, 0.5]),
+                ("R", [0.0, -0.5, 0.5]),
+                ("X", [0.0, -0.5, 0.0]),
+                ("Y", [0.5, 0.0, 0.0]),
+                ("Z", [-0.5, 0.0, 0.5]),
+            ],
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::lattice_param::LatticeCart;
+    use crate::test_fixtures::{abc_lattice, cell, cubic_cell, frac_atoms};
+
+    fn close(a: [f64; 3], b: [f64; 3]) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-8)
+    }
+
+    fn find<'a>(path: &'a KpointPath, label: &str) -> &'a PathPoint {
+        path.points().find(|p| p.label == label).unwrap()
+    }
+
+    /// The lattice spanned by `rows` of a conventional cell given as Cartesian rows.
+    fn centred(rows: Mat3, conv: Mat3) -> Lattice {
+        let [a, b, c] = math::mat_mul(rows, conv);
+        LatticeCart {
+            unit: None,
+            a,
+            b,
+            c,
+        }
+        .into()
+    }
+
+    fn orthorhombic(a: f64, b: f64, c: f64) -> Mat3 {
+        [[a, 0.0, 0.0], [0.0, b, 0.0], [0.0, 0.0, c]]
+    }
+
+    /// Setyawan–Curtarolo C-centred monoclinic cell with unique axis a and `α` in degrees.
+    fn mclc_lattice(a: f64, b: f64, c: f64, alpha: f64) -> Lattice {
+        let alpha = alpha.to_radians();
+        let conv = [
+            [a, 0.0, 0.0],
+            [0.0, b, 0.0],
+            [0.0, c * alpha.cos(), c * alpha.sin()],
+        ];
+        centred(BASE_MCL, conv)
+    }
+
+    /// Asserts that the point `label` of a path made for `lattice` is equivalent to
+    /// `expected`, given in the reciprocal basis of `lattice`, under the lattice
+    /// holohedry and reciprocal lattice translations.
+    ///
+    /// The symmetry finder may pick any of the equivalent standard settings, so the
+    /// path can hold any image of the tabulated point.
+    fn assert_point(path: &KpointPath, lattice: &Lattice, label: &str, expected: [f64; 3]) {
+        let doc = cell(lattice.clone(), frac_atoms(&[("H", [0.0; 3])]));
+        let k = find(path, label).coord;
+        let equivalent = doc.find_symmetry().unwrap().operations.iter().any(|op| {
+            let image = math::vec_mat(k, math::to_f64(op.rotation));
+            image
+                .iter()
+                .zip(expected)
+                .all(|(x, y)| ((x - y) - (x - y).round()).abs() < 1e-8)
+        });
+        assert!(
+            equivalent,
+            "{label} = {k:?} is not equivalent to {expected:?}"
+        );
+    }
+
+    #[test]
+    fn cubic_lattices() {
+        let path = abc_lattice([4.0; 3], [90.0; 3])
+            .high_symmetry_path()
+            .unwrap();
+        assert_eq!(path.variant, LatticeVariant::Cub);
+        assert_eq!(path.segments.len(), 2);
+        let labels: Vec<&str> = path.segments[0].iter().map(|p| p.label).collect();
+        assert_eq!(labels, ["Γ", "X", "M", "Γ", "R", "X"]);
+
+        // The FCC primitive cell as CASTEP users usually write it.
+        let fcc: Lattice = LatticeCart {
+            unit: None,
+            a: [0.0, 2.0, 2.0],
+            b: [2.0, 0.0, 2.0],
+            c: [2.0, 2.0, 0.0],
+        }
+        .into();
+        let path = fcc.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Fcc);
+        let x = find(&path, "X").coord;
+        // X lies at half a reciprocal conventional vector, 1/(2·4 Å) from Γ.
+        let cart = math::vec_mat(x, fcc.reciprocal_vectors().unwrap());
+        assert!((math::norm(cart) - 0.25).abs() < 1e-10);
+    }
+
+    #[test]
+    fn conventional_fcc_cell_keeps_cartesian_points() {
+        let doc = cubic_cell(
+            4.0,
+            &[
+                ("Cu", [0.0, 0.0, 0.0]),
+                ("Cu", [0.0, 0.5, 0.5]),
+                ("Cu", [0.5, 0.0, 0.5]),
+                ("Cu", [0.5, 0.5, 0.0]),
+            ],
+        );
+        let path = doc.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Fcc);
+        // L = (½,½,½) of the primitive reciprocal basis is (½,½,½) of the cube's.
+        assert!(close(find(&path, "L").coord.map(f64::abs), [0.5, 0.5, 0.5]));
+    }
+
+    #[test]
+    fn tetragonal_and_orthorhombic_variants() {
+        let body_centred: Lattice = LatticeCart {
+            unit: None,
+            a: [-2.0, 2.0, 3.0],
+            b: [2.0, -2.0, 3.0],
+            c: [2.0, 2.0, -3.0],
+        }
+        .into();
+        // Conventional a = 4 Å and c = 6 Å.
+        assert_eq!(
+            body_centred.high_symmetry_path().unwrap().variant,
+            LatticeVariant::Bct2
+        );
+
+        let orc = abc_lattice([5.0, 3.0, 4.0], [90.0; 3])
+            .high_symmetry_path()
+            .unwrap();
+        assert_eq!(orc.variant, LatticeVariant::Orc);
+        // X sits on the shortest axis, here the input b.
+        assert!(close(find(&orc, "X").coord.map(f64::abs), [0.0, 0.5, 0.0]));
+    }
+
+    #[test]
+    fn tet_and_bct1_points() {
+        let tet = abc_lattice([3.0, 3.0, 5.0], [90.0; 3]);
+        let path = tet.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Tet);
+        assert_point(&path, &tet, "A", [0.5, 0.5, 0.5]);
+        assert_point(&path, &tet, "R", [0.0, 0.5, 0.5]);
+        assert_point(&path, &tet, "Z", [0.0, 0.0, 0.5]);
+
+        // Conventional a = 4 Å and c = 3 Å.
+        let bct = centred(BODY, orthorhombic(4.0, 4.0, 3.0));
+        let path = bct.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Bct1);
+        let eta = (1.0 + 9.0 / 16.0) / 4.0;
+        assert_point(&path, &bct, "M", [-0.5, 0.5, 0.5]);
+        assert_point(&path, &bct, "Z", [eta, eta, -eta]);
+        assert_point(&path, &bct, "Z1", [-eta, 1.0 - eta, eta]);
+    }
+
+    #[test]
+    fn face_centred_orthorhombic_variants() {
+        // 1/a² > 1/b² + 1/c²
+        let orcf1 = centred(FACE, orthorhombic(3.0, 5.0, 6.0));
+        let path = orcf1.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Orcf1);
+        let zeta = (1.0 + 9.0 / 25.0 - 9.0 / 36.0) / 4.0;
+        let eta = (1.0 + 9.0 / 25.0 + 9.0 / 36.0) / 4.0;
+        assert_point(&path, &orcf1, "A", [0.5, 0.5 + zeta, zeta]);
+        assert_point(&path, &orcf1, "X", [0.0, eta, eta]);
+        assert_point(&path, &orcf1, "X1", [1.0, 1.0 - eta, 1.0 - eta]);
+
+        // 1/a² < 1/b² + 1/c²
+        let orcf2 = centred(FACE, orthorhombic(4.0, 5.0, 6.0));
+        let path = orcf2.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Orcf2);
+        let eta = (1.0 + 16.0 / 25.0 - 16.0 / 36.0) / 4.0;
+        let phi = (1.0 + 36.0 / 25.0 - 36.0 / 16.0) / 4.0;
+        let delta = (1.0 + 25.0 / 16.0 - 25.0 / 36.0) / 4.0;
+        assert_point(&path, &orcf2, "C", [0.5, 0.5 - eta, 1.0 - eta]);
+        assert_point(&path, &orcf2, "D", [0.5 - delta, 0.5, 1.0 - delta]);
+        assert_point(&path, &orcf2, "H", [1.0 - phi, 0.5 - phi, 0.5]);
+
+        // 1/a² = 1/b² + 1/c² with b = 4 Å and c = 6 Å.
+        let a2: f64 = 576.0 / 52.0;
+        let orcf3 = centred(FACE, orthorhombic(a2.sqrt(), 4.0, 6.0));
+        let path = orcf3.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Orcf3);
+        let zeta = (1.0 + a2 / 16.0 - a2 / 36.0) / 4.0;
+        let eta = (1.0 + a2 / 16.0 + a2 / 36.0) / 4.0;
+        assert_point(&path, &orcf3, "A1", [0.5, 0.5 - zeta, 1.0 - zeta]);
+        assert_point(&path, &orcf3, "X", [0.0, eta, eta]);
+        assert!(path.points().all(|p| p.label != "X1"));
+    }
+
+    #[test]
+    fn body_and_base_centred_orthorhombic_points() {
+        let orci = centred(BODY, orthorhombic(3.0, 4.0, 5.0));
+        let path = orci.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Orci);
+        let (zeta, eta, delta, mu) = (0.34, 0.41, 0.07, 0.25);
+        assert_point(&path, &orci, "L", [-mu, mu, 0.5 - delta]);
+        assert_point(&path, &orci, "X", [-zeta, zeta, zeta]);
+        assert_point(&path, &orci, "Y", [eta, -eta, eta]);
+
+        let orcc = centred(BASE_ORC, orthorhombic(3.0, 5.0, 4.0));
+        let path = orcc.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Orcc);
+        let zeta = 0.34;
+        assert_point(&path, &orcc, "A", [zeta, zeta, 0.5]);
+        assert_point(&path, &orcc, "X1", [-zeta, 1.0 - zeta, 0.0]);
+        assert_point(&path, &orcc, "T", [-0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn base_centred_monoclinic_variants() {
+        let (b, c) = (5.0, 6.0);
+        let alpha = 70.0_f64;
+        let (cos, sin) = (alpha.to_radians().cos(), alpha.to_radians().sin());
+
+        // k_γ > 90° for a < b sin α, and k_γ = 90° for a = b sin α.
+        for (a, variant) in [
+            (3.0, LatticeVariant::Mclc1),
+            (b * sin, LatticeVariant::Mclc2),
+        ] {
+            let lattice = mclc_lattice(a, b, c, alpha);
+            let path = lattice.high_symmetry_path().unwrap();
+            assert_eq!(path.variant, variant);
+            let zeta = (2.0 - b * cos / c) / (4.0 * sin * sin);
+            let eta = 0.5 + 2.0 * zeta * c * cos / b;
+            let psi = 0.75 - a * a / (4.0 * b * b * sin * sin);
+            let phi = psi + (0.75 - psi) * b * cos / c;
+            assert_point(&path, &lattice, "F1", [zeta, zeta, eta]);
+            assert_point(&path, &lattice, "I", [phi, 1.0 - phi, 0.5]);
+            assert_point(&path, &lattice, "M", [0.5, 0.0, 0.5]);
+        }
+
+        // k_γ < 90° with b cos α / c + b² sin² α / a² below and equal to 1.
+        let a4 = b * sin / (1.0 - b * cos / c).sqrt();
+        for (a, variant) in [(6.5, LatticeVariant::Mclc3), (a4, LatticeVariant::Mclc4)] {
+            let lattice = mclc_lattice(a, b, c, alpha);
+            let path = lattice.high_symmetry_path().unwrap();
+            assert_eq!(path.variant, variant);
+            let mu = (1.0 + b * b / (a * a)) / 4.0;
+            let delta = b * c * cos / (2.0 * a * a);
+            let zeta = mu - 0.25 + (1.0 - b * cos / c) / (4.0 * sin * sin);
+            let eta = 0.5 + 2.0 * zeta * c * cos / b;
+            let phi = 1.0 + zeta - 2.0 * mu;
+            let psi = eta - 2.0 * delta;
+            assert_point(&path, &lattice, "Y", [mu, mu, delta]);
+            assert_point(&path, &lattice, "H", [zeta, zeta, eta]);
+            assert_point(&path, &lattice, "F", [1.0 - phi, 1.0 - phi, 1.0 - psi]);
+        }
+
+        // k_γ < 90° with b cos α / c + b² sin² α / a² above 1.
+        let a = 5.0;
+        let lattice = mclc_lattice(a, b, c, alpha);
+        let path = lattice.high_symmetry_path().unwrap();
+        assert_eq!(path.variant, LatticeVariant::Mclc5);
+        let zeta = (b * b / (a * a) + (1.0 - b * cos / c) / (sin * sin)) / 4.0;
+        let eta = 0.5 + 2.0 * zeta * c * cos / b;
+        let mu = eta / 2.0 + b * b / (4.0 * a * a) - b * c * cos / (2.0 * a * a);
+        let nu = 2.0 * mu - zeta;
+        let omega = (4.0 * nu - 1.0 - b * b * sin * sin / (a * a)) * c / (2.0 * b * cos);
+        let delta = zeta * c * cos / b + omega / 2.0 - 0.25;
+        let rho = 1.0 - zeta * a * a / (b * b);
+        assert_point(&path, &lattice, "F", [nu, nu, omega]);
+        assert_point(&path, &lattice, "Y", [mu, mu, delta]);
+        assert_point(&path, &lattice, "I", [rho, 1.0 - rho, 0.5]);
+    }
+
+    #[test]
+    fn hexagonal_and_rhombohedral_lattices() {
+        let hex = abc_lattice([3.0, 3.0, 5.0], [90.0, 90.0, 120.0])
+            .high_symmetry_path()
+            .unwrap();
+        assert_eq!(hex.variant, LatticeVariant::Hex);
+        let k = find(&hex, "K").coord;
+        let m = find(&hex, "M").coord;
+        let b = abc_lattice([3.0, 3.0, 5.0], [90.0, 90.0, 120.0])
+            .reciprocal_vectors()
+            .unwrap();
+        let ratio = math::norm(math::vec_mat(k, b)) / math::norm(math::vec_mat(m, b));
+        assert!((ratio - 2.0 / 3.0_f64.sqrt()).abs() < 1e-10);
+
+        let rhl1 = abc_lattice([4.0; 3], [60.5; 3])
+            .high_symmetry_path()
+            .unwrap();
+        assert_eq!(rhl1.variant, LatticeVariant::Rhl1);
+        let rhl2 = abc_lattice([4.0; 3], [100.0; 3])
+            .high_symmetry_path()
+            .unwrap();
+        assert_eq!(rhl2.variant, LatticeVariant::Rhl2);
+    }
+
+    #[test]
+    fn low_symmetry_lattices() {
+        let mcl = abc_lattice([4.0, 5.0, 6.0], [90.0, 100.0, 90.0])
+            .high_symmetry_path()
+            .unwrap();
+        assert_eq!(mcl.variant, LatticeVariant::Mcl);
+        let tri = abc_lattice([4.0, 5.0, 6.0], [80.0, 85.0, 70.0])
+            .high_symmetry_path()
+            .unwrap();
+        assert!(matches!(
+            tri.variant,
+            LatticeVariant::Tri1a | LatticeVariant::Tri1b
+        ));
+        assert_eq!(tri.segments.len(), 4);
+    }
+
+    #[test]
+    fn path_blocks_list_every_point() {
+        let path = abc_lattice([4.0; 3], [90.0; 3])
+            .high_symmetry_path()
+            .unwrap();
+        let block = path.to_bs_kpoint_path();
+        assert_eq!(block.points.len(), 8);
+        // X is half a reciprocal vector along one of the cube axes.
+        let x = block.points[1].coord.map(f64::abs);
+        assert_eq!(x.iter().sum::<f64>(), 0.5);
+        assert_eq!(x.iter().filter(|&&c| c == 0.0).count(), 2);
+        assert_eq!(block.points[1].label.as_deref(), Some("X"));
+        // Γ-X-M-Γ-R-X|M-R
+        assert_eq!(block.breaks, vec![6]);
+        let fine = path.to_phonon_fine_kpoint_path();
+        assert_eq!(fine.points.len(), 8);
+        assert_eq!(fine.segments().map(<[_]>::len).collect::<Vec<_>>(), [6, 2]);
+    }
+}
@@ -18,7 +18,25 @@ pub mod cell;
 pub mod param;
 pub mod units;
 mod cell_document;
+pub mod composition;
+pub mod dynamics;
+mod edit;
+pub mod geometry;
+pub mod interpolation;
+mod ion_index;
+pub mod kpath;
+pub mod magnetism;
+mod math;
 mod param_document;
+pub mod periodic_table;
+pub mod strain;
+pub mod surface;
+#[cfg(test)]
+mod test_fixtures;
+mod transform;
 
-pub use cell_document::{CellDocument, CellDocumentBuilder, Lattice, Positions};
+pub use cell_document::{
+    CellDocument, CellDocumentBuilder, Lattice, Positions, PositionsIntermediate, PositionsProduct,
+};
+pub use ion_index::{DanglingIon, IonIndex};
 pub use param_document::{ParamDocument, ParamDocumentBuilder};
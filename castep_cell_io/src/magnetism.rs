@@ -0,0 +1,449 @@
+//! Collinear magnetic orderings for ground-state searches.
+//!
+//! An ordering gives every atom of the magnetic species a `SPIN` of `+m` or `-m`, with
+//! `m` the moment of its species. Reversing every spin gives the same state, so each
+//! arrangement is listed once together with its reverse. With symmetry reduction on,
+//! arrangements related by an operation of the non-magnetic structure are listed once,
+//! as are arrangements that repeat with a period already covered by a smaller supercell.
+
+use std::collections::HashSet;
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::positions::Spin;
+use crate::cell::species::Species;
+use crate::math::{self, IMat3, Vec3};
+use crate::param::exchange_correlation::SpinTreatment;
+use crate::{CellDocument, ParamDocument, Positions};
+
+/// Largest number of magnetic sites enumerated in one supercell.
+const MAX_SITES: usize = 20;
+
+/// Total spins closer to zero than this count as compensated.
+const SPIN_TOL: f64 = 1e-6;
+
+/// Kind of a collinear ordering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MagneticOrder {
+    /// Every moment points the same way.
+    Ferromagnetic,
+    /// Opposite moments cancel exactly.
+    Antiferromagnetic,
+    /// Opposite moments leave a net spin.
+    Ferrimagnetic,
+}
+
+/// One collinear spin arrangement.
+#[derive(Debug, Clone)]
+pub struct MagneticOrdering {
+    /// Supercell matrix the arrangement lives in, as passed to
+    /// [`CellDocument::transform`].
+    pub supercell: [[i32; 3]; 3],
+    /// The cell with `SPIN` set on every magnetic atom and cleared on the others.
+    pub cell: CellDocument,
+    /// Kind of the ordering.
+    pub order: MagneticOrder,
+    /// Sum of the atomic spins, the value `SPIN` in the param file should take.
+    pub total_spin: f64,
+    /// Number of arrangements in the supercell this one stands for, counting the
+    /// reversed ones.
+    pub multiplicity: usize,
+}
+
+/// Settings for enumerating collinear magnetic orderings.
+///
+/// # Example
+///
+/// ```no_run
+/// use castep_cell_io::CellDocument;
+/// use castep_cell_io::cell::species::Species;
+/// use castep_cell_io::magnetism::MagneticOrderings;
+///
+/// # fn run(doc: &CellDocument) -> castep_cell_fmt::CResult<()> {
+/// let orderings = MagneticOrderings::builder()
+///     .moments(vec![(Species::Symbol("Ni".into()), 2.0)])
+///     .supercells(vec![
+///         [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+///         [[2, 0, 0], [0, 1, 0], [0, 0, 1]],
+///     ])
+///     .build()
+///     .enumerate(doc)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, bon::Builder)]
+pub struct MagneticOrderings {
+    /// Magnetic species and the size of their moment, as a `SPIN` value (Nup − Ndown).
+    pub moments: Vec<(Species, f64)>,
+    /// Supercells to enumerate in, as matrices whose columns are the supercell vectors
+    /// in fractional coordinates of the cell (default: the cell itself).
+    #[builder(default = vec![math::IIDENTITY])]
+    pub supercells: Vec<[[i32; 3]; 3]>,
+    /// Lists arrangements related by a symmetry operation of the non-magnetic
+    /// structure, or found in a smaller listed supercell, only once.
+    #[builder(default = true)]
+    pub unique: bool,
+}
+
+impl MagneticOrderings {
+    /// Returns the orderings of `doc`, supercell by supercell, ferromagnetic first.
+    ///
+    /// Symmetry comes from [`find_symmetry`](CellDocument::find_symmetry) on the
+    /// structure without spins, using `SYMMETRY_TOL`. Fails if a moment is not
+    /// positive, a species does not occur, a supercell matrix is singular or a
+    /// supercell holds more than 20 magnetic atoms.
+    pub fn enumerate(&self, doc: &CellDocument) -> CResult<Vec<MagneticOrdering>> {
+        if let Some((species, m)) = self
+            .moments
+            .iter()
+            .find(|(_, m)| !m.is_finite() || *m <= 0.0)
+        {
+            return Err(Error::Message(format!(
+                "moment of {species} must be positive, got {m}"
+            )));
+        }
+        let index = doc.ion_index();
+        if let Some((missing, _)) = self.moments.iter().find(|(s, _)| index.count(s) == 0) {
+            return Err(Error::Message(format!(
+                "species {missing} does not occur in the cell"
+            )));
+        }
+        let mut parent = doc.clone();
+        parent.symmetry_ops = None;
+        set_spins(&mut parent.positions, &vec![None; doc.positions.len()]);
+        let rotations: Vec<IMat3> = if self.unique {
+            let mut rotations: Vec<IMat3> = Vec::new();
+            for op in parent.find_symmetry()?.operations {
+                if !rotations.contains(&op.rotation) {
+                    rotations.push(op.rotation);
+                }
+            }
+            rotations
+        } else {
+            Vec::new()
+        };
+        let tolerance = doc.symmetry_tol.unwrap_or_default();
+        let tol = tolerance.value * tolerance.unit.in_angstrom();
+
+        let mut orderings = Vec::new();
+        for &matrix in &self.supercells {
+            let cell = if matrix == math::IIDENTITY {
+                parent.clone()
+            } else {
+                parent.transform(matrix)?
+            };
+            let sites = Sites::new(&cell, &self.moments, tol)?;
+            let n = sites.atoms.len();
+
+            // Site permutations of the symmetry operations, and for every smaller
+            // supercell the translations spanning each of its orientations.
+            let mut operations = Vec::new();
+            let mut periods: Vec<[Vec<usize>; 3]> = Vec::new();
+            if self.unique {
+                for op in cell.find_symmetry()?.operations {
+                    operations.push(sites.permutation(|x| op.apply(x))?);
+                }
+                let size = math::imat_det(matrix).abs();
+                let m_inv = math::inverse(math::to_f64(matrix))
+                    .ok_or_else(|| Error::Message("supercell matrix is singular".into()))?;
+                for smaller in self
+                    .supercells
+                    .iter()
+                    .filter(|m| math::imat_det(**m).abs() < size)
+                {
+                    for r in &rotations {
+                        let spans = math::imat_mul(*r, *smaller);
+                        let shift = |j: usize| {
+                            let v = [spans[0][j], spans[1][j], spans[2][j]].map(f64::from);
+                            math::mat_vec(m_inv, v)
+                        };
+                        let [s0, s1, s2] = [0, 1, 2].map(shift);
+                        periods.push([
+                            sites.permutation(|x| math::add(x, s0))?,
+                            sites.permutation(|x| math::add(x, s1))?,
+                            sites.permutation(|x| math::add(x, s2))?,
+                        ]);
+                    }
+                }
+            }
+
+            // Bit `i` set means site `i` points down.
+            let all: u32 = (1 << n) - 1;
+            for config in 0..=all {
+                let mut orbit: HashSet<u32> = HashSet::from([config, config ^ all]);
+                for p in &operations {
+                    let image = permute(config, p);
+                    orbit.insert(image);
+                    orbit.insert(image ^ all);
+                }
+                if orbit.iter().any(|&c| c < config) {
+                    continue;
+                }
+                let repeats = |t: &[Vec<usize>; 3]| t.iter().all(|p| permute(config, p) == config);
+                if periods.iter().any(repeats) {
+                    continue;
+                }
+                orderings.push(sites.ordering(&cell, matrix, config, orbit.len()));
+            }
+        }
+        Ok(orderings)
+    }
+}
+
+/// Magnetic atoms of a cell.
+struct Sites {
+    /// Atom index and moment of every magnetic site.
+    atoms: Vec<(usize, f64)>,
+    coords: Vec<Vec3>,
+    vectors: [[f64; 3]; 3],
+    tol: f64,
+}
+
+impl Sites {
+    fn new(cell: &CellDocument, moments: &[(Species, f64)], tol: f64) -> CResult<Self> {
+        let atoms: Vec<(usize, f64)> = cell
+            .positions
+            .species()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| {
+                moments
+                    .iter()
+                    .find(|(m, _)| m.same_species(s))
+                    .map(|(_, m)| (i, *m))
+            })
+            .collect();
+        if atoms.len() > MAX_SITES {
+            return Err(Error::Message(format!(
+                "{} magnetic atoms are too many to enumerate, at most {MAX_SITES} are allowed",
+                atoms.len()
+            )));
+        }
+        let coords = cell.positions.frac_coords(&cell.lattice)?;
+        Ok(Self {
+            coords: atoms.iter().map(|(i, _)| coords[*i]).collect(),
+            atoms,
+            vectors: cell.lattice.vectors(),
+            tol,
+        })
+    }
+
+    /// Returns where every site goes under `map`, given in fractional coordinates.
+    fn permutation(&self, map: impl Fn(Vec3) -> Vec3) -> CResult<Vec<usize>> {
+        self.coords
+            .iter()
+            .map(|x| {
+                let y = map(*x);
+                self.coords
+                    .iter()
+                    .position(|z| {
+                        math::periodic_distance(self.vectors, math::sub(y, *z)) < self.tol
+                    })
+                    .ok_or_else(|| {
+                        Error::Message("symmetry operation does not map the magnetic sites".into())
+                    })
+            })
+            .collect()
+    }
+
+    fn ordering(
+        &self,
+        cell: &CellDocument,
+        supercell: IMat3,
+        config: u32,
+        multiplicity: usize,
+    ) -> MagneticOrdering {
+        let mut spins = vec![None; cell.positions.len()];
+        let mut total_spin = 0.0;
+        for (k, (i, m)) in self.atoms.iter().enumerate() {
+            let s = if config & (1 << k) == 0 { *m } else { -m };
+            spins[*i] = Some(Spin::Scalar(s));
+            total_spin += s;
+        }
+        let order = if config == 0 {
+            MagneticOrder::Ferromagnetic
+        } else if total_spin.abs() < SPIN_TOL {
+            MagneticOrder::Antiferromagnetic
+        } else {
+            MagneticOrder::Ferrimagnetic
+        };
+        let mut cell = cell.clone();
+        set_spins(&mut cell.positions, &spins);
+        MagneticOrdering {
+            supercell,
+            cell,
+            order,
+            total_spin,
+            multiplicity,
+        }
+    }
+}
+
+/// Moves the spin of site `i` to site `p[i]`.
+fn permute(config: u32, p: &[usize]) -> u32 {
+    p.iter()
+        .enumerate()
+        .filter(|(i, _)| config & (1 << i) != 0)
+        .fold(0, |image, (_, &j)| image | (1 << j))
+}
+
+fn set_spins(positions: &mut Positions, spins: &[Option<Spin>]) {
+    match positions {
+        Positions::Frac(frac) => frac
+            .positions
+            .iter_mut()
+            .zip(spins)
+            .for_each(|(p, s)| p.spin = *s),
+        Positions::Abs(abs) => abs
+            .positions
+            .iter_mut()
+            .zip(spins)
+            .for_each(|(p, s)| p.spin = *s),
+    }
+}
+
+impl CellDocument {
+    /// Checks the `SPIN` qualifiers of the positions against the spin settings of
+    /// `param`.
+    ///
+    /// Atomic spins need a spin-polarised calculation (`SPIN_POLARIZED : TRUE` or a
+    /// `SPIN_TREATMENT` other than `NONE`), and vector spins need
+    /// `SPIN_TREATMENT : VECTOR`. When `param` sets `SPIN`, collinear `SPIN` qualifiers,
+    /// weighted by their `MIXTURE` fractions, must add up to it; `MAGMOM` polarisations
+    /// are not summed.
+    pub fn check_spin(&self, param: &ParamDocument) -> CResult<()> {
+        let spins = self.positions.spins();
+        if spins.iter().all(Option::is_none) {
+            return Ok(());
+        }
+        let xc = &param.exchange_correlation;
+        let treatment = xc
+            .spin_treatment
+            .unwrap_or(if xc.spin_polarized.is_some_and(|p| p.0) {
+                SpinTreatment::Scalar
+            } else {
+                SpinTreatment::None
+            });
+        let vector = spins.iter().flatten().any(Spin::is_vector);
+        match treatment {
+            SpinTreatment::None => {
+                return Err(Error::Message(
+                    "atomic SPIN needs SPIN_POLARIZED : TRUE or a SPIN_TREATMENT".into(),
+                ));
+            }
+            SpinTreatment::Scalar if vector => {
+                return Err(Error::Message(
+                    "vector SPIN qualifiers need SPIN_TREATMENT : VECTOR".into(),
+                ));
+            }
+            _ => {}
+        }
+        let magmom = spins
+            .iter()
+            .flatten()
+            .any(|s| matches!(s, Spin::Magmom(_) | Spin::MagmomVector(_)));
+        if let Some(total) = param.electronic.spin
+            && !vector
+            && !magmom
+        {
+            let sum: f64 = spins
+                .iter()
+                .zip(self.positions.mixtures())
+                .filter_map(|(s, mixture)| {
+                    let weight = mixture.map_or(1.0, |(_, w)| w);
+                    s.and_then(|s| s.scalar()).map(|s| s * weight)
+                })
+                .sum();
+            if (sum - total.0).abs() > SPIN_TOL {
+                return Err(Error::Message(format!(
+                    "atomic SPIN values add up to {sum} but SPIN is {}",
+                    total.0
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::param::electronic::Spin as TotalSpin;
+    use crate::param::exchange_correlation::SpinPolarized;
+    use crate::test_fixtures::{cell, cubic_lattice, frac_atoms};
+
+    fn iron() -> CellDocument {
+        let mut positions = frac_atoms(&[("Fe", [0.0; 3])]);
+        positions.positions[0].spin = Some(1.0.into());
+        cell(cubic_lattice(2.9), positions)
+    }
+
+    fn diagonal(a: i32, b: i32, c: i32) -> IMat3 {
+        [[a, 0, 0], [0, b, 0], [0, 0, c]]
+    }
+
+    #[test]
+    fn square_supercell_has_four_orderings() {
+        let orderings = MagneticOrderings::builder()
+            .moments(vec![(Species::Symbol("Fe".into()), 2.0)])
+            .supercells(vec![math::IIDENTITY, diagonal(2, 2, 1)])
+            .build()
+            .enumerate(&iron())
+            .unwrap();
+        let orders: Vec<_> = orderings
+            .iter()
+            .map(|o| (o.order, o.multiplicity))
+            .collect();
+        // FM in the cell; in the supercell one 3:1 ordering, stripes and a checkerboard.
+        assert_eq!(
+            orders,
+            [
+                (MagneticOrder::Ferromagnetic, 2),
+                (MagneticOrder::Ferrimagnetic, 8),
+                (MagneticOrder::Antiferromagnetic, 4),
+                (MagneticOrder::Antiferromagnetic, 2),
+            ]
+        );
+        assert!((orderings[1].total_spin - 4.0).abs() < 1e-12);
+        assert_eq!(orderings[1].cell.positions.len(), 4);
+        assert_eq!(
+            orderings[0].cell.positions.spins(),
+            vec![Some(Spin::Scalar(2.0))]
+        );
+    }
+
+    #[test]
+    fn without_symmetry_every_arrangement_is_listed() {
+        let orderings = MagneticOrderings::builder()
+            .moments(vec![(Species::Symbol("Fe".into()), 2.0)])
+            .supercells(vec![diagonal(2, 2, 1)])
+            .unique(false)
+            .build()
+            .enumerate(&iron())
+            .unwrap();
+        assert_eq!(orderings.len(), 8);
+        assert!(
+            MagneticOrderings::builder()
+                .moments(vec![(Species::Symbol("Co".into()), 2.0)])
+                .build()
+                .enumerate(&iron())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn spins_must_match_the_param() {
+        let mut doc = iron().transform(diagonal(2, 1, 1)).unwrap();
+        if let Positions::Frac(frac) = &mut doc.positions {
+            frac.positions[1].spin = Some(Spin::Scalar(-1.0));
+        }
+        let mut param = ParamDocument::default();
+        assert!(doc.check_spin(&param).is_err());
+        param.exchange_correlation.spin_polarized = Some(SpinPolarized(true));
+        assert!(doc.check_spin(&param).is_ok());
+        param.electronic.spin = Some(TotalSpin(0.0));
+        assert!(doc.check_spin(&param).is_ok());
+        param.electronic.spin = Some(TotalSpin(2.0));
+        assert!(doc.check_spin(&param).is_err());
+    }
+}
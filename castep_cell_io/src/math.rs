@@ -0,0 +1,491 @@
+//! Small fixed-size linear algebra helpers shared by the structure analysis code.
+//!
+//! Lattices are stored as `[[f64; 3]; 3]` with the lattice vectors `a`, `b`, `c`
+//! as rows, matching `LATTICE_CART`. Fractional coordinates are column vectors, so
+//! the Cartesian position of `f` is `f · A` ([`frac_to_cart`]). Basis changes are
+//! integer matrices whose *columns* are the new basis vectors expressed in the old
+//! fractional coordinates, so the new lattice rows are `Pᵀ · A`.
+
+use castep_cell_fmt::{CResult, Error};
+
+pub(crate) type Vec3 = [f64; 3];
+pub(crate) type Mat3 = [[f64; 3]; 3];
+pub(crate) type IMat3 = [[i32; 3]; 3];
+
+pub(crate) const IDENTITY: Mat3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+pub(crate) const IIDENTITY: IMat3 = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+
+pub(crate) fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub(crate) fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub(crate) fn scale(a: Vec3, s: f64) -> Vec3 {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub(crate) fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub(crate) fn norm(a: Vec3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+pub(crate) fn transpose(m: Mat3) -> Mat3 {
+    let mut t = [[0.0; 3]; 3];
+    for (i, row) in m.iter().enumerate() {
+        for (j, v) in row.iter().enumerate() {
+            t[j][i] = *v;
+        }
+    }
+    t
+}
+
+pub(crate) fn mat_mul(a: Mat3, b: Mat3) -> Mat3 {
+    let mut c = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            c[i][j] = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    c
+}
+
+/// `M · v` with `v` a column vector.
+pub(crate) fn mat_vec(m: Mat3, v: Vec3) -> Vec3 {
+    [dot(m[0], v), dot(m[1], v), dot(m[2], v)]
+}
+
+/// `vᵀ · M` with `v` a row vector.
+pub(crate) fn vec_mat(v: Vec3, m: Mat3) -> Vec3 {
+    mat_vec(transpose(m), v)
+}
+
+pub(crate) fn det(m: Mat3) -> f64 {
+    dot(m[0], cross(m[1], m[2]))
+}
+
+pub(crate) fn inverse(m: Mat3) -> Option<Mat3> {
+    let d = det(m);
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let c0 = cross(m[1], m[2]);
+    let c1 = cross(m[2], m[0]);
+    let c2 = cross(m[0], m[1]);
+    Some(transpose([scale(c0, 1.0 / d), scale(c1, 1.0 / d), scale(c2, 1.0 / d)]))
+}
+
+/// Cartesian position of the fractional coordinate `f` in the lattice `a` (rows).
+pub(crate) fn frac_to_cart(a: Mat3, f: Vec3) -> Vec3 {
+    vec_mat(f, a)
+}
+
+/// Inverse of the lattice `a` (rows), failing if the lattice vectors are linearly
+/// dependent.
+pub(crate) fn lattice_inverse(a: Mat3) -> CResult<Mat3> {
+    inverse(a).ok_or_else(|| Error::Message("lattice vectors are linearly dependent".into()))
+}
+
+/// Reciprocal lattice vectors (rows) without the factor of 2π, failing like
+/// [`lattice_inverse`].
+pub(crate) fn reciprocal(a: Mat3) -> CResult<Mat3> {
+    Ok(transpose(lattice_inverse(a)?))
+}
+
+/// Maps every component into `[0, 1)`.
+pub(crate) fn wrap(f: Vec3) -> Vec3 {
+    f.map(|x| {
+        let w = x - x.floor();
+        if w >= 1.0 - 1e-12 { 0.0 } else { w }
+    })
+}
+
+/// Shifts a fractional difference into `[-0.5, 0.5]`.
+pub(crate) fn min_image(d: Vec3) -> Vec3 {
+    d.map(|x| x - x.round())
+}
+
+/// Length of the shortest lattice translate of the fractional vector `d`.
+pub(crate) fn periodic_distance(a: Mat3, d: Vec3) -> f64 {
+    norm(shortest_vector(a, d))
+}
+
+/// Cartesian form of the shortest lattice translate of the fractional vector `d`.
+pub(crate) fn shortest_vector(a: Mat3, d: Vec3) -> Vec3 {
+    let d = min_image(d);
+    let mut best = (f64::INFINITY, [0.0; 3]);
+    for i in -1..=1 {
+        for j in -1..=1 {
+            for k in -1..=1 {
+                let v = frac_to_cart(a, add(d, [i as f64, j as f64, k as f64]));
+                let length = norm(v);
+                if length < best.0 {
+                    best = (length, v);
+                }
+            }
+        }
+    }
+    best.1
+}
+
+pub(crate) fn to_f64(m: IMat3) -> Mat3 {
+    m.map(|row| row.map(f64::from))
+}
+
+/// Rounds `m` to an integer matrix if every entry is within `tol` of an integer.
+pub(crate) fn to_int(m: Mat3, tol: f64) -> Option<IMat3> {
+    let mut out = [[0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            let r = m[i][j].round();
+            if (m[i][j] - r).abs() > tol {
+                return None;
+            }
+            out[i][j] = r as i32;
+        }
+    }
+    Some(out)
+}
+
+pub(crate) fn imat_mul(a: IMat3, b: IMat3) -> IMat3 {
+    let mut c = [[0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            c[i][j] = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    c
+}
+
+pub(crate) fn imat_vec(m: IMat3, v: [i32; 3]) -> [i32; 3] {
+    [0, 1, 2].map(|i| (0..3).map(|k| m[i][k] * v[k]).sum())
+}
+
+pub(crate) fn imat_det(m: IMat3) -> i32 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+pub(crate) fn itranspose(m: IMat3) -> IMat3 {
+    let mut t = [[0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            t[j][i] = m[i][j];
+        }
+    }
+    t
+}
+
+/// Inverse of a unimodular integer matrix.
+pub(crate) fn iinverse(m: IMat3) -> Option<IMat3> {
+    to_int(inverse(to_f64(m))?, 1e-6)
+}
+
+/// Lattice rows `Pᵀ · A` of the basis described by the integer column matrix `p`.
+pub(crate) fn change_basis(a: Mat3, p: IMat3) -> Mat3 {
+    mat_mul(transpose(to_f64(p)), a)
+}
+
+/// Delaunay (Selling) reduction of the lattice rows `a`.
+///
+/// Returns the unimodular column matrix `P` (determinant +1) of the reduced basis.
+pub(crate) fn delaunay_reduce(a: Mat3, eps: f64) -> IMat3 {
+    let mut b = [a[0], a[1], a[2], scale(add(add(a[0], a[1]), a[2]), -1.0)];
+    let mut c: [[i32; 3]; 4] = [[1, 0, 0], [0, 1, 0], [0, 0, 1], [-1, -1, -1]];
+    for _ in 0..1000 {
+        let mut changed = false;
+        'search: for i in 0..4 {
+            for j in (i + 1)..4 {
+                if dot(b[i], b[j]) > eps {
+                    for k in 0..4 {
+                        if k != i && k != j {
+                            b[k] = add(b[k], b[i]);
+                            c[k] = [0, 1, 2].map(|n| c[k][n] + c[i][n]);
+                        }
+                    }
+                    b[i] = scale(b[i], -1.0);
+                    c[i] = c[i].map(|x| -x);
+                    changed = true;
+                    break 'search;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    let pair = |i: usize, j: usize| (add(b[i], b[j]), [0, 1, 2].map(|n| c[i][n] + c[j][n]));
+    let mut candidates = vec![
+        (b[0], c[0]),
+        (b[1], c[1]),
+        (b[2], c[2]),
+        (b[3], c[3]),
+        pair(0, 1),
+        pair(1, 2),
+        pair(2, 0),
+    ];
+    candidates.sort_by(|x, y| norm(x.0).total_cmp(&norm(y.0)));
+    let mut chosen: Vec<(Vec3, [i32; 3])> = Vec::with_capacity(3);
+    for cand in candidates {
+        let independent = match chosen.len() {
+            0 => norm(cand.0) > eps,
+            1 => norm(cross(chosen[0].0, cand.0)) > eps,
+            2 => det([chosen[0].0, chosen[1].0, cand.0]).abs() > eps,
+            _ => false,
+        };
+        if independent {
+            chosen.push(cand);
+        }
+    }
+    let mut p = itranspose([chosen[0].1, chosen[1].1, chosen[2].1]);
+    if imat_det(p) < 0 {
+        p = p.map(|row| row.map(|x| -x));
+    }
+    p
+}
+
+/// Niggli reduction of the lattice rows `a` (Křivý–Gruber, with the stable comparisons
+/// of Grosse-Kunstleve et al.).
+///
+/// Returns the unimodular column matrix `P` (determinant +1) of the reduced basis.
+pub(crate) fn niggli_reduce(a: Mat3, eps: f64) -> IMat3 {
+    let sign = |x: f64| if x > eps { 1 } else if x < -eps { -1 } else { 0 };
+    let mut p = IIDENTITY;
+    for _ in 0..1000 {
+        let b = change_basis(a, p);
+        let (aa, bb, cc) = (dot(b[0], b[0]), dot(b[1], b[1]), dot(b[2], b[2]));
+        let (xi, eta, zeta) = (2.0 * dot(b[1], b[2]), 2.0 * dot(b[0], b[2]), 2.0 * dot(b[0], b[1]));
+        let step: IMat3 = if aa > bb + eps || ((aa - bb).abs() < eps && xi.abs() > eta.abs() + eps) {
+            [[0, -1, 0], [-1, 0, 0], [0, 0, -1]]
+        } else if bb > cc + eps || ((bb - cc).abs() < eps && eta.abs() > zeta.abs() + eps) {
+            [[-1, 0, 0], [0, 0, -1], [0, -1, 0]]
+        } else {
+            let (l, m, n) = (sign(xi), sign(eta), sign(zeta));
+            let flip = if l * m * n == 1 {
+                [l, m, n]
+            } else {
+                // Make every off-diagonal term non-positive, using a zero term to keep det +1.
+                let mut f = [l, m, n].map(|s| if s == 1 { -1 } else { 1 });
+                if f.iter().product::<i32>() < 0
+                    && let Some(z) = [l, m, n].iter().position(|s| *s == 0)
+                {
+                    f[z] = -1;
+                }
+                f
+            };
+            if flip != [1, 1, 1] && flip.iter().product::<i32>() == 1 {
+                p = imat_mul(p, [[flip[0], 0, 0], [0, flip[1], 0], [0, 0, flip[2]]]);
+                continue;
+            }
+            let s = |x: f64| if x > 0.0 { 1 } else { -1 };
+            if xi.abs() > bb + eps
+                || ((xi - bb).abs() < eps && 2.0 * eta < zeta - eps)
+                || ((xi + bb).abs() < eps && zeta < -eps)
+            {
+                [[1, 0, 0], [0, 1, -s(xi)], [0, 0, 1]]
+            } else if eta.abs() > aa + eps
+                || ((eta - aa).abs() < eps && 2.0 * xi < zeta - eps)
+                || ((eta + aa).abs() < eps && zeta < -eps)
+            {
+                [[1, 0, -s(eta)], [0, 1, 0], [0, 0, 1]]
+            } else if zeta.abs() > aa + eps
+                || ((zeta - aa).abs() < eps && 2.0 * xi < eta - eps)
+                || ((zeta + aa).abs() < eps && eta < -eps)
+            {
+                [[1, -s(zeta), 0], [0, 1, 0], [0, 0, 1]]
+            } else if xi + eta + zeta + aa + bb < -eps
+                || ((xi + eta + zeta + aa + bb).abs() < eps && 2.0 * (aa + eta) + zeta > eps)
+            {
+                [[1, 0, 1], [0, 1, 1], [0, 0, 1]]
+            } else {
+                break;
+            }
+        };
+        p = imat_mul(p, step);
+    }
+    p
+}
+
+/// Basis (rows) of the integer lattice spanned by `gens`, by integer row reduction.
+pub(crate) fn lattice_basis(gens: &[[i64; 3]]) -> Option<[[i64; 3]; 3]> {
+    let mut rows: Vec<[i64; 3]> = gens.to_vec();
+    let mut basis = Vec::with_capacity(3);
+    for col in 0..3 {
+        loop {
+            let pivot = rows
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r[col] != 0)
+                .min_by_key(|(_, r)| r[col].abs())
+                .map(|(i, _)| i);
+            let Some(pivot) = pivot else { break };
+            let p = rows[pivot];
+            let mut done = true;
+            for (i, r) in rows.iter_mut().enumerate() {
+                if i != pivot && r[col] != 0 {
+                    let q = r[col].div_euclid(p[col]);
+                    for n in 0..3 {
+                        r[n] -= q * p[n];
+                    }
+                    if r[col] != 0 {
+                        done = false;
+                    }
+                }
+            }
+            if done {
+                basis.push(rows.remove(pivot));
+                break;
+            }
+        }
+    }
+    (basis.len() == 3).then(|| [basis[0], basis[1], basis[2]])
+}
+
+/// Row transform `L`, column transform `R` and diagonal form `D` of [`diagonalize`].
+pub(crate) type Diagonalized = (Vec<Vec<i64>>, [[i64; 3]; 3], Vec<[i64; 3]>);
+
+/// Diagonalises the integer `m × 3` matrix `a` with unimodular row and column
+/// operations, returning `(L, R, D)` with `L · a · R = D` and `D` zero off the
+/// diagonal.
+pub(crate) fn diagonalize(a: &[[i64; 3]]) -> Diagonalized {
+    let m = a.len();
+    let mut d = a.to_vec();
+    let mut l: Vec<Vec<i64>> = (0..m).map(|i| (0..m).map(|j| i64::from(i == j)).collect()).collect();
+    let mut r = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+    for t in 0..m.min(3) {
+        loop {
+            let pivot = (t..m)
+                .flat_map(|i| (t..3).map(move |j| (i, j)))
+                .filter(|&(i, j)| d[i][j] != 0)
+                .min_by_key(|&(i, j)| d[i][j].abs());
+            let Some((pi, pj)) = pivot else { return (l, r, d) };
+            d.swap(t, pi);
+            l.swap(t, pi);
+            for row in d.iter_mut() {
+                row.swap(t, pj);
+            }
+            for row in r.iter_mut() {
+                row.swap(t, pj);
+            }
+            let p = d[t][t];
+            let mut clean = true;
+            for i in (t + 1)..m {
+                let q = d[i][t].div_euclid(p);
+                if q != 0 {
+                    let (pivot_d, pivot_l) = (d[t], l[t].clone());
+                    for (x, p) in d[i].iter_mut().zip(pivot_d) {
+                        *x -= q * p;
+                    }
+                    for (x, p) in l[i].iter_mut().zip(pivot_l) {
+                        *x -= q * p;
+                    }
+                }
+                clean &= d[i][t] == 0;
+            }
+            for j in (t + 1)..3 {
+                let q = d[t][j].div_euclid(p);
+                if q != 0 {
+                    for row in d.iter_mut() {
+                        row[j] -= q * row[t];
+                    }
+                    for row in r.iter_mut() {
+                        row[j] -= q * row[t];
+                    }
+                }
+                clean &= d[t][j] == 0;
+            }
+            if clean {
+                break;
+            }
+        }
+    }
+    (l, r, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inverse_roundtrip() {
+        let m = [[2.0, 1.0, 0.0], [0.0, 3.0, 1.0], [1.0, 0.0, 4.0]];
+        let p = mat_mul(m, inverse(m).unwrap());
+        for (row, expected) in p.iter().zip(IDENTITY) {
+            for (x, y) in row.iter().zip(expected) {
+                assert!((x - y).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_delaunay_reduces_skewed_basis() {
+        let a = [[1.0, 0.0, 0.0], [5.0, 1.0, 0.0], [3.0, 7.0, 1.0]];
+        let p = delaunay_reduce(a, 1e-8);
+        assert_eq!(imat_det(p), 1);
+        let reduced = change_basis(a, p);
+        for v in reduced {
+            assert!((norm(v) - 1.0).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn test_niggli_reduce() {
+        // A conventional fcc cell expressed with a skewed basis.
+        let a = [[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]];
+        let skew = [[1, 3, 0], [0, 1, 0], [-2, 1, 1]];
+        let skewed = change_basis(a, skew);
+        let p = niggli_reduce(skewed, 1e-8);
+        assert_eq!(imat_det(p), 1);
+        let reduced = change_basis(skewed, p);
+        let g: Vec<f64> = reduced.iter().map(|v| dot(*v, *v)).collect();
+        for x in g {
+            assert!((x - 8.0).abs() < 1e-9);
+        }
+        // The reduced cell is the primitive fcc cell with 60° angles.
+        let cos = dot(reduced[0], reduced[1]) / 8.0;
+        assert!((cos.abs() - 0.5).abs() < 1e-9);
+        // Reducing an already reduced cell is the identity.
+        assert_eq!(niggli_reduce(reduced, 1e-8), IIDENTITY);
+    }
+
+    #[test]
+    fn test_lattice_basis_of_centred_lattice() {
+        // Body-centred lattice scaled by 2.
+        let basis = lattice_basis(&[[2, 0, 0], [0, 2, 0], [0, 0, 2], [1, 1, 1]]).unwrap();
+        let det = basis[0][0] * (basis[1][1] * basis[2][2] - basis[1][2] * basis[2][1])
+            - basis[0][1] * (basis[1][0] * basis[2][2] - basis[1][2] * basis[2][0])
+            + basis[0][2] * (basis[1][0] * basis[2][1] - basis[1][1] * basis[2][0]);
+        assert_eq!(det.abs(), 4);
+    }
+
+    #[test]
+    fn test_diagonalize() {
+        let a = [[2, 0, 0], [0, 2, 0], [1, 1, 0], [0, 0, 0]];
+        let (l, r, d) = diagonalize(&a);
+        for i in 0..a.len() {
+            for j in 0..3 {
+                let v: i64 = (0..a.len())
+                    .map(|k| l[i][k] * (0..3).map(|n| a[k][n] * r[n][j]).sum::<i64>())
+                    .sum();
+                assert_eq!(v, d[i][j]);
+                if i != j {
+                    assert_eq!(d[i][j], 0);
+                }
+            }
+        }
+    }
+}
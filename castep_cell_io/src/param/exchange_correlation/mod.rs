@@ -1,5 +1,7 @@
 mod k_scrn_averaging_scheme;
+mod spin_orbit_coupling;
 mod spin_polarized;
+mod spin_treatment;
 mod xc_functional;
 mod nlxc_exchange_reflect_kpts;
 mod nlxc_impose_trs;
@@ -12,7 +14,9 @@ mod nlxc_ppd_size_z;
 mod xc_definition;
 
 pub use k_scrn_averaging_scheme::KScrnAveragingScheme;
+pub use spin_orbit_coupling::SpinOrbitCoupling;
 pub use spin_polarized::SpinPolarized;
+pub use spin_treatment::SpinTreatment;
 pub use xc_functional::XcFunctional;
 pub use nlxc_exchange_reflect_kpts::NlxcExchangeReflectKpts;
 pub use nlxc_impose_trs::NlxcImposeTrs;
@@ -0,0 +1,59 @@
+use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue};
+use castep_cell_fmt::parse::{FromCellValue, FromKeyValue};
+use castep_cell_fmt::CResult;
+use castep_cell_fmt::query::value_as_bool;
+
+/// Controls whether spin-orbit coupling is included.
+///
+/// Keyword type: Logical
+///
+/// Default: false
+///
+/// Example:
+/// SPIN_ORBIT_COUPLING : TRUE
+///
+/// Note: requires SPIN_TREATMENT : VECTOR and relativistic (j-dependent) pseudopotentials.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpinOrbitCoupling(pub bool);
+
+impl FromCellValue for SpinOrbitCoupling {
+    fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
+        Ok(Self(value_as_bool(value)?))
+    }
+}
+
+impl FromKeyValue for SpinOrbitCoupling {
+    const KEY_NAME: &'static str = "SPIN_ORBIT_COUPLING";
+
+    fn from_cell_value_kv(value: &CellValue<'_>) -> CResult<Self> {
+        Self::from_cell_value(value)
+    }
+}
+
+impl ToCell for SpinOrbitCoupling {
+    fn to_cell(&self) -> Cell<'_> {
+        Cell::KeyValue("SPIN_ORBIT_COUPLING", CellValue::Bool(self.0))
+    }
+}
+
+impl ToCellValue for SpinOrbitCoupling {
+    fn to_cell_value(&self) -> CellValue<'_> {
+        CellValue::Bool(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_cell_value() {
+        assert!(SpinOrbitCoupling::from_cell_value(&CellValue::Bool(true)).unwrap().0);
+        assert!(!SpinOrbitCoupling::from_cell_value(&CellValue::Bool(false)).unwrap().0);
+    }
+
+    #[test]
+    fn test_key_name() {
+        assert_eq!(SpinOrbitCoupling::KEY_NAME, "SPIN_ORBIT_COUPLING");
+    }
+}
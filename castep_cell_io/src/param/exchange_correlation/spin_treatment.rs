@@ -0,0 +1,103 @@
+use castep_cell_fmt::{Cell, CellValue, ToCell, ToCellValue};
+use castep_cell_fmt::parse::{FromCellValue, FromKeyValue};
+use castep_cell_fmt::{CResult, Error};
+use castep_cell_fmt::query::value_as_str;
+
+/// Determines how electron spin is treated.
+///
+/// Keyword type: String
+///
+/// Default: SpinTreatment::None, or SpinTreatment::Scalar if SPIN_POLARIZED is true
+///
+/// Example:
+/// SPIN_TREATMENT : VECTOR
+///
+/// Note: VECTOR enables non-collinear magnetism; initial moments are then given as
+/// three-component SPIN or MAGMOM qualifiers in the positions block.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Default)]
+pub enum SpinTreatment {
+    /// Non-spin-polarized calculation
+    #[default]
+    None,
+    /// Collinear spin-polarized calculation
+    Scalar,
+    /// Non-collinear spin-polarized calculation
+    Vector,
+}
+
+
+impl FromCellValue for SpinTreatment {
+    fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
+        match value_as_str(value)?.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "scalar" => Ok(Self::Scalar),
+            "vector" => Ok(Self::Vector),
+            other => Err(Error::Message(format!("unknown SpinTreatment: {other}"))),
+        }
+    }
+}
+
+impl FromKeyValue for SpinTreatment {
+    const KEY_NAME: &'static str = "SPIN_TREATMENT";
+
+    fn from_cell_value_kv(value: &CellValue<'_>) -> CResult<Self> {
+        Self::from_cell_value(value)
+    }
+}
+
+impl ToCell for SpinTreatment {
+    fn to_cell(&self) -> Cell<'_> {
+        Cell::KeyValue("SPIN_TREATMENT", self.to_cell_value())
+    }
+}
+
+impl ToCellValue for SpinTreatment {
+    fn to_cell_value(&self) -> CellValue<'_> {
+        CellValue::String(
+            match self {
+                SpinTreatment::None => "NONE",
+                SpinTreatment::Scalar => "SCALAR",
+                SpinTreatment::Vector => "VECTOR",
+            }
+            .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use castep_cell_fmt::CellValue;
+
+    #[test]
+    fn test_case_insensitive_parsing() {
+        let val = CellValue::Str("vector");
+        assert_eq!(SpinTreatment::from_cell_value(&val).unwrap(), SpinTreatment::Vector);
+
+        let val = CellValue::Str("Scalar");
+        assert_eq!(SpinTreatment::from_cell_value(&val).unwrap(), SpinTreatment::Scalar);
+
+        let val = CellValue::Str("NONE");
+        assert_eq!(SpinTreatment::from_cell_value(&val).unwrap(), SpinTreatment::None);
+    }
+
+    #[test]
+    fn test_invalid_variant() {
+        let val = CellValue::Str("collinear");
+        assert!(SpinTreatment::from_cell_value(&val).is_err());
+    }
+
+    #[test]
+    fn test_key_name() {
+        assert_eq!(SpinTreatment::KEY_NAME, "SPIN_TREATMENT");
+    }
+
+    #[test]
+    fn test_to_cell_value() {
+        assert_eq!(
+            SpinTreatment::Vector.to_cell_value(),
+            CellValue::String("VECTOR".to_string())
+        );
+    }
+}
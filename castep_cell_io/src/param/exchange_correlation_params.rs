@@ -10,6 +10,8 @@ use super::exchange_correlation::*;
 pub struct ExchangeCorrelationParams {
     pub k_scrn_averaging_scheme: Option<KScrnAveragingScheme>,
     pub spin_polarized: Option<SpinPolarized>,
+    pub spin_treatment: Option<SpinTreatment>,
+    pub spin_orbit_coupling: Option<SpinOrbitCoupling>,
     pub xc_functional: Option<XcFunctional>,
     pub nlxc_exchange_reflect_kpts: Option<NlxcExchangeReflectKpts>,
     pub nlxc_impose_trs: Option<NlxcImposeTrs>,
@@ -25,6 +27,18 @@ pub struct ExchangeCorrelationParams {
 impl ExchangeCorrelationParams {
     /// Validates intra-group constraints for exchange-correlation parameters
     pub fn validate(self) -> Result<Self, String> {
+        if self.spin_orbit_coupling.is_some_and(|soc| soc.0)
+            && self.spin_treatment != Some(SpinTreatment::Vector)
+        {
+            return Err("SPIN_ORBIT_COUPLING requires SPIN_TREATMENT : VECTOR.".into());
+        }
+
+        if self.spin_polarized == Some(SpinPolarized(false))
+            && matches!(self.spin_treatment, Some(SpinTreatment::Scalar | SpinTreatment::Vector))
+        {
+            return Err("SPIN_POLARIZED : FALSE conflicts with a spin-polarized SPIN_TREATMENT.".into());
+        }
+
         Ok(self)
     }
 }
@@ -34,6 +48,8 @@ impl FromCellFile for ExchangeCorrelationParams {
         Self::builder()
             .maybe_k_scrn_averaging_scheme(KScrnAveragingScheme::from_cells(tokens).ok().flatten())
             .maybe_spin_polarized(SpinPolarized::from_cells(tokens).ok().flatten())
+            .maybe_spin_treatment(SpinTreatment::from_cells(tokens).ok().flatten())
+            .maybe_spin_orbit_coupling(SpinOrbitCoupling::from_cells(tokens).ok().flatten())
             .maybe_xc_functional(XcFunctional::from_cells(tokens).ok().flatten())
             .maybe_nlxc_exchange_reflect_kpts(NlxcExchangeReflectKpts::from_cells(tokens).ok().flatten())
             .maybe_nlxc_impose_trs(NlxcImposeTrs::from_cells(tokens).ok().flatten())
@@ -55,6 +71,8 @@ impl ToCellFile for ExchangeCorrelationParams {
         let mut cells = Vec::new();
         if let Some(v) = &self.k_scrn_averaging_scheme { cells.push(v.to_cell()); }
         if let Some(v) = &self.spin_polarized { cells.push(v.to_cell()); }
+        if let Some(v) = &self.spin_treatment { cells.push(v.to_cell()); }
+        if let Some(v) = &self.spin_orbit_coupling { cells.push(v.to_cell()); }
         if let Some(v) = &self.xc_functional { cells.push(v.to_cell()); }
         if let Some(v) = &self.nlxc_exchange_reflect_kpts { cells.push(v.to_cell()); }
         if let Some(v) = &self.nlxc_impose_trs { cells.push(v.to_cell()); }
@@ -110,4 +128,25 @@ mod tests {
         let params = ExchangeCorrelationParams::builder().build();
         assert!(params.validate().is_ok());
     }
+
+    #[test]
+    fn test_validate_spin_orbit_coupling() {
+        let params = ExchangeCorrelationParams::builder()
+            .spin_treatment(SpinTreatment::Vector)
+            .spin_orbit_coupling(SpinOrbitCoupling(true))
+            .build();
+        assert!(params.validate().is_ok());
+
+        let params = ExchangeCorrelationParams::builder()
+            .spin_treatment(SpinTreatment::Scalar)
+            .spin_orbit_coupling(SpinOrbitCoupling(true))
+            .build();
+        assert!(params.validate().is_err());
+
+        let params = ExchangeCorrelationParams::builder()
+            .spin_polarized(SpinPolarized(false))
+            .spin_treatment(SpinTreatment::Vector)
+            .build();
+        assert!(params.validate().is_err());
+    }
 }
@@ -0,0 +1,270 @@
+//! Element data: symbols, atomic numbers, standard masses, covalent radii and typical
+//! valences for hydrogen to oganesson.
+//!
+//! Masses are IUPAC standard atomic weights in atomic mass units; elements without a
+//! standard weight use the mass number of their longest-lived isotope. Covalent radii
+//! are the single-bond values of Cordero et al. (2008) up to curium and of Pyykkö and
+//! Atsumi (2009) beyond, in Å (low-spin values for Mn, Fe and Co, sp³ for carbon).
+
+/// A chemical element.
+#[derive(Debug, PartialEq)]
+pub struct Element {
+    /// Atomic number, 1 to 118.
+    pub atomic_number: u32,
+    /// Element symbol, e.g. `Fe`.
+    pub symbol: &'static str,
+    /// English name, e.g. `Iron`.
+    pub name: &'static str,
+    /// Standard atomic mass in amu.
+    pub mass: f64,
+    /// Single-bond covalent radius in Å.
+    pub covalent_radius: f64,
+    /// Most common valence, as a rough guide only.
+    pub valence: u32,
+}
+
+impl Element {
+    /// Looks up an element by atomic number.
+    pub fn from_number(atomic_number: u32) -> Option<&'static Element> {
+        atomic_number
+            .checked_sub(1)
+            .and_then(|i| ELEMENTS.get(i as usize))
+    }
+
+    /// Looks up an element by symbol, ignoring case.
+    pub fn from_symbol(symbol: &str) -> Option<&'static Element> {
+        ELEMENTS
+            .iter()
+            .find(|e| e.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    /// Resolves a CASTEP species label to its element.
+    ///
+    /// Labels are an element symbol, optionally followed by digits or by a `:` or `_`
+    /// suffix, as in `Fe1`, `Fe:up` or `O_surf`, or a bare atomic number such as `26`.
+    /// A two-letter symbol wins over a one-letter one, so `Co1` is cobalt, while labels
+    /// with any other tail, such as `Sx`, name no element.
+    pub fn from_label(label: &str) -> Option<&'static Element> {
+        if let Ok(n) = label.parse::<u32>() {
+            return Element::from_number(n);
+        }
+        (1..=label.len().min(2)).rev().find_map(|n| {
+            let symbol = label.get(..n)?;
+            let rest = &label[n..];
+            let custom = rest.is_empty()
+                || rest.bytes().all(|b| b.is_ascii_digit())
+                || rest.starts_with([':', '_']);
+            if custom && symbol.bytes().all(|b| b.is_ascii_alphabetic()) {
+                Element::from_symbol(symbol)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// All 118 elements, ordered by atomic number.
+    pub fn all() -> &'static [Element] {
+        &ELEMENTS
+    }
+}
+
+impl std::fmt::Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.symbol)
+    }
+}
+
+const fn el(
+    atomic_number: u32,
+    symbol: &'static str,
+    name: &'static str,
+    mass: f64,
+    covalent_radius: f64,
+    valence: u32,
+) -> Element {
+    Element {
+        atomic_number,
+        symbol,
+        name,
+        mass,
+        covalent_radius,
+        valence,
+    }
+}
+
+static ELEMENTS: [Element; 118] = [
+    el(1, "H", "Hydrogen", 1.008, 0.31, 1),
+    el(2, "He", "Helium", 4.0026, 0.28, 0),
+    el(3, "Li", "Lithium", 6.94, 1.28, 1),
+    el(4, "Be", "Beryllium", 9.0122, 0.96, 2),
+    el(5, "B", "Boron", 10.81, 0.84, 3),
+    el(6, "C", "Carbon", 12.011, 0.76, 4),
+    el(7, "N", "Nitrogen", 14.007, 0.71, 3),
+    el(8, "O", "Oxygen", 15.999, 0.66, 2),
+    el(9, "F", "Fluorine", 18.998, 0.57, 1),
+    el(10, "Ne", "Neon", 20.180, 0.58, 0),
+    el(11, "Na", "Sodium", 22.990, 1.66, 1),
+    el(12, "Mg", "Magnesium", 24.305, 1.41, 2),
+    el(13, "Al", "Aluminium", 26.982, 1.21, 3),
+    el(14, "Si", "Silicon", 28.085, 1.11, 4),
+    el(15, "P", "Phosphorus", 30.974, 1.07, 3),
+    el(16, "S", "Sulfur", 32.06, 1.05, 2),
+    el(17, "Cl", "Chlorine", 35.45, 1.02, 1),
+    el(18, "Ar", "Argon", 39.948, 1.06, 0),
+    el(19, "K", "Potassium", 39.098, 2.03, 1),
+    el(20, "Ca", "Calcium", 40.078, 1.76, 2),
+    el(21, "Sc", "Scandium", 44.956, 1.70, 3),
+    el(22, "Ti", "Titanium", 47.867, 1.60, 4),
+    el(23, "V", "Vanadium", 50.942, 1.53, 5),
+    el(24, "Cr", "Chromium", 51.996, 1.39, 3),
+    el(25, "Mn", "Manganese", 54.938, 1.39, 2),
+    el(26, "Fe", "Iron", 55.845, 1.32, 3),
+    el(27, "Co", "Cobalt", 58.933, 1.26, 2),
+    el(28, "Ni", "Nickel", 58.693, 1.24, 2),
+    el(29, "Cu", "Copper", 63.546, 1.32, 2),
+    el(30, "Zn", "Zinc", 65.38, 1.22, 2),
+    el(31, "Ga", "Gallium", 69.723, 1.22, 3),
+    el(32, "Ge", "Germanium", 72.630, 1.20, 4),
+    el(33, "As", "Arsenic", 74.922, 1.19, 3),
+    el(34, "Se", "Selenium", 78.971, 1.20, 2),
+    el(35, "Br", "Bromine", 79.904, 1.20, 1),
+    el(36, "Kr", "Krypton", 83.798, 1.16, 0),
+    el(37, "Rb", "Rubidium", 85.468, 2.20, 1),
+    el(38, "Sr", "Strontium", 87.62, 1.95, 2),
+    el(39, "Y", "Yttrium", 88.906, 1.90, 3),
+    el(40, "Zr", "Zirconium", 91.224, 1.75, 4),
+    el(41, "Nb", "Niobium", 92.906, 1.64, 5),
+    el(42, "Mo", "Molybdenum", 95.95, 1.54, 6),
+    el(43, "Tc", "Technetium", 98.0, 1.47, 7),
+    el(44, "Ru", "Ruthenium", 101.07, 1.46, 4),
+    el(45, "Rh", "Rhodium", 102.91, 1.42, 3),
+    el(46, "Pd", "Palladium", 106.42, 1.39, 2),
+    el(47, "Ag", "Silver", 107.87, 1.45, 1),
+    el(48, "Cd", "Cadmium", 112.41, 1.44, 2),
+    el(49, "In", "Indium", 114.82, 1.42, 3),
+    el(50, "Sn", "Tin", 118.71, 1.39, 4),
+    el(51, "Sb", "Antimony", 121.76, 1.39, 3),
+    el(52, "Te", "Tellurium", 127.60, 1.38, 2),
+    el(53, "I", "Iodine", 126.90, 1.39, 1),
+    el(54, "Xe", "Xenon", 131.29, 1.40, 0),
+    el(55, "Cs", "Caesium", 132.91, 2.44, 1),
+    el(56, "Ba", "Barium", 137.33, 2.15, 2),
+    el(57, "La", "Lanthanum", 138.91, 2.07, 3),
+    el(58, "Ce", "Cerium", 140.12, 2.04, 3),
+    el(59, "Pr", "Praseodymium", 140.91, 2.03, 3),
+    el(60, "Nd", "Neodymium", 144.24, 2.01, 3),
+    el(61, "Pm", "Promethium", 145.0, 1.99, 3),
+    el(62, "Sm", "Samarium", 150.36, 1.98, 3),
+    el(63, "Eu", "Europium", 151.96, 1.98, 3),
+    el(64, "Gd", "Gadolinium", 157.25, 1.96, 3),
+    el(65, "Tb", "Terbium", 158.93, 1.94, 3),
+    el(66, "Dy", "Dysprosium", 162.50, 1.92, 3),
+    el(67, "Ho", "Holmium", 164.93, 1.92, 3),
+    el(68, "Er", "Erbium", 167.26, 1.89, 3),
+    el(69, "Tm", "Thulium", 168.93, 1.90, 3),
+    el(70, "Yb", "Ytterbium", 173.05, 1.87, 3),
+    el(71, "Lu", "Lutetium", 174.97, 1.87, 3),
+    el(72, "Hf", "Hafnium", 178.49, 1.75, 4),
+    el(73, "Ta", "Tantalum", 180.95, 1.70, 5),
+    el(74, "W", "Tungsten", 183.84, 1.62, 6),
+    el(75, "Re", "Rhenium", 186.21, 1.51, 7),
+    el(76, "Os", "Osmium", 190.23, 1.44, 4),
+    el(77, "Ir", "Iridium", 192.22, 1.41, 4),
+    el(78, "Pt", "Platinum", 195.08, 1.36, 2),
+    el(79, "Au", "Gold", 196.97, 1.36, 3),
+    el(80, "Hg", "Mercury", 200.59, 1.32, 2),
+    el(81, "Tl", "Thallium", 204.38, 1.45, 1),
+    el(82, "Pb", "Lead", 207.2, 1.46, 2),
+    el(83, "Bi", "Bismuth", 208.98, 1.48, 3),
+    el(84, "Po", "Polonium", 209.0, 1.40, 4),
+    el(85, "At", "Astatine", 210.0, 1.50, 1),
+    el(86, "Rn", "Radon", 222.0, 1.50, 0),
+    el(87, "Fr", "Francium", 223.0, 2.60, 1),
+    el(88, "Ra", "Radium", 226.0, 2.21, 2),
+    el(89, "Ac", "Actinium", 227.0, 2.15, 3),
+    el(90, "Th", "Thorium", 232.04, 2.06, 4),
+    el(91, "Pa", "Protactinium", 231.04, 2.00, 5),
+    el(92, "U", "Uranium", 238.03, 1.96, 6),
+    el(93, "Np", "Neptunium", 237.0, 1.90, 5),
+    el(94, "Pu", "Plutonium", 244.0, 1.87, 4),
+    el(95, "Am", "Americium", 243.0, 1.80, 3),
+    el(96, "Cm", "Curium", 247.0, 1.69, 3),
+    el(97, "Bk", "Berkelium", 247.0, 1.68, 3),
+    el(98, "Cf", "Californium", 251.0, 1.68, 3),
+    el(99, "Es", "Einsteinium", 252.0, 1.65, 3),
+    el(100, "Fm", "Fermium", 257.0, 1.67, 3),
+    el(101, "Md", "Mendelevium", 258.0, 1.73, 3),
+    el(102, "No", "Nobelium", 259.0, 1.76, 2),
+    el(103, "Lr", "Lawrencium", 262.0, 1.61, 3),
+    el(104, "Rf", "Rutherfordium", 267.0, 1.57, 4),
+    el(105, "Db", "Dubnium", 268.0, 1.49, 5),
+    el(106, "Sg", "Seaborgium", 269.0, 1.43, 6),
+    el(107, "Bh", "Bohrium", 270.0, 1.41, 7),
+    el(108, "Hs", "Hassium", 269.0, 1.34, 8),
+    el(109, "Mt", "Meitnerium", 278.0, 1.29, 3),
+    el(110, "Ds", "Darmstadtium", 281.0, 1.28, 2),
+    el(111, "Rg", "Roentgenium", 282.0, 1.21, 1),
+    el(112, "Cn", "Copernicium", 285.0, 1.22, 2),
+    el(113, "Nh", "Nihonium", 286.0, 1.36, 1),
+    el(114, "Fl", "Flerovium", 289.0, 1.43, 2),
+    el(115, "Mc", "Moscovium", 290.0, 1.62, 1),
+    el(116, "Lv", "Livermorium", 293.0, 1.75, 2),
+    el(117, "Ts", "Tennessine", 294.0, 1.65, 1),
+    el(118, "Og", "Oganesson", 294.0, 1.57, 0),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookups_both_ways() {
+        for (i, element) in Element::all().iter().enumerate() {
+            assert_eq!(element.atomic_number as usize, i + 1);
+            assert_eq!(Element::from_symbol(element.symbol), Some(element));
+            assert_eq!(Element::from_number(element.atomic_number), Some(element));
+        }
+        assert_eq!(Element::from_symbol("fe").unwrap().atomic_number, 26);
+        assert_eq!(Element::from_number(26).unwrap().symbol, "Fe");
+        assert!(Element::from_number(0).is_none());
+        assert!(Element::from_number(250).is_none());
+        assert!(Element::from_symbol("Sx").is_none());
+    }
+
+    #[test]
+    fn test_custom_labels() {
+        for label in ["Fe", "Fe:up", "Fe1", "Fe_2", "FE:b", "26"] {
+            assert_eq!(
+                Element::from_label(label).map(|e| e.symbol),
+                Some("Fe"),
+                "{label}"
+            );
+        }
+        // The longest matching symbol wins.
+        for (label, symbol) in [("O:surf", "O"), ("H_w", "H"), ("C12", "C"), ("Co1", "Co")] {
+            assert_eq!(Element::from_label(label).unwrap().symbol, symbol, "{label}");
+        }
+        // Letters after the symbol are a typo, not a custom label.
+        for label in ["Sx", "Cx", "Ow", "Fe1a", "Xy:1"] {
+            assert!(Element::from_label(label).is_none(), "{label}");
+        }
+        assert!(Element::from_label("Q").is_none());
+        assert!(Element::from_label("250").is_none());
+        assert!(Element::from_label("").is_none());
+    }
+
+    #[test]
+    fn test_element_data() {
+        let iron = Element::from_symbol("Fe").unwrap();
+        assert_eq!(iron.name, "Iron");
+        assert!((iron.mass - 55.845).abs() < 1e-9);
+        assert!((iron.covalent_radius - 1.32).abs() < 1e-9);
+        let inversions = Element::all()
+            .windows(2)
+            .filter(|w| w[1].mass < w[0].mass)
+            .map(|w| w[1].symbol)
+            .collect::<Vec<_>>();
+        // The well-known inversions of the periodic table.
+        assert_eq!(inversions, ["K", "Ni", "I", "Pa", "Np", "Am", "Hs"]);
+    }
+}
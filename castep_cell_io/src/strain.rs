@@ -0,0 +1,324 @@
+//! Homogeneous strain of a cell, for elastic-constant and equation-of-state workflows.
+//!
+//! Strains are small symmetric tensors written in Voigt order
+//! `[e_xx, e_yy, e_zz, 2e_yz, 2e_xz, 2e_xy]`, i.e. with engineering shear components,
+//! and deform the cell by `x → (I + ε) x`. Atoms keep their fractional coordinates, so
+//! absolute positions, including the transition-state geometries, move with the
+//! lattice. Other Cartesian quantities (`IONIC_VELOCITIES`, constraint directions,
+//! external fields) are left as they are.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::constraints::{CellConstraints, FixVOL};
+use crate::cell::lattice_param::LatticeCart;
+use crate::cell::symmetry::{SymmetryOp, SymmetryOps};
+use crate::math::{self, Mat3};
+use crate::{CellDocument, Lattice, Positions, PositionsIntermediate, PositionsProduct};
+
+/// A symmetric strain in Voigt notation with engineering shear components.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VoigtStrain(pub [f64; 6]);
+
+impl VoigtStrain {
+    /// A strain of `value` in the single Voigt component `component` (0-based).
+    ///
+    /// Fails unless `component` is below 6.
+    pub fn component(component: usize, value: f64) -> CResult<Self> {
+        let mut strain = [0.0; 6];
+        *strain.get_mut(component).ok_or_else(|| {
+            Error::Message(format!("Voigt component must be 0 to 5, got {component}"))
+        })? = value;
+        Ok(Self(strain))
+    }
+
+    /// The isotropic strain that scales the volume by `factor`.
+    pub fn volumetric(factor: f64) -> Self {
+        let e = factor.cbrt() - 1.0;
+        Self([e, e, e, 0.0, 0.0, 0.0])
+    }
+
+    /// Returns the strain tensor `ε`.
+    pub fn tensor(&self) -> Mat3 {
+        let [xx, yy, zz, yz, xz, xy] = self.0;
+        [
+            [xx, xy / 2.0, xz / 2.0],
+            [xy / 2.0, yy, yz / 2.0],
+            [xz / 2.0, yz / 2.0, zz],
+        ]
+    }
+
+    /// Returns the deformation gradient `I + ε`.
+    pub fn deformation(&self) -> Mat3 {
+        let e = self.tensor();
+        [0, 1, 2].map(|i| [0, 1, 2].map(|j| e[i][j] + if i == j { 1.0 } else { 0.0 }))
+    }
+}
+
+/// One member of a strain set.
+#[derive(Debug, Clone)]
+pub struct StrainedCell {
+    /// Strain applied to the reference cell.
+    pub strain: VoigtStrain,
+    /// The strained cell.
+    pub cell: CellDocument,
+}
+
+impl CellDocument {
+    /// Returns the cell deformed by `strain`, for a fixed-cell calculation.
+    ///
+    /// The lattice is written as `LATTICE_CART` in its current unit, `CELL_CONSTRAINTS`
+    /// fixes every lattice parameter and `FIX_VOL` is removed, so only the ions relax.
+    /// `SYMMETRY_OPS` keeps the operations that are still symmetries of the strained
+    /// lattice.
+    pub fn strained(&self, strain: VoigtStrain) -> CResult<CellDocument> {
+        let mut cell = self.deformed(strain.deformation())?;
+        cell.cell_constraints = Some(CellConstraints {
+            lengths: [0; 3],
+            angles: [0; 3],
+        });
+        cell.fix_vol = None;
+        Ok(cell)
+    }
+
+    /// Returns the cell with its volume scaled by `factor` and its shape unchanged, for
+    /// an equation-of-state point.
+    ///
+    /// `LATTICE_ABC` stays `LATTICE_ABC`. `FIX_VOL` is set so that a cell relaxation
+    /// only adjusts the shape; `CELL_CONSTRAINTS` still holds under an isotropic strain
+    /// and is kept.
+    pub fn scaled_volume(&self, factor: f64) -> CResult<CellDocument> {
+        if factor <= 0.0 || !factor.is_finite() {
+            return Err(Error::Message(format!(
+                "volume factor must be positive, got {factor}"
+            )));
+        }
+        let mut cell = self.deformed(VoigtStrain::volumetric(factor).deformation())?;
+        if let Lattice::Abc(mut abc) = self.lattice {
+            abc.abc = abc.abc.map(|x| x * factor.cbrt());
+            cell.lattice = Lattice::Abc(abc);
+        }
+        cell.fix_vol = Some(FixVOL(true));
+        Ok(cell)
+    }
+
+    /// Returns the cells strained by `±k·delta`, `k = 1..=steps`, in each of the six
+    /// Voigt components in turn, for a stress–strain fit of the elastic constants.
+    ///
+    /// Fails unless `delta` is positive and `steps` at least 1.
+    pub fn elastic_strain_set(&self, delta: f64, steps: usize) -> CResult<Vec<StrainedCell>> {
+        if delta <= 0.0 || !delta.is_finite() || steps == 0 {
+            return Err(Error::Message(format!(
+                "strain set needs a positive delta and at least one step, got {delta} and {steps}"
+            )));
+        }
+        let mut set = Vec::with_capacity(12 * steps);
+        for component in 0..6 {
+            for k in 1..=steps {
+                for sign in [-1.0, 1.0] {
+                    let strain = VoigtStrain::component(component, sign * k as f64 * delta)?;
+                    set.push(StrainedCell {
+                        strain,
+                        cell: self.strained(strain)?,
+                    });
+                }
+            }
+        }
+        Ok(set)
+    }
+
+    /// Returns `points` cells with volumes spread evenly over
+    /// `[1 - max_change, 1 + max_change]` times the current volume, see
+    /// [`scaled_volume`](Self::scaled_volume).
+    ///
+    /// Fails unless `0 < max_change < 1` and `points` is at least 2.
+    pub fn volume_strain_set(&self, max_change: f64, points: usize) -> CResult<Vec<StrainedCell>> {
+        if !(max_change > 0.0 && max_change < 1.0) || points < 2 {
+            return Err(Error::Message(format!(
+                "volume set needs 0 < max_change < 1 and at least two points, got {max_change} and {points}"
+            )));
+        }
+        (0..points)
+            .map(|i| {
+                let t = i as f64 / (points - 1) as f64;
+                let factor = 1.0 - max_change + 2.0 * max_change * t;
+                Ok(StrainedCell {
+                    strain: VoigtStrain::volumetric(factor),
+                    cell: self.scaled_volume(factor)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Applies the deformation gradient `f` to the lattice and the absolute positions.
+    fn deformed(&self, f: Mat3) -> CResult<CellDocument> {
+        if math::det(f) <= 0.0 {
+            return Err(Error::Message(
+                "strain inverts or collapses the cell".into(),
+            ));
+        }
+        let f_t = math::transpose(f);
+        let lattice = match &self.lattice {
+            Lattice::Cart(cart) => {
+                let [a, b, c] = [cart.a, cart.b, cart.c].map(|v| math::vec_mat(v, f_t));
+                LatticeCart { a, b, c, ..*cart }
+            }
+            Lattice::Abc(abc) => {
+                let scale = abc.unit.unwrap_or_default().in_angstrom();
+                let [a, b, c] = math::mat_mul(abc.vectors(), f_t).map(|v| v.map(|x| x / scale));
+                LatticeCart {
+                    unit: abc.unit,
+                    a,
+                    b,
+                    c,
+                }
+            }
+        };
+        let deform = |coord: [f64; 3]| math::vec_mat(coord, f_t);
+
+        let mut cell = self.clone();
+        cell.lattice = Lattice::Cart(lattice);
+        if let Positions::Abs(abs) = &mut cell.positions {
+            abs.positions
+                .iter_mut()
+                .for_each(|p| p.coord = deform(p.coord));
+        }
+        if let Some(PositionsIntermediate::Abs(abs)) = &mut cell.positions_intermediate {
+            abs.positions
+                .iter_mut()
+                .for_each(|p| p.coord = deform(p.coord));
+        }
+        if let Some(PositionsProduct::Abs(abs)) = &mut cell.positions_product {
+            abs.positions
+                .iter_mut()
+                .for_each(|p| p.coord = deform(p.coord));
+        }
+        if let Some(ops) = &self.symmetry_ops {
+            cell.symmetry_ops = Some(SymmetryOps {
+                ops: ops
+                    .ops
+                    .iter()
+                    .filter_map(|op| op.to_frac(&self.lattice).ok())
+                    .map(|frac| SymmetryOp::from_frac(&frac, &cell.lattice))
+                    .collect::<CResult<Vec<_>>>()?
+                    .into_iter()
+                    .filter(|op| op.is_orthogonal(&cell.lattice, 1e-6))
+                    .collect(),
+            });
+        }
+        Ok(cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::positions::{PositionAbsEntry, PositionsAbs};
+    use crate::cell::species::Species;
+    use crate::test_fixtures::{abc_lattice, cell, cubic_lattice};
+
+    fn cubic() -> CellDocument {
+        let positions = PositionsAbs {
+            unit: None,
+            positions: vec![
+                PositionAbsEntry::builder()
+                    .species(Species::Symbol("Cs".into()))
+                    .coord([0.0; 3])
+                    .build(),
+                PositionAbsEntry::builder()
+                    .species(Species::Symbol("Cl".into()))
+                    .coord([2.0, 2.0, 2.0])
+                    .build(),
+            ],
+        };
+        CellDocument {
+            fix_vol: Some(FixVOL(true)),
+            ..cell(cubic_lattice(4.0), positions)
+        }
+    }
+
+    #[test]
+    fn shear_moves_absolute_positions_with_the_cell() {
+        let cell = cubic()
+            .strained(VoigtStrain::component(5, 0.02).unwrap())
+            .unwrap();
+        let Lattice::Cart(lattice) = &cell.lattice else {
+            panic!("expected LATTICE_CART")
+        };
+        assert_eq!(lattice.a, [4.0, 0.04, 0.0]);
+        assert_eq!(lattice.b, [0.04, 4.0, 0.0]);
+        let Positions::Abs(abs) = &cell.positions else {
+            panic!("expected POSITIONS_ABS")
+        };
+        assert_eq!(abs.positions[1].coord, [2.02, 2.02, 2.0]);
+        assert_eq!(
+            cell.cell_constraints,
+            Some(CellConstraints {
+                lengths: [0; 3],
+                angles: [0; 3]
+            })
+        );
+        assert_eq!(cell.fix_vol, None);
+    }
+
+    #[test]
+    fn symmetry_ops_keep_only_surviving_operations() {
+        let mut doc = cubic();
+        doc.symmetry_ops = Some(SymmetryOps {
+            ops: vec![
+                SymmetryOp::identity(),
+                // Four-fold rotation about z.
+                SymmetryOp {
+                    rotation: [[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+                    translation: [0.0; 3],
+                },
+                // Two-fold rotation about z.
+                SymmetryOp {
+                    rotation: [[-1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]],
+                    translation: [0.0; 3],
+                },
+            ],
+        });
+        let strained = doc
+            .strained(VoigtStrain::component(0, 0.01).unwrap())
+            .unwrap();
+        assert_eq!(strained.symmetry_ops.unwrap().ops.len(), 2);
+        let scaled = doc.scaled_volume(1.1).unwrap();
+        assert_eq!(scaled.symmetry_ops.unwrap().ops.len(), 3);
+    }
+
+    #[test]
+    fn volume_scaling_keeps_the_shape() {
+        let doc = CellDocument {
+            lattice: abc_lattice([3.0, 4.0, 5.0], [90.0, 90.0, 120.0]),
+            ..cubic()
+        };
+        let scaled = doc.scaled_volume(1.331).unwrap();
+        let Lattice::Abc(abc) = &scaled.lattice else {
+            panic!("expected LATTICE_ABC")
+        };
+        for (got, want) in abc.abc.iter().zip([3.3, 4.4, 5.5]) {
+            assert!((got - want).abs() < 1e-12);
+        }
+        assert_eq!(abc.angles, [90.0, 90.0, 120.0]);
+        assert!((scaled.lattice.volume() / doc.lattice.volume() - 1.331).abs() < 1e-12);
+        assert_eq!(scaled.fix_vol, Some(FixVOL(true)));
+        assert!(doc.scaled_volume(-1.0).is_err());
+    }
+
+    #[test]
+    fn strain_sets_cover_every_component() {
+        let set = cubic().elastic_strain_set(0.005, 2).unwrap();
+        assert_eq!(set.len(), 24);
+        assert_eq!(set[3].strain, VoigtStrain::component(0, 0.01).unwrap());
+        assert!(VoigtStrain::component(6, 0.01).is_err());
+        let volumes: Vec<f64> = cubic()
+            .volume_strain_set(0.06, 5)
+            .unwrap()
+            .iter()
+            .map(|s| s.cell.lattice.volume() / 64.0)
+            .collect();
+        for (got, want) in volumes.iter().zip([0.94, 0.97, 1.0, 1.03, 1.06]) {
+            assert!((got - want).abs() < 1e-12, "{volumes:?}");
+        }
+    }
+}
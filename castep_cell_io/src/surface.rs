@@ -0,0 +1,515 @@
+//! Surface slabs cut from a bulk cell.
+//!
+//! A slab is built in three steps. The Miller indices pick two lattice vectors spanning
+//! the surface plane and a third one reaching the next equivalent plane; repeating this
+//! oriented cell `layers` times along the third vector gives the slab. Finally the third
+//! vector is replaced by one normal to the surface, long enough to hold the slab plus
+//! the vacuum gap, so the `c` axis of a slab is always perpendicular to `a` and `b`.
+//!
+//! Per-ion blocks are replicated as in [`CellDocument::transform`]. The
+//! transition-state geometries and `NONLINEAR_CONSTRAINTS` describe the bulk and are
+//! dropped, as are the blocks [`transform`](CellDocument::transform) drops.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::constraints::{AtomSelector, CellConstraints, IonMotion, IonicConstraints};
+use crate::cell::lattice_param::LatticeCart;
+use crate::cell::positions::{PositionsAbs, PositionsFrac};
+use crate::cell::species::Species;
+use crate::math::{self, IMat3, Mat3, Vec3};
+use crate::{CellDocument, Lattice, Positions};
+
+/// Settings for cutting a slab.
+///
+/// # Example
+///
+/// ```no_run
+/// use castep_cell_io::CellDocument;
+/// use castep_cell_io::surface::Slab;
+///
+/// # fn run(bulk: &CellDocument) -> castep_cell_fmt::CResult<()> {
+/// let slab = Slab::builder()
+///     .miller([1, 1, 1])
+///     .layers(4)
+///     .vacuum(15.0)
+///     .fixed_layers(2)
+///     .build()
+///     .cut(bulk)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, bon::Builder)]
+pub struct Slab {
+    /// Miller indices of the surface in the reciprocal basis of the bulk cell; common
+    /// factors are removed.
+    pub miller: [i32; 3],
+    /// Number of repeats of the oriented bulk cell, each one interplanar spacing thick.
+    pub layers: u32,
+    /// Gap between the top of the slab and the bottom of its periodic image, in Å.
+    pub vacuum: f64,
+    /// Index into [`terminations`](Self::terminations) of the atomic plane at the
+    /// bottom of the slab.
+    #[builder(default)]
+    pub termination: usize,
+    /// Puts the slab in the middle of the cell, with half the vacuum on either side;
+    /// otherwise the bottom plane sits at `c = 0`.
+    #[builder(default = true)]
+    pub centre: bool,
+    /// Uses a rectangular surface cell when one exists with at most twice the area of
+    /// the primitive one, e.g. the centred cell of bcc (110) or the √3 cell of a
+    /// hexagonal plane.
+    #[builder(default)]
+    pub orthogonalise: bool,
+    /// Fixes the atoms in this many bottom layers through `IONIC_CONSTRAINTS`.
+    pub fixed_layers: Option<u32>,
+    /// Writes `CELL_CONSTRAINTS` that keep the `c` axis and the angles it makes fixed,
+    /// so a cell relaxation cannot collapse the vacuum.
+    #[builder(default)]
+    pub fix_vacuum: bool,
+    /// Atoms whose heights differ by less than this many Å lie in one plane.
+    #[builder(default = 0.01)]
+    pub tolerance: f64,
+}
+
+/// One atomic plane a slab can start from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Termination {
+    /// Height of the plane above the origin of the bulk cell, along the surface normal
+    /// and within one interplanar spacing, in Å.
+    pub height: f64,
+    /// Species in the plane, in order of first appearance.
+    pub species: Vec<Species>,
+}
+
+/// Surface basis of a bulk lattice.
+struct Orientation {
+    /// Columns are the two in-plane vectors and the out-of-plane repeat, in fractional
+    /// coordinates of the bulk cell.
+    matrix: IMat3,
+    /// Unit normal of the surface, in the direction of the out-of-plane repeat.
+    normal: Vec3,
+    /// Height of the out-of-plane repeat, in Å.
+    spacing: f64,
+}
+
+/// Lowest height and atoms of an atomic plane of the oriented cell.
+struct Plane {
+    height: f64,
+    atoms: Vec<usize>,
+}
+
+impl Slab {
+    /// Returns the distinct atomic planes of one oriented bulk cell, bottom first.
+    ///
+    /// Fails if the Miller indices are all zero.
+    pub fn terminations(&self, bulk: &CellDocument) -> CResult<Vec<Termination>> {
+        let orientation = self.orientation(bulk)?;
+        let species = bulk.positions.species();
+        Ok(self
+            .planes(bulk, &orientation)?
+            .into_iter()
+            .map(|plane| {
+                let mut kinds: Vec<Species> = Vec::new();
+                for &i in &plane.atoms {
+                    if !kinds.iter().any(|s| s.same_species(species[i])) {
+                        kinds.push(species[i].clone());
+                    }
+                }
+                Termination {
+                    height: plane.height,
+                    species: kinds,
+                }
+            })
+            .collect())
+    }
+
+    /// Cuts the slab from `bulk`.
+    ///
+    /// Positions keep the coordinate mode of `bulk` and are wrapped into the surface
+    /// cell. Fails if the Miller indices are all zero, `layers` is zero, the vacuum is
+    /// negative, the termination does not exist or more layers are fixed than built.
+    pub fn cut(&self, bulk: &CellDocument) -> CResult<CellDocument> {
+        if self.layers == 0 {
+            return Err(Error::Message("a slab needs at least one layer".into()));
+        }
+        if self.vacuum < 0.0 || !self.vacuum.is_finite() {
+            return Err(Error::Message(format!(
+                "vacuum must be non-negative, got {} Å",
+                self.vacuum
+            )));
+        }
+        if let Some(fixed) = self.fixed_layers.filter(|&n| n > self.layers) {
+            return Err(Error::Message(format!(
+                "cannot fix {fixed} layers of a {}-layer slab",
+                self.layers
+            )));
+        }
+        let orientation = self.orientation(bulk)?;
+        let planes = self.planes(bulk, &orientation)?;
+        let bottom = planes.get(self.termination).ok_or_else(|| {
+            Error::Message(format!(
+                "termination {} does not exist, the surface has {} planes",
+                self.termination,
+                planes.len()
+            ))
+        })?;
+
+        // Move the chosen plane just above the origin so it ends up at the bottom.
+        let repeat = column(orientation.matrix, 2);
+        let shift = math::scale(
+            repeat,
+            -(bottom.height - self.tolerance / 2.0) / orientation.spacing,
+        );
+        let mut shifted = CellDocument {
+            positions_intermediate: None,
+            positions_product: None,
+            nonlinear_constraints: None,
+            ..bulk.clone()
+        };
+        let coords: Vec<Vec3> = bulk
+            .positions
+            .frac_coords(&bulk.lattice)?
+            .iter()
+            .map(|x| math::add(*x, shift))
+            .collect();
+        shifted.positions = place(&bulk.positions, &bulk.lattice, &coords);
+
+        let mut matrix = orientation.matrix;
+        for row in &mut matrix {
+            row[2] *= self.layers as i32;
+        }
+        let mut slab = shifted.transform(matrix)?;
+
+        // Stand the slab on the surface normal, replacing the oblique repeat.
+        let [a, b, _] = slab.lattice.vectors();
+        let heights: Vec<f64> = slab
+            .positions
+            .cart_coords(&slab.lattice)
+            .iter()
+            .map(|r| math::dot(*r, orientation.normal))
+            .collect();
+        let lowest = heights.iter().copied().fold(f64::INFINITY, f64::min);
+        let highest = heights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let length = highest - lowest + self.vacuum;
+        if length <= 0.0 {
+            return Err(Error::Message(
+                "a single-plane slab needs some vacuum".into(),
+            ));
+        }
+        let c = math::scale(orientation.normal, length);
+        let vectors = [a, b, c];
+        let inverse = math::lattice_inverse(vectors)?;
+        let offset = if self.centre { self.vacuum / 2.0 } else { 0.0 };
+        let coords: Vec<Vec3> = slab
+            .positions
+            .cart_coords(&slab.lattice)
+            .iter()
+            .zip(&heights)
+            .map(|(r, h)| {
+                let in_plane = math::sub(*r, math::scale(orientation.normal, *h));
+                let r = math::add(in_plane, math::scale(c, (h - lowest + offset) / length));
+                let x = math::vec_mat(r, inverse);
+                [x[0].rem_euclid(1.0), x[1].rem_euclid(1.0), x[2]]
+            })
+            .collect();
+        let unit = slab.lattice.unit();
+        let f = unit.unwrap_or_default().in_angstrom();
+        let [a, b, c] = vectors.map(|v| v.map(|x| x / f));
+        slab.lattice = Lattice::Cart(LatticeCart { unit, a, b, c });
+        slab.positions = place(&slab.positions, &slab.lattice, &coords);
+
+        if let Some(fixed) = self.fixed_layers.filter(|&n| n > 0) {
+            let height = offset + f64::from(fixed) * orientation.spacing - self.tolerance / 2.0;
+            let selector = AtomSelector::BelowHeight(height);
+            let mut constraints = slab.ionic_constraints.take().unwrap_or(IonicConstraints {
+                constraints: Vec::new(),
+            });
+            constraints.add_selection(&slab, &selector, IonMotion::Fixed)?;
+            slab.ionic_constraints = Some(constraints);
+        }
+        if self.fix_vacuum {
+            slab.cell_constraints = Some(CellConstraints {
+                lengths: [1, 2, 0],
+                angles: [0, 0, 3],
+            });
+        }
+        Ok(slab)
+    }
+
+    /// Finds the surface basis: reduced in-plane vectors and an out-of-plane repeat
+    /// one interplanar spacing high, forming a right-handed basis of the bulk lattice.
+    fn orientation(&self, bulk: &CellDocument) -> CResult<Orientation> {
+        let g = self
+            .miller
+            .iter()
+            .fold(0, |g, &x| gcd(g, x.unsigned_abs() as i32));
+        if g == 0 {
+            return Err(Error::Message("Miller indices must not all be zero".into()));
+        }
+        let h = self.miller.map(|x| i64::from(x / g));
+        let (l, r, d) = math::diagonalize(&[h]);
+        // `h · R = (±1, 0, 0)`, so the last two columns of `R` lie in the plane.
+        let col = |j: usize| [r[0][j], r[1][j], r[2][j]].map(|x| x as i32);
+        let sign = (l[0][0] * d[0][0]) as i32;
+        let repeat = col(0).map(|x| x * sign);
+
+        let a = bulk.lattice.vectors();
+        let cart = |v: [i32; 3]| math::vec_mat(v.map(f64::from), a);
+        let (mut u, mut w) = reduce_2d(a, col(1), col(2));
+        if self.orthogonalise
+            && let Some((x, y)) = rectangular(a, u, w)
+        {
+            (u, w) = (x, y);
+        }
+        if math::det([cart(u), cart(w), cart(repeat)]) < 0.0 {
+            w = w.map(|x| -x);
+        }
+        let normal = math::cross(cart(u), cart(w));
+        let normal = math::scale(normal, 1.0 / math::norm(normal));
+        Ok(Orientation {
+            matrix: [0, 1, 2].map(|i| [u[i], w[i], repeat[i]]),
+            normal,
+            spacing: math::dot(cart(repeat), normal),
+        })
+    }
+
+    /// Groups the atoms of one oriented cell into planes by their height along the
+    /// normal, merging a plane at the top with one at the bottom if they are periodic
+    /// images.
+    fn planes(&self, bulk: &CellDocument, orientation: &Orientation) -> CResult<Vec<Plane>> {
+        let p_inv =
+            math::inverse(math::to_f64(orientation.matrix)).expect("surface basis is unimodular");
+        let mut heights: Vec<(f64, usize)> = bulk
+            .positions
+            .frac_coords(&bulk.lattice)?
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let z = math::mat_vec(p_inv, *x)[2].rem_euclid(1.0);
+                (z * orientation.spacing, i)
+            })
+            .collect();
+        heights.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+        let mut planes: Vec<Plane> = Vec::new();
+        let mut previous = f64::NEG_INFINITY;
+        for (h, i) in heights {
+            match planes.last_mut() {
+                Some(plane) if h - previous < self.tolerance => plane.atoms.push(i),
+                _ => planes.push(Plane {
+                    height: h,
+                    atoms: vec![i],
+                }),
+            }
+            previous = h;
+        }
+        if planes.len() > 1 && planes[0].height + orientation.spacing - previous < self.tolerance {
+            let top = planes.pop().expect("more than one plane");
+            planes[0].height = top.height - orientation.spacing;
+            planes[0].atoms.splice(0..0, top.atoms);
+        }
+        Ok(planes)
+    }
+}
+
+fn column(m: IMat3, j: usize) -> Vec3 {
+    [m[0][j], m[1][j], m[2][j]].map(f64::from)
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Gauss–Lagrange reduction of the plane lattice spanned by `u` and `w`, given in
+/// fractional coordinates of the lattice rows `a`.
+fn reduce_2d(a: Mat3, mut u: [i32; 3], mut w: [i32; 3]) -> ([i32; 3], [i32; 3]) {
+    let cart = |v: [i32; 3]| math::vec_mat(v.map(f64::from), a);
+    loop {
+        if math::norm(cart(w)) < math::norm(cart(u)) {
+            std::mem::swap(&mut u, &mut w);
+        }
+        let (cu, cw) = (cart(u), cart(w));
+        let ratio = math::dot(cu, cw) / math::dot(cu, cu);
+        // Reduced once `w` projects onto at most half of `u`.
+        if ratio.abs() <= 0.5 + 1e-9 {
+            return (u, w);
+        }
+        let m = ratio.round() as i32;
+        w = [0, 1, 2].map(|k| w[k] - m * u[k]);
+    }
+}
+
+/// Shortest pair of perpendicular vectors spanning a sublattice of index 1 or 2 of the
+/// plane lattice spanned by `u` and `w`.
+fn rectangular(a: Mat3, u: [i32; 3], w: [i32; 3]) -> Option<([i32; 3], [i32; 3])> {
+    let cart = |v: [i32; 3]| math::vec_mat(v.map(f64::from), a);
+    let combine = |(i, j): (i32, i32)| [0, 1, 2].map(|k| i * u[k] + j * w[k]);
+    let coefficients: Vec<(i32, i32)> = (-3..=3)
+        .flat_map(|i| (-3..=3).map(move |j| (i, j)))
+        .filter(|&c| c != (0, 0))
+        .collect();
+    let mut best: Option<(i32, f64, [i32; 3], [i32; 3])> = None;
+    for &(i, j) in &coefficients {
+        for &(k, l) in &coefficients {
+            let index = i * l - j * k;
+            if !(1..=2).contains(&index) {
+                continue;
+            }
+            let (x, y) = (combine((i, j)), combine((k, l)));
+            let (cx, cy) = (cart(x), cart(y));
+            let (nx, ny) = (math::norm(cx), math::norm(cy));
+            if math::dot(cx, cy).abs() > 1e-8 * nx * ny {
+                continue;
+            }
+            let size = nx + ny;
+            let beats =
+                best.is_none_or(|(bi, bs, _, _)| index < bi || (index == bi && size < bs - 1e-8));
+            if beats {
+                best = Some((index, size, x, y));
+            }
+        }
+    }
+    best.map(|(_, _, x, y)| (x, y))
+}
+
+/// Builds a positions block in the mode of `positions` with atom `i` at fractional
+/// coordinates `coords[i]` of `lattice`.
+fn place(positions: &Positions, lattice: &Lattice, coords: &[Vec3]) -> Positions {
+    match positions {
+        Positions::Frac(frac) => {
+            let mut frac: PositionsFrac = frac.clone();
+            for (p, x) in frac.positions.iter_mut().zip(coords) {
+                p.coord = *x;
+            }
+            Positions::Frac(frac)
+        }
+        Positions::Abs(abs) => {
+            let mut abs: PositionsAbs = abs.clone();
+            let f = abs.unit.unwrap_or_default().in_angstrom();
+            let vectors = lattice.vectors();
+            for (p, x) in abs.positions.iter_mut().zip(coords) {
+                p.coord = math::frac_to_cart(vectors, *x).map(|v| v / f);
+            }
+            Positions::Abs(abs)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::cubic_cell;
+
+    fn fcc() -> CellDocument {
+        cubic_cell(
+            3.6,
+            &[
+                ("Cu", [0.0, 0.0, 0.0]),
+                ("Cu", [0.5, 0.5, 0.0]),
+                ("Cu", [0.5, 0.0, 0.5]),
+                ("Cu", [0.0, 0.5, 0.5]),
+            ],
+        )
+    }
+
+    #[test]
+    fn fcc_100_slab_is_centred_in_its_vacuum() {
+        let slab = Slab::builder()
+            .miller([2, 0, 0])
+            .layers(2)
+            .vacuum(10.0)
+            .fixed_layers(1)
+            .fix_vacuum(true)
+            .build()
+            .cut(&fcc())
+            .unwrap();
+        assert_eq!(slab.positions.len(), 8);
+        let [a, b, c] = slab.lattice.vectors();
+        assert!((math::norm(a) - 3.6).abs() < 1e-9);
+        assert!(math::dot(a, b).abs() < 1e-9);
+        assert!(math::dot(a, c).abs() < 1e-9 && math::dot(b, c).abs() < 1e-9);
+        // Four planes 1.8 Å apart, then 10 Å of vacuum.
+        assert!((math::norm(c) - 15.4).abs() < 1e-9);
+        let heights: Vec<f64> = slab
+            .positions
+            .cart_coords(&slab.lattice)
+            .iter()
+            .map(|r| math::dot(*r, c) / math::norm(c))
+            .collect();
+        let lowest = heights.iter().copied().fold(f64::INFINITY, f64::min);
+        assert!((lowest - 5.0).abs() < 1e-9, "{heights:?}");
+        // The bottom layer holds the two lowest planes, two atoms each.
+        let fixed = slab.ionic_constraints.unwrap().constraints;
+        assert_eq!(fixed.len(), 12);
+        assert_eq!(slab.cell_constraints.unwrap().lengths, [1, 2, 0]);
+    }
+
+    #[test]
+    fn termination_picks_the_bottom_plane() {
+        let cscl = cubic_cell(4.1, &[("Cs", [0.0; 3]), ("Cl", [0.5, 0.5, 0.5])]);
+        let slab = Slab::builder()
+            .miller([0, 0, 1])
+            .layers(3)
+            .vacuum(12.0)
+            .termination(1)
+            .centre(false)
+            .build();
+        let terminations = slab.terminations(&cscl).unwrap();
+        assert_eq!(terminations.len(), 2);
+        assert_eq!(terminations[1].species, vec![Species::Symbol("Cl".into())]);
+        assert!((terminations[1].height - 2.05).abs() < 1e-9);
+
+        let cut = slab.cut(&cscl).unwrap();
+        let coords = cut.positions.frac_coords(&cut.lattice).unwrap();
+        let species = cut.positions.species();
+        let bottom = (0..coords.len())
+            .min_by(|&i, &j| coords[i][2].total_cmp(&coords[j][2]))
+            .unwrap();
+        assert_eq!(species[bottom].to_string(), "Cl");
+        assert!(coords[bottom][2].abs() < 1e-9);
+        assert!(
+            Slab::builder()
+                .miller([0, 0, 1])
+                .layers(1)
+                .vacuum(10.0)
+                .termination(2)
+                .build()
+                .cut(&cscl)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn hexagonal_plane_can_be_made_rectangular() {
+        let slab = Slab::builder()
+            .miller([1, 1, 1])
+            .layers(3)
+            .vacuum(10.0)
+            .orthogonalise(true)
+            .build();
+        let cut = slab.cut(&fcc()).unwrap();
+        let [a, b, _] = cut.lattice.vectors();
+        assert!(math::dot(a, b).abs() < 1e-9);
+        // The rectangular cell doubles the four-atom hexagonal cell of each plane.
+        assert_eq!(cut.positions.len(), 24);
+
+        let hexagonal = Slab {
+            orthogonalise: false,
+            ..slab
+        };
+        let cut = hexagonal.cut(&fcc()).unwrap();
+        let [a, b, _] = cut.lattice.vectors();
+        let cos = math::dot(a, b) / (math::norm(a) * math::norm(b));
+        assert!((cos.abs() - 0.5).abs() < 1e-9);
+        assert_eq!(cut.positions.len(), 12);
+        assert!(
+            Slab::builder()
+                .miller([0, 0, 0])
+                .layers(1)
+                .vacuum(10.0)
+                .build()
+                .cut(&fcc())
+                .is_err()
+        );
+    }
+}
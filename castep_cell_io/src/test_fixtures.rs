@@ -0,0 +1,66 @@
+//! Lattices and cells shared by the unit tests.
+
+use crate::cell::lattice_param::{LatticeABC, LatticeCart};
+use crate::cell::positions::{PositionFracEntry, PositionsFrac};
+use crate::cell::species::Species;
+use crate::{CellDocument, Lattice, Positions};
+
+/// `LATTICE_CART` of a cube with edge `a` Å.
+pub(crate) fn cubic_lattice(a: f64) -> Lattice {
+    orthorhombic_lattice(a, a, a)
+}
+
+/// `LATTICE_CART` with edges of `a`, `b` and `c` Å along x, y and z.
+pub(crate) fn orthorhombic_lattice(a: f64, b: f64, c: f64) -> Lattice {
+    LatticeCart {
+        unit: None,
+        a: [a, 0.0, 0.0],
+        b: [0.0, b, 0.0],
+        c: [0.0, 0.0, c],
+    }
+    .into()
+}
+
+/// `LATTICE_ABC` with lengths in Å and angles in degrees.
+pub(crate) fn abc_lattice(abc: [f64; 3], angles: [f64; 3]) -> Lattice {
+    LatticeABC {
+        unit: None,
+        abc,
+        angles,
+    }
+    .into()
+}
+
+/// A `POSITIONS_FRAC` row of an element symbol, without spin or mixture.
+pub(crate) fn atom(symbol: &str, coord: [f64; 3]) -> PositionFracEntry {
+    PositionFracEntry {
+        species: Species::Symbol(symbol.into()),
+        coord,
+        spin: None,
+        mixture: None,
+    }
+}
+
+/// `POSITIONS_FRAC` of element symbols at fractional coordinates.
+pub(crate) fn frac_atoms(atoms: &[(&str, [f64; 3])]) -> PositionsFrac {
+    PositionsFrac {
+        positions: atoms
+            .iter()
+            .map(|(symbol, coord)| atom(symbol, *coord))
+            .collect(),
+    }
+}
+
+/// A cell with nothing but `lattice` and `positions` set.
+pub(crate) fn cell(lattice: impl Into<Lattice>, positions: impl Into<Positions>) -> CellDocument {
+    CellDocument::builder()
+        .lattice(lattice)
+        .positions(positions)
+        .build()
+        .unwrap()
+}
+
+/// A cube with edge `a` Å holding `atoms` at fractional coordinates.
+pub(crate) fn cubic_cell(a: f64, atoms: &[(&str, [f64; 3])]) -> CellDocument {
+    cell(cubic_lattice(a), frac_atoms(atoms))
+}
@@ -0,0 +1,781 @@
+//! Basis changes of a cell: supercells, lattice reduction and primitive cells.
+//!
+//! Every transformation keeps the Cartesian orientation of the cell, so Cartesian
+//! quantities (`IONIC_CONSTRAINTS` coefficients, `IONIC_VELOCITIES`, absolute positions)
+//! carry over unchanged; `QUANTIZATION_AXIS` is re-expressed in the new fractional
+//! basis. Per-ion blocks are renumbered to follow the new atom order.
+
+use castep_cell_fmt::{CResult, Error};
+
+use crate::cell::constraints::{
+    AtomSite, IonicConstraintEntry, IonicConstraints, NonlinearConstraint, NonlinearConstraints,
+};
+use crate::cell::lattice_param::LatticeCart;
+use crate::cell::positions::{PositionAbsEntry, PositionFracEntry, PositionsAbs, PositionsFrac};
+use crate::cell::species::{AtomHubbardU, HubbardU, QuantizationAxis, Species};
+use crate::cell::symmetry::{atom_kinds, primitive_transformation};
+use crate::cell::velocities::{IonicVelocities, IonicVelocityEntry};
+use crate::math::{self, IMat3, Mat3, Vec3};
+use crate::units::LengthUnit;
+use crate::{CellDocument, IonIndex, Lattice, Positions};
+
+impl Lattice {
+    /// Returns the lattice whose vectors are the columns of `matrix`, given in
+    /// fractional coordinates of `self`.
+    ///
+    /// The result is a `LATTICE_CART` in the unit of `self` with the same orientation.
+    pub fn transform(&self, matrix: [[i32; 3]; 3]) -> Lattice {
+        self.change_basis(math::to_f64(matrix))
+    }
+
+    /// Niggli-reduces the lattice.
+    ///
+    /// Returns the reduced lattice and the integer matrix whose columns are the original
+    /// vectors in fractional coordinates of the reduced lattice, so that
+    /// `reduced.transform(matrix)` recovers `self`. Fails if the lattice vectors are
+    /// linearly dependent.
+    pub fn niggli_reduce(&self) -> CResult<(Lattice, [[i32; 3]; 3])> {
+        let p = math::niggli_reduce(self.vectors(), self.reduction_eps());
+        Ok((self.transform(p), reduction_inverse(p)?))
+    }
+
+    /// Delaunay-reduces the lattice, returning the same kind of matrix as
+    /// [`niggli_reduce`](Self::niggli_reduce).
+    pub fn delaunay_reduce(&self) -> CResult<(Lattice, [[i32; 3]; 3])> {
+        let p = math::delaunay_reduce(self.vectors(), self.reduction_eps());
+        Ok((self.transform(p), reduction_inverse(p)?))
+    }
+
+    pub(crate) fn unit(&self) -> Option<LengthUnit> {
+        match self {
+            Lattice::Cart(cart) => cart.unit,
+            Lattice::Abc(abc) => abc.unit,
+        }
+    }
+
+    fn reduction_eps(&self) -> f64 {
+        1e-5 * self.volume().powf(2.0 / 3.0)
+    }
+
+    fn change_basis(&self, p: Mat3) -> Lattice {
+        let unit = self.unit();
+        let f = unit.unwrap_or_default().in_angstrom();
+        let rows = math::mat_mul(math::transpose(p), self.vectors());
+        let [a, b, c] = rows.map(|v| v.map(|x| x / f));
+        Lattice::Cart(LatticeCart { unit, a, b, c })
+    }
+}
+
+impl CellDocument {
+    /// Re-expresses the cell in the basis whose vectors are the columns of `matrix`,
+    /// given in fractional coordinates of the current lattice.
+    ///
+    /// With `|det(matrix)| > 1` the result is a supercell: atoms, `IONIC_CONSTRAINTS`,
+    /// `NONLINEAR_CONSTRAINTS`, per-ion `HUBBARD_U` entries, `IONIC_VELOCITIES` and the
+    /// transition-state geometries are replicated into every copy. Positions are wrapped into the new cell and
+    /// nonlinear-constraint image indices are updated to match.
+    ///
+    /// Blocks expressed in the old reciprocal or direct basis (`SYMMETRY_OPS`,
+    /// `CELL_CONSTRAINTS`, explicit k-point lists, paths and grids, and the phonon
+    /// supercell blocks) are dropped.
+    pub fn transform(&self, matrix: [[i32; 3]; 3]) -> CResult<CellDocument> {
+        if math::imat_det(matrix) == 0 {
+            return Err(Error::Message("transformation matrix is singular".into()));
+        }
+        rebuild(
+            self,
+            math::to_f64(matrix),
+            &supercell_translations(matrix)?,
+            None,
+        )
+    }
+
+    /// Niggli-reduces the cell, carrying positions and per-ion blocks along as
+    /// [`transform`](Self::transform) does.
+    ///
+    /// Returns the reduced cell and the matrix recovering the input:
+    /// `reduced.transform(matrix)` gives back the original lattice and atoms.
+    pub fn niggli_reduce(&self) -> CResult<(CellDocument, [[i32; 3]; 3])> {
+        let (_, recover) = self.lattice.niggli_reduce()?;
+        let p = reduction_inverse(recover)?;
+        Ok((self.transform(p)?, recover))
+    }
+
+    /// Finds the primitive cell using `SYMMETRY_TOL` if set, or the CASTEP default of
+    /// 0.01 Å otherwise.
+    ///
+    /// Atoms related by a pure translation are merged, and per-ion blocks are mapped
+    /// onto the remaining atoms with duplicates removed; atoms merged into one must
+    /// agree on `IONIC_VELOCITIES`, per-ion `HUBBARD_U` values and transition-state
+    /// displacements. Returns the primitive
+    /// cell and the integer matrix recovering the input: `primitive.transform(matrix)`
+    /// rebuilds the original lattice and atoms, up to the order of the atoms.
+    pub fn primitive_cell(&self) -> CResult<(CellDocument, [[i32; 3]; 3])> {
+        let tolerance = self.symmetry_tol.unwrap_or_default();
+        let (_, primitive) = primitive_transformation(self, &tolerance)?;
+        let recover = math::inverse(primitive)
+            .and_then(|m| math::to_int(m, 1e-6))
+            .ok_or_else(|| Error::Message("primitive basis is not a sublattice".into()))?;
+        let tol = tolerance.value * tolerance.unit.in_angstrom();
+        let cell = rebuild(self, primitive, &[[0.0; 3]], Some(tol))?;
+        Ok((cell, recover))
+    }
+}
+
+/// Inverse of a reduction matrix, which is unimodular unless the lattice was degenerate.
+fn reduction_inverse(p: IMat3) -> CResult<IMat3> {
+    math::iinverse(p).ok_or_else(|| {
+        Error::Message("lattice reduction failed: lattice vectors are linearly dependent".into())
+    })
+}
+
+/// Lattice vectors of the current cell, in its fractional coordinates, that lie inside
+/// the cell spanned by the columns of `matrix`; the origin comes first.
+fn supercell_translations(matrix: IMat3) -> CResult<Vec<Vec3>> {
+    let p_inv = math::inverse(math::to_f64(matrix))
+        .ok_or_else(|| Error::Message("transformation matrix is singular".into()))?;
+    let mut lo = [0; 3];
+    let mut hi = [0; 3];
+    for corner in 0..8 {
+        for (i, (lo, hi)) in lo.iter_mut().zip(hi.iter_mut()).enumerate() {
+            let x: i32 = (0..3)
+                .filter(|k| corner & (1 << k) != 0)
+                .map(|k| matrix[i][k])
+                .sum();
+            *lo = (*lo).min(x);
+            *hi = (*hi).max(x);
+        }
+    }
+    let mut translations = Vec::new();
+    for i in lo[0]..=hi[0] {
+        for j in lo[1]..=hi[1] {
+            for k in lo[2]..=hi[2] {
+                let n = [i as f64, j as f64, k as f64];
+                let f = math::mat_vec(p_inv, n);
+                if f.iter().all(|x| *x > -1e-9 && *x < 1.0 - 1e-9) {
+                    translations.push([i, j, k]);
+                }
+            }
+        }
+    }
+    translations.sort_by_key(|n| (*n != [0, 0, 0], *n));
+    Ok(translations.into_iter().map(|n| n.map(f64::from)).collect())
+}
+
+/// Returns `true` if the constraints act on the same atoms, with every image shifted
+/// by one common lattice vector.
+fn same_up_to_translation(a: &NonlinearConstraint, b: &NonlinearConstraint) -> bool {
+    let shift =
+        |x: &AtomSite, y: &AtomSite| [0, 1, 2].map(|k| y.image_indices[k] - x.image_indices[k]);
+    a.constraint_type == b.constraint_type
+        && a.atom_sites.len() == b.atom_sites.len()
+        && a.atom_sites.iter().zip(&b.atom_sites).all(|(x, y)| {
+            x.species == y.species
+                && x.ion_number == y.ion_number
+                && shift(x, y) == shift(&a.atom_sites[0], &b.atom_sites[0])
+        })
+}
+
+/// Rebuilds `doc` in the basis whose vectors are the columns of `p`, in fractional
+/// coordinates of `doc`.
+///
+/// Every atom is placed once per entry of `translations`. With `merge` set, images of
+/// equivalent atoms closer than that distance (Å) collapse into a single atom.
+fn rebuild(
+    doc: &CellDocument,
+    p: Mat3,
+    translations: &[Vec3],
+    merge: Option<f64>,
+) -> CResult<CellDocument> {
+    let p_inv = math::inverse(p)
+        .ok_or_else(|| Error::Message("transformation matrix is singular".into()))?;
+    let lattice = doc.lattice.change_basis(p);
+    let a = lattice.vectors();
+    let coords = doc.positions.frac_coords(&doc.lattice)?;
+    let kinds = atom_kinds(&doc.positions);
+    let old = IonIndex::new(&doc.positions);
+
+    // copies[c][i] is the new atom holding old atom i shifted by translations[c].
+    let mut sources: Vec<usize> = Vec::new();
+    let mut new_coords: Vec<Vec3> = Vec::new();
+    let mut copies = vec![vec![0; coords.len()]; translations.len()];
+    for (i, x) in coords.iter().enumerate() {
+        for (c, t) in translations.iter().enumerate() {
+            let y = math::wrap(math::mat_vec(p_inv, math::add(*x, *t)));
+            let existing = merge.and_then(|tol| {
+                (0..new_coords.len()).find(|&j| {
+                    kinds[sources[j]] == kinds[i]
+                        && math::periodic_distance(a, math::sub(new_coords[j], y)) < tol
+                })
+            });
+            copies[c][i] = existing.unwrap_or_else(|| {
+                sources.push(i);
+                new_coords.push(y);
+                new_coords.len() - 1
+            });
+        }
+    }
+
+    // Builds a positions block in the mode of `positions` with atom j at `coords[j]`.
+    let place = |positions: &Positions, coords: &[Vec3]| match positions {
+        Positions::Frac(frac) => Positions::Frac(PositionsFrac {
+            positions: sources
+                .iter()
+                .zip(coords)
+                .map(|(&i, x)| PositionFracEntry {
+                    coord: *x,
+                    ..frac.positions[i].clone()
+                })
+                .collect(),
+        }),
+        Positions::Abs(abs) => {
+            let f = abs.unit.unwrap_or_default().in_angstrom();
+            Positions::Abs(PositionsAbs {
+                unit: abs.unit,
+                positions: sources
+                    .iter()
+                    .zip(coords)
+                    .map(|(&i, x)| PositionAbsEntry {
+                        coord: math::frac_to_cart(a, *x).map(|v| v / f),
+                        ..abs.positions[i].clone()
+                    })
+                    .collect(),
+            })
+        }
+    };
+    let positions = place(&doc.positions, &new_coords);
+    let new = IonIndex::new(&positions);
+
+    // Transition-state geometries keep each atom's displacement from the reactant, so
+    // wrapping the reactant does not tear images apart.
+    let match_tol = merge.unwrap_or(0.0).max(1e-6);
+    let image = |positions: &Positions, block: &str| -> CResult<Positions> {
+        let shifted = positions.frac_coords(&doc.lattice)?;
+        if shifted.len() != coords.len() {
+            return Err(Error::Message(format!(
+                "{block} lists {} atoms but the cell has {}",
+                shifted.len(),
+                coords.len()
+            )));
+        }
+        let displacements: Vec<Vec3> = shifted
+            .iter()
+            .zip(&coords)
+            .map(|(y, x)| math::mat_vec(p_inv, math::sub(*y, *x)))
+            .collect();
+        let mut image_coords: Vec<Option<Vec3>> = vec![None; sources.len()];
+        for copy in &copies {
+            for (i, &j) in copy.iter().enumerate() {
+                let y = math::add(new_coords[j], displacements[i]);
+                match image_coords[j] {
+                    None => image_coords[j] = Some(y),
+                    Some(z) if math::norm(math::frac_to_cart(a, math::sub(y, z))) < match_tol => {}
+                    Some(_) => {
+                        return Err(Error::Message(format!(
+                            "atoms merged into {} {} have different {block} displacements",
+                            new.species(j),
+                            new.ion_number(j)
+                        )));
+                    }
+                }
+            }
+        }
+        let image_coords: Vec<Vec3> = image_coords.into_iter().flatten().collect();
+        Ok(place(positions, &image_coords))
+    };
+    let positions_intermediate = doc
+        .positions_intermediate
+        .as_ref()
+        .map(|p| image(&p.to_positions(), "POSITIONS_*_INTERMEDIATE").map(Into::into))
+        .transpose()?;
+    let positions_product = doc
+        .positions_product
+        .as_ref()
+        .map(|p| image(&p.to_positions(), "POSITIONS_*_PRODUCT").map(Into::into))
+        .transpose()?;
+
+    let ionic_constraints = doc
+        .ionic_constraints
+        .as_ref()
+        .map(|ic| -> CResult<IonicConstraints> {
+            let mut numbers: Vec<u32> = Vec::new();
+            for entry in &ic.constraints {
+                if !numbers.contains(&entry.constraint_number) {
+                    numbers.push(entry.constraint_number);
+                }
+            }
+            let mut groups: Vec<Vec<(usize, [f64; 3])>> = Vec::new();
+            for number in numbers {
+                let entries = ic
+                    .constraints
+                    .iter()
+                    .filter(|e| e.constraint_number == number)
+                    .map(|e| Ok((old.resolve(&e.species, e.ion_number)?, e.coefficients)))
+                    .collect::<CResult<Vec<_>>>()?;
+                for copy in &copies {
+                    let mapped: Vec<(usize, [f64; 3])> =
+                        entries.iter().map(|(i, r)| (copy[*i], *r)).collect();
+                    if !groups.contains(&mapped) {
+                        groups.push(mapped);
+                    }
+                }
+            }
+            Ok(IonicConstraints {
+                constraints: groups
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(k, group)| group.iter().map(move |(j, r)| (k, *j, *r)))
+                    .map(|(k, j, coefficients)| IonicConstraintEntry {
+                        constraint_number: k as u32 + 1,
+                        species: new.species(j).clone(),
+                        ion_number: new.ion_number(j),
+                        coefficients,
+                    })
+                    .collect(),
+            })
+        })
+        .transpose()?;
+
+    let nonlinear_constraints = doc
+        .nonlinear_constraints
+        .as_ref()
+        .map(|nc| -> CResult<NonlinearConstraints> {
+            let mut constraints: Vec<NonlinearConstraint> = Vec::new();
+            for constraint in &nc.constraints {
+                for t in translations {
+                    let atom_sites = constraint
+                        .atom_sites
+                        .iter()
+                        .map(|site| {
+                            let i = old.resolve(&site.species, site.ion_number)?;
+                            let image = site.image_indices.map(f64::from);
+                            let u =
+                                math::mat_vec(p_inv, math::add(math::add(coords[i], image), *t));
+                            let j = copies
+                                .iter()
+                                .map(|copy| copy[i])
+                                .find(|&j| {
+                                    math::periodic_distance(a, math::sub(u, new_coords[j]))
+                                        < match_tol
+                                })
+                                .ok_or_else(|| {
+                                    Error::Message(
+                                        "nonlinear constraint site has no image in the new cell"
+                                            .into(),
+                                    )
+                                })?;
+                            let shift = math::sub(u, new_coords[j]).map(|x| x.round() as i32);
+                            Ok(AtomSite::new(new.species(j).clone(), new.ion_number(j), shift))
+                        })
+                        .collect::<CResult<Vec<_>>>()?;
+                    let mapped = NonlinearConstraint {
+                        constraint_type: constraint.constraint_type.clone(),
+                        atom_sites,
+                    };
+                    if !constraints
+                        .iter()
+                        .any(|c| same_up_to_translation(c, &mapped))
+                    {
+                        constraints.push(mapped);
+                    }
+                }
+            }
+            Ok(NonlinearConstraints { constraints })
+        })
+        .transpose()?;
+
+    let hubbard_u = doc
+        .hubbard_u
+        .as_ref()
+        .map(|hu| -> CResult<HubbardU> {
+            let mut atom_u_values: Vec<AtomHubbardU> = Vec::new();
+            for entry in &hu.atom_u_values {
+                let mapped: Vec<AtomHubbardU> = match entry.ion_number {
+                    None => vec![entry.clone()],
+                    Some(n) => {
+                        let i = old.resolve(&entry.species, n)?;
+                        copies
+                            .iter()
+                            .map(|copy| AtomHubbardU {
+                                species: new.species(copy[i]).clone(),
+                                ion_number: Some(new.ion_number(copy[i])),
+                                orbitals: entry.orbitals.clone(),
+                            })
+                            .collect()
+                    }
+                };
+                for m in mapped {
+                    let clash = atom_u_values.iter().find(|e| {
+                        e.ion_number == m.ion_number
+                            && e.species.same_species(&m.species)
+                    });
+                    match clash {
+                        Some(e) if *e == m => {}
+                        Some(_) => {
+                            return Err(Error::Message(format!(
+                                "atoms merged into {} {} have different HUBBARD_U values",
+                                m.species,
+                                m.ion_number.unwrap_or_default()
+                            )));
+                        }
+                        None => atom_u_values.push(m),
+                    }
+                }
+            }
+            Ok(HubbardU {
+                unit: hu.unit,
+                atom_u_values,
+            })
+        })
+        .transpose()?;
+
+    let ionic_velocities = doc
+        .ionic_velocities
+        .as_ref()
+        .map(|iv| -> CResult<IonicVelocities> {
+            if iv.velocities.len() != coords.len() {
+                return Err(Error::Message(format!(
+                    "IONIC_VELOCITIES lists {} ions but the cell has {}",
+                    iv.velocities.len(),
+                    coords.len()
+                )));
+            }
+            let mut velocities: Vec<Option<[f64; 3]>> = vec![None; sources.len()];
+            for copy in &copies {
+                for (entry, &j) in iv.velocities.iter().zip(copy) {
+                    match velocities[j] {
+                        None => velocities[j] = Some(entry.velocity),
+                        Some(v)
+                            if math::norm(math::sub(v, entry.velocity))
+                                <= 1e-8 * (1.0 + math::norm(v)) => {}
+                        Some(_) => {
+                            return Err(Error::Message(format!(
+                                "atoms merged into {} {} have different IONIC_VELOCITIES",
+                                new.species(j), new.ion_number(j)
+                            )));
+                        }
+                    }
+                }
+            }
+            Ok(IonicVelocities {
+                unit: iv.unit,
+                velocities: velocities
+                    .into_iter()
+                    .enumerate()
+                    .map(|(j, v)| IonicVelocityEntry {
+                        species: new.species(j).clone(),
+                        velocity: v.unwrap_or_default(),
+                    })
+                    .collect(),
+            })
+        })
+        .transpose()?;
+
+    Ok(CellDocument {
+        lattice,
+        positions,
+        positions_intermediate,
+        positions_product,
+        ionic_constraints,
+        nonlinear_constraints,
+        hubbard_u,
+        ionic_velocities,
+        quantization_axis: doc.quantization_axis.map(|qa| QuantizationAxis {
+            direction: math::mat_vec(p_inv, qa.direction),
+        }),
+        symmetry_ops: None,
+        cell_constraints: None,
+        kpoints_list: None,
+        kpoints_mp_grid: None,
+        kpoints_mp_offset: None,
+        bs_kpoint_path: None,
+        bs_kpoints_list: None,
+        optics_kpoints_list: None,
+        magres_kpoints_list: None,
+        spectral_kpoint_path: None,
+        spectral_kpoints_list: None,
+        spectral_kpoints_mp_grid: None,
+        spectral_kpoints_mp_offset: None,
+        phonon_kpoint_list: None,
+        phonon_kpoint_path: None,
+        phonon_kpoints_mp_grid: None,
+        phonon_kpoints_mp_offset: None,
+        phonon_fine_kpoint_path: None,
+        phonon_fine_kpoints_mp_grid: None,
+        phonon_fine_kpoints_mp_offset: None,
+        phonon_fine_kpoint_list: None,
+        phonon_supercell_matrix: None,
+        supercell_kpoint_list: None,
+        ..doc.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::constraints::ConstraintType;
+    use crate::cell::species::OrbitalU;
+    use crate::test_fixtures::{atom, cubic_lattice, frac_atoms};
+
+    fn cart(rows: Mat3) -> Lattice {
+        Lattice::Cart(LatticeCart {
+            unit: None,
+            a: rows[0],
+            b: rows[1],
+            c: rows[2],
+        })
+    }
+
+    fn assert_close(a: Mat3, b: Mat3) {
+        for (x, y) in a.iter().flatten().zip(b.iter().flatten()) {
+            assert!((x - y).abs() < 1e-9, "{a:?} != {b:?}");
+        }
+    }
+
+    /// Cartesian positions of the sites of every nonlinear constraint.
+    fn constraint_geometry(doc: &CellDocument) -> Vec<Vec<Vec3>> {
+        let table = IonIndex::new(&doc.positions);
+        let coords = doc.positions.frac_coords(&doc.lattice).unwrap();
+        let a = doc.lattice.vectors();
+        doc.nonlinear_constraints
+            .as_ref()
+            .unwrap()
+            .constraints
+            .iter()
+            .map(|c| {
+                c.atom_sites
+                    .iter()
+                    .map(|s| {
+                        let i = table.resolve(&s.species, s.ion_number).unwrap();
+                        let x = math::add(coords[i], s.image_indices.map(f64::from));
+                        math::frac_to_cart(a, x)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn bond_length(sites: &[Vec3]) -> f64 {
+        math::norm(math::sub(sites[1], sites[0]))
+    }
+
+    /// A CsCl-type cell with one of every per-ion block.
+    fn cscl() -> CellDocument {
+        CellDocument::builder()
+            .lattice(cubic_lattice(4.0))
+            .positions(frac_atoms(&[("Cs", [0.0; 3]), ("Cl", [0.5; 3])]))
+            .ionic_constraints(IonicConstraints {
+                constraints: vec![IonicConstraintEntry {
+                    constraint_number: 1,
+                    species: Species::Symbol("Cl".into()),
+                    ion_number: 1,
+                    coefficients: [0.0, 0.0, 1.0],
+                }],
+            })
+            .nonlinear_constraints(NonlinearConstraints {
+                constraints: vec![NonlinearConstraint {
+                    constraint_type: ConstraintType::Distance,
+                    atom_sites: vec![
+                        AtomSite::new(Species::Symbol("Cs".into()), 1, [1, 0, 0]),
+                        AtomSite::new(Species::Symbol("Cl".into()), 1, [0, 0, 0]),
+                    ],
+                }],
+            })
+            .hubbard_u(HubbardU {
+                unit: None,
+                atom_u_values: vec![AtomHubbardU {
+                    species: Species::Symbol("Cs".into()),
+                    ion_number: Some(1),
+                    orbitals: vec![OrbitalU::D(2.0)],
+                }],
+            })
+            .ionic_velocities(IonicVelocities {
+                unit: None,
+                velocities: vec![
+                    IonicVelocityEntry {
+                        species: Species::Symbol("Cs".into()),
+                        velocity: [0.1, 0.0, 0.0],
+                    },
+                    IonicVelocityEntry {
+                        species: Species::Symbol("Cl".into()),
+                        velocity: [0.0, 0.2, 0.0],
+                    },
+                ],
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lattice_reduction_is_recoverable() {
+        let lattice = cart([[3.0, 0.0, 0.0], [7.0, 4.0, 0.0], [-2.0, 9.0, 5.0]]);
+        for (reduced, recover) in [
+            lattice.niggli_reduce().unwrap(),
+            lattice.delaunay_reduce().unwrap(),
+        ] {
+            assert!((reduced.volume() - lattice.volume()).abs() < 1e-9);
+            let lengths: f64 = reduced.vectors().iter().map(|v| math::norm(*v)).sum();
+            let original: f64 = lattice.vectors().iter().map(|v| math::norm(*v)).sum();
+            assert!(lengths < original);
+            assert_close(reduced.transform(recover).vectors(), lattice.vectors());
+        }
+    }
+
+    #[test]
+    fn test_supercell_replicates_per_ion_blocks() {
+        let doc = cscl();
+        let supercell = doc.transform([[2, 0, 0], [0, 1, 0], [0, 0, 1]]).unwrap();
+        assert_eq!(supercell.positions.len(), 4);
+        assert!((supercell.lattice.volume() - 2.0 * doc.lattice.volume()).abs() < 1e-9);
+
+        let ic = &supercell.ionic_constraints.as_ref().unwrap().constraints;
+        assert_eq!(ic.len(), 2);
+        assert_eq!(
+            ic.iter()
+                .map(|e| (e.constraint_number, e.ion_number))
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (2, 2)]
+        );
+        assert_eq!(supercell.hubbard_u.as_ref().unwrap().atom_u_values.len(), 2);
+        let velocities = &supercell.ionic_velocities.as_ref().unwrap().velocities;
+        assert_eq!(velocities.len(), 4);
+        assert_eq!(velocities[1].velocity, [0.1, 0.0, 0.0]);
+
+        // Both copies of the constrained bond keep their length.
+        let original = bond_length(&constraint_geometry(&doc)[0]);
+        let geometry = constraint_geometry(&supercell);
+        assert_eq!(geometry.len(), 2);
+        for sites in geometry {
+            assert!((bond_length(&sites) - original).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_supercell_keeps_transition_state_displacements() {
+        let mut doc = cscl();
+        // The Cl atom moves across the cell boundary in the product.
+        doc.positions_product = Some(
+            Positions::Frac(frac_atoms(&[("Cs", [0.0; 3]), ("Cl", [0.5, 0.5, 1.1])])).into(),
+        );
+        let supercell = doc.transform([[1, 0, 0], [0, 1, 0], [0, 0, 2]]).unwrap();
+        supercell.check_transition_state().unwrap();
+        let reactant = supercell.positions.cart_coords(&supercell.lattice);
+        let product = supercell
+            .positions_product
+            .as_ref()
+            .unwrap()
+            .to_positions()
+            .cart_coords(&supercell.lattice);
+        assert_eq!(product.len(), 4);
+        for (x, y) in reactant.iter().zip(&product) {
+            let shift = math::norm(math::sub(*y, *x));
+            let expected = if x[2] == y[2] { 0.0 } else { 2.4 };
+            assert!((shift - expected).abs() < 1e-9, "{x:?} -> {y:?}");
+        }
+    }
+
+    #[test]
+    fn test_niggli_reduce_document_roundtrip() {
+        let mut doc = cscl();
+        doc.lattice = cart([[4.0, 0.0, 0.0], [8.0, 4.0, 0.0], [4.0, -4.0, 4.0]]);
+        doc.quantization_axis = Some(QuantizationAxis {
+            direction: [0.0, 0.0, 1.0],
+        });
+        let (reduced, recover) = doc.niggli_reduce().unwrap();
+        // The quantization axis keeps its Cartesian direction.
+        let axis = |d: &CellDocument| {
+            math::frac_to_cart(d.lattice.vectors(), d.quantization_axis.unwrap().direction)
+        };
+        let shift = math::sub(axis(&reduced), axis(&doc));
+        assert!(math::norm(shift) < 1e-9, "{shift:?}");
+        let rows = reduced.lattice.vectors();
+        for (i, v) in rows.iter().enumerate() {
+            assert!((math::norm(*v) - 4.0).abs() < 1e-9);
+            assert!(math::dot(*v, rows[(i + 1) % 3]).abs() < 1e-9);
+        }
+        let bond = bond_length(&constraint_geometry(&reduced)[0]);
+        assert!((bond - bond_length(&constraint_geometry(&doc)[0])).abs() < 1e-9);
+
+        let recovered = reduced.transform(recover).unwrap();
+        assert_close(recovered.lattice.vectors(), doc.lattice.vectors());
+        assert_eq!(
+            recovered.positions.frac_coords(&recovered.lattice).unwrap(),
+            doc.positions.frac_coords(&doc.lattice).unwrap()
+        );
+        assert_eq!(recovered.nonlinear_constraints, doc.nonlinear_constraints);
+        assert_eq!(recovered.ionic_constraints, doc.ionic_constraints);
+    }
+
+    #[test]
+    fn test_primitive_cell_of_conventional_fcc() {
+        let a = 3.6;
+        let mut atoms = Vec::new();
+        let mut velocities = Vec::new();
+        for f in [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.5, 0.5],
+            [0.5, 0.0, 0.5],
+            [0.5, 0.5, 0.0],
+        ] {
+            atoms.push(atom("Cu", f));
+            velocities.push(IonicVelocityEntry {
+                species: Species::Symbol("Cu".into()),
+                velocity: [0.0, 0.0, 1.0],
+            });
+        }
+        let site = |ion, image| AtomSite::new(Species::Symbol("Cu".into()), ion, image);
+        let doc = CellDocument::builder()
+            .lattice(cubic_lattice(a))
+            .positions(PositionsFrac { positions: atoms })
+            .ionic_velocities(IonicVelocities {
+                unit: None,
+                velocities,
+            })
+            .nonlinear_constraints(NonlinearConstraints {
+                constraints: vec![
+                    NonlinearConstraint {
+                        constraint_type: ConstraintType::Distance,
+                        atom_sites: vec![site(1, [0, 0, 0]), site(2, [0, 0, 0])],
+                    },
+                    // The same bond translated by a centring vector.
+                    NonlinearConstraint {
+                        constraint_type: ConstraintType::Distance,
+                        atom_sites: vec![site(3, [0, 0, 0]), site(4, [0, 0, 1])],
+                    },
+                ],
+            })
+            .build()
+            .unwrap();
+
+        let (primitive, recover) = doc.primitive_cell().unwrap();
+        assert_eq!(primitive.positions.len(), 1);
+        assert_eq!(math::imat_det(recover), 4);
+        assert!((primitive.lattice.volume() - a.powi(3) / 4.0).abs() < 1e-9);
+        assert_eq!(
+            primitive
+                .ionic_velocities
+                .as_ref()
+                .unwrap()
+                .velocities
+                .len(),
+            1
+        );
+        let geometry = constraint_geometry(&primitive);
+        assert_eq!(geometry.len(), 1);
+        assert!((bond_length(&geometry[0]) - a / 2.0_f64.sqrt()).abs() < 1e-9);
+
+        let recovered = primitive.transform(recover).unwrap();
+        assert_close(recovered.lattice.vectors(), doc.lattice.vectors());
+        assert_eq!(recovered.positions.len(), 4);
+        assert_eq!(recovered.find_symmetry().unwrap().space_group.number, 225);
+        assert_eq!(constraint_geometry(&recovered).len(), 4);
+    }
+
+    #[test]
+    fn test_primitive_cell_rejects_conflicting_velocities() {
+        let mut doc = cscl().transform([[1, 0, 0], [0, 2, 0], [0, 0, 1]]).unwrap();
+        doc.ionic_velocities.as_mut().unwrap().velocities[1].velocity = [5.0, 0.0, 0.0];
+        let err = doc.primitive_cell().unwrap_err();
+        assert!(err.to_string().contains("IONIC_VELOCITIES"));
+    }
+}
@@ -4,6 +4,8 @@ use castep_cell_fmt::{CResult, Error};
 use castep_cell_fmt::query::value_as_str;
 use serde::{Deserialize, Serialize};
 
+use super::length_units::BOHR_IN_ANG;
+
 /// Specifies the units in which inverse length will be reported.
 ///
 /// Keyword type: String
@@ -32,6 +34,18 @@ pub enum InvLengthUnit {
     Angstrom,
 }
 
+impl InvLengthUnit {
+    /// Returns one unit in Å⁻¹.
+    pub const fn in_inv_angstrom(&self) -> f64 {
+        match self {
+            InvLengthUnit::Bohr => 1.0 / BOHR_IN_ANG,
+            InvLengthUnit::Meter => 1.0e-10,
+            InvLengthUnit::NanoMeter => 0.1,
+            InvLengthUnit::Angstrom => 1.0,
+        }
+    }
+}
+
 // Implement ToCell for InvLengthUnit to enable serialization via your custom backend
 impl FromCellValue for InvLengthUnit {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
@@ -31,6 +31,22 @@ pub enum LengthUnit {
     Ang,
 }
 
+/// Bohr radius in Å (CODATA 2018).
+pub(crate) const BOHR_IN_ANG: f64 = 0.529_177_210_903;
+
+impl LengthUnit {
+    /// Returns the length of one unit in Å.
+    pub const fn in_angstrom(&self) -> f64 {
+        match self {
+            LengthUnit::Bohr | LengthUnit::BohrA0 => BOHR_IN_ANG,
+            LengthUnit::Meter => 1.0e10,
+            LengthUnit::Centimeter => 1.0e8,
+            LengthUnit::Nanometer => 10.0,
+            LengthUnit::Ang => 1.0,
+        }
+    }
+}
+
 impl FromCellValue for LengthUnit {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value_as_str(value)?.to_ascii_lowercase().as_str() {
@@ -114,6 +130,13 @@ mod tests {
         assert_eq!(LengthUnit::default(), LengthUnit::Ang);
     }
 
+    #[test]
+    fn test_in_angstrom() {
+        assert_eq!(LengthUnit::Ang.in_angstrom(), 1.0);
+        assert_eq!(LengthUnit::Nanometer.in_angstrom(), 10.0);
+        assert!((LengthUnit::Bohr.in_angstrom() - 0.529177).abs() < 1e-6);
+    }
+
     #[test]
     fn test_to_cell_value() {
         assert_eq!(LengthUnit::Bohr.to_cell_value(), CellValue::String("bohr".to_string()));
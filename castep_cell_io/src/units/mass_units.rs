@@ -41,6 +41,21 @@ pub enum MassUnit {
     Gram,
 }
 
+/// Atomic mass unit in g (CODATA 2018).
+pub(crate) const AMU_IN_G: f64 = 1.660_539_066_60e-24;
+
+impl MassUnit {
+    /// Returns the mass of one unit in amu.
+    pub const fn in_amu(&self) -> f64 {
+        match self {
+            MassUnit::ElectronMass => 5.485_799_090_65e-4,
+            MassUnit::AtomicMassUnit => 1.0,
+            MassUnit::Kilogram => 1.0e3 / AMU_IN_G,
+            MassUnit::Gram => 1.0 / AMU_IN_G,
+        }
+    }
+}
+
 impl FromCellValue for MassUnit {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value_as_str(value)?.to_ascii_lowercase().as_str() {
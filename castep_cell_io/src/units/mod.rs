@@ -22,6 +22,7 @@ pub use frequency_unit::FrequencyUnit;
 pub use inv_length_units::InvLengthUnit;
 pub use length_units::LengthUnit;
 pub use mass_units::MassUnit;
+pub(crate) use mass_units::AMU_IN_G;
 pub use pressure_unit::PressureUnit;
 pub use quadrupole_moment_units::QuadrupoleMomentUnit;
 pub use temperature_unit::TemperatureUnit;
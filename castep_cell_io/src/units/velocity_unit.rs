@@ -4,6 +4,8 @@ use castep_cell_fmt::{CResult, Error};
 use castep_cell_fmt::query::value_as_str;
 use serde::{Deserialize, Serialize};
 
+use super::length_units::BOHR_IN_ANG;
+
 /// Specifies the units in which velocity will be reported.
 ///
 /// Keyword type: String
@@ -38,6 +40,21 @@ pub enum VelocityUnit {
     MetersPerSecond,
 }
 
+impl VelocityUnit {
+    /// Returns the velocity of one unit in Å/ps.
+    pub const fn in_ang_per_ps(&self) -> f64 {
+        match self {
+            // a0 Eh / hbar = 2.187 691 263 64e6 m/s (CODATA 2018).
+            VelocityUnit::AtomicUnitOfVelocity => 21_876.912_636_4,
+            VelocityUnit::AngPerPs => 1.0,
+            VelocityUnit::AngPerFs => 1.0e3,
+            VelocityUnit::BohrPerPs => BOHR_IN_ANG,
+            VelocityUnit::BohrPerFs => BOHR_IN_ANG * 1.0e3,
+            VelocityUnit::MetersPerSecond => 1.0e-2,
+        }
+    }
+}
+
 impl FromCellValue for VelocityUnit {
     fn from_cell_value(value: &CellValue<'_>) -> CResult<Self> {
         match value_as_str(value)?.to_ascii_lowercase().as_str() {